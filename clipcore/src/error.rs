@@ -0,0 +1,41 @@
+//! Failures clipd reports in a [`Response`](crate::Response).
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// What went wrong, for clients that react to more than the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The frame was too long or not JSON of a request clipd knows.
+    BadRequest,
+    /// A request other than `Hello` opened the connection.
+    HandshakeRequired,
+    /// `Hello` asked for a protocol newer than clipd's.
+    UnsupportedProtocol,
+    /// The entry or backup the request names does not exist.
+    NotFound,
+    /// The request was understood, but carrying it out failed.
+    Failed,
+    /// A code added by a newer clipd.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A request clipd could not answer, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[error("{message}")]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    /// The cause as clipd logged it, for showing to the user.
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Framing: a little-endian `u32` length, then that many bytes of JSON.
+
+use std::io;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{Request, MAX_REQUEST_LEN};
+
+/// Why a frame could not be read.
+#[derive(Debug, Error)]
+pub enum FrameError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The body was left unread, so the stream is out of step.
+    #[error("request of {0} bytes is over the {MAX_REQUEST_LEN}-byte limit")]
+    TooLong(u32),
+    /// The whole body was read; the next frame can follow.
+    #[error("malformed message")]
+    Malformed(#[source] serde_json::Error),
+}
+
+/// Read the JSON of a request whose frame header said it is `len` bytes.
+pub async fn read_request<S>(stream: &mut S, len: u32) -> Result<Request, FrameError>
+where
+    S: AsyncRead + Unpin + ?Sized,
+{
+    if len > MAX_REQUEST_LEN {
+        return Err(FrameError::TooLong(len));
+    }
+    read_body(stream, len).await
+}
+
+/// Read one whole frame. Responses have no length limit.
+pub async fn read_frame<T, S>(stream: &mut S) -> Result<T, FrameError>
+where
+    T: DeserializeOwned,
+    S: AsyncRead + Unpin + ?Sized,
+{
+    let len = stream.read_u32_le().await?;
+    read_body(stream, len).await
+}
+
+async fn read_body<T, S>(stream: &mut S, len: u32) -> Result<T, FrameError>
+where
+    T: DeserializeOwned,
+    S: AsyncRead + Unpin + ?Sized,
+{
+    let mut buf = vec![0u8; len as usize];
+    stream.read_exact(&mut buf).await?;
+    serde_json::from_slice(&buf).map_err(FrameError::Malformed)
+}
+
+/// Write `message` as one frame and flush it.
+pub async fn write_frame<T, S>(stream: &mut S, message: &T) -> io::Result<()>
+where
+    T: Serialize,
+    S: AsyncWrite + Unpin + ?Sized,
+{
+    let payload = serde_json::to_vec(message)?;
+    stream.write_u32_le(payload.len() as u32).await?;
+    stream.write_all(&payload).await?;
+    stream.flush().await
+}
@@ -0,0 +1,29 @@
+//! The protocol clipd and clipctl speak: requests, responses, and the frames
+//! they travel in. Both ends take their types from here, so a field added on
+//! one side cannot silently go missing on the other.
+
+mod error;
+mod frame;
+mod request;
+mod response;
+
+pub use error::{ErrorCode, ErrorResponse};
+pub use frame::{read_frame, read_request, write_frame, FrameError};
+pub use request::{Request, RequestKind};
+pub use response::{
+    BackupSummary, BlobStats, CaptureStatus, Checksums, CompanionInfo, DayCount, Diagnostics, Digest, Entity,
+    EntityKind, EntryPayload, EntrySource, EntrySummary, Highlight, ImageInfo, KeySummary, PasteVerdict,
+    LargeEntry, ProfileSummary, QueueStats, Response, ServerEvent, ServerInfo, SessionSummary, StyleRun, Suggestion,
+    UsageCount, UsageStats,
+};
+
+/// Bumped whenever the wire format changes incompatibly.
+///
+/// 2: requests before `Hello` are refused, and failures come back as
+/// [`Response::error`] instead of a dropped connection.
+/// 3: `ListPage` pages through the history with a cursor and a limit.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Longest request a client may send. Requests are a few hundred bytes of
+/// JSON; the limit stops a corrupt length prefix from allocating gigabytes.
+pub const MAX_REQUEST_LEN: u32 = 1024 * 1024;
@@ -0,0 +1,147 @@
+//! What clients ask clipd.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub kind: RequestKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RequestKind {
+    /// Must come first on every connection; clipd answers anything else
+    /// with [`ErrorCode::HandshakeRequired`](crate::ErrorCode::HandshakeRequired).
+    Hello { client_version: String, protocol: u32 },
+    /// The first page of the history, as `ListPage` without a cursor or limit
+    /// gives it; kept for clients that predate paging.
+    List,
+    /// A page of the history, newest first: without a `cursor`, every pinned
+    /// entry and then the newest `limit` others; with one, the `limit` entries
+    /// after the page that answered with it as `next_cursor`. `limit`
+    /// defaults to 256 and is at most 1000.
+    ListPage {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cursor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    /// Have clipd push [`ServerEvent::EntryAdded`](crate::ServerEvent::EntryAdded)
+    /// on this connection for every entry it stores from now on, between
+    /// the answers to requests.
+    Subscribe,
+    Search { query: String },
+    /// Ask whether entry `id` may be pasted into the app in front; `force`
+    /// overrides a `[paste]` rule after the user confirmed. Unless `plain`
+    /// (the client pastes text of its own, such as a transform's), clipd puts
+    /// RTF and HTML entries on the clipboard itself when `[paste]` wants them
+    /// rich, and files entries as the files.
+    Paste {
+        id: u64,
+        #[serde(default)]
+        force: bool,
+        #[serde(default)]
+        plain: bool,
+        /// `general.hide_from_windows_history` of the client, for documents clipd pastes.
+        #[serde(default)]
+        hide_from_history: bool,
+    },
+    /// Entry `id`; with `payload`, also the image or document data its
+    /// summary leaves out, for pasting it whole.
+    Get {
+        id: u64,
+        #[serde(default)]
+        payload: bool,
+    },
+    AddTag { id: u64, tag: String },
+    RemoveTag { id: u64, tag: String },
+    /// Tag every entry matching `query`, not only the ones a search lists.
+    TagMatching { query: String, tag: String },
+    /// Delete every entry matching `query`; an empty query deletes nothing.
+    DeleteMatching { query: String },
+    /// Delete entry `id` from the history.
+    Delete { id: u64 },
+    /// Delete the unpinned entries captured more than `older_than_days` ago.
+    /// Without it, wipe the active profile: every entry, pinned or archived,
+    /// and its backups.
+    Clear {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        older_than_days: Option<u32>,
+    },
+    /// Pin an entry below the other pinned ones, or unpin it.
+    SetPinned { id: u64, pinned: bool },
+    /// Swap a pinned entry with its neighbour above (`up`) or below.
+    MovePin { id: u64, up: bool },
+    /// Write the history to `path` as JSON; `redact` masks secrets, and a
+    /// `passphrase` or `user_key` encrypts the file.
+    Export {
+        path: String,
+        #[serde(default)]
+        redact: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase: Option<String>,
+        /// Seal with this Windows user's key, so only this account can import it.
+        #[serde(default)]
+        user_key: bool,
+    },
+    /// Read entries from `path`; encrypted exports need their `passphrase`.
+    Import {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase: Option<String>,
+    },
+    /// Write one entry's content to `path` (images as PNG).
+    SaveEntry { id: u64, path: String },
+    ReloadConfig,
+    Diagnose,
+    /// Captures per day, for the calendar heatmap.
+    DailyCounts,
+    /// Capture sessions, newest first.
+    Sessions,
+    TagSession { session: u64, tag: String },
+    ExportSession { session: u64, path: String },
+    /// Every profile with its size and newest entry.
+    Profiles,
+    /// Capture into another profile until clipd restarts or switches again.
+    SwitchProfile { name: String },
+    /// Encryption keys in `keys.json`.
+    Keys,
+    /// Make a new current key; older ones stay to open what they sealed.
+    RotateKey,
+    /// How a companion app on the LAN pairs with clipd; with `reset`, first
+    /// replace the pairing key, unpairing every app.
+    Companion {
+        #[serde(default)]
+        reset: bool,
+    },
+    /// Trust `process` until clipd restarts and release its quarantined entries.
+    ApproveSource { process: String },
+    /// Digests of an entry's content, as `SaveEntry` would write it.
+    Checksums { id: u64 },
+    /// Markup of an RTF or HTML entry, as `SaveEntry` would write it.
+    Source { id: u64 },
+    /// Store every format on the clipboard as one snapshot entry.
+    Snapshot,
+    /// Put a snapshot entry's formats back on the clipboard.
+    Restore { id: u64 },
+    /// Stop capturing for `minutes`, or resume when 0; without `minutes`,
+    /// resume if capture is paused and pause for `ignore.pause_minutes`
+    /// otherwise. Answered with `capture`.
+    PauseCapture {
+        #[serde(default)]
+        minutes: Option<u32>,
+    },
+    /// Backups of the active profile's database, newest first.
+    Backups,
+    /// Back the database up now.
+    CreateBackup,
+    /// Replace the history with backup `name`, backing up the current one first.
+    RestoreBackup { name: String },
+    /// Move archived entry `id` (from an `in:archive` search) back into the history.
+    Unarchive { id: u64 },
+    /// The entry most likely to be pasted next, from what went into the app
+    /// in front and at this time of day before. Answered with `suggestion`.
+    Suggest,
+    /// How the clipboard was used over the last `days` days, for
+    /// `clipctl insights`. Answered with `stats`.
+    Stats { days: u32 },
+}
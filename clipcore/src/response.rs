@@ -0,0 +1,474 @@
+//! What clipd answers, and what it pushes unasked.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ErrorResponse;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub entries: Vec<EntrySummary>,
+    /// Where the next page of a `ListPage` starts; `None` after the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<ServerEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<Diagnostics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<Vec<DayCount>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions: Option<Vec<SessionSummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<Vec<ProfileSummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<KeySummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub companion: Option<CompanionInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backups: Option<Vec<BackupSummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<Checksums>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<EntrySource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste: Option<PasteVerdict>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<EntryPayload>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<CaptureStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<Suggestion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<UsageStats>,
+    /// Entries tagged by `TagMatching`, or deleted by `DeleteMatching` or `Clear`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed: Option<u64>,
+    /// Why the request failed; the other fields are empty when it is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+impl Response {
+    /// The answer to a request that failed with `error`.
+    pub fn failed(error: ErrorResponse) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+
+    /// `Err` with clipd's reason when the request failed.
+    pub fn into_result(self) -> Result<Self, ErrorResponse> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self),
+        }
+    }
+}
+
+/// Build and protocol information returned from the `Hello` handshake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub version: String,
+    pub protocol: u32,
+}
+
+/// Daemon-side health report consumed by `clipctl doctor`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub db_path: String,
+    pub entries: u64,
+    /// Problems reported by SQLite's integrity check; empty when healthy.
+    pub integrity: Vec<String>,
+    /// How captures fared on their way to the database since clipd started.
+    #[serde(default)]
+    pub queue: QueueStats,
+    /// Storage saved by keeping identical images once.
+    #[serde(default)]
+    pub blobs: BlobStats,
+}
+
+/// Counts since clipd started, for `clipctl doctor`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub captured: u64,
+    pub stored: u64,
+    /// Captures dropped by `drop_oldest`.
+    pub dropped: u64,
+    /// Captures written to `spill/` by `spill`.
+    pub spilled: u64,
+    /// Times the watcher waited for room under `block`.
+    pub blocked: u64,
+    /// Most captures waiting at once.
+    pub peak: usize,
+    /// Most bytes of text and data waiting at once.
+    #[serde(default)]
+    pub peak_bytes: u64,
+    /// Clipboard formats not read for exceeding `capture.max_entry_mb`.
+    #[serde(default)]
+    pub rejected: u64,
+    /// Longest time from capture to stored, in milliseconds.
+    pub slowest_ms: u64,
+}
+
+/// How much storage sharing identical images saves, for `clipctl doctor`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobStats {
+    /// Entries whose image is a shared blob.
+    pub images: u64,
+    /// Distinct images stored.
+    pub blobs: u64,
+    /// Bytes the blobs take.
+    pub stored_bytes: u64,
+    /// Bytes more it would take to store every entry's image separately.
+    pub saved_bytes: u64,
+}
+
+/// Entries captured on one calendar day (`YYYY-MM-DD`, display timezone).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayCount {
+    pub date: String,
+    pub count: u64,
+}
+
+/// One clipd run's captures, for the session picker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: u64,
+    /// First and last capture, formatted per the `[time]` settings.
+    pub started: String,
+    pub ended: String,
+    pub entries: u64,
+    /// Whether this is the session clipd is capturing into now.
+    pub current: bool,
+}
+
+/// One profile as listed by `Profiles`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    /// Whether clipd is capturing into this profile.
+    pub active: bool,
+    pub entries: u64,
+    /// Title or preview of the newest entry.
+    pub latest: Option<String>,
+}
+
+/// One encryption key as listed by `Keys`; the key itself never leaves clipd.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeySummary {
+    pub id: u32,
+    /// Formatted per the `[time]` settings.
+    pub created: String,
+    /// Whether new exports are sealed with this key.
+    pub current: bool,
+}
+
+/// What a companion app needs to pair, for `clipctl companion`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionInfo {
+    /// `host:port` the companion endpoint listens on; `None` while
+    /// `companion.enabled` is off.
+    pub address: Option<String>,
+    /// The pairing key, hex-encoded: anyone who has it can read and add to
+    /// the history.
+    pub key: String,
+}
+
+/// One database backup, for `clipctl backup`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSummary {
+    /// File name, which `RestoreBackup` takes.
+    pub name: String,
+    /// Formatted per the `[time]` settings.
+    pub created: String,
+    /// What the backup was taken before, such as `import` or `manual`.
+    pub reason: String,
+    pub bytes: u64,
+}
+
+/// Lowercase hex digests of one entry's content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checksums {
+    pub id: u64,
+    pub sha256: String,
+    pub md5: String,
+    pub crc32: String,
+}
+
+/// One of the digests in [`Checksums`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Digest {
+    Sha256,
+    Md5,
+    Crc32,
+}
+
+impl Digest {
+    pub const ALL: [Digest; 3] = [Digest::Sha256, Digest::Md5, Digest::Crc32];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Sha256 => "SHA-256",
+            Self::Md5 => "MD5",
+            Self::Crc32 => "CRC32",
+        }
+    }
+}
+
+impl Checksums {
+    pub fn get(&self, digest: Digest) -> &str {
+        match digest {
+            Digest::Sha256 => &self.sha256,
+            Digest::Md5 => &self.md5,
+            Digest::Crc32 => &self.crc32,
+        }
+    }
+}
+
+/// Raw markup of one document entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntrySource {
+    pub id: u64,
+    pub text: String,
+}
+
+/// Answer to `Paste`: whether clipctl may put the entry on the clipboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteVerdict {
+    pub id: u64,
+    /// Executable the paste is headed for, when known.
+    pub target: Option<String>,
+    /// Why a `[paste]` rule forbids the paste; `None` when it may go ahead.
+    pub blocked: Option<String>,
+    /// clipd already put the entry on the clipboard in its rich format.
+    #[serde(default)]
+    pub pasted: bool,
+}
+
+/// Answer to `Suggest`, when the paste history points at an entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub id: u64,
+    /// App in front the suggestion is for; `None` when it is unknown and
+    /// only the time of day counted.
+    pub target: Option<String>,
+}
+
+/// Answer to `Stats`: what was captured over the last `days` days, leaving
+/// out quarantined entries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub days: u32,
+    pub entries: u64,
+    /// Apps copied from, most entries first.
+    pub apps: Vec<UsageCount>,
+    /// Entry kinds, most entries first.
+    pub kinds: Vec<UsageCount>,
+    /// Tags, most entries first.
+    pub tags: Vec<UsageCount>,
+    /// Entries per hour of the day, 0 to 23, in clipd's `[time]` timezone.
+    pub hours: Vec<u64>,
+    /// The largest entries, biggest first.
+    pub biggest: Vec<LargeEntry>,
+}
+
+/// An app, kind, or tag in [`UsageStats`] and its number of entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageCount {
+    pub name: String,
+    pub count: u64,
+}
+
+/// One of the largest entries in [`UsageStats`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargeEntry {
+    pub id: u64,
+    /// Title, or the first line of the text.
+    pub label: String,
+    pub kind: String,
+    pub bytes: u64,
+    /// When it was copied, formatted per the `[time]` settings.
+    pub created: String,
+}
+
+/// Whether clipd is capturing, as `PauseCapture` leaves it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureStatus {
+    /// When capture resumes by itself, formatted for display; `None` while
+    /// clipd captures.
+    pub paused_until: Option<String>,
+}
+
+/// The data clipd stores for an entry besides its text, as `Get` with
+/// `payload` returns it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPayload {
+    pub id: u64,
+    /// A DIB for an image, the document for RTF and HTML, the formats for a
+    /// snapshot; empty for text.
+    pub data: Vec<u8>,
+}
+
+/// Unsolicited notifications pushed to connected clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerEvent {
+    ShuttingDown,
+    /// A new entry, for connections that sent `Subscribe`.
+    EntryAdded(Box<EntrySummary>),
+}
+
+/// One history entry as lists and searches return it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntrySummary {
+    pub id: u64,
+    pub preview: String,
+    pub created_at: String,
+    /// `created_at` formatted per the `[time]` settings; missing from daemons
+    /// that predate them.
+    #[serde(default)]
+    pub created_display: Option<String>,
+    pub kind: String,
+    pub source_process: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+    /// Short label for long text, shown in the list instead of `preview`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Held back from an untrusted process; only listed by `is:quarantined`.
+    #[serde(default)]
+    pub quarantined: bool,
+    /// Why the text looks dangerous to paste into a shell, if it does.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// ISO 639-1 code of the text's language, when it could be told.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Listed first, in the order arranged with `MovePin`.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Bold, italic, and underlined stretches of an RTF or HTML entry's `preview`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub styles: Vec<StyleRun>,
+    /// Entries copied together from one app share this; listed by `group:N`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_group: Option<u64>,
+    /// Clipboard formats stored in a snapshot entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<String>,
+    /// Found by an `in:archive` search; `id` names it in the archive, so
+    /// only `Unarchive` takes it.
+    #[serde(default)]
+    pub archived: bool,
+    /// Where a search's words matched `preview`, in reading order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<Highlight>,
+    /// Email addresses, phone numbers, ids, and issues found in the text, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<Entity>,
+    /// The clipboard holds this entry now, so pasting it changes nothing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_clipboard: bool,
+    /// Times the content was copied, the first included; 0 from an older clipd.
+    #[serde(default)]
+    pub use_count: u32,
+}
+
+impl EntrySummary {
+    /// Capture time as the daemon formats it, falling back to raw RFC 3339.
+    pub fn display_time(&self) -> &str {
+        self.created_display.as_deref().unwrap_or(&self.created_at)
+    }
+}
+
+/// Formatting between byte offsets `start` and `end` of a preview.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleRun {
+    pub start: usize,
+    pub end: usize,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+}
+
+/// A search match between byte offsets `start` and `end` of a preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Something in an entry's text that can be acted on by itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    /// As written in the text.
+    pub value: String,
+    /// The issue's title, for an issue its tracker was asked about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Email,
+    Phone,
+    Ipv4,
+    Ipv6,
+    Uuid,
+    /// A git commit hash, full or abbreviated.
+    Sha,
+    /// A Docker container id, full or as `docker ps` shortens it.
+    Container,
+    /// A Jira key like `OPS-123`, or a GitHub issue as `#456` or `owner/repo#789`.
+    Issue,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 8] = [
+        EntityKind::Email,
+        EntityKind::Phone,
+        EntityKind::Ipv4,
+        EntityKind::Ipv6,
+        EntityKind::Uuid,
+        EntityKind::Sha,
+        EntityKind::Container,
+        EntityKind::Issue,
+    ];
+
+    /// As in `has:email`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::Ipv4 => "ipv4",
+            Self::Ipv6 => "ipv6",
+            Self::Uuid => "uuid",
+            Self::Sha => "sha",
+            Self::Container => "container",
+            Self::Issue => "issue",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Size and origin of a captured image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Resolution stored in the bitmap, or the monitor's when the bitmap has none.
+    pub dpi: Option<u32>,
+    /// Device name of the monitor the foreground window was on, e.g. `\\.\DISPLAY1`.
+    pub monitor: Option<String>,
+}
@@ -0,0 +1,223 @@
+# Deutsche Texte für clipctl; fehlende Meldungen kommen aus en-US.ftl.
+
+## Hilfe
+
+help = Hilfe
+help-title = Tastenbelegung
+help-navigation = Navigation:
+help-move-down = Nach unten
+help-move-up = Nach oben
+help-top = Zum Anfang
+help-bottom = Zum Ende
+help-count = Um eine Anzahl bewegen
+help-go-to = Zu Eintrag 5
+help-marks = Marke a setzen / anspringen
+help-jumps = Zurück / vorwärts springen
+help-actions = Aktionen:
+help-paste = Ausgewählten Eintrag einfügen
+help-search = Suche starten
+help-add-tag = Tag hinzufügen
+help-remove-tag = Tag entfernen
+help-delete = Eintrag löschen
+help-clear = Alten Verlauf leeren oder ganz löschen
+help-export = Verlauf als JSON exportieren
+help-export-redacted = Mit maskierten Geheimnissen exportieren
+help-import = Verlauf aus JSON importieren
+help-save = Eintrag in Datei speichern
+help-explorer = Bild im Explorer zeigen (zum Herausziehen)
+help-copied-together = Zusammen kopierte Einträge zeigen / einfügen
+help-source = RTF/HTML-Quelltext in der Vorschau umschalten
+help-restore = Archivierten Eintrag wiederherstellen (Suche in:archive)
+help-snapshot = Alle Zwischenablageformate sichern (Enter stellt sie wieder her)
+help-send = Eintrag an einen Befehl senden
+help-calendar = Kalender der Einträge pro Tag
+help-sessions = Sitzungen: durchsuchen, taggen oder exportieren
+help-guest = Gastmodus: Inhalte beim Teilen verbergen
+help-profiles = Profile: festlegen, wohin clipd aufzeichnet
+help-split = In zwei Bereiche teilen / Bereich wechseln
+help-general = Allgemein:
+help-help = Diese Hilfe zeigen
+help-palette = Befehlspalette (alle Aktionen)
+help-settings = Einstellungen öffnen
+help-macros = Makro a aufzeichnen (q beendet) / abspielen
+help-quit = Beenden
+help-close = Hoch/Runter, Bild auf/ab blättern; s: Einstellungen; andere Taste schließt
+
+## Verlauf und Vorschau
+
+list-history = Verlauf
+list-history-help = Verlauf (? für Hilfe)
+list-history-guest = Verlauf · Gastmodus, Inhalte verborgen (H zeigt sie)
+preview = Vorschau
+preview-type = Art:
+preview-time = Zeit:
+preview-source = Quelle:
+preview-language = Sprache:
+preview-copies = Kopiert:
+preview-copies-count =
+    { $count ->
+        [one] einmal
+       *[other] { $count }-mal
+    }
+preview-copied-together = Zusammen kopiert:
+preview-copied-together-keys = (r zeigen, R einfügen)
+preview-formats = Formate:
+preview-emails = E-Mail:
+preview-phones = Telefon:
+preview-ipv4 = IPv4:
+preview-ipv6 = IPv6:
+preview-uuids = UUID:
+preview-commits = Commit:
+preview-containers = Container:
+preview-issues = Ticket:
+preview-git = Git:
+preview-git-summary =
+    { $kind ->
+        [patch] Patch
+       *[diff] Diff
+    }, { $files ->
+        [one] 1 Datei
+       *[other] { $files } Dateien
+    }, +{ $added } -{ $removed } (:apply speichert ihn)
+preview-size = Größe:
+preview-image-dpi = mit { $dpi } dpi
+preview-image-monitor = auf { $monitor }
+preview-tags = Tags:
+preview-line-endings = Zeilenenden:
+preview-encoding = Kodierung:
+preview-checksum-match = stimmt mit #{ $id } überein
+preview-guest = Inhalt im Gastmodus verborgen
+preview-quarantined = Quarantäne: nicht vertrauenswürdige Quelle, wird innerhalb einer Stunde gelöscht (A gibt frei)
+preview-archived = Archiviert: u holt den Eintrag in den Verlauf zurück
+preview-on-clipboard = Schon in der Zwischenablage: Strg+V fügt ihn ein
+preview-warnings = Vorsicht beim Einfügen: { $warnings }
+preview-no-selection = <keine Auswahl>
+loading = Wird geladen…
+offline-banner = Daemon offline - zwischengespeicherter Verlauf (schreibgeschützt, Texteinträge lassen sich kopieren)
+
+## Befehlszeile
+
+command = Befehl
+prompt-search-hint = (/ startet die Suche)
+prompt-recording = Aufnahme @{ $register }
+prompt-search = Suche:
+prompt-add-tag = Tag hinzufügen:
+prompt-remove-tag = Tag entfernen:
+prompt-export = Exportieren nach:
+prompt-export-redacted = Maskiert exportieren nach:
+prompt-export-encrypted = Verschlüsselt exportieren nach:
+prompt-passphrase-export = Passphrase (leer: nur dieses Windows-Konto):
+prompt-passphrase-repeat = Passphrase wiederholen:
+prompt-passphrase = Passphrase:
+prompt-import = Importieren aus:
+prompt-save-entry = Eintrag speichern unter:
+prompt-send = Senden an:
+prompt-tag-results = Alle Treffer taggen:
+prompt-tag-session = Ganze Sitzung taggen:
+prompt-export-session = Sitzung exportieren nach:
+mode-settings = Einstellungen
+mode-calendar = Kalender
+mode-sessions = Sitzungen
+mode-profiles = Profile
+mode-panes = In welchen Bereich einfügen?
+
+## Rückfragen, mit y oder n beantwortet
+
+confirm-start-clipd = clipd läuft nicht. Jetzt starten? [y/N]
+confirm-delete = "{ $label }" löschen? [y/N]
+confirm-wipe = Den ganzen Verlauf löschen, auch angeheftete und archivierte Einträge und Sicherungen? [y/N]
+prompt-clear = Nicht angeheftete Einträge älter als einen [d] Tag, eine [w] Woche oder einen [m] Monat löschen, oder [a]lles?
+confirm-delete-matching = Alle Einträge löschen, die '{ $query }' finden? [y/N]
+confirm-overwrite = { $path } existiert - überschreiben? [y/N]
+confirm-paste-anyway = { $reason } - trotzdem einfügen? [y/N]
+
+## Statusmeldungen
+
+status-cancelled = abgebrochen
+status-macro-stopped = Makro angehalten
+status-macro-recorded =
+    { $count ->
+        [one] 1 Taste in @{ $register } aufgezeichnet
+       *[other] { $count } Tasten in @{ $register } aufgezeichnet
+    }
+status-macro-recording = Aufnahme @{ $register }, q beendet
+status-no-action = keine Aktion passt zu '{ $query }'
+status-no-more-jumps = keine weiteren Sprünge
+status-mark-set = Marke '{ $name }' gesetzt
+status-mark-not-set = Marke '{ $name }' ist nicht gesetzt
+status-mark-not-listed = Marke '{ $name }' ist nicht in der aktuellen Liste
+status-entry-not-listed = Eintrag #{ $id } ist nicht mehr aufgeführt
+status-suggested = ausgewählt, was Sie um diese Zeit meist einfügen
+status-suggested-for = ausgewählt, was Sie meist in { $app } einfügen
+status-entry-archived = Eintrag ist archiviert - u holt ihn zuerst in den Verlauf zurück
+status-search-first = zuerst suchen - "alle löschen" entfernt, was eine Suche findet
+status-joined = Bereiche zusammengeführt
+status-split = geteilt - Ctrl+w wechselt den Bereich
+status-not-split = die Liste ist nicht geteilt (v teilt sie)
+status-other-pane = der andere Bereich braucht is:pinned oder ein einzelnes Tag als Suche
+status-pinned-only = nur angeheftete Einträge lassen sich verschieben
+status-images-only = nur Bilder lassen sich im Explorer zeigen
+status-source-unknown = der Quellprozess des Eintrags ist unbekannt
+status-trust-failed = vertrauenswürdiger Prozess konnte nicht gespeichert werden: { $error }
+status-trusted = { $process } ist vertrauenswürdig
+status-not-quarantined = Eintrag ist nicht in Quarantäne
+status-restored = in den Verlauf zurückgeholt
+status-not-archived = Eintrag ist nicht archiviert (Suche in:archive)
+status-no-source = nur RTF- und HTML-Einträge haben einen Quelltext
+status-copied-alone = Eintrag wurde einzeln kopiert
+status-snapshot-stored = Zwischenablage gesichert - Enter darauf stellt alle Formate wieder her
+status-no-conversion = kein Wert „{ $conversion }“ für diesen Eintrag
+status-no-email = keine E-Mail-Adresse in diesem Eintrag
+status-no-phone = keine Telefonnummer in diesem Eintrag
+status-no-ipv4 = keine IPv4-Adresse in diesem Eintrag
+status-no-ipv6 = keine IPv6-Adresse in diesem Eintrag
+status-no-uuid = keine UUID in diesem Eintrag
+status-no-commit = kein Commit-Hash in diesem Eintrag
+status-no-container = keine Container-ID in diesem Eintrag
+status-no-issue = kein Ticket-Verweis in diesem Eintrag
+status-no-diff = kein Diff oder Patch in diesem Eintrag
+status-capture-paused = Aufzeichnung pausiert bis { $until } - erneutes :pause setzt sie fort
+status-capture-resumed = Aufzeichnung fortgesetzt
+status-opened = { $uri } geöffnet
+status-theme-failed = Theme konnte nicht gespeichert werden: { $error }
+status-guest-on = Gastmodus an - Inhalte verborgen
+status-guest-off = Gastmodus aus
+status-image-capture-on = Bildaufzeichnung an
+status-image-capture-off = Bildaufzeichnung aus
+status-image-capture-failed = Bildaufzeichnung konnte nicht umgeschaltet werden: { $error }
+status-copied = { $label } { $value } kopiert
+status-offline-text-only = offline lassen sich nur Texteinträge kopieren
+status-group-dangerous = die Gruppe enthält gefährlich wirkende Einträge; bitte einzeln einfügen
+status-group-empty = die Gruppe enthält keinen Text zum Einfügen
+status-send-text-only = nur Texteinträge lassen sich senden (Eintrag ist { $kind })
+status-sent = Eintrag { $id } an '{ $command }' gesendet
+status-setting-failed = Einstellung konnte nicht gespeichert werden: { $error }
+status-profile = zeichne in Profil '{ $name }' auf
+status-remote-image = Bilder eines entfernten Verlaufs lassen sich nicht im Explorer zeigen; w speichert sie auf dem anderen Rechner
+status-no-panes = kein anderer { $multiplexer }-Bereich zum Einfügen
+status-passphrase-mismatch = Passphrasen stimmen nicht überein - Export abgebrochen
+status-changed =
+    { $count ->
+        [one] 1 Eintrag geändert
+       *[other] { $count } Einträge geändert
+    }
+status-drag = { $path } aus dem Explorer ziehen
+status-starting-clipd = clipd wird gestartet...
+status-autostart-failed = Autostart nicht eingerichtet: { $error }
+status-daemon-stopped = Daemon beendet
+status-connection-lost = Daemon beendet (Verbindung verloren)
+status-not-sent = Daemon beendet - Anfrage nicht gesendet
+status-request-failed = clipd: { $error }
+status-clipd-newer = clipd { $server } ist neuer als clipctl { $client }; bitte clipctl aktualisieren
+status-version-mismatch = Versionen passen nicht: clipd { $server } / clipctl { $client }
+
+## Was offline den Daemon braucht
+
+offline-groups = Daemon offline - Gruppen brauchen clipd
+offline-archive = Daemon offline - das Archiv braucht clipd
+offline-save = Daemon offline - Einträge lassen sich nicht speichern
+offline-view = Daemon offline - diese Ansicht braucht clipd
+offline-checksums = Daemon offline - Prüfsummen brauchen clipd
+offline-source = Daemon offline - Quelltext braucht clipd
+offline-snapshots = Daemon offline - Sicherungen brauchen clipd
+offline-read-only = Daemon offline - Verlauf ist schreibgeschützt
@@ -0,0 +1,227 @@
+# clipctl's help, prompts, and status messages.
+#
+# Every message clipctl shows is here; other catalogs translate any subset
+# and fall back to this one. Labels keep their colon, and clipctl adds the
+# space after them.
+
+## Help overlay
+
+help = Help
+help-title = Keybindings
+help-navigation = Navigation:
+help-move-down = Move down
+help-move-up = Move up
+help-top = Go to top
+help-bottom = Go to bottom
+help-count = Move by a count
+help-go-to = Go to entry 5
+help-marks = Set / jump to mark a
+help-jumps = Jump back / forward
+help-actions = Actions:
+help-paste = Paste selected entry
+help-search = Start search
+help-add-tag = Add tag to entry
+help-remove-tag = Remove tag from entry
+help-delete = Delete entry
+help-clear = Clear old history, or wipe all of it
+help-export = Export history to JSON
+help-export-redacted = Export with secrets masked
+help-import = Import history from JSON
+help-save = Save entry to file
+help-explorer = Show image in Explorer (drag it out)
+help-copied-together = View / paste entries copied together
+help-source = Toggle RTF/HTML source in preview
+help-restore = Restore archived entry (search in:archive)
+help-snapshot = Snapshot all clipboard formats (Enter restores)
+help-send = Send entry to a command
+help-calendar = Calendar of captures per day
+help-sessions = Sessions: browse, tag, or export one
+help-guest = Guest mode: hide content while sharing
+help-profiles = Profiles: switch where clipd captures
+help-split = Split into two panes / switch pane
+help-general = General:
+help-help = Show this help
+help-palette = Command palette (every action)
+help-settings = Open settings
+help-macros = Record macro a (q stops) / play it
+help-quit = Quit
+help-close = Up/Down or PgUp/PgDn scroll, s opens settings, any other key closes help
+
+## History list and preview
+
+list-history = History
+list-history-help = History (? for help)
+list-history-guest = History · guest mode, content hidden (H to show)
+preview = Preview
+preview-type = Type:
+preview-time = Time:
+preview-source = Source:
+preview-language = Language:
+preview-copies = Copied:
+preview-copies-count =
+    { $count ->
+        [one] once
+       *[other] { $count } times
+    }
+preview-copied-together = Copied together:
+preview-copied-together-keys = (r view, R paste)
+preview-formats = Formats:
+preview-emails = Email:
+preview-phones = Phone:
+preview-ipv4 = IPv4:
+preview-ipv6 = IPv6:
+preview-uuids = UUID:
+preview-commits = Commit:
+preview-containers = Container:
+preview-issues = Issue:
+preview-git = Git:
+preview-git-summary =
+    { $kind ->
+        [patch] patch
+       *[diff] diff
+    }, { $files ->
+        [one] 1 file
+       *[other] { $files } files
+    }, +{ $added } -{ $removed } (:apply saves it)
+preview-size = Size:
+preview-image-dpi = @ { $dpi } dpi
+preview-image-monitor = on { $monitor }
+preview-tags = Tags:
+preview-line-endings = Line endings:
+preview-encoding = Encoding:
+preview-checksum-match = matches #{ $id }
+preview-guest = Content hidden in guest mode
+preview-quarantined = Quarantined: untrusted source, deleted within the hour (A approves it)
+preview-archived = Archived: u restores it to the history
+preview-on-clipboard = Already on the clipboard: Ctrl+V pastes it
+preview-warnings = Careful pasting: { $warnings }
+preview-no-selection = <no selection>
+loading = Loading…
+offline-banner = daemon offline - showing cached history (read-only, text entries can still be copied)
+
+## Command bar
+
+command = Command
+prompt-search-hint = (press / to search)
+prompt-recording = recording @{ $register }
+prompt-search = Search:
+prompt-add-tag = Add tag:
+prompt-remove-tag = Remove tag:
+prompt-export = Export to:
+prompt-export-redacted = Export redacted to:
+prompt-export-encrypted = Export encrypted to:
+prompt-passphrase-export = Passphrase (empty: this Windows account only):
+prompt-passphrase-repeat = Repeat passphrase:
+prompt-passphrase = Passphrase:
+prompt-import = Import from:
+prompt-save-entry = Save entry to:
+prompt-send = Send to:
+prompt-tag-results = Tag all results:
+prompt-tag-session = Tag whole session:
+prompt-export-session = Export session to:
+mode-settings = Settings
+mode-calendar = Calendar
+mode-sessions = Sessions
+mode-profiles = Profiles
+mode-panes = Paste into which pane?
+
+## Questions, answered with y or n
+
+confirm-start-clipd = clipd is not running. Start it now? [y/N]
+confirm-delete = delete "{ $label }"? [y/N]
+confirm-wipe = wipe the whole history, pinned and archived entries and backups included? [y/N]
+prompt-clear = Clear unpinned entries older than a [d]ay, [w]eek, or [m]onth, or wipe [a]ll?
+confirm-delete-matching = delete every entry matching '{ $query }'? [y/N]
+confirm-overwrite = { $path } exists - overwrite? [y/N]
+confirm-paste-anyway = { $reason } - paste anyway? [y/N]
+
+## Status messages
+
+status-cancelled = cancelled
+status-macro-stopped = macro stopped
+status-macro-recorded =
+    { $count ->
+        [one] recorded 1 key into @{ $register }
+       *[other] recorded { $count } keys into @{ $register }
+    }
+status-macro-recording = recording @{ $register }, q to stop
+status-no-action = no action matches '{ $query }'
+status-no-more-jumps = no more jumps
+status-mark-set = mark '{ $name }' set
+status-mark-not-set = mark '{ $name }' not set
+status-mark-not-listed = mark '{ $name }' is not in the current list
+status-entry-not-listed = entry #{ $id } is no longer listed
+status-suggested = selected what you usually paste at this time
+status-suggested-for = selected what you usually paste into { $app }
+status-entry-archived = entry is archived - u restores it to the history first
+status-search-first = search first - delete all removes what a search matches
+status-joined = joined panes
+status-split = split - Ctrl+w switches panes
+status-not-split = the list is not split (v splits it)
+status-other-pane = the other pane needs is:pinned or a single tag as its search
+status-pinned-only = only pinned entries can be moved
+status-images-only = only image entries can be shown in Explorer
+status-source-unknown = the entry's source process is unknown
+status-trust-failed = failed to save trusted process: { $error }
+status-trusted = trusted { $process }
+status-not-quarantined = entry is not quarantined
+status-restored = restored to the history
+status-not-archived = entry is not archived (search in:archive)
+status-no-source = only RTF and HTML entries have a source
+status-copied-alone = entry was copied on its own
+status-snapshot-stored = clipboard snapshot stored - Enter on it restores every format
+status-no-conversion = no { $conversion } for this entry
+status-no-email = no email address in this entry
+status-no-phone = no phone number in this entry
+status-no-ipv4 = no IPv4 address in this entry
+status-no-ipv6 = no IPv6 address in this entry
+status-no-uuid = no UUID in this entry
+status-no-commit = no commit hash in this entry
+status-no-container = no container id in this entry
+status-no-issue = no issue reference in this entry
+status-no-diff = no diff or patch in this entry
+status-capture-paused = capture paused until { $until } - :pause again resumes it
+status-capture-resumed = capture resumed
+status-opened = opened { $uri }
+status-theme-failed = failed to save theme: { $error }
+status-guest-on = guest mode on - content hidden
+status-guest-off = guest mode off
+status-image-capture-on = image capture on
+status-image-capture-off = image capture off
+status-image-capture-failed = failed to toggle image capture: { $error }
+status-copied = copied { $label } { $value }
+status-offline-text-only = only text entries can be copied while offline
+status-group-dangerous = group has entries that look dangerous to paste; paste them one at a time
+status-group-empty = group has no text to paste
+status-send-text-only = only text entries can be sent (entry is { $kind })
+status-sent = sent entry { $id } to '{ $command }'
+status-setting-failed = failed to save setting: { $error }
+status-profile = capturing into profile '{ $name }'
+status-remote-image = images of a remote history cannot be shown in Explorer; w saves them on the other machine
+status-no-panes = no other { $multiplexer } pane to paste into
+status-passphrase-mismatch = passphrases do not match - export cancelled
+status-changed =
+    { $count ->
+        [one] 1 entry changed
+       *[other] { $count } entries changed
+    }
+status-drag = drag { $path } out of Explorer
+status-starting-clipd = starting clipd...
+status-autostart-failed = autostart not enabled: { $error }
+status-daemon-stopped = daemon stopped
+status-connection-lost = daemon stopped (connection lost)
+status-not-sent = daemon stopped - request not sent
+status-request-failed = clipd: { $error }
+status-clipd-newer = clipd { $server } is newer than clipctl { $client }; upgrade clipctl
+status-version-mismatch = clipd { $server } / clipctl { $client } version mismatch
+
+## What needs the daemon while it is offline
+
+offline-groups = daemon offline - groups need clipd
+offline-archive = daemon offline - the archive needs clipd
+offline-save = daemon offline - entries cannot be saved
+offline-view = daemon offline - this view needs clipd
+offline-checksums = daemon offline - checksums need clipd
+offline-source = daemon offline - source needs clipd
+offline-snapshots = daemon offline - snapshots need clipd
+offline-read-only = daemon offline - history is read-only
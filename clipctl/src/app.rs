@@ -1,14 +1,23 @@
-use anyhow::{Context, Result};
-use tokio::sync::mpsc;
+use anyhow::{bail, Context, Result};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
 use tokio::time::{self, Duration};
 
-use crate::ipc::{Client, Request, RequestKind};
+use crate::cache;
+use crate::config::{self, ClientConfig};
+use crate::daemon::{self, SpawnPolicy};
+use crate::i18n::{self, t};
+use crate::ipc::{Client, Request, RequestKind, ServerEvent, ServerInfo, PROTOCOL_VERSION};
+use crate::onboarding::Onboarding;
+use crate::remote;
 use crate::ui::{HandleOutcome, TerminalUi, UiEvent};
 
 pub struct App;
 
 impl App {
-    pub async fn run() -> Result<()> {
+    /// Run the TUI; returns text the stdout paste method wants printed after exit.
+    pub async fn run(config: ClientConfig) -> Result<Option<String>> {
+        i18n::select(&config.language);
         let (event_tx, mut event_rx) = mpsc::unbounded_channel();
 
         tokio::spawn({
@@ -36,22 +45,69 @@ impl App {
             }
         });
 
-        let mut ui = TerminalUi::new()?;
-        let mut client = Client::connect().await?;
+        let mut ui = TerminalUi::new(&config)?;
+        let onboarded = config.first_run && onboard(&mut ui, &mut event_rx).await?;
 
-        client
-            .send(&Request {
-                kind: RequestKind::List,
-            })
-            .await
-            .context("failed to request initial history")?;
+        // Dropped once the daemon goes away; the UI stays open read-only.
+        let mut client = match connect_or_spawn(&mut ui, &mut event_rx, config.spawn_daemon).await {
+            Ok(mut connection) => {
+                let server = connection.handshake().await?;
+                if let Some(warning) = version_warning(&server) {
+                    tracing::warn!(%warning, "daemon version skew");
+                    ui.set_status(warning);
+                }
+                // Subscribed before listing, so nothing copied in between is missed.
+                if let Err(err) = connection.request(RequestKind::Subscribe).await {
+                    tracing::warn!(%err, "daemon does not push new entries");
+                }
 
-        // Wait for the initial response before starting the UI
-        let initial_response = client.next_message().await?;
-        ui.ingest_response(initial_response)?;
+                // A daemon that was already running still holds the old defaults;
+                // ReloadConfig answers with the history list just like List.
+                connection
+                    .send(&Request {
+                        kind: if onboarded {
+                            RequestKind::ReloadConfig
+                        } else {
+                            RequestKind::List
+                        },
+                    })
+                    .await
+                    .context("failed to request initial history")?;
+
+                // Wait for the initial response before starting the UI; entries
+                // pushed before it are in it already.
+                let initial_response = loop {
+                    let response = connection.next_message().await?;
+                    if response.event.is_none() {
+                        break response;
+                    }
+                };
+                ui.ingest_response(initial_response)?;
+                if config.suggestions {
+                    if let Err(err) = suggest(&mut connection, &mut ui).await {
+                        tracing::warn!(%err, "no suggestion from the daemon");
+                    }
+                }
+                Some(connection)
+            }
+            Err(err) => match cache::load() {
+                Ok(Some(entries)) => {
+                    tracing::warn!(%err, "daemon unreachable, opening cached history");
+                    ui.go_offline(Some(entries));
+                    None
+                }
+                Ok(None) => return Err(err),
+                Err(cache_err) => {
+                    tracing::warn!(%cache_err, "failed to load history cache");
+                    return Err(err);
+                }
+            },
+        };
 
         let mut tick = time::interval(Duration::from_millis(75));
         ui.draw()?;
+        // A request is out; macro playback waits for its answer.
+        let mut awaiting = false;
 
         loop {
             tokio::select! {
@@ -60,24 +116,215 @@ impl App {
                 }
                 event = event_rx.recv() => match event {
                     Some(event) => {
-                        let HandleOutcome { should_exit, request } = ui.handle_event(event)?;
-                        if let Some(req) = request {
-                            client.send(&req).await?;
-                        }
-                        if should_exit {
+                        let outcome = ui.handle_event(event)?;
+                        if dispatch(outcome, &mut client, &mut ui, &mut awaiting).await {
                             break;
                         }
                     }
                     None => break,
                 },
-                response = client.next_message() => {
-                    ui.ingest_response(response?)?;
+                // One macro key at a time, taking turns with input so a keypress
+                // can stop it; each key waits for the answer its predecessor's
+                // request produced, so it sees the list that request made.
+                _ = std::future::ready(()), if !awaiting && ui.playing_macro() => {
+                    if let Some(outcome) = ui.play_next() {
+                        if dispatch(outcome?, &mut client, &mut ui, &mut awaiting).await {
+                            break;
+                        }
+                    }
+                }
+                response = next_message(&mut client) => {
+                    // A pushed entry answers no request.
+                    let pushed = response.as_ref().is_ok_and(|r| matches!(r.event, Some(ServerEvent::EntryAdded(_))));
+                    awaiting &= pushed;
+                    match response {
+                        Ok(response) => match response.event {
+                            Some(ServerEvent::ShuttingDown) => {
+                                client = None;
+                                ui.go_offline(None);
+                                ui.set_status(t!("status-daemon-stopped"));
+                            }
+                            Some(ServerEvent::EntryAdded(entry)) => ui.add_entry(*entry),
+                            None => {
+                                ui.ingest_response(response)?;
+                                if ui.exit_requested() {
+                                    break;
+                                }
+                                // Some answers need another request, like a pasted image's data.
+                                let outcome = HandleOutcome { should_exit: false, request: None };
+                                if dispatch(outcome, &mut client, &mut ui, &mut awaiting).await {
+                                    break;
+                                }
+                            }
+                        },
+                        Err(err) => {
+                            tracing::warn!(%err, "lost connection to daemon");
+                            client = None;
+                            ui.go_offline(None);
+                            ui.set_status(t!("status-connection-lost"));
+                        }
+                    }
                     ui.draw()?;  // Immediately redraw after receiving new data
                 }
             }
         }
 
-        Ok(())
+        Ok(ui.take_paste_output())
+    }
+}
+
+/// Send the requests a key produced, if any; returns whether the UI should exit.
+async fn dispatch(
+    outcome: HandleOutcome,
+    client: &mut Option<Client>,
+    ui: &mut TerminalUi,
+    awaiting: &mut bool,
+) -> bool {
+    let HandleOutcome { should_exit, request } = outcome;
+    let follow_ups = ui.take_follow_ups();
+    for req in request.into_iter().chain(follow_ups) {
+        match client.as_mut() {
+            Some(c) => match c.send(&req).await {
+                Ok(()) => *awaiting = true,
+                Err(err) => {
+                    tracing::warn!(%err, "failed to send request");
+                    *client = None;
+                    ui.go_offline(None);
+                    ui.set_status(t!("status-not-sent"));
+                    break;
+                }
+            },
+            None => {
+                ui.set_status(t!("status-not-sent"));
+                break;
+            }
+        }
+    }
+    should_exit
+}
+
+/// Select the entry clipd suggests pasting; entries pushed meanwhile are merged.
+async fn suggest(connection: &mut Client, ui: &mut TerminalUi) -> Result<()> {
+    connection.send(&Request { kind: RequestKind::Suggest }).await?;
+    loop {
+        let response = connection.next_message().await?;
+        match response.event {
+            Some(ServerEvent::EntryAdded(entry)) => ui.add_entry(*entry),
+            Some(ServerEvent::ShuttingDown) => bail!("clipd is shutting down"),
+            None => {
+                if let Some(suggestion) = response.into_result()?.suggestion {
+                    ui.suggest(suggestion);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Wait for the next daemon message, or forever once disconnected.
+async fn next_message(client: &mut Option<Client>) -> Result<crate::ipc::Response> {
+    match client {
+        Some(client) => client.next_message().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Connect to clipd, launching it first if it is not running and policy allows.
+async fn connect_or_spawn(
+    ui: &mut TerminalUi,
+    events: &mut UnboundedReceiver<UiEvent>,
+    policy: SpawnPolicy,
+) -> Result<Client> {
+    let err = match Client::connect().await {
+        Ok(client) => return Ok(client),
+        Err(err) => err,
+    };
+
+    // A clipd started here would not be the one asked for.
+    if policy == SpawnPolicy::Never || remote::active() {
+        return Err(err);
+    }
+
+    let clipd = match daemon::locate_clipd() {
+        Ok(path) => path,
+        Err(locate_err) => {
+            tracing::warn!(%locate_err, "cannot start clipd automatically");
+            return Err(err);
+        }
+    };
+
+    if policy == SpawnPolicy::Ask
+        && !confirm(ui, events, &t!("confirm-start-clipd")).await?
+    {
+        ui.clear_status();
+        return Err(err);
+    }
+
+    ui.set_status(t!("status-starting-clipd"));
+    ui.draw()?;
+    let client = daemon::spawn_and_connect(&clipd).await;
+    ui.clear_status();
+    client
+}
+
+/// Run the first-run wizard and write its answers to a new config file.
+///
+/// Returns false if input ended before the wizard finished.
+async fn onboard(ui: &mut TerminalUi, events: &mut UnboundedReceiver<UiEvent>) -> Result<bool> {
+    let mut wizard = Onboarding::new();
+    ui.draw_onboarding(&wizard)?;
+
+    loop {
+        let Some(UiEvent::Input(event)) = events.recv().await else {
+            return Ok(false);
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press && wizard.handle_key(key) {
+                break;
+            }
+        }
+        ui.draw_onboarding(&wizard)?;
+    }
+
+    let path = config::create(&wizard.settings())?;
+    tracing::info!(path = %path.display(), "wrote initial configuration");
+
+    if wizard.autostart() {
+        if let Err(err) = daemon::locate_clipd().and_then(|clipd| daemon::enable_autostart(&clipd)) {
+            tracing::warn!(%err, "failed to enable autostart");
+            ui.set_status(t!("status-autostart-failed", error = err.to_string()));
+        }
     }
+    Ok(true)
 }
 
+/// Show a yes/no question in the command bar and wait for the answer.
+async fn confirm(
+    ui: &mut TerminalUi,
+    events: &mut UnboundedReceiver<UiEvent>,
+    question: &str,
+) -> Result<bool> {
+    ui.set_status(question);
+    ui.draw()?;
+
+    while let Some(UiEvent::Input(event)) = events.recv().await {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                return Ok(matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')));
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Describe a non-fatal mismatch between this clipctl and the daemon.
+fn version_warning(server: &ServerInfo) -> Option<String> {
+    let ours = env!("CARGO_PKG_VERSION");
+    if server.protocol > PROTOCOL_VERSION {
+        Some(t!("status-clipd-newer", server = server.version.as_str(), client = ours))
+    } else if server.version != ours {
+        Some(t!("status-version-mismatch", server = server.version.as_str(), client = ours))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,60 @@
+//! On-disk cache of the last history list received from clipd.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+
+use crate::ipc::EntrySummary;
+use crate::remote;
+
+const CACHE_FILE: &str = "history-cache.json";
+
+/// The cache of this machine's history, or of the `--remote` one's.
+fn cache_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com", "rusty-clipboard", "clipmgr")
+        .context("failed to determine application directories")?;
+    let file = match remote::target() {
+        Some(target) => {
+            let target: String = target
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            format!("history-cache-{target}.json")
+        }
+        None => CACHE_FILE.to_string(),
+    };
+    Ok(dirs.cache_dir().join(file))
+}
+
+/// Persist the entry list so clipctl can open read-only when clipd is down.
+pub fn save(entries: &[EntrySummary]) -> Result<()> {
+    let path = cache_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create cache directory: {}", parent.display()))?;
+    }
+
+    // Write to a sibling file first so a crash never leaves a torn cache.
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(entries)?)
+        .with_context(|| format!("failed to write history cache: {}", tmp.display()))?;
+    fs::rename(&tmp, &path)
+        .with_context(|| format!("failed to replace history cache: {}", path.display()))?;
+    Ok(())
+}
+
+/// Load the cached entry list, if one was written before.
+pub fn load() -> Result<Option<Vec<EntrySummary>>> {
+    let path = cache_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let data = fs::read(&path)
+        .with_context(|| format!("failed to read history cache: {}", path.display()))?;
+    let entries = serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse history cache: {}", path.display()))?;
+    Ok(Some(entries))
+}
@@ -0,0 +1,159 @@
+//! Calendar heatmap of captures per day, GitHub-contributions style.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+
+use crate::ipc::DayCount;
+use crate::theme::Theme;
+
+/// Most weeks shown, about one year.
+const MAX_WEEKS: i64 = 53;
+/// Shades between an empty day and the busiest one.
+const LEVELS: u64 = 4;
+
+pub struct Calendar {
+    counts: HashMap<NaiveDate, u64>,
+    today: NaiveDate,
+    cursor: NaiveDate,
+}
+
+impl Calendar {
+    pub fn new(days: Vec<DayCount>) -> Self {
+        let counts = days
+            .into_iter()
+            .filter_map(|day| {
+                let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").ok()?;
+                Some((date, day.count))
+            })
+            .collect();
+        let today = Local::now().date_naive();
+        Self {
+            counts,
+            today,
+            cursor: today,
+        }
+    }
+
+    /// Move the cursor by `days`, staying within the last year and not past today.
+    pub fn move_cursor(&mut self, days: i64) {
+        let earliest = self.today - Duration::weeks(MAX_WEEKS);
+        self.cursor = (self.cursor + Duration::days(days)).clamp(earliest, self.today);
+    }
+
+    pub fn selected(&self) -> NaiveDate {
+        self.cursor
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        // Each week is a two-character column after the weekday labels.
+        let weeks = (i64::from(area.width.saturating_sub(2 + 4)) / 2).clamp(1, MAX_WEEKS);
+        let this_monday = self.today - Duration::days(self.today.weekday().num_days_from_monday().into());
+        let first_monday = this_monday - Duration::weeks(weeks - 1);
+        // Keep the cursor visible when the window is narrower than its week.
+        let first_monday = first_monday.min(
+            self.cursor - Duration::days(self.cursor.weekday().num_days_from_monday().into()),
+        );
+
+        let busiest = self.counts.values().copied().max().unwrap_or(0);
+        let mut lines = Vec::new();
+
+        let mut months = String::from("    ");
+        for week in 0..weeks {
+            let monday = first_monday + Duration::weeks(week);
+            let label = (0..7)
+                .map(|offset| monday + Duration::days(offset))
+                .find(|day| day.day() == 1)
+                .map(|day| day.format("%b").to_string());
+            let column = 4 + week as usize * 2;
+            if let Some(label) = label.filter(|_| months.len() <= column) {
+                months.extend(std::iter::repeat_n(' ', column - months.len()));
+                months.push_str(&label);
+            }
+        }
+        lines.push(Line::styled(months, theme.style_metadata_label()));
+
+        for weekday in 0..7 {
+            let label = match weekday {
+                0 => "Mon ",
+                2 => "Wed ",
+                4 => "Fri ",
+                _ => "    ",
+            };
+            let mut spans = vec![Span::styled(label, theme.style_metadata_label())];
+            for week in 0..weeks {
+                let day = first_monday + Duration::weeks(week) + Duration::days(weekday);
+                if day > self.today {
+                    spans.push(Span::raw("  "));
+                    continue;
+                }
+                let count = self.counts.get(&day).copied().unwrap_or(0);
+                let mut style = Style::default().fg(shade(theme, count, busiest));
+                if day == self.cursor {
+                    style = style.bg(theme.list_selected_bg);
+                }
+                spans.push(Span::styled(theme.glyphs.day(count, busiest), style));
+                spans.push(Span::raw(" "));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let count = self.counts.get(&self.cursor).copied().unwrap_or(0);
+        let total: u64 = self
+            .counts
+            .iter()
+            .filter(|(day, _)| **day >= first_monday)
+            .map(|(_, count)| count)
+            .sum();
+        lines.push(Line::raw(""));
+        lines.push(Line::from(vec![
+            Span::styled(
+                self.cursor.format("%a %Y-%m-%d").to_string(),
+                theme.style_metadata_value(),
+            ),
+            Span::styled(
+                format!("  {count} capture(s) · {total} in view"),
+                theme.style_metadata_label(),
+            ),
+        ]));
+        lines.push(Line::styled(
+            "h/l week · j/k day · Enter show that day · Esc close",
+            Style::default()
+                .fg(theme.metadata_label)
+                .add_modifier(Modifier::ITALIC),
+        ));
+
+        let calendar = Paragraph::new(lines).block(
+            Block::default()
+                .title(Span::styled(" Calendar ", theme.style_title()))
+                .borders(theme.glyphs.borders())
+                .border_style(theme.style_border_focused())
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(calendar, area);
+    }
+}
+
+/// Cell color for `count` captures, from a faint border tone up to full green.
+fn shade(theme: &Theme, count: u64, busiest: u64) -> Color {
+    if count == 0 || busiest == 0 {
+        return blend(theme.background, theme.border, 0.3);
+    }
+    let level = (count * LEVELS).div_ceil(busiest).clamp(1, LEVELS);
+    blend(theme.background, theme.code_icon, level as f32 / LEVELS as f32)
+}
+
+fn blend(from: Color, to: Color, amount: f32) -> Color {
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+        _ => to,
+    }
+}
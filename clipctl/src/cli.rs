@@ -0,0 +1,211 @@
+//! Command-line interface; without a subcommand clipctl opens the TUI.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::transform::Transform;
+
+#[derive(Debug, Parser)]
+#[command(name = "clipctl", version, about = "Terminal UI and CLI for rusty-clipboard")]
+pub struct Cli {
+    /// Run clipd inside clipctl with a throwaway history, for trying things out.
+    #[arg(long, global = true)]
+    pub embedded: bool,
+    /// Connect to the clipd on this pipe, and start clipd on it (CLIPMGR_PIPE).
+    #[arg(long, global = true, value_name = "NAME")]
+    pub pipe: Option<String>,
+    /// Use the profile's own clipd if it has a pipe of its own, starting clipd
+    /// in it (CLIPMGR_PROFILE).
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Browse the history of the clipd on another machine, through SSH
+    /// (CLIPMGR_REMOTE). clipctl must be installed there too.
+    #[arg(long, global = true, value_name = "USER@HOST", conflicts_with = "embedded")]
+    pub remote: Option<String>,
+    /// Override a config key for this run, over the files and the
+    /// environment, e.g. `--set ui.theme=dracula`; repeatable.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Read or change settings shared by clipd and clipctl.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print the history, newest first: one line per entry, or JSON.
+    List {
+        /// Print a JSON array of entries instead, for scripts.
+        #[arg(long)]
+        json: bool,
+        /// Print at most this many entries.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Print the entries a search finds, with the TUI's search syntax
+    /// (`kind:url`, `tag:work`, ...).
+    Search {
+        query: String,
+        /// Print a JSON array of entries instead, for scripts.
+        #[arg(long)]
+        json: bool,
+        /// Print at most this many entries.
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Put a text entry on the clipboard.
+    Copy { id: u64 },
+    /// Delete an entry from the history.
+    Delete { id: u64 },
+    /// Print an entry's full text (also used as the fzf preview command).
+    Get {
+        id: u64,
+        /// Write the raw content to a file instead: PNG for images, RTF as-is.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it already exists.
+        #[arg(long, requires = "output")]
+        force: bool,
+    },
+    /// Pick an entry with fzf and copy it to the clipboard.
+    Fzf {
+        /// Extra arguments passed through to fzf.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        fzf_args: Vec<String>,
+    },
+    /// Pick an entry in the TUI (or by id) and pipe it into a shell command.
+    Pick {
+        /// Command that receives the entry on stdin; defaults to `general.send_command`.
+        #[arg(long)]
+        exec: Option<String>,
+        /// Skip the picker and send this entry.
+        #[arg(long)]
+        id: Option<u64>,
+    },
+    /// List profiles side by side, or choose the one clipd captures into.
+    Profile {
+        #[command(subcommand)]
+        action: Option<ProfileAction>,
+    },
+    /// List or rotate the keys clipd seals exports with (protected by Windows DPAPI).
+    Keys {
+        #[command(subcommand)]
+        action: Option<KeysAction>,
+    },
+    /// Print the address and key a phone companion app pairs with
+    /// (`companion.enabled`).
+    Companion {
+        /// Make a new pairing key first; apps paired with the old one stop working.
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Print text from stdin, or an entry, with a transform applied.
+    Transform {
+        name: Transform,
+        /// Transform this entry instead of stdin.
+        #[arg(long)]
+        id: Option<u64>,
+    },
+    /// Type stdin, or an entry, into the window in front as keystrokes, for
+    /// remote desktops, VM consoles, and fields that refuse Ctrl+V.
+    Type {
+        /// Type this entry instead of stdin.
+        #[arg(long)]
+        id: Option<u64>,
+        /// Wait this long first, to bring the target window to the front.
+        #[arg(long, default_value_t = 3000)]
+        wait_ms: u64,
+    },
+    /// Store every format on the clipboard (text, HTML, spreadsheet data, ...) as one entry.
+    Snapshot,
+    /// Put a snapshot's formats back on the clipboard exactly as they were.
+    Restore { id: u64 },
+    /// List, take, or restore backups of the history database.
+    Backup {
+        #[command(subcommand)]
+        action: Option<BackupAction>,
+    },
+    /// Check the daemon, pipe, database, config, clipboard, and autostart.
+    Doctor,
+    /// Summarize recent clipboard habits as Markdown: top apps, busiest
+    /// hours, kinds, tags, and the biggest entries. Worked out locally.
+    Insights {
+        /// How many days back to look.
+        #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u32).range(1..))]
+        days: u32,
+        /// Write the report to this file instead of printing it.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Relay stdin and stdout to clipd; what `--remote` runs over SSH.
+    #[command(hide = true)]
+    Bridge,
+    /// Put stdin on the clipboard and keep it there; what pastes run on Linux.
+    #[command(hide = true)]
+    Hold {
+        /// Bytes of stdin that are the text; the rest is the entry's data.
+        #[arg(long)]
+        text_len: usize,
+        /// Kind of the entry the data is, such as `image` or `html`.
+        #[arg(long)]
+        kind: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupAction {
+    /// Show every backup, newest first (the default).
+    List,
+    /// Back the database up now.
+    Create,
+    /// Replace the history with a backup; the current history is backed up first.
+    Restore {
+        /// Backup file name, as `clipctl backup list` shows it.
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeysAction {
+    /// Show every key and which one is current (the default).
+    List,
+    /// Make a new current key; older keys still open what they sealed.
+    Rotate,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    /// Show every profile with its size and newest entry (the default).
+    List,
+    /// Capture into a profile until clipd restarts.
+    Use {
+        name: String,
+        /// Also make it the profile clipd starts in (`profiles.active`).
+        #[arg(long)]
+        save: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print one key, or every known key when none is given.
+    Get { key: Option<String> },
+    /// Print the effective configuration, with the machine file, the user
+    /// file, the environment, and `--set` flags merged.
+    Show {
+        /// Say where each value came from.
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Validate and write a key, then ask clipd to reload.
+    Set { key: String, value: String },
+    /// Open the config file in $VISUAL/$EDITOR (notepad by default).
+    Edit,
+    /// Print the location of the config file.
+    Path,
+}
@@ -0,0 +1,382 @@
+//! One-shot subcommands that talk to a running clipd.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::{self, ClientConfig};
+use crate::ipc::{Client, EntrySummary, RequestKind};
+use crate::paste::{self, PasteEngine, PasteMethod};
+use crate::transform::Transform;
+
+/// Connect to clipd and check that it speaks our protocol.
+pub async fn connect() -> Result<Client> {
+    let mut client = Client::connect().await?;
+    client.handshake().await?;
+    Ok(client)
+}
+
+pub async fn fetch(client: &mut Client, id: u64) -> Result<EntrySummary> {
+    client
+        .request(RequestKind::Get { id, payload: false })
+        .await?
+        .entries
+        .into_iter()
+        .next()
+        .with_context(|| format!("no entry with id {id}"))
+}
+
+/// Full text of a text or URL entry.
+pub async fn text(id: u64) -> Result<String> {
+    let mut client = connect().await?;
+    let entry = fetch(&mut client, id).await?;
+    if !matches!(entry.kind.as_str(), "text" | "url") {
+        bail!("entry {id} is {}, not text", entry.kind);
+    }
+    Ok(entry.preview)
+}
+
+/// `clipctl transform <name>`: print stdin, or entry `id`, transformed.
+pub async fn transform(transform: Transform, id: Option<u64>) -> Result<()> {
+    let text = match id {
+        Some(id) => text(id).await?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read stdin as UTF-8 text")?;
+            text
+        }
+    };
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(transform.apply(&text).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// `clipctl type`: type stdin, or entry `id`, into the window in front after
+/// `wait_ms`, one character every `general.type_delay_ms`.
+pub async fn type_text(id: Option<u64>, wait_ms: u64) -> Result<()> {
+    let text = match id {
+        Some(id) => text(id).await?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read stdin as UTF-8 text")?;
+            text
+        }
+    };
+    let delay = Duration::from_millis(ClientConfig::load()?.type_delay_ms);
+    tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+    tokio::task::spawn_blocking(move || paste::type_text(&text, delay)).await?
+}
+
+/// `clipctl get <id>`: write the entry's text to stdout, exactly as stored.
+pub async fn get(id: u64) -> Result<()> {
+    let mut client = connect().await?;
+    let entry = fetch(&mut client, id).await?;
+
+    let mut stdout = std::io::stdout().lock();
+    if matches!(entry.kind.as_str(), "text" | "url") {
+        stdout.write_all(entry.preview.as_bytes())?;
+    } else {
+        writeln!(stdout, "{} ({}, {})", entry.preview, entry.kind, entry.display_time())?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// The history, newest first, fetched a page at a time until there are
+/// `limit` entries or no more. Pinned entries come first and count too.
+pub async fn list_entries(client: &mut Client, limit: Option<usize>) -> Result<Vec<EntrySummary>> {
+    let mut entries = Vec::new();
+    let mut cursor = None;
+    loop {
+        let wanted = limit.map(|limit| u32::try_from(limit.saturating_sub(entries.len())).unwrap_or(u32::MAX));
+        let response = client.request(RequestKind::ListPage { cursor, limit: wanted }).await?;
+        entries.extend(response.entries);
+        cursor = response.next_cursor;
+        if cursor.is_none() || limit.is_some_and(|limit| entries.len() >= limit) {
+            return Ok(entries);
+        }
+    }
+}
+
+/// `clipctl list`: the history, newest first.
+pub async fn list(json: bool, limit: Option<usize>) -> Result<()> {
+    let mut client = connect().await?;
+    let entries = list_entries(&mut client, limit).await?;
+    print_entries(entries, json, limit)
+}
+
+/// `clipctl search <query>`: the entries clipd finds for `query`.
+pub async fn search(query: String, json: bool, limit: Option<usize>) -> Result<()> {
+    let mut client = connect().await?;
+    let entries = client.request(RequestKind::Search { query }).await?.entries;
+    print_entries(entries, json, limit)
+}
+
+/// One line per entry, `id<TAB>kind<TAB>time<TAB>text`, with the text on
+/// one line so `cut`, `ForEach-Object`, and fzf can split it; or a JSON
+/// array of whole entries.
+fn print_entries(mut entries: Vec<EntrySummary>, json: bool, limit: Option<usize>) -> Result<()> {
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+    let mut stdout = std::io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut stdout, &entries).context("failed to write entries as JSON")?;
+        writeln!(stdout)?;
+    } else {
+        for entry in &entries {
+            let line = entry.preview.split_whitespace().collect::<Vec<_>>().join(" ");
+            writeln!(stdout, "{}\t{}\t{}\t{line}", entry.id, entry.kind, entry.display_time())?;
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// `clipctl copy <id>`: put a text entry on the clipboard.
+pub async fn copy(id: u64) -> Result<()> {
+    let mut client = connect().await?;
+    copy_with(&mut client, id).await
+}
+
+/// Put text entry `id` on the clipboard, unless a `[paste]` rule blocks it
+/// for the app in front.
+pub async fn copy_with(client: &mut Client, id: u64) -> Result<()> {
+    let entry = fetch(client, id).await?;
+    if !matches!(entry.kind.as_str(), "text" | "url") {
+        bail!("only text entries can be copied from the command line (entry {id} is {})", entry.kind);
+    }
+
+    let hide_from_history = ClientConfig::load()?.hide_from_windows_history;
+    let paste = RequestKind::Paste {
+        id,
+        force: false,
+        plain: true,
+        hide_from_history,
+    };
+    let verdict = client.request(paste).await?.paste;
+    if let Some(reason) = verdict.and_then(|verdict| verdict.blocked) {
+        bail!("not copying entry {id}: {reason}; paste it from the clipctl TUI to override");
+    }
+    PasteEngine::new(PasteMethod::SendInput, hide_from_history).paste(&entry.preview, Some(id))?;
+    eprintln!("copied entry {id} to the clipboard");
+    if !entry.warnings.is_empty() {
+        eprintln!("warning: check before pasting: {}", entry.warnings.join(", "));
+    }
+    Ok(())
+}
+
+/// `clipctl delete <id>`: remove an entry from the history.
+pub async fn delete(id: u64) -> Result<()> {
+    let mut client = connect().await?;
+    // Fetched first, so a wrong id is an error rather than a silent no-op.
+    fetch(&mut client, id).await?;
+    client
+        .request(RequestKind::Delete { id })
+        .await
+        .with_context(|| format!("clipd could not delete entry {id}"))?;
+    eprintln!("deleted entry {id}");
+    Ok(())
+}
+
+/// Resolve `path` against our working directory; clipd writes files from its own.
+pub fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    let cwd = std::env::current_dir().context("failed to read the current directory")?;
+    Ok(cwd.join(path))
+}
+
+/// Whether `path` is a passphrase-protected export (an age file).
+pub fn is_encrypted_archive(path: &Path) -> bool {
+    let mut header = [0; 22];
+    std::fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+        .is_ok_and(|()| &header == b"age-encryption.org/v1\n")
+}
+
+/// `clipctl get <id> --output <path>`: have clipd write the entry's raw content.
+pub async fn save(id: u64, path: &Path, force: bool) -> Result<()> {
+    let path = absolute(path)?;
+    if path.exists() && !force {
+        bail!("{} already exists; pass --force to overwrite it", path.display());
+    }
+
+    let mut client = connect().await?;
+    client
+        .request(RequestKind::SaveEntry {
+            id,
+            path: path.display().to_string(),
+        })
+        .await
+        .with_context(|| format!("clipd could not save entry {id}; check the id and that the path is writable"))?;
+    eprintln!("saved entry {id} to {}", path.display());
+    Ok(())
+}
+
+/// `clipctl snapshot`: store the whole clipboard and print the new entry.
+pub async fn snapshot() -> Result<()> {
+    let mut client = connect().await?;
+    let entries = client
+        .request(RequestKind::Snapshot)
+        .await
+        .context("clipd could not snapshot the clipboard; is it empty or held open by another app?")?
+        .entries;
+    let entry = entries
+        .into_iter()
+        .filter(|entry| entry.kind == "snapshot")
+        .max_by_key(|entry| entry.id)
+        .context("clipd did not store a snapshot; is it up to date?")?;
+    println!("stored snapshot {}: {}", entry.id, entry.formats.join(", "));
+    Ok(())
+}
+
+/// `clipctl restore <id>`: put a snapshot's formats back on the clipboard.
+pub async fn restore(id: u64) -> Result<()> {
+    let mut client = connect().await?;
+    client
+        .request(RequestKind::Restore { id })
+        .await
+        .with_context(|| format!("clipd could not restore entry {id}; is it a snapshot?"))?;
+    println!("restored snapshot {id} to the clipboard");
+    Ok(())
+}
+
+/// `clipctl profile list`: one line per profile with its size and newest entry.
+pub async fn profiles() -> Result<()> {
+    let mut client = connect().await?;
+    let profiles = client
+        .request(RequestKind::Profiles)
+        .await?
+        .profiles
+        .context("clipd did not return profiles; is it up to date?")?;
+
+    let width = profiles.iter().map(|profile| profile.name.len()).max().unwrap_or(0);
+    for profile in profiles {
+        let marker = if profile.active { '*' } else { ' ' };
+        let latest: String = profile
+            .latest
+            .unwrap_or_default()
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .take(60)
+            .collect();
+        println!(
+            "{marker} {:<width$}  {:>6} entries  {latest}",
+            profile.name, profile.entries
+        );
+    }
+    Ok(())
+}
+
+/// `clipctl profile use <name>`: switch the profile clipd captures into.
+pub async fn use_profile(name: &str, save: bool) -> Result<()> {
+    let mut client = connect().await?;
+    client
+        .request(RequestKind::SwitchProfile {
+            name: name.to_string(),
+        })
+        .await
+        .with_context(|| format!("clipd could not switch to profile '{name}'; is it defined under [profiles]?"))?;
+    if save {
+        config::store("profiles.active", name)?;
+        println!("capturing into profile '{name}' (also saved as the startup profile)");
+    } else {
+        println!("capturing into profile '{name}' until clipd restarts");
+    }
+    Ok(())
+}
+
+/// `clipctl keys list`: the keys clipd seals exports with, current one marked.
+pub async fn keys() -> Result<()> {
+    print_keys(RequestKind::Keys).await
+}
+
+/// `clipctl keys rotate`: switch new exports to a fresh key.
+pub async fn rotate_key() -> Result<()> {
+    print_keys(RequestKind::RotateKey).await?;
+    println!("new exports are sealed with the current key; older ones still import");
+    Ok(())
+}
+
+async fn print_keys(request: RequestKind) -> Result<()> {
+    let mut client = connect().await?;
+    let keys = client
+        .request(request)
+        .await?
+        .keys
+        .context("clipd did not return keys; is it up to date?")?;
+    for key in keys {
+        let marker = if key.current { '*' } else { ' ' };
+        println!("{marker} key {:<4} created {}", key.id, key.created);
+    }
+    Ok(())
+}
+
+/// `clipctl companion`: what a phone app needs to pair with clipd.
+pub async fn companion(reset: bool) -> Result<()> {
+    let mut client = connect().await?;
+    let info = client
+        .request(RequestKind::Companion { reset })
+        .await?
+        .companion
+        .context("clipd did not return pairing details; is it up to date?")?;
+    match info.address {
+        Some(address) => println!("address  {address}"),
+        None => println!("address  none: set companion.enabled = true and restart clipd"),
+    }
+    println!("key      {}", info.key);
+    if reset {
+        println!("apps paired with the old key have to pair again");
+    }
+    Ok(())
+}
+
+/// `clipctl backup list`: backups of the history database, newest first.
+pub async fn backups() -> Result<()> {
+    print_backups(RequestKind::Backups).await
+}
+
+/// `clipctl backup create`: back the database up now.
+pub async fn create_backup() -> Result<()> {
+    print_backups(RequestKind::CreateBackup).await
+}
+
+/// `clipctl backup restore`: replace the history with a backup.
+pub async fn restore_backup(name: String) -> Result<()> {
+    print_backups(RequestKind::RestoreBackup { name: name.clone() }).await?;
+    println!("restored {name}; the history it replaced is the newest 'restore' backup");
+    Ok(())
+}
+
+async fn print_backups(request: RequestKind) -> Result<()> {
+    let mut client = connect().await?;
+    let backups = client
+        .request(request)
+        .await?
+        .backups
+        .context("clipd did not return backups; is it up to date?")?;
+    if backups.is_empty() {
+        println!("no backups yet");
+    }
+    for backup in backups {
+        println!(
+            "{:<32} {:<10} {:>8} KiB  {}",
+            backup.name,
+            backup.reason,
+            backup.bytes.div_ceil(1024),
+            backup.created
+        );
+    }
+    Ok(())
+}
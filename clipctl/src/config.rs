@@ -0,0 +1,824 @@
+//! Client configuration and the `clipctl config` subcommand.
+//!
+//! clipd and clipctl share one `config.toml`, layered over a machine-wide
+//! file and under the environment and `--set` flags (see `clipd::layers`);
+//! each side reads the keys it owns and ignores the rest.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use clipd::layers::{self, Origin, Sources};
+use directories::ProjectDirs;
+use serde::Deserialize;
+use toml_edit::DocumentMut;
+
+use crate::cli::ConfigAction;
+use crate::daemon::SpawnPolicy;
+use crate::keymap::KeyMap;
+use crate::paste::PasteMethod;
+use crate::ipc::{Client, RequestKind};
+
+const CONFIG_FILE: &str = "config.toml";
+const DEFAULT_PREVIEW_LENGTH: usize = 80;
+const DEFAULT_TYPE_DELAY_MS: u64 = 10;
+/// `config show --origin` lines origins up here unless a value runs past it.
+const SHOW_ORIGIN_COLUMN: usize = 60;
+/// Seed for `config edit` when no file exists yet, so every option is documented.
+const EXAMPLE_CONFIG: &str = include_str!("../../config/config.example.toml");
+
+pub fn config_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com", "rusty-clipboard", "clipmgr")
+        .context("failed to determine application directories")?;
+    Ok(dirs.config_dir().join(CONFIG_FILE))
+}
+
+/// Settings clipctl itself consumes.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub theme: String,
+    pub spawn_daemon: SpawnPolicy,
+    pub paste_method: PasteMethod,
+    /// Characters of each entry shown in the history list.
+    pub preview_length: usize,
+    /// Ask before actions that overwrite data, such as exporting over a file.
+    pub confirm_prompts: bool,
+    /// Show each entry's capture time in the history list.
+    pub show_timestamps: bool,
+    /// Hide entry content, showing only kinds and times (for screen sharing).
+    pub guest_mode: bool,
+    /// Show metric/imperial equivalents of quantities such as `5 mi` in the preview.
+    pub unit_conversions: bool,
+    /// Select the entry usually pasted into the app in front, or at this time
+    /// of day, when the TUI opens.
+    pub suggestions: bool,
+    /// ASCII tags instead of emoji, the high-contrast theme, and a layout
+    /// screen readers follow, whatever `theme` says.
+    pub accessible: bool,
+    /// Language of help, prompts, and status messages, such as "de"; empty
+    /// follows the Windows display language.
+    pub language: String,
+    /// Shell command pre-filled by "send to command" and used by `clipctl pick`.
+    pub send_command: Option<String>,
+    /// Mark pasted entries so Windows clipboard history (Win+V) skips them.
+    pub hide_from_windows_history: bool,
+    /// Pause between keystrokes when typing an entry instead of pasting it.
+    pub type_delay_ms: u64,
+    /// Saved searches shown as tabs, in file order.
+    pub views: Vec<View>,
+    /// Keys bound to palette commands in `[keys]`.
+    pub keys: KeyMap,
+    /// No config file exists yet; the TUI runs the onboarding wizard.
+    pub first_run: bool,
+}
+
+/// A named search from a `[[views]]` table.
+#[derive(Debug, Clone, Deserialize)]
+pub struct View {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FileConfig {
+    general: GeneralSection,
+    ui: UiSection,
+    views: Vec<View>,
+    keys: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GeneralSection {
+    spawn_daemon: Option<String>,
+    paste_behavior: Option<String>,
+    send_command: Option<String>,
+    hide_from_windows_history: Option<bool>,
+    type_delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct UiSection {
+    theme: Option<String>,
+    preview_length: Option<usize>,
+    confirm_prompts: Option<bool>,
+    show_timestamps: Option<bool>,
+    guest_mode: Option<bool>,
+    unit_conversions: Option<bool>,
+    suggestions: Option<bool>,
+    accessible: Option<bool>,
+    language: Option<String>,
+}
+
+impl ClientConfig {
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+        let first_run = !path.exists();
+        let layered = layers::gather(&Sources::system(path))?.check()?;
+        let file: FileConfig = toml::from_str(&layered.document().to_string())
+            .context("failed to read the merged configuration")?;
+
+        let spawn_daemon = file
+            .general
+            .spawn_daemon
+            .and_then(|raw| SpawnPolicy::parse(&raw))
+            .unwrap_or(SpawnPolicy::Ask);
+
+        Ok(Self {
+            theme: file.ui.theme.unwrap_or_else(|| "nord".to_string()),
+            spawn_daemon,
+            paste_method: file
+                .general
+                .paste_behavior
+                .and_then(|raw| PasteMethod::parse(&raw))
+                .unwrap_or(PasteMethod::SendInput),
+            preview_length: file.ui.preview_length.unwrap_or(DEFAULT_PREVIEW_LENGTH),
+            confirm_prompts: file.ui.confirm_prompts.unwrap_or(true),
+            show_timestamps: file.ui.show_timestamps.unwrap_or(true),
+            guest_mode: file.ui.guest_mode.unwrap_or(false),
+            unit_conversions: file.ui.unit_conversions.unwrap_or(true),
+            suggestions: file.ui.suggestions.unwrap_or(true),
+            accessible: file.ui.accessible.unwrap_or(false),
+            language: file.ui.language.unwrap_or_default(),
+            send_command: file.general.send_command.filter(|command| !command.trim().is_empty()),
+            hide_from_windows_history: file.general.hide_from_windows_history.unwrap_or(false),
+            type_delay_ms: file.general.type_delay_ms.unwrap_or(DEFAULT_TYPE_DELAY_MS),
+            views: file.views,
+            keys: KeyMap::parse(&file.keys).0,
+            first_run,
+        })
+    }
+}
+
+/// Which process has to pick up a changed key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    Daemon,
+    Client,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+    Integer { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+    /// Comma-separated subset of the given names, stored as a TOML array.
+    List(&'static [&'static str]),
+    /// Comma-separated free-form names, such as executables, stored as a TOML array.
+    Names,
+    Bool,
+    PipeName,
+    /// Free-form string, such as a shell command.
+    Text,
+}
+
+/// A key `clipctl config set` knows how to validate.
+struct KeySpec {
+    key: &'static str,
+    kind: ValueKind,
+    owner: Owner,
+    default: &'static str,
+    help: &'static str,
+}
+
+const KEYS: &[KeySpec] = &[
+    KeySpec {
+        key: "general.max_entries",
+        kind: ValueKind::Integer { min: 1, max: 1_000_000 },
+        owner: Owner::Daemon,
+        default: "10000",
+        help: "maximum number of entries retained in the database",
+    },
+    KeySpec {
+        key: "general.max_db_bytes",
+        kind: ValueKind::Integer { min: 0, max: i64::MAX },
+        owner: Owner::Daemon,
+        default: "0",
+        help: "bytes of entry content kept before entries are evicted (0 disables)",
+    },
+    KeySpec {
+        key: "general.eviction",
+        kind: ValueKind::Choice(&["oldest", "largest"]),
+        owner: Owner::Daemon,
+        default: "oldest",
+        help: "which unpinned entries go first when over max_db_bytes",
+    },
+    KeySpec {
+        key: "general.archive",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "false",
+        help: "move entries retention drops into a compressed archive (search in:archive) instead of deleting them",
+    },
+    KeySpec {
+        key: "general.duplicates",
+        kind: ValueKind::Choice(&["bump", "skip"]),
+        owner: Owner::Daemon,
+        default: "bump",
+        help: "move an entry copied again to the top, or leave it where it was",
+    },
+    KeySpec {
+        key: "general.sort_locale",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "\"\"",
+        help: "locale sort:title and sort:tag order by, e.g. \"sv\" or \"de\"; empty follows the system",
+    },
+    KeySpec {
+        key: "general.backups",
+        kind: ValueKind::Integer { min: 0, max: 100 },
+        owner: Owner::Daemon,
+        default: "5",
+        help: "database backups kept from before imports, migrations, bulk deletes, and restores (0 disables)",
+    },
+    KeySpec {
+        key: "general.pipe_name",
+        kind: ValueKind::PipeName,
+        owner: Owner::Daemon,
+        default: if cfg!(windows) { r"\\.\pipe\clipmgr" } else { "clipmgr.sock in the data directory" },
+        help: "named pipe clipd listens on, or its socket's path on Linux and macOS (restart clipd after changing)",
+    },
+    KeySpec {
+        key: "general.spawn_daemon",
+        kind: ValueKind::Choice(&["auto", "ask", "never"]),
+        owner: Owner::Client,
+        default: "ask",
+        help: "whether clipctl starts clipd when it is not running",
+    },
+    KeySpec {
+        key: "general.paste_behavior",
+        kind: ValueKind::Choice(&["send_input", "stdout", "type", "pane"]),
+        owner: Owner::Client,
+        default: "send_input",
+        help: "how clipctl hands over the selected entry; type sends it as keystrokes, pane into a tmux or WezTerm pane",
+    },
+    KeySpec {
+        key: "general.send_command",
+        kind: ValueKind::Text,
+        owner: Owner::Client,
+        default: "\"\"",
+        help: "shell command that \"send to command\" pipes the entry into",
+    },
+    KeySpec {
+        key: "general.hide_from_windows_history",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "false",
+        help: "keep entries pasted from clipctl out of Windows clipboard history (Win+V)",
+    },
+    KeySpec {
+        key: "general.type_delay_ms",
+        kind: ValueKind::Integer { min: 0, max: 1000 },
+        owner: Owner::Client,
+        default: "10",
+        help: "pause between keystrokes when paste_behavior is type; raise it for slow remote sessions",
+    },
+    KeySpec {
+        key: "capture.allowed_kinds",
+        kind: ValueKind::List(&["text", "url", "rtf", "html", "image", "files"]),
+        owner: Owner::Daemon,
+        default: "text,url,rtf,image,files",
+        help: "clipboard formats clipd records; html is off unless listed",
+    },
+    KeySpec {
+        key: "capture.project_tags",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "true",
+        help: "tag entries with project:<name> from the window they were copied in",
+    },
+    KeySpec {
+        key: "capture.group_window_secs",
+        kind: ValueKind::Integer { min: 0, max: 3600 },
+        owner: Owner::Daemon,
+        default: "10",
+        help: "link copies from one app this many seconds apart as copied together; 0 turns it off",
+    },
+    KeySpec {
+        key: "capture.quarantine",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "false",
+        help: "hold back copies from processes not in capture.trusted_processes",
+    },
+    KeySpec {
+        key: "capture.trusted_processes",
+        kind: ValueKind::Names,
+        owner: Owner::Daemon,
+        default: "",
+        help: "executables whose copies skip quarantine, e.g. code.exe",
+    },
+    KeySpec {
+        key: "capture.backpressure",
+        kind: ValueKind::Choice(&["block", "drop_oldest", "spill"]),
+        owner: Owner::Daemon,
+        default: "block",
+        help: "what a copy does while clipd's capture queue is full (restart clipd after changing)",
+    },
+    KeySpec {
+        key: "capture.max_entry_mb",
+        kind: ValueKind::Integer { min: 1, max: 4096 },
+        owner: Owner::Daemon,
+        default: "64",
+        help: "copies larger than this many megabytes are not captured",
+    },
+    KeySpec {
+        key: "capture.memory_budget_mb",
+        kind: ValueKind::Integer { min: 1, max: 16384 },
+        owner: Owner::Daemon,
+        default: "256",
+        help: "megabytes of captures clipd holds while the database is busy (restart clipd after changing)",
+    },
+    KeySpec {
+        key: "capture.poll_interval_ms",
+        kind: ValueKind::Integer { min: 50, max: 5000 },
+        owner: Owner::Daemon,
+        default: "250",
+        help: "how often clipd checks the clipboard while you are active",
+    },
+    KeySpec {
+        key: "capture.idle_poll_interval_ms",
+        kind: ValueKind::Integer { min: 50, max: 60000 },
+        owner: Owner::Daemon,
+        default: "2000",
+        help: "slowest clipd checks the clipboard when idle, and how often while the workstation is locked",
+    },
+    KeySpec {
+        key: "ignore.processes",
+        kind: ValueKind::Names,
+        owner: Owner::Daemon,
+        default: "KeePass*.exe,Bitwarden.exe,1Password.exe,Dashlane*.exe,Enpass.exe,RoboForm.exe",
+        help: "executables whose copies are never captured, with * and ? wildcards",
+    },
+    KeySpec {
+        key: "ignore.cards",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "true",
+        help: "leave text containing a card number out of the history",
+    },
+    KeySpec {
+        key: "ignore.pause_minutes",
+        kind: ValueKind::Integer { min: 1, max: 1440 },
+        owner: Owner::Daemon,
+        default: "15",
+        help: "how long :pause stops capture",
+    },
+    KeySpec {
+        key: "issues.github_repo",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "\"\"",
+        help: "owner/repo whose issue a bare #456 is, for its title",
+    },
+    KeySpec {
+        key: "issues.jira_url",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "\"\"",
+        help: "Jira site issue titles are looked up on, e.g. https://example.atlassian.net",
+    },
+    KeySpec {
+        key: "issues.jira_projects",
+        kind: ValueKind::Names,
+        owner: Owner::Daemon,
+        default: "",
+        help: "Jira project keys whose issue titles are looked up; all when empty",
+    },
+    KeySpec {
+        key: "battery.low_percent",
+        kind: ValueKind::Integer { min: 0, max: 100 },
+        owner: Owner::Daemon,
+        default: "20",
+        help: "battery charge at or below which clipd polls slowly to save power; 0 turns it off",
+    },
+    KeySpec {
+        key: "battery.skip_images",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "false",
+        help: "leave images out of the history while the battery is low",
+    },
+    KeySpec {
+        key: "export.redact",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "false",
+        help: "mask passwords, keys, and sensitive-tagged entries in every export",
+    },
+    KeySpec {
+        key: "companion.enabled",
+        kind: ValueKind::Bool,
+        owner: Owner::Daemon,
+        default: "false",
+        help: "let a paired phone app push text and fetch entries over the LAN (restart clipd after changing)",
+    },
+    KeySpec {
+        key: "companion.port",
+        kind: ValueKind::Integer { min: 1, max: 65535 },
+        owner: Owner::Daemon,
+        default: "47800",
+        help: "TCP port the companion endpoint listens on (restart clipd after changing)",
+    },
+    KeySpec {
+        key: "ui.theme",
+        kind: ValueKind::Choice(&["nord", "dracula", "tokyo-night", "gruvbox", "high-contrast"]),
+        owner: Owner::Client,
+        default: "nord",
+        help: "color theme for the TUI",
+    },
+    KeySpec {
+        key: "ui.preview_length",
+        kind: ValueKind::Integer { min: 20, max: 500 },
+        owner: Owner::Client,
+        default: "80",
+        help: "characters of each entry shown in the history list",
+    },
+    KeySpec {
+        key: "ui.confirm_prompts",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "true",
+        help: "ask before overwriting files on export",
+    },
+    KeySpec {
+        key: "ui.show_timestamps",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "true",
+        help: "show capture times in the history list",
+    },
+    KeySpec {
+        key: "ui.guest_mode",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "false",
+        help: "start with entry content hidden, for screen sharing (toggle with H)",
+    },
+    KeySpec {
+        key: "ui.unit_conversions",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "true",
+        help: "show metric/imperial equivalents of quantities like \"5 mi\" in the preview",
+    },
+    KeySpec {
+        key: "ui.suggestions",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "true",
+        help: "on opening, select the entry usually pasted into the app in front or at this time",
+    },
+    KeySpec {
+        key: "ui.accessible",
+        kind: ValueKind::Bool,
+        owner: Owner::Client,
+        default: "false",
+        help: "ASCII tags instead of emoji, high-contrast colors, and stacked panes for screen readers",
+    },
+    KeySpec {
+        key: "ui.language",
+        kind: ValueKind::Text,
+        owner: Owner::Client,
+        default: "\"\"",
+        help: "language of help, prompts, and status messages, e.g. \"de\"; empty follows the system",
+    },
+    KeySpec {
+        key: "time.format",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "%Y-%m-%d %H:%M",
+        help: "strftime-style format for timestamps in clipctl and exports",
+    },
+    KeySpec {
+        key: "time.timezone",
+        kind: ValueKind::Choice(&["local", "utc"]),
+        owner: Owner::Daemon,
+        default: "local",
+        help: "show times in the local timezone or in UTC",
+    },
+    KeySpec {
+        key: "time.locale",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "\"\"",
+        help: "locale for month and day names, e.g. de_DE (system locale when empty)",
+    },
+    KeySpec {
+        key: "profiles.active",
+        kind: ValueKind::Text,
+        owner: Owner::Daemon,
+        default: "default",
+        help: "profile clipd captures into when it starts",
+    },
+];
+
+fn find_key(key: &str) -> Result<&'static KeySpec> {
+    KEYS.iter().find(|spec| spec.key == key).with_context(|| {
+        let known: Vec<_> = KEYS.iter().map(|spec| spec.key).collect();
+        format!("unknown config key '{key}' (known keys: {})", known.join(", "))
+    })
+}
+
+impl KeySpec {
+    /// Parse a command-line value into a typed TOML value.
+    fn parse(&self, raw: &str) -> Result<toml_edit::Value> {
+        match self.kind {
+            ValueKind::Integer { min, max } => {
+                let value: i64 = raw
+                    .parse()
+                    .with_context(|| format!("{} expects an integer, got '{raw}'", self.key))?;
+                if !(min..=max).contains(&value) {
+                    bail!("{} must be between {min} and {max}, got {value}", self.key);
+                }
+                Ok(value.into())
+            }
+            ValueKind::Choice(choices) => {
+                if !choices.contains(&raw) {
+                    bail!(
+                        "{} must be one of: {}, got '{raw}'",
+                        self.key,
+                        choices.join(", ")
+                    );
+                }
+                Ok(raw.into())
+            }
+            ValueKind::List(choices) => {
+                let mut items = toml_edit::Array::new();
+                for item in raw.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+                    if !choices.contains(&item) {
+                        bail!(
+                            "{} entries must be among: {}, got '{item}'",
+                            self.key,
+                            choices.join(", ")
+                        );
+                    }
+                    items.push(item);
+                }
+                Ok(items.into())
+            }
+            ValueKind::Names => Ok(raw
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect::<toml_edit::Array>()
+                .into()),
+            ValueKind::Bool => match raw {
+                "true" | "on" | "yes" => Ok(true.into()),
+                "false" | "off" | "no" => Ok(false.into()),
+                _ => bail!("{} expects true or false, got '{raw}'", self.key),
+            },
+            ValueKind::PipeName => {
+                if !cfg!(windows) {
+                    if !Path::new(raw).is_absolute() {
+                        bail!("{} must be the absolute path of a socket, got '{raw}'", self.key);
+                    }
+                } else if !raw.starts_with(r"\\.\pipe\") {
+                    bail!(r"{} must start with \\.\pipe\, got '{raw}'", self.key);
+                }
+                Ok(raw.into())
+            }
+            ValueKind::Text => Ok(raw.into()),
+        }
+    }
+}
+
+/// Parse the file and collect every problem, each with its line.
+pub fn validate(raw: &str) -> Vec<String> {
+    let mut problems: Vec<String> = clipd::schema::check(raw).iter().map(ToString::to_string).collect();
+    // Only clipctl knows which key names in `[keys]` are keys.
+    if let Ok(file) = toml::from_str::<FileConfig>(raw) {
+        problems.extend(KeyMap::parse(&file.keys).1);
+    }
+    problems
+}
+
+fn read_document(path: &Path) -> Result<DocumentMut> {
+    if !path.exists() {
+        return Ok(DocumentMut::new());
+    }
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read config file: {}", path.display()))?;
+    raw.parse()
+        .with_context(|| format!("failed to parse config file: {}", path.display()))
+}
+
+fn write_document(path: &Path, doc: &DocumentMut) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create config directory: {}", parent.display()))?;
+    }
+    fs::write(path, doc.to_string())
+        .with_context(|| format!("failed to write config file: {}", path.display()))
+}
+
+/// Validate `raw` for `key` and store it in `doc`, creating the section if needed.
+fn set_key(doc: &mut DocumentMut, key: &str, raw: &str) -> Result<&'static KeySpec> {
+    let spec = find_key(key)?;
+    let value = spec.parse(raw)?;
+
+    let (section, name) = spec.key.split_once('.').expect("keys are dotted");
+    if !doc.contains_key(section) {
+        doc[section] = toml_edit::table();
+    }
+    doc[section][name] = toml_edit::Item::Value(value);
+    Ok(spec)
+}
+
+/// Create the config file from the documented template with `values` applied.
+pub fn create(values: &[(&str, String)]) -> Result<PathBuf> {
+    let path = config_path()?;
+    let mut doc: DocumentMut = EXAMPLE_CONFIG
+        .parse()
+        .context("bundled example config is invalid")?;
+    for (key, raw) in values {
+        set_key(&mut doc, key, raw)?;
+    }
+    write_document(&path, &doc)?;
+    Ok(path)
+}
+
+/// Flip "image" in `capture.allowed_kinds`; returns whether images are now captured.
+pub fn toggle_image_capture() -> Result<bool> {
+    let path = config_path()?;
+    let mut doc = read_document(&path)?;
+
+    let current: Vec<String> = match doc
+        .get("capture")
+        .and_then(|capture| capture.get("allowed_kinds"))
+        .and_then(|item| item.as_array())
+    {
+        Some(kinds) => kinds.iter().filter_map(|kind| kind.as_str().map(str::to_string)).collect(),
+        // An absent key means clipd captures its default kinds.
+        None => ["text", "url", "rtf", "image", "files"].map(String::from).to_vec(),
+    };
+
+    let enable = !current.iter().any(|kind| kind == "image");
+    let mut kinds: Vec<String> = current.into_iter().filter(|kind| kind != "image").collect();
+    if enable {
+        kinds.push("image".to_string());
+    }
+
+    set_key(&mut doc, "capture.allowed_kinds", &kinds.join(","))?;
+    write_document(&path, &doc)?;
+    Ok(enable)
+}
+
+/// Add `process` to `capture.trusted_processes`; returns false if it was already there.
+pub fn trust_process(process: &str) -> Result<bool> {
+    let path = config_path()?;
+    let mut doc = read_document(&path)?;
+
+    let mut trusted: Vec<String> = doc
+        .get("capture")
+        .and_then(|capture| capture.get("trusted_processes"))
+        .and_then(|item| item.as_array())
+        .map(|names| names.iter().filter_map(|name| name.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    if trusted.iter().any(|name| name.eq_ignore_ascii_case(process)) {
+        return Ok(false);
+    }
+    trusted.push(process.to_string());
+
+    set_key(&mut doc, "capture.trusted_processes", &trusted.join(","))?;
+    write_document(&path, &doc)?;
+    Ok(true)
+}
+
+/// Persist one key from inside the TUI.
+pub fn store(key: &str, raw: &str) -> Result<()> {
+    let path = config_path()?;
+    let mut doc = read_document(&path)?;
+    set_key(&mut doc, key, raw)?;
+    write_document(&path, &doc)
+}
+
+pub async fn run(action: ConfigAction) -> Result<()> {
+    let path = config_path()?;
+
+    match action {
+        ConfigAction::Path => println!("{}", path.display()),
+        ConfigAction::Get { key: None } => {
+            for spec in KEYS {
+                print_key(&path, spec)?;
+            }
+        }
+        ConfigAction::Get { key: Some(key) } => print_key(&path, find_key(&key)?)?,
+        ConfigAction::Show { origin } => show(&path, origin)?,
+        ConfigAction::Set { key, value } => {
+            let mut doc = read_document(&path)?;
+            let spec = set_key(&mut doc, &key, &value)?;
+            write_document(&path, &doc)?;
+
+            let (section, name) = spec.key.split_once('.').expect("keys are dotted");
+            println!("{} = {}", spec.key, doc[section][name]);
+
+            if spec.owner == Owner::Daemon {
+                reload_daemon().await;
+            }
+        }
+        ConfigAction::Edit => {
+            if !path.exists() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, EXAMPLE_CONFIG)
+                    .with_context(|| format!("failed to create {}", path.display()))?;
+            }
+
+            open_editor(&path)?;
+
+            let raw = fs::read_to_string(&path)?;
+            let problems = validate(&raw);
+            if !problems.is_empty() {
+                for problem in &problems {
+                    eprintln!("error: {problem}");
+                }
+                bail!("{} has {} problem(s); daemon not reloaded", path.display(), problems.len());
+            }
+            reload_daemon().await;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_key(path: &Path, spec: &KeySpec) -> Result<()> {
+    let doc = read_document(path)?;
+    let (section, name) = spec.key.split_once('.').expect("keys are dotted");
+    match doc.get(section).and_then(|table| table.get(name)) {
+        Some(item) => println!("{} = {}", spec.key, item.to_string().trim()),
+        None => println!("{} = {}  # default: {}", spec.key, spec.default, spec.help),
+    }
+    Ok(())
+}
+
+/// `config show`: every value in effect, set somewhere or left at its
+/// default, in key order; with `origin`, the layer each came from.
+fn show(path: &Path, origin: bool) -> Result<()> {
+    let layered = layers::gather(&Sources::system(path.to_path_buf()))?;
+    for (source, problem) in &layered.problems {
+        eprintln!("ignored {source}: {problem}");
+    }
+
+    let mut values: BTreeMap<String, (String, String)> = KEYS
+        .iter()
+        .map(|spec| (spec.key.to_string(), (spec.default.to_string(), Origin::Default.to_string())))
+        .collect();
+    for (key, value, from) in layered.values() {
+        // Tokens stay out of terminals and screenshots.
+        let value = if key.ends_with("_token") { "\"********\"".to_string() } else { value.to_string() };
+        values.insert(key, (value, from.to_string()));
+    }
+
+    let width = values
+        .iter()
+        .map(|(key, (value, _))| key.len() + value.chars().count() + 3)
+        .max()
+        .unwrap_or(0)
+        .min(SHOW_ORIGIN_COLUMN);
+    for (key, (value, from)) in values {
+        let line = format!("{key} = {value}");
+        if origin {
+            println!("{line:<width$}  # {from}");
+        } else {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+fn open_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad".into() } else { "vi".into() });
+
+    let status = Command::new(&editor)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to launch editor '{editor}'"))?;
+    if !status.success() {
+        bail!("editor '{editor}' exited with {status}");
+    }
+    Ok(())
+}
+
+/// Ask a running daemon to re-read the config; a stopped one picks it up on start.
+async fn reload_daemon() {
+    let result = async {
+        let mut client = Client::connect().await?;
+        client.handshake().await?;
+        client.request(RequestKind::ReloadConfig).await?;
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => println!("clipd reloaded its configuration"),
+        Err(err) => println!("clipd not reloaded ({err:#}); changes apply on next start"),
+    }
+}
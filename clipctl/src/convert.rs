@@ -0,0 +1,172 @@
+//! Conversions of short entries that are a number: timestamps, other bases,
+//! byte sizes, and metric/imperial quantities.
+
+use chrono::{Local, TimeZone};
+
+/// Longer entries are prose or code, not a value to convert.
+const MAX_LEN: usize = 40;
+
+/// Unix seconds read as a timestamp: 2000-01-01 through 2100-01-01. Outside
+/// that, a number is more likely a count or an id.
+const TIMESTAMPS: std::ops::RangeInclusive<i64> = 946_684_800..=4_102_444_800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    /// Unix seconds or milliseconds as local time.
+    LocalTime,
+    /// A `0x` or `0b` number in decimal.
+    Decimal,
+    /// A decimal number in hex.
+    Hex,
+    /// A byte count in KiB, MiB...
+    Size,
+    /// A size such as `1.5 GB` in bytes.
+    Bytes,
+    /// A quantity such as `5 mi` in the other measurement system.
+    Unit,
+}
+
+impl Conversion {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::LocalTime => "Local time",
+            Self::Decimal => "Decimal",
+            Self::Hex => "Hex",
+            Self::Size => "Size",
+            Self::Bytes => "Bytes",
+            Self::Unit => "Converted",
+        }
+    }
+}
+
+/// Units recognized after a number, and the unit and factor each converts to.
+/// Names are matched case-insensitively.
+const UNITS: &[(&[&str], &str, f64)] = &[
+    (&["mi", "mile", "miles"], "km", 1.609_344),
+    (&["km", "kilometer", "kilometers", "kilometre", "kilometres"], "mi", 1.0 / 1.609_344),
+    (&["ft", "foot", "feet"], "m", 0.3048),
+    (&["m", "meter", "meters", "metre", "metres"], "ft", 1.0 / 0.3048),
+    (&["in", "inch", "inches"], "cm", 2.54),
+    (&["cm"], "in", 1.0 / 2.54),
+    (&["lb", "lbs", "pound", "pounds"], "kg", 0.453_592_37),
+    (&["kg", "kilogram", "kilograms"], "lb", 1.0 / 0.453_592_37),
+    (&["oz", "ounce", "ounces"], "g", 28.349_523_125),
+    (&["g", "gram", "grams"], "oz", 1.0 / 28.349_523_125),
+    (&["gal", "gallon", "gallons"], "L", 3.785_411_784),
+    (&["l", "liter", "liters", "litre", "litres"], "gal", 1.0 / 3.785_411_784),
+    (&["mph"], "km/h", 1.609_344),
+    (&["km/h", "kmh", "kph"], "mph", 1.0 / 1.609_344),
+];
+
+/// Every conversion that applies to `text`, in a fixed order; `units` adds
+/// metric/imperial equivalents.
+pub fn conversions(text: &str, units: bool) -> Vec<(Conversion, String)> {
+    let text = text.trim();
+    let mut found = Vec::new();
+    if text.is_empty() || text.len() > MAX_LEN {
+        return found;
+    }
+
+    if let Some(value) = parse_prefixed(text) {
+        found.push((Conversion::Decimal, value.to_string()));
+    }
+    if text.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(value) = text.parse::<u64>() {
+            if let Some(time) = timestamp(text, value) {
+                found.push((Conversion::LocalTime, time));
+            }
+            found.push((Conversion::Hex, format!("0x{value:x}")));
+            if value >= 1024 {
+                found.push((Conversion::Size, human_size(value)));
+            }
+        }
+    }
+    if let Some(bytes) = parse_size(text) {
+        found.push((Conversion::Bytes, bytes.to_string()));
+    }
+    if units {
+        if let Some(converted) = convert_quantity(text) {
+            found.push((Conversion::Unit, converted));
+        }
+    }
+    found
+}
+
+/// `5 mi` as `8.05 km`, `72 °F` as `22.2 °C`.
+fn convert_quantity(text: &str) -> Option<String> {
+    let split = text.find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '-')))?;
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.replace(',', "").parse().ok()?;
+    let unit = unit.trim().trim_start_matches('°').to_lowercase();
+
+    match unit.as_str() {
+        "f" | "fahrenheit" => return Some(format!("{} °C", round((value - 32.0) / 1.8))),
+        "c" | "celsius" => return Some(format!("{} °F", round(value * 1.8 + 32.0))),
+        _ => {}
+    }
+    let &(_, target, factor) = UNITS.iter().find(|(names, _, _)| names.contains(&unit.as_str()))?;
+    Some(format!("{} {target}", round(value * factor)))
+}
+
+/// Two decimals at most, without trailing zeros.
+fn round(value: f64) -> String {
+    let text = format!("{value:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// `0x1f`, `0b1010`.
+fn parse_prefixed(text: &str) -> Option<u64> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        return u64::from_str_radix(&hex.replace('_', ""), 16).ok();
+    }
+    if let Some(binary) = lower.strip_prefix("0b") {
+        return u64::from_str_radix(&binary.replace('_', ""), 2).ok();
+    }
+    None
+}
+
+/// Ten digits are seconds, thirteen are milliseconds.
+fn timestamp(digits: &str, value: u64) -> Option<String> {
+    let millis = match digits.len() {
+        10 => i64::try_from(value).ok()?.checked_mul(1000)?,
+        13 => i64::try_from(value).ok()?,
+        _ => return None,
+    };
+    if !TIMESTAMPS.contains(&(millis / 1000)) {
+        return None;
+    }
+    let time = Local.timestamp_millis_opt(millis).single()?;
+    Some(time.format("%Y-%m-%d %H:%M:%S %:z").to_string())
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.2} {}", UNITS[unit])
+}
+
+/// `1.5 GB` (powers of 1000), `512KiB` (powers of 1024), `300 bytes`.
+fn parse_size(text: &str) -> Option<u64> {
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "b" | "byte" | "bytes" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "kib" => 1024,
+        "mib" => 1024_u64.pow(2),
+        "gib" => 1024_u64.pow(3),
+        "tib" => 1024_u64.pow(4),
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
@@ -0,0 +1,116 @@
+//! Locating and launching clipd when the pipe is not available.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::ipc::Client;
+
+/// How long to wait for a freshly spawned daemon to create its pipe.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Per-user registry key whose values Windows runs at sign-in.
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+const RUN_VALUE: &str = "rusty-clipboard";
+
+/// What to do when clipd is not running (`general.spawn_daemon`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPolicy {
+    /// Start clipd without asking.
+    Auto,
+    /// Ask before starting clipd (default).
+    Ask,
+    /// Never start clipd; fail or fall back to the offline cache.
+    Never,
+}
+
+impl SpawnPolicy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "auto" => Some(Self::Auto),
+            "ask" => Some(Self::Ask),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Find `clipd` next to the running `clipctl` executable.
+pub fn locate_clipd() -> Result<PathBuf> {
+    let exe = env::current_exe().context("failed to resolve clipctl executable path")?;
+    let clipd = exe.with_file_name(format!("clipd{}", env::consts::EXE_SUFFIX));
+    if !clipd.exists() {
+        bail!("clipd not found next to clipctl (looked for {})", clipd.display());
+    }
+    Ok(clipd)
+}
+
+/// Start clipd at sign-in through the `Run` registry key.
+///
+/// clipd is a console program, so it goes through a headless conhost to avoid
+/// leaving an empty window open after login.
+pub fn enable_autostart(clipd: &Path) -> Result<()> {
+    let command = format!(r#"conhost.exe --headless "{}""#, clipd.display());
+    let status = Command::new("reg")
+        .args(["add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d", &command, "/f"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("failed to run reg.exe")?;
+    if !status.success() {
+        bail!("reg.exe could not write {RUN_KEY}\\{RUN_VALUE}");
+    }
+    Ok(())
+}
+
+/// Whether [`enable_autostart`] has registered clipd.
+pub fn autostart_registered() -> bool {
+    Command::new("reg")
+        .args(["query", RUN_KEY, "/v", RUN_VALUE])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Launch clipd detached from this console and wait for its pipe.
+pub async fn spawn_and_connect(clipd: &PathBuf) -> Result<Client> {
+    let mut command = Command::new(clipd);
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        // A separate process group keeps Ctrl+C in this terminal from killing the daemon.
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+    }
+
+    command
+        .spawn()
+        .with_context(|| format!("failed to launch {}", clipd.display()))?;
+    tracing::info!(path = %clipd.display(), "spawned clipd, waiting for pipe");
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        match Client::connect().await {
+            Ok(client) => return Ok(client),
+            Err(err) if Instant::now() >= deadline => {
+                return Err(err.context(format!(
+                    "clipd was started but its pipe did not appear within {}s",
+                    STARTUP_TIMEOUT.as_secs()
+                )));
+            }
+            Err(_) => sleep(POLL_INTERVAL).await,
+        }
+    }
+}
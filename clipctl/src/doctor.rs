@@ -0,0 +1,409 @@
+//! `clipctl doctor`: checks the usual reasons "it doesn't work" and prints fixes.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clipd::layers::{self, Origin, Sources};
+use directories::{ProjectDirs, UserDirs};
+
+use crate::config;
+use crate::daemon;
+use crate::ipc::{self, BlobStats, Client, QueueStats, RequestKind};
+
+/// First bytes of every SQLite 3 database file.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+/// Marker install.ps1 writes around its PowerShell profile block.
+const PROFILE_MARKER: &str = "# >>> rusty-clipboard integration (managed)";
+/// `ERROR_PIPE_BUSY`: every pipe instance is taken by other clients.
+const ERROR_PIPE_BUSY: i32 = 231;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Skip,
+}
+
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Skip, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn print(&self) {
+        let label = match self.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+            Status::Skip => "skip",
+        };
+        println!("[{label:>4}] {:<10} {}", self.name, self.detail);
+        if let Some(fix) = &self.fix {
+            println!("{:17} fix: {fix}", "");
+        }
+    }
+}
+
+pub async fn run() -> Result<()> {
+    let mut checks = vec![check_config(), check_layers(), check_database_file()];
+    checks.extend(check_daemon().await);
+    checks.push(check_clipboard());
+    checks.push(check_autostart());
+
+    for check in &checks {
+        check.print();
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
+fn check_config() -> Check {
+    let path = match config::config_path() {
+        Ok(path) => path,
+        Err(err) => return Check::fail("config", format!("{err:#}"), "set %APPDATA% for this user"),
+    };
+    if !path.exists() {
+        return Check::ok("config", format!("no {} yet; using defaults", path.display()));
+    }
+
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            return Check::fail(
+                "config",
+                format!("cannot read {}: {err}", path.display()),
+                "check the file's permissions",
+            )
+        }
+    };
+    let problems = config::validate(&raw);
+    if problems.is_empty() {
+        Check::ok("config", format!("{} is valid", path.display()))
+    } else {
+        Check::fail(
+            "config",
+            format!("{}: {}", path.display(), problems.join("; ")),
+            "correct the file with `clipctl config edit`",
+        )
+    }
+}
+
+/// The layers over the user's file: the machine-wide file, `CLIPMGR_*`
+/// variables, and `--set` flags.
+fn check_layers() -> Check {
+    let path = match config::config_path() {
+        Ok(path) => path,
+        Err(err) => return Check::skip("layers", format!("{err:#}")),
+    };
+    let user = path.display().to_string();
+    let layered = match layers::gather(&Sources::system(path)) {
+        Ok(layered) => layered,
+        Err(err) => return Check::fail("layers", format!("{err:#}"), "check the file's permissions"),
+    };
+    // The user's own file has the check above.
+    let problems: Vec<String> = layered
+        .problems
+        .iter()
+        .filter(|(source, _)| *source != user)
+        .map(|(source, problem)| format!("{source}: {problem}"))
+        .collect();
+    if !problems.is_empty() {
+        return Check::fail(
+            "layers",
+            problems.join("; "),
+            "correct the machine file, or the variables and flags named",
+        );
+    }
+    let overrides = layered
+        .values()
+        .filter(|(_, _, origin)| !matches!(origin, Origin::User { .. }))
+        .count();
+    Check::ok(
+        "layers",
+        format!("{overrides} value(s) set outside {user}; see `clipctl config show --origin`"),
+    )
+}
+
+fn database_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com", "rusty-clipboard", "clipmgr")
+        .context("failed to determine application directories")?;
+    Ok(dirs.data_local_dir().join("history.db"))
+}
+
+fn check_database_file() -> Check {
+    let path = match database_path() {
+        Ok(path) => path,
+        Err(err) => return Check::fail("database", format!("{err:#}"), "set %LOCALAPPDATA% for this user"),
+    };
+    if !path.exists() {
+        return Check::warn(
+            "database",
+            format!("{} does not exist yet", path.display()),
+            "start clipd once; it creates the database on first run",
+        );
+    }
+
+    let mut header = [0u8; 16];
+    let read = File::open(&path).and_then(|mut file| file.read_exact(&mut header));
+    match read {
+        Ok(()) if &header == SQLITE_HEADER => Check::ok("database", format!("{} is readable", path.display())),
+        Ok(()) => Check::fail(
+            "database",
+            format!("{} is not a SQLite database", path.display()),
+            "stop clipd, rename the file to history.db.bak, and restart clipd to create a fresh one",
+        ),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Check::fail(
+            "database",
+            format!("{} is truncated", path.display()),
+            "stop clipd, rename the file to history.db.bak, and restart clipd to create a fresh one",
+        ),
+        Err(err) => Check::fail(
+            "database",
+            format!("cannot read {}: {err}", path.display()),
+            "clipd and clipctl must run as the user that owns the file",
+        ),
+    }
+}
+
+/// Connection, handshake, and the daemon's own integrity check.
+async fn check_daemon() -> Vec<Check> {
+    let pipe = match ipc::pipe_name() {
+        Ok(pipe) => pipe,
+        Err(err) => {
+            return vec![
+                Check::fail(
+                    "daemon",
+                    err.to_string(),
+                    "start clipd; it replaces the file a crashed daemon left behind",
+                ),
+                Check::skip("integrity", "needs a running daemon"),
+            ]
+        }
+    };
+    let mut client = match Client::connect().await {
+        Ok(client) => client,
+        Err(err) => {
+            let io_err = err.downcast_ref::<io::Error>();
+            let check = match io_err.map(|e| (e.kind(), e.raw_os_error())) {
+                Some((io::ErrorKind::NotFound, _)) => Check::fail(
+                    "daemon",
+                    format!("nothing is listening on {pipe}"),
+                    "start clipd (run `clipd`, or open a new PowerShell window to use the profile autostart)",
+                ),
+                Some((io::ErrorKind::PermissionDenied, _)) => Check::fail(
+                    "pipe",
+                    format!("access to {pipe} was denied"),
+                    "clipd runs as another user or elevated; stop it (Stop-Process -Name clipd) and start it from a normal shell",
+                ),
+                Some((_, Some(ERROR_PIPE_BUSY))) => Check::warn(
+                    "pipe",
+                    format!("{pipe} is busy serving other clients"),
+                    "retry in a moment; restart clipd if it stays busy",
+                ),
+                _ => Check::fail("daemon", format!("{err:#}"), "restart clipd and run doctor again"),
+            };
+            return vec![check, Check::skip("integrity", "needs a running daemon")];
+        }
+    };
+
+    let mut checks = vec![Check::ok("daemon", format!("clipd is listening on {pipe}"))];
+
+    match client.handshake().await {
+        Ok(info) if info.version != env!("CARGO_PKG_VERSION") => checks.push(Check::warn(
+            "version",
+            format!("clipd {} / clipctl {}", info.version, env!("CARGO_PKG_VERSION")),
+            "restart clipd from the same install as clipctl",
+        )),
+        Ok(info) => checks.push(Check::ok(
+            "version",
+            format!("clipd {} (protocol v{})", info.version, info.protocol),
+        )),
+        Err(err) => {
+            // The handshake error carries its own restart hint; `fix` repeats it.
+            let summary = err.to_string().lines().next().unwrap_or_default().to_string();
+            checks.push(Check::fail(
+                "version",
+                summary,
+                "restart clipd from the same install as clipctl",
+            ));
+            checks.push(Check::skip("integrity", "needs a compatible daemon"));
+            return checks;
+        }
+    }
+
+    let diagnostics = client
+        .request(RequestKind::Diagnose)
+        .await
+        .map(|response| response.diagnostics);
+
+    let (queue, images) = match &diagnostics {
+        Ok(Some(diag)) => (Some(check_queue(&diag.queue)), check_images(&diag.blobs)),
+        _ => (None, None),
+    };
+    checks.push(match diagnostics {
+        Ok(Some(diag)) if diag.integrity.is_empty() => Check::ok(
+            "integrity",
+            format!("{} entries, integrity check passed", diag.entries),
+        ),
+        Ok(Some(diag)) => Check::fail(
+            "integrity",
+            format!("{}: {}", diag.db_path, diag.integrity.join("; ")),
+            format!(
+                "stop clipd, back up {}, then recover it with `sqlite3 history.db .recover` or move it aside",
+                diag.db_path
+            ),
+        ),
+        Ok(None) => Check::skip("integrity", "clipd did not report diagnostics"),
+        Err(err) => Check::fail("integrity", format!("{err:#}"), "check the clipd log for database errors"),
+    });
+    checks.extend(queue);
+    checks.extend(images);
+    checks
+}
+
+/// How much identical images share, once there are any.
+fn check_images(blobs: &BlobStats) -> Option<Check> {
+    const MB: f64 = 1024.0 * 1024.0;
+    (blobs.images > 0).then(|| {
+        Check::ok(
+            "images",
+            format!(
+                "{} images stored as {} ({:.1} MB), {:.1} MB saved by sharing identical ones",
+                blobs.images,
+                blobs.blobs,
+                blobs.stored_bytes as f64 / MB,
+                blobs.saved_bytes as f64 / MB
+            ),
+        )
+    })
+}
+
+/// Whether captures reached the database without being held up or lost.
+fn check_queue(queue: &QueueStats) -> Check {
+    let detail = format!(
+        "{} of {} captures stored, slowest after {} ms, at most {} waiting ({:.1} MB)",
+        queue.stored,
+        queue.captured,
+        queue.slowest_ms,
+        queue.peak,
+        queue.peak_bytes as f64 / (1024.0 * 1024.0)
+    );
+    if queue.dropped > 0 {
+        Check::warn(
+            "captures",
+            format!("{detail}; {} dropped while the queue was full", queue.dropped),
+            "check the clipd log for slow database writes, or set capture.backpressure = \"spill\"",
+        )
+    } else if queue.rejected > 0 {
+        Check::warn(
+            "captures",
+            format!("{detail}; {} copies too large to capture", queue.rejected),
+            "raise capture.max_entry_mb to keep copies that large",
+        )
+    } else if queue.blocked > 0 || queue.spilled > 0 {
+        Check::warn(
+            "captures",
+            format!("{detail}; queue filled up {} times", queue.blocked + queue.spilled),
+            "check the clipd log for slow database writes",
+        )
+    } else {
+        Check::ok("captures", detail)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn check_clipboard() -> Check {
+    match clipboard_win::Clipboard::new_attempts(10) {
+        Ok(_clipboard) => Check::ok("clipboard", "clipboard can be opened"),
+        Err(err) => Check::fail(
+            "clipboard",
+            format!("could not open the clipboard: {err}"),
+            "another program is holding the clipboard open; close clipboard tools or remote sessions and retry",
+        ),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn check_clipboard() -> Check {
+    match arboard::Clipboard::new() {
+        Ok(_clipboard) => Check::ok("clipboard", "clipboard can be opened"),
+        Err(err) => Check::fail(
+            "clipboard",
+            format!("could not open the clipboard: {err}"),
+            "run clipctl in a desktop session; on Linux, DISPLAY or WAYLAND_DISPLAY must be set",
+        ),
+    }
+}
+
+/// Look for the sign-in registration or install.ps1's PowerShell profile block.
+fn check_autostart() -> Check {
+    if daemon::autostart_registered() {
+        return Check::ok("autostart", "clipd starts at sign-in (Run registry key)");
+    }
+
+    let Some(documents) = UserDirs::new().and_then(|dirs| dirs.document_dir().map(PathBuf::from)) else {
+        return Check::skip("autostart", "could not locate the Documents folder");
+    };
+
+    let profile = ["PowerShell", "WindowsPowerShell"]
+        .iter()
+        .flat_map(|dir| {
+            let base = documents.join(dir);
+            ["Microsoft.PowerShell_profile.ps1", "profile.ps1"].map(|file| base.join(file))
+        })
+        .find(|path| {
+            fs::read_to_string(path)
+                .map(|raw| raw.contains(PROFILE_MARKER))
+                .unwrap_or(false)
+        });
+
+    let Some(profile) = profile else {
+        return Check::warn(
+            "autostart",
+            "clipd is not started automatically",
+            "run install.ps1 to add the PowerShell profile integration, or start clipd yourself",
+        );
+    };
+
+    let clipd = env::var_os("RUSTY_CLIPBOARD_HOME")
+        .map(|home| PathBuf::from(home).join(format!("clipd{}", env::consts::EXE_SUFFIX)));
+    match clipd {
+        Some(clipd) if clipd.exists() => Check::ok("autostart", format!("enabled in {}", profile.display())),
+        Some(clipd) => Check::warn(
+            "autostart",
+            format!("{} is missing", clipd.display()),
+            "run install.ps1 again to reinstall the binaries",
+        ),
+        None => Check::warn(
+            "autostart",
+            "RUSTY_CLIPBOARD_HOME is not set, so the profile cannot find clipd",
+            "run install.ps1 again, then open a new terminal",
+        ),
+    }
+}
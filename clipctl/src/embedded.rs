@@ -0,0 +1,72 @@
+//! `clipctl --embedded`: clipd running inside clipctl, for trying things out
+//! without installing or starting the daemon.
+//!
+//! The embedded clipd watches the real clipboard and reads the usual
+//! `config.toml`, but keeps its history in a fresh temporary folder that is
+//! deleted on exit, and serves clipctl over an in-memory connection instead
+//! of the pipe, so it neither needs nor disturbs a clipd that is running.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use clipd::backend::SystemClipboard;
+use clipd::config::Config;
+use clipd::ipc::Server;
+use clipd::service::{ClipdService, ShutdownHandle};
+use parking_lot::Mutex;
+use tokio::io::DuplexStream;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+/// How long the embedded clipd gets to store its last captures on exit.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set while the embedded clipd runs; clients connect to it instead of the pipe.
+static SERVER: Mutex<Option<(Server, ShutdownHandle)>> = Mutex::new(None);
+
+/// The clipd running in this process.
+pub struct Embedded {
+    shutdown: ShutdownHandle,
+    service: JoinHandle<Result<()>>,
+    data_dir: PathBuf,
+}
+
+/// Start clipd in this process; `Client::connect` reaches it from then on.
+pub fn start() -> Result<Embedded> {
+    let data_dir = std::env::temp_dir().join(format!("clipmgr-embedded-{}", std::process::id()));
+    let config = Config::load_layers(&Config::sources()?, data_dir.clone())
+        .context("failed to load the configuration for embedded clipd")?;
+    let service = ClipdService::with_backend(config, Arc::new(SystemClipboard::default()))
+        .context("failed to start embedded clipd")?;
+    let shutdown = service.shutdown_handle();
+    *SERVER.lock() = Some((service.server(), shutdown.clone()));
+    Ok(Embedded {
+        shutdown,
+        service: tokio::spawn(service.run_headless()),
+        data_dir,
+    })
+}
+
+/// A new in-memory connection to the embedded clipd, if one is running.
+pub fn connect() -> Option<DuplexStream> {
+    let server = SERVER.lock();
+    let (server, shutdown) = server.as_ref()?;
+    Some(server.connect_in_memory(shutdown.subscribe()))
+}
+
+impl Embedded {
+    /// Stop clipd and delete the history it kept.
+    pub async fn stop(self) {
+        // Dropping the server closes the databases, so the folder can go.
+        SERVER.lock().take();
+        self.shutdown.trigger();
+        match tokio::time::timeout(STOP_TIMEOUT, self.service).await {
+            Ok(Ok(Err(err))) => eprintln!("embedded clipd failed: {err:#}"),
+            Ok(Err(err)) => eprintln!("embedded clipd panicked: {err}"),
+            Ok(Ok(Ok(()))) => {}
+            Err(_) => eprintln!("embedded clipd did not stop in time"),
+        }
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
@@ -0,0 +1,39 @@
+//! Line endings and encoding problems of text entries, for the preview.
+
+/// `CRLF`, `LF`, `CR`, or `mixed`; `None` for single-line text.
+pub fn line_endings(text: &str) -> Option<&'static str> {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let cr = text.matches('\r').count() - crlf;
+    match (crlf > 0, lf > 0, cr > 0) {
+        (false, false, false) => None,
+        (true, false, false) => Some("CRLF"),
+        (false, true, false) => Some("LF"),
+        (false, false, true) => Some("CR"),
+        _ => Some("mixed"),
+    }
+}
+
+/// Signs that text went through the wrong encoding somewhere.
+pub fn suspicious(text: &str) -> Vec<&'static str> {
+    let mut notes = Vec::new();
+    if text.starts_with('\u{FEFF}') {
+        notes.push("UTF-8 BOM");
+    }
+    if text.contains('\u{FFFD}') {
+        notes.push("replacement characters (�)");
+    }
+    if looks_double_encoded(text) {
+        notes.push("double-encoded UTF-8 (Ã© for é)");
+    }
+    notes
+}
+
+/// UTF-8 read as Windows-1252 or Latin-1: `Ã©`, `Â `, `â€™`.
+fn looks_double_encoded(text: &str) -> bool {
+    text.contains("â€")
+        || text
+            .chars()
+            .zip(text.chars().skip(1))
+            .any(|(lead, next)| matches!(lead, 'Ã' | 'Â') && ('\u{80}'..='\u{BF}').contains(&next))
+}
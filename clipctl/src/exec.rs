@@ -0,0 +1,54 @@
+//! Piping an entry into a user-supplied shell command (`clipctl pick --exec`, `|`).
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+/// Run `command` through the platform shell with `input` on stdin and return its stdout.
+///
+/// A non-zero exit is an error carrying the command's stderr.
+pub fn pipe_to_command(command: &str, input: &str) -> Result<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start '{command}'"))?;
+
+    {
+        let mut stdin = child.stdin.take().expect("command stdin is piped");
+        // Commands that ignore their input may exit before reading it all.
+        if let Err(err) = stdin.write_all(input.as_bytes()) {
+            if err.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(err).with_context(|| format!("failed to write to '{command}'"));
+            }
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to wait for '{command}'"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("'{command}' exited with {}: {}", output.status, stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    // Passed raw so cmd sees the user's quoting unchanged.
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").raw_arg(command);
+    shell
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
@@ -0,0 +1,68 @@
+//! `clipctl fzf`: pick an entry with an external fzf process instead of the TUI.
+
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::commands;
+
+/// Longest single-line preview handed to fzf; its preview pane shows the rest.
+const LINE_WIDTH: usize = 200;
+
+pub async fn run(fzf_args: Vec<String>) -> Result<()> {
+    let mut client = commands::connect().await?;
+    let entries = commands::list_entries(&mut client, None).await?;
+    if entries.is_empty() {
+        bail!("clipboard history is empty");
+    }
+
+    let exe = env::current_exe().context("failed to resolve clipctl executable path")?;
+    let preview = format!("\"{}\" get {{1}}", exe.display());
+
+    // Lines are `id<TAB>kind<TAB>text`; only kind and text are shown and matched.
+    let mut fzf = Command::new("fzf")
+        .args(["--delimiter", "\t", "--with-nth", "2..", "--tiebreak", "index"])
+        .args(["--preview", &preview, "--preview-window", "right:50%:wrap"])
+        .args(&fzf_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to start fzf; install it and make sure it is on PATH")?;
+
+    {
+        let mut stdin = fzf.stdin.take().expect("fzf stdin is piped");
+        for entry in &entries {
+            let line: String = entry
+                .preview
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(LINE_WIDTH)
+                .collect();
+            // fzf may exit before reading everything; a broken pipe is expected then.
+            if writeln!(stdin, "{}\t{}\t{}", entry.id, entry.kind, line).is_err() {
+                break;
+            }
+        }
+    }
+
+    let output = fzf.wait_with_output().context("failed to wait for fzf")?;
+    match output.status.code() {
+        Some(0) => {}
+        // No match, or the user pressed Esc/Ctrl+C.
+        Some(1) | Some(130) => return Ok(()),
+        _ => bail!("fzf exited with {}", output.status),
+    }
+
+    let selected = String::from_utf8_lossy(&output.stdout);
+    let id: u64 = selected
+        .split('\t')
+        .next()
+        .and_then(|id| id.trim().parse().ok())
+        .context("could not read the entry id from fzf's output")?;
+
+    commands::copy_with(&mut client, id).await
+}
@@ -0,0 +1,106 @@
+//! Copied git diffs and `git format-patch` mails: recognized for the
+//! preview's +/- coloring and summary, and for saving as a `.patch` file.
+
+use ratatui::text::{Line, Span};
+
+use crate::theme::Theme;
+
+/// A unified diff found in an entry, and how much it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diff {
+    /// A mail from `git format-patch`, which `git am` applies with its commit message.
+    pub patch: bool,
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// What each line of a diff is, for counting and coloring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    /// `diff --git`, `index`, and the `---`/`+++` file names.
+    Header,
+    /// `@@ -1,4 +1,5 @@`, where each hunk starts.
+    Hunk,
+    Added,
+    Removed,
+    /// Unchanged lines, and anything around the diff like a commit message.
+    Context,
+}
+
+/// The diff in `text`, if it has one: file names followed by at least one hunk.
+pub fn detect(text: &str) -> Option<Diff> {
+    let parts = parts(text);
+    if !parts.contains(&Part::Header) || !parts.contains(&Part::Hunk) {
+        return None;
+    }
+    let count = |part| parts.iter().filter(|&&each| each == part).count();
+    let lines: Vec<&str> = text.lines().collect();
+    let new_files = lines
+        .iter()
+        .zip(&parts)
+        .filter(|(line, part)| **part == Part::Header && line.starts_with("+++ "))
+        .count();
+    Some(Diff {
+        patch: lines.first().is_some_and(|first| is_patch_mail(first)),
+        files: new_files.max(lines.iter().filter(|line| line.starts_with("diff ")).count()),
+        added: count(Part::Added),
+        removed: count(Part::Removed),
+    })
+}
+
+/// The first `max` lines of `text`, which [`detect`] found a diff in, colored
+/// by what they change.
+pub fn lines(text: &str, theme: &Theme, max: usize) -> Vec<Line<'static>> {
+    text.lines()
+        .zip(parts(text))
+        .take(max)
+        .map(|(line, part)| {
+            let style = match part {
+                Part::Header => theme.style_title(),
+                Part::Hunk => theme.style_metadata_label(),
+                Part::Added => theme.style_diff_added(),
+                Part::Removed => theme.style_diff_removed(),
+                Part::Context => theme.style_list_item(),
+            };
+            Line::from(Span::styled(line.to_string(), style))
+        })
+        .collect()
+}
+
+fn parts(text: &str) -> Vec<Part> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut in_hunk = false;
+    let mut parts = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        let file_names = (line.starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")))
+            || (line.starts_with("+++ ") && i > 0 && lines[i - 1].starts_with("--- "));
+        // Lines in a hunk start with a space, `+`, or `-`, so `diff` starts the next file.
+        let part = if file_names || line.starts_with("diff ") || (!in_hunk && line.starts_with("index ")) {
+            in_hunk = false;
+            Part::Header
+        } else if line.starts_with("@@ -") {
+            in_hunk = true;
+            Part::Hunk
+        } else if *line == "-- " {
+            // The signature line format-patch ends the mail with.
+            in_hunk = false;
+            Part::Context
+        } else if in_hunk && line.starts_with('+') {
+            Part::Added
+        } else if in_hunk && line.starts_with('-') {
+            Part::Removed
+        } else {
+            Part::Context
+        };
+        parts.push(part);
+    }
+    parts
+}
+
+/// Whether `line` opens a `git format-patch` mail: `From <commit> Mon Sep 17 00:00:00 2001`.
+fn is_patch_mail(line: &str) -> bool {
+    line.strip_prefix("From ")
+        .and_then(|rest| rest.split_once(' '))
+        .is_some_and(|(commit, _)| commit.len() == 40 && commit.bytes().all(|b| b.is_ascii_hexdigit()))
+}
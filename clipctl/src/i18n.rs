@@ -0,0 +1,94 @@
+//! Message catalog for the TUI's help, prompts, and status messages.
+//!
+//! Messages are looked up by id in the Fluent files under `clipctl/locales/`,
+//! which are compiled in. `ui.language` picks the catalog, or the Windows
+//! display language when it is empty; messages a catalog lacks, and every
+//! message of a language without one, come from `en-US`.
+
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+/// Catalogs compiled into clipctl, by language tag; `en-US` has every message.
+pub const CATALOGS: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+/// Where messages are looked up, in order: the chosen catalog, then `en-US`.
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Show messages in `language` (`ui.language`), such as "de" or "de-AT";
+/// empty follows the system. Only the first call counts, and messages shown
+/// before it are in English.
+pub fn select(language: &str) {
+    let _ = BUNDLES.set(bundles(language));
+}
+
+fn bundles(language: &str) -> Vec<FluentBundle<FluentResource>> {
+    let requested = match language {
+        "" => sys_locale::get_locale().unwrap_or_default(),
+        language => language.to_string(),
+    };
+    let requested: LanguageIdentifier = requested.parse().unwrap_or_else(|_| {
+        tracing::warn!(language = %requested, "ignoring unknown ui.language; using English");
+        LanguageIdentifier::default()
+    });
+    // A catalog serves every region of its language: "de" for "de-AT".
+    let (english, translations) = CATALOGS.split_first().expect("the English catalog is compiled in");
+    translations
+        .iter()
+        .filter(|(tag, _)| tag.parse::<LanguageIdentifier>().is_ok_and(|id| id.language == requested.language))
+        .chain([english])
+        .map(|(tag, source)| bundle(tag, source))
+        .collect()
+}
+
+/// The catalog `source` for language `tag`.
+pub fn bundle(tag: &str, source: &str) -> FluentBundle<FluentResource> {
+    let language: LanguageIdentifier = tag.parse().expect("catalogs are named by language tags");
+    let resource = FluentResource::try_new(source.to_string())
+        .unwrap_or_else(|(_, errors)| panic!("catalog {tag} does not parse: {errors:?}"));
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // Terminals print the bidi isolation marks Fluent puts around arguments.
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("catalog {tag} repeats messages: {errors:?}"));
+    bundle
+}
+
+/// Message `id` with `args`, in the chosen language; use [`t!`].
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundles = BUNDLES.get_or_init(|| bundles("en-US"));
+    for bundle in bundles {
+        let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+            continue;
+        };
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        if errors.is_empty() {
+            return text.into_owned();
+        }
+        tracing::warn!(id, ?errors, "failed to format message");
+    }
+    tracing::warn!(id, "message missing from the catalog");
+    id.to_string()
+}
+
+/// `t!("id")` or `t!("id", name = value, ...)`: message `id` from the
+/// catalog, with `$name` set to each value.
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+pub(crate) use t;
@@ -0,0 +1,120 @@
+//! Normal-mode key sequence parsing: count prefixes, `gg`, marks, and macros.
+
+use crossterm::event::KeyCode;
+
+/// Counts above this are clamped; nobody means `1000000j`.
+const MAX_COUNT: usize = 99_999;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    #[default]
+    None,
+    /// Saw `g`, waiting for the second `g`.
+    G,
+    /// Saw `m`, waiting for the mark name.
+    SetMark,
+    /// Saw `'` or `` ` ``, waiting for the mark name.
+    JumpMark,
+    /// Saw `q`, waiting for the register to record into.
+    Record,
+    /// Saw `@`, waiting for the register to play.
+    Play,
+}
+
+/// What a key (sequence) resolved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// Part of a longer sequence; nothing to do yet.
+    Pending,
+    /// An incomplete sequence was abandoned.
+    Cancelled,
+    Down(usize),
+    Up(usize),
+    Top,
+    Bottom,
+    /// 1-based position from `5G` or `5gg`.
+    Line(usize),
+    SetMark(char),
+    JumpMark(char),
+    Record(char),
+    /// Play a macro register this many times; `@` is the last one played.
+    Play(char, usize),
+    /// Not a motion; handle the key as a regular binding.
+    Key(KeyCode),
+}
+
+/// State machine fed one normal-mode key at a time.
+#[derive(Debug, Default)]
+pub struct NormalInput {
+    count: Option<usize>,
+    pending: Pending,
+}
+
+impl NormalInput {
+    pub fn feed(&mut self, code: KeyCode) -> Motion {
+        let pending = std::mem::take(&mut self.pending);
+        let count = self.count;
+
+        let motion = match (pending, code) {
+            (Pending::SetMark, KeyCode::Char(c)) if c.is_ascii_alphabetic() => Motion::SetMark(c),
+            (Pending::JumpMark, KeyCode::Char(c)) if c.is_ascii_alphabetic() => Motion::JumpMark(c),
+            (Pending::Record, KeyCode::Char(c)) if c.is_ascii_alphanumeric() => Motion::Record(c),
+            (Pending::Play, KeyCode::Char(c)) if c.is_ascii_alphanumeric() || c == '@' => {
+                Motion::Play(c, count.unwrap_or(1))
+            }
+            (Pending::G, KeyCode::Char('g')) => count.map_or(Motion::Top, Motion::Line),
+            (Pending::None, _) => return self.feed_fresh(code),
+            _ => Motion::Cancelled,
+        };
+        self.count = None;
+        motion
+    }
+
+    fn feed_fresh(&mut self, code: KeyCode) -> Motion {
+        if let KeyCode::Char(c @ '0'..='9') = code {
+            // A leading 0 is not a count; let it fall through as a key.
+            if c != '0' || self.count.is_some() {
+                let digit = c.to_digit(10).unwrap_or_default() as usize;
+                self.count = Some((self.count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+                return Motion::Pending;
+            }
+        }
+
+        let pending = match code {
+            KeyCode::Char('g') => Some(Pending::G),
+            KeyCode::Char('m') => Some(Pending::SetMark),
+            KeyCode::Char('\'') | KeyCode::Char('`') => Some(Pending::JumpMark),
+            KeyCode::Char('q') => Some(Pending::Record),
+            KeyCode::Char('@') => Some(Pending::Play),
+            _ => None,
+        };
+        if let Some(pending) = pending {
+            self.pending = pending;
+            return Motion::Pending;
+        }
+
+        let count = self.count.take();
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => Motion::Down(count.unwrap_or(1)),
+            KeyCode::Char('k') | KeyCode::Up => Motion::Up(count.unwrap_or(1)),
+            KeyCode::Char('G') => count.map_or(Motion::Bottom, Motion::Line),
+            KeyCode::Esc if count.is_some() => Motion::Cancelled,
+            _ => Motion::Key(code),
+        }
+    }
+
+    /// Keys typed so far in an unfinished sequence, for the command bar.
+    pub fn pending_keys(&self) -> Option<String> {
+        let prefix = self.count.map(|n| n.to_string()).unwrap_or_default();
+        let suffix = match self.pending {
+            Pending::None => "",
+            Pending::G => "g",
+            Pending::SetMark => "m",
+            Pending::JumpMark => "'",
+            Pending::Record => "q",
+            Pending::Play => "@",
+        };
+        let keys = prefix + suffix;
+        (!keys.is_empty()).then_some(keys)
+    }
+}
@@ -0,0 +1,110 @@
+//! `clipctl insights`: a Markdown report of recent clipboard habits. clipd
+//! counts them in the local history; nothing leaves the machine.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::commands;
+use crate::convert;
+use crate::ipc::{RequestKind, UsageCount, UsageStats};
+
+/// Blocks in the bar of the busiest hour; the others are scaled to it.
+const BAR_WIDTH: u64 = 20;
+
+/// `clipctl insights`: print the report for the last `days` days, or write
+/// it to `output`.
+pub async fn run(days: u32, output: Option<&Path>) -> Result<()> {
+    let mut client = commands::connect().await?;
+    let stats = client
+        .request(RequestKind::Stats { days })
+        .await?
+        .stats
+        .context("clipd did not return usage stats; is it up to date?")?;
+    let report = report(&stats);
+    match output {
+        Some(path) => {
+            std::fs::write(path, report).with_context(|| format!("failed to write {}", path.display()))?;
+            eprintln!("wrote insights to {}", path.display());
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
+fn report(stats: &UsageStats) -> String {
+    let days = if stats.days == 1 { "day".to_string() } else { format!("{} days", stats.days) };
+    let mut lines = vec![format!("# Clipboard insights: the last {days}"), String::new()];
+    if stats.entries == 0 {
+        lines.push(format!("Nothing was captured in the last {days}."));
+        return lines.join("\n") + "\n";
+    }
+    let per_day = stats.entries as f64 / f64::from(stats.days.max(1));
+    lines.push(format!("{} entries captured, {per_day:.1} a day.", stats.entries));
+
+    section(&mut lines, "Top apps");
+    counts(&mut lines, "App", &stats.apps, stats.entries);
+
+    section(&mut lines, "Busiest hours");
+    let busiest = stats.hours.iter().copied().max().unwrap_or(0).max(1);
+    lines.push("| Hour | Entries | |".to_string());
+    lines.push("|:--|--:|:--|".to_string());
+    for (hour, &count) in stats.hours.iter().enumerate().filter(|(_, &count)| count > 0) {
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(busiest) as usize);
+        lines.push(format!("| {hour:02}:00 | {count} | {bar} |"));
+    }
+
+    section(&mut lines, "Kinds");
+    counts(&mut lines, "Kind", &stats.kinds, stats.entries);
+
+    section(&mut lines, "Tags");
+    if stats.tags.is_empty() {
+        lines.push("No entries were tagged.".to_string());
+    } else {
+        counts(&mut lines, "Tag", &stats.tags, stats.entries);
+    }
+
+    section(&mut lines, "Biggest entries");
+    lines.push("| Id | Entry | Kind | Size | Copied |".to_string());
+    lines.push("|--:|:--|:--|--:|:--|".to_string());
+    for entry in &stats.biggest {
+        lines.push(format!(
+            "| {} | {} | {} | {} | {} |",
+            entry.id,
+            cell(&entry.label),
+            entry.kind,
+            size(entry.bytes),
+            entry.created
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn section(lines: &mut Vec<String>, title: &str) {
+    lines.push(String::new());
+    lines.push(format!("## {title}"));
+    lines.push(String::new());
+}
+
+/// A table of `counts` with each one's share of `total` entries.
+fn counts(lines: &mut Vec<String>, heading: &str, counts: &[UsageCount], total: u64) {
+    lines.push(format!("| {heading} | Entries | Share |"));
+    lines.push("|:--|--:|--:|".to_string());
+    for count in counts {
+        let share = count.count as f64 * 100.0 / total as f64;
+        lines.push(format!("| {} | {} | {share:.0}% |", cell(&count.name), count.count));
+    }
+}
+
+/// `text` made safe for a table cell.
+fn cell(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else {
+        convert::human_size(bytes)
+    }
+}
@@ -0,0 +1,82 @@
+//! Zero-width, bidi, and control characters: shown as badges in the preview
+//! and removed by "paste sanitized".
+
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+
+/// clipd's warning for text with hidden characters, which sanitizing settles.
+pub const WARNING: &str = "invisible characters";
+
+/// Characters that render as nothing, reorder the text around them, or drive
+/// the terminal (escape sequences); tab and line breaks are ordinary text.
+pub fn is_hidden(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00}'..='\u{08}'
+            | '\u{0B}'..='\u{0C}'
+            | '\u{0E}'..='\u{1F}'
+            | '\u{7F}'..='\u{9F}'
+            | '\u{00AD}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E007F}'
+    )
+}
+
+/// `text` without hidden characters.
+pub fn strip(text: &str) -> String {
+    text.chars().filter(|&c| !is_hidden(c)).collect()
+}
+
+/// `line` as spans, each hidden character replaced by a badge in `badge` style.
+pub fn reveal(line: &str, text: Style, badge: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    for c in line.chars() {
+        if !is_hidden(c) {
+            plain.push(c);
+            continue;
+        }
+        if !plain.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut plain), text));
+        }
+        spans.push(Span::styled(label(c), badge));
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, text));
+    }
+    Line::from(spans)
+}
+
+fn label(c: char) -> String {
+    let name = match c {
+        // Control Pictures block: ␀ through ␟, and ␡.
+        '\u{00}'..='\u{1F}' => {
+            return char::from_u32(0x2400 + c as u32).map_or_else(String::new, String::from)
+        }
+        '\u{7F}' => return "␡".to_string(),
+        '\u{00AD}' => "SHY",
+        '\u{200B}' => "ZWSP",
+        '\u{200C}' => "ZWNJ",
+        '\u{200D}' => "ZWJ",
+        '\u{200E}' => "LRM",
+        '\u{200F}' => "RLM",
+        '\u{202A}' => "LRE",
+        '\u{202B}' => "RLE",
+        '\u{202C}' => "PDF",
+        '\u{202D}' => "LRO",
+        '\u{202E}' => "RLO",
+        '\u{2060}' => "WJ",
+        '\u{2066}' => "LRI",
+        '\u{2067}' => "RLI",
+        '\u{2068}' => "FSI",
+        '\u{2069}' => "PDI",
+        '\u{FEFF}' => "BOM",
+        _ => return format!("⟨U+{:04X}⟩", c as u32),
+    };
+    format!("⟨{name}⟩")
+}
@@ -1,75 +1,189 @@
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::env;
+use std::io;
+
+use anyhow::{bail, Context, Result};
+use clipcore::{read_frame, write_frame, ErrorCode};
+use clipd::config::Config;
+use clipd::runtime::RuntimeInfo;
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream};
+#[cfg(target_os = "windows")]
 use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient};
+#[cfg(not(target_os = "windows"))]
+use tokio::net::UnixStream;
 
-const PIPE_NAME: &str = r"\\.\pipe\clipmgr";
+use crate::embedded;
+use crate::remote;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Request {
-    pub kind: RequestKind,
-}
+pub use clipcore::{
+    BlobStats, Checksums, DayCount, Digest, Entity, EntityKind, EntryPayload, EntrySource, EntrySummary,
+    Highlight, PasteVerdict, ProfileSummary, QueueStats, Request, RequestKind, Response, ServerEvent, ServerInfo,
+    SessionSummary, StyleRun, Suggestion, UsageCount, UsageStats, PROTOCOL_VERSION,
+};
+
+#[cfg(target_os = "windows")]
+pub const PIPE_NAME: &str = r"\\.\pipe\clipmgr";
+
+/// A connection to the clipd on this machine: its named pipe, or its Unix
+/// domain socket on Linux and macOS.
+#[cfg(target_os = "windows")]
+pub type Pipe = NamedPipeClient;
+#[cfg(not(target_os = "windows"))]
+pub type Pipe = UnixStream;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum RequestKind {
-    List,
-    Search { query: String },
-    Paste { id: u64 },
-    AddTag { id: u64, tag: String },
-    RemoveTag { id: u64, tag: String },
-    Export { path: String },
-    Import { path: String },
+/// How to check that clipd is running, for errors about reaching it.
+#[cfg(target_os = "windows")]
+const RUNNING_CHECK: &str = "Get-Process clipd";
+#[cfg(not(target_os = "windows"))]
+const RUNNING_CHECK: &str = "pgrep -l clipd";
+
+/// The pipe to connect to. `CLIPMGR_PIPE` (`--pipe`) names it outright;
+/// otherwise it is the one clipd announced in its runtime file, which is the
+/// profile daemon's with `CLIPMGR_PROFILE` (`--profile`) naming a profile
+/// with a pipe of its own. With no file it is the pipe clipd would listen on,
+/// as with daemons that predate the file. A file left by a daemon that
+/// exited fails with `NotFound`, like a pipe nobody listens on.
+pub fn pipe_name() -> Result<String> {
+    // clipd's own reading of the config, so both ends agree on the pipe.
+    let config = match Config::load() {
+        Ok(config) => config,
+        #[cfg(target_os = "windows")]
+        Err(err) => {
+            tracing::warn!(%err, "cannot read clipd's settings; trying the default pipe");
+            return Ok(PIPE_NAME.to_string());
+        }
+        // The socket is in the data directory the settings name.
+        #[cfg(not(target_os = "windows"))]
+        Err(err) => return Err(err.context("cannot find clipd's socket")),
+    };
+    if env::var_os("CLIPMGR_PIPE").is_some() {
+        return Ok(config.pipe_name);
+    }
+    let path = config.runtime_path;
+    let announced = RuntimeInfo::read(&path).unwrap_or_else(|err| {
+        tracing::warn!(%err, "ignoring clipd's runtime file");
+        None
+    });
+    match announced {
+        Some(info) if info.is_running() => Ok(info.pipe_name),
+        Some(info) => Err(io::Error::from(io::ErrorKind::NotFound)).with_context(|| {
+            format!(
+                "clipd is not running: PID {} exited without removing {}",
+                info.pid,
+                path.display()
+            )
+        }),
+        None => Ok(config.pipe_name),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Response {
-    pub entries: Vec<EntrySummary>,
+/// Connect to the clipd listening on `pipe_name` on this machine.
+#[cfg(target_os = "windows")]
+pub async fn open_pipe(pipe_name: &str) -> io::Result<Pipe> {
+    ClientOptions::new().open(pipe_name)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EntrySummary {
-    pub id: u64,
-    pub preview: String,
-    pub created_at: String,
-    pub kind: String,
-    pub source_process: Option<String>,
-    pub tags: Vec<String>,
+#[cfg(not(target_os = "windows"))]
+pub async fn open_pipe(pipe_name: &str) -> io::Result<Pipe> {
+    UnixStream::connect(pipe_name).await
 }
 
+/// How clipctl reaches clipd: its named pipe, an in-memory duplex to the
+/// clipd running inside clipctl with `--embedded`, or an SSH session to
+/// another machine's with `--remote`.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl Transport for Pipe {}
+
+impl Transport for DuplexStream {}
+
 pub struct Client {
-    pipe: NamedPipeClient,
+    pipe: Box<dyn Transport>,
 }
 
 impl Client {
     pub async fn connect() -> Result<Self> {
-        let pipe = ClientOptions::new()
-            .open(PIPE_NAME)
-            .with_context(|| {
-                format!(
-                    "failed to connect to pipe {PIPE_NAME}\n\
-                    This usually means:\n\
-                    1. The clipd daemon is not running - start it with: cargo run --bin clipd\n\
-                    2. The daemon was started with different permissions (e.g., as administrator)\n\
-                    3. Check if clipd is running: Get-Process clipd"
-                )
-            })?;
-        Ok(Self { pipe })
+        if let Some(stream) = embedded::connect() {
+            return Ok(Self { pipe: Box::new(stream) });
+        }
+        if let Some(target) = remote::target() {
+            return Ok(Self { pipe: Box::new(remote::connect(&target)?) });
+        }
+        let pipe_name = pipe_name()?;
+        let pipe = open_pipe(&pipe_name).await.with_context(|| {
+            format!(
+                "failed to connect to pipe {pipe_name}\n\
+                This usually means:\n\
+                1. The clipd daemon is not running - start it with: cargo run --bin clipd\n\
+                2. The daemon was started with different permissions (e.g., as administrator)\n\
+                3. Check if clipd is running: {RUNNING_CHECK}"
+            )
+        })?;
+        Ok(Self { pipe: Box::new(pipe) })
     }
 
     pub async fn send(&mut self, request: &Request) -> Result<()> {
-        let payload = serde_json::to_vec(request)?;
-        let len = payload.len() as u32;
-        self.pipe.write_u32_le(len).await?;
-        self.pipe.write_all(&payload).await?;
-        self.pipe.flush().await?;
-        Ok(())
+        Ok(write_frame(&mut self.pipe, request).await?)
+    }
+
+    /// Send one request and wait for its response; fails with clipd's
+    /// reason when it could not carry the request out.
+    pub async fn request(&mut self, kind: RequestKind) -> Result<Response> {
+        self.send(&Request { kind }).await?;
+        Ok(self.next_message().await?.into_result()?)
+    }
+
+    /// Exchange versions with the daemon.
+    ///
+    /// Fails with upgrade guidance when clipd speaks an older protocol, or
+    /// refuses ours as newer than its own; daemons that predate the handshake
+    /// drop the connection on the unknown request.
+    pub async fn handshake(&mut self) -> Result<ServerInfo> {
+        let client_version = env!("CARGO_PKG_VERSION");
+        self.send(&Request {
+            kind: RequestKind::Hello {
+                client_version: client_version.to_string(),
+                protocol: PROTOCOL_VERSION,
+            },
+        })
+        .await?;
+
+        let response = self.next_message().await.with_context(|| {
+            format!(
+                "clipd did not answer the version handshake; it is probably older than \
+                clipctl {client_version}.\n\
+                Restart the daemon from the same install: Stop-Process -Name clipd; clipd"
+            )
+        })?;
+        if let Some(error) = response.error {
+            if error.code == ErrorCode::UnsupportedProtocol {
+                bail!(
+                    "{error}.\n\
+                    Restart the daemon from the same install: Stop-Process -Name clipd; clipd"
+                );
+            }
+            return Err(error).context("clipd refused the version handshake");
+        }
+        let info = response
+            .server
+            .context("clipd answered the handshake without server information")?;
+
+        if info.protocol < PROTOCOL_VERSION {
+            bail!(
+                "clipd {} speaks protocol v{} but clipctl {} needs v{}.\n\
+                Restart the daemon from the same install: Stop-Process -Name clipd; clipd",
+                info.version,
+                info.protocol,
+                client_version,
+                PROTOCOL_VERSION
+            );
+        }
+        Ok(info)
     }
 
+    /// The next answer or event from clipd. A request that failed arrives
+    /// as a response with `error` set, not as `Err`.
     pub async fn next_message(&mut self) -> Result<Response> {
-        let len = self.pipe.read_u32_le().await?;
-        let mut buf = vec![0u8; len as usize];
-        self.pipe.read_exact(&mut buf).await?;
-        Ok(serde_json::from_slice(&buf)?)
+        Ok(read_frame(&mut self.pipe).await?)
     }
 }
 
@@ -0,0 +1,58 @@
+//! Editor-style jump list walked with Ctrl+o / Ctrl+i.
+
+/// Oldest jumps are forgotten past this point.
+const MAX_JUMPS: usize = 100;
+
+/// A viewed position: the entry plus the search that was active, so going back
+/// can restore a list the entry is actually in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jump {
+    pub id: u64,
+    pub filter: String,
+}
+
+#[derive(Debug, Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    /// Position of the jump currently shown; equals `jumps.len()` when the user
+    /// is past the newest recorded jump.
+    cursor: usize,
+}
+
+impl JumpList {
+    /// Remember the position being left by a jump, dropping any forward history.
+    pub fn record(&mut self, from: Jump) {
+        self.jumps.truncate(self.cursor);
+        if self.jumps.last() != Some(&from) {
+            self.jumps.push(from);
+        }
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.cursor = self.jumps.len();
+    }
+
+    /// Step back (Ctrl+o); `current` is kept so `forward` can return to it.
+    pub fn back(&mut self, current: Jump) -> Option<Jump> {
+        if self.cursor == self.jumps.len() {
+            if self.jumps.last() != Some(&current) {
+                self.jumps.push(current);
+            }
+            self.cursor = self.jumps.len() - 1;
+        }
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.jumps[self.cursor].clone())
+    }
+
+    /// Step forward (Ctrl+i) after going back.
+    pub fn forward(&mut self) -> Option<Jump> {
+        if self.cursor + 1 >= self.jumps.len() {
+            return None;
+        }
+        self.cursor += 1;
+        Some(self.jumps[self.cursor].clone())
+    }
+}
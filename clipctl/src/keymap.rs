@@ -0,0 +1,111 @@
+//! Key bindings from `[keys]` in config.toml: a key such as `X`, `ctrl+y`,
+//! or `F5` bound to a palette command such as `delete` or `copy sha`. In
+//! the history list they come before the built-in keys, so they can replace
+//! one; `Ctrl+w`, `Ctrl+o`, `Tab`, and `Alt+1`..`9` stay as they are.
+
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::palette::{Action, ACTIONS};
+
+#[derive(Debug, Clone)]
+struct Binding {
+    code: KeyCode,
+    /// Only Ctrl and Alt; Shift is in the character itself.
+    modifiers: KeyModifiers,
+    /// As written in the config, for the palette.
+    name: String,
+    action: Action,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    bindings: Vec<Binding>,
+}
+
+impl KeyMap {
+    /// The bindings in a `[keys]` table, and what is wrong with the ones
+    /// left out.
+    pub fn parse(table: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut bindings = Vec::new();
+        let mut problems = Vec::new();
+        for (name, command) in table {
+            let Some((code, modifiers)) = parse_key(name) else {
+                problems.push(format!(
+                    "keys.\"{name}\" is not a key; use a character, F1-F12, or a name like PageDown, \
+                     optionally after ctrl+ or alt+"
+                ));
+                continue;
+            };
+            let Some(spec) = ACTIONS.iter().find(|spec| spec.command == command.trim()) else {
+                problems.push(format!("keys.\"{name}\" is bound to \"{command}\", which is not a palette command"));
+                continue;
+            };
+            bindings.push(Binding {
+                code,
+                modifiers,
+                name: name.clone(),
+                action: spec.action,
+            });
+        }
+        (Self { bindings }, problems)
+    }
+
+    /// The action bound to a key pressed with `modifiers`.
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        self.bindings
+            .iter()
+            .find(|binding| binding.code == code && binding.modifiers == modifiers)
+            .map(|binding| binding.action)
+    }
+
+    /// The key bound to `action`, as the config writes it.
+    pub fn key_for(&self, action: Action) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(|binding| binding.name.as_str())
+    }
+}
+
+/// `ctrl+y`, `alt+F5`, `X`, or `PageDown` as a key code and modifiers.
+fn parse_key(name: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    // A `+` by itself, or after a modifier, is the key.
+    while let Some((prefix, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match prefix.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            _ => return None,
+        };
+        rest = key;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next()?, chars.next()) {
+        // Terminals send Ctrl+Y as Ctrl+y.
+        (c, None) if modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Char(c.to_ascii_lowercase()),
+        (c, None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            other => KeyCode::F(other.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+        },
+    };
+    Some((code, modifiers))
+}
@@ -0,0 +1,98 @@
+//! Keyboard macros: `qa` records keys into register `a` until the next `q`,
+//! and `@a` plays them back.
+
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::{bail, Result};
+use crossterm::event::KeyEvent;
+
+/// Playback stops once this many keys are played and queued, so a macro that
+/// replays itself ends.
+const MAX_PLAYED_KEYS: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    /// Keys still to play, fed to the UI one at a time.
+    queue: VecDeque<KeyEvent>,
+    /// Keys fed since playback started, counting those of every macro a
+    /// macro played; a register replaying itself never queues many at once.
+    played: usize,
+    /// Register replayed by `@@`.
+    last_played: Option<char>,
+}
+
+impl Macros {
+    /// Register being recorded into, if any.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Add a key typed by the user to the macro being recorded.
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+
+    /// Save the recording to its register, returning the register and its length.
+    pub fn stop_recording(&mut self) -> Option<(char, usize)> {
+        let (register, keys) = self.recording.take()?;
+        let len = keys.len();
+        self.registers.insert(register, keys);
+        Some((register, len))
+    }
+
+    /// Queue register `register` (`@` for the last one played) `count` times,
+    /// ahead of whatever is already queued, as when a macro plays another.
+    pub fn play(&mut self, register: char, count: usize) -> Result<()> {
+        let register = match register {
+            '@' => match self.last_played {
+                Some(last) => last,
+                None => bail!("no macro played yet"),
+            },
+            register => register,
+        };
+        let keys = match self.registers.get(&register) {
+            Some(keys) if !keys.is_empty() => keys,
+            _ => bail!("register {register} is empty"),
+        };
+        if self.played + self.queue.len() + keys.len().saturating_mul(count) > MAX_PLAYED_KEYS {
+            self.stop_playing();
+            bail!("macro stopped: more than {MAX_PLAYED_KEYS} keys");
+        }
+
+        self.last_played = Some(register);
+        for _ in 0..count {
+            for key in keys.iter().rev() {
+                self.queue.push_front(*key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Next key of a macro being played.
+    pub fn next_key(&mut self) -> Option<KeyEvent> {
+        let key = self.queue.pop_front()?;
+        self.played += 1;
+        Some(key)
+    }
+
+    /// Whether macro keys are waiting to be played.
+    pub fn playing(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Drop the rest of a macro being played, returning whether one was.
+    pub fn stop_playing(&mut self) -> bool {
+        let playing = !self.queue.is_empty();
+        self.queue.clear();
+        self.played = 0;
+        playing
+    }
+}
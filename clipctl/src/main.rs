@@ -1,34 +1,158 @@
 //! clipctl - terminal UI client for clipboard manager.
 
 mod app;
+mod cache;
+mod calendar;
+mod cli;
+mod commands;
+mod config;
+mod convert;
+mod daemon;
+mod doctor;
+mod embedded;
+mod encoding;
+mod exec;
+mod fzf;
+mod git;
+mod i18n;
+mod insights;
+mod input;
+mod invisible;
 mod ipc;
+mod jumps;
+mod keymap;
+mod macros;
+mod mux;
+mod onboarding;
+mod palette;
+mod panes;
 mod paste;
+mod profiles;
+mod remote;
+mod reveal;
+mod richtext;
+mod sessions;
+mod settings;
+mod stats;
 mod syntax;
+mod tabs;
 mod theme;
+mod transform;
 mod ui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::Parser;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use tokio::runtime::Runtime;
 
+use crate::cli::{BackupAction, Cli, Command, KeysAction, ProfileAction};
+use crate::config::ClientConfig;
+use crate::paste::PasteMethod;
+
 fn main() -> Result<()> {
+    let cli = Cli::parse();
+    // Passed on as the variables clipd reads, so a clipd that clipctl starts
+    // listens where clipctl connects.
+    if let Some(pipe) = &cli.pipe {
+        std::env::set_var("CLIPMGR_PIPE", pipe);
+    }
+    if let Some(profile) = &cli.profile {
+        std::env::set_var("CLIPMGR_PROFILE", profile);
+    }
+    if let Some(remote) = &cli.remote {
+        std::env::set_var("CLIPMGR_REMOTE", remote);
+    }
+    clipd::layers::set_flags(cli.set.clone());
     let rt = Runtime::new()?;
-    rt.block_on(async { run_async().await })
+    rt.block_on(async {
+        let embedded = if cli.embedded { Some(embedded::start()?) } else { None };
+        let result = match cli.command {
+            Some(Command::Config { action }) => config::run(action).await,
+            Some(Command::Doctor) => doctor::run().await,
+            Some(Command::Insights { days, output }) => insights::run(days, output.as_deref()).await,
+            Some(Command::Bridge) => remote::bridge().await,
+            Some(Command::Hold { text_len, kind }) => paste::hold(text_len, kind.as_deref()),
+            Some(Command::List { json, limit }) => commands::list(json, limit).await,
+            Some(Command::Search { query, json, limit }) => commands::search(query, json, limit).await,
+            Some(Command::Copy { id }) => commands::copy(id).await,
+            Some(Command::Delete { id }) => commands::delete(id).await,
+            Some(Command::Get { id, output, force }) => match output {
+                Some(path) => commands::save(id, &path, force).await,
+                None => commands::get(id).await,
+            },
+            Some(Command::Fzf { fzf_args }) => fzf::run(fzf_args).await,
+            Some(Command::Pick { exec, id }) => pick(exec, id).await,
+            Some(Command::Transform { name, id }) => commands::transform(name, id).await,
+            Some(Command::Type { id, wait_ms }) => commands::type_text(id, wait_ms).await,
+            Some(Command::Profile { action }) => match action.unwrap_or(ProfileAction::List) {
+                ProfileAction::List => commands::profiles().await,
+                ProfileAction::Use { name, save } => commands::use_profile(&name, save).await,
+            },
+            Some(Command::Snapshot) => commands::snapshot().await,
+            Some(Command::Restore { id }) => commands::restore(id).await,
+            Some(Command::Keys { action }) => match action.unwrap_or(KeysAction::List) {
+                KeysAction::List => commands::keys().await,
+                KeysAction::Rotate => commands::rotate_key().await,
+            },
+            Some(Command::Companion { reset }) => commands::companion(reset).await,
+            Some(Command::Backup { action }) => match action.unwrap_or(BackupAction::List) {
+                BackupAction::List => commands::backups().await,
+                BackupAction::Create => commands::create_backup().await,
+                BackupAction::Restore { name } => commands::restore_backup(name).await,
+            },
+            None => run_async().await,
+        };
+        if let Some(embedded) = embedded {
+            embedded.stop().await;
+        }
+        result
+    })
 }
 
 async fn run_async() -> Result<()> {
+    let config = ClientConfig::load()?;
+    if let Some(output) = run_tui(config).await? {
+        print!("{output}");
+    }
+    Ok(())
+}
+
+/// Run the TUI; returns text held back by the stdout paste method.
+async fn run_tui(config: ClientConfig) -> Result<Option<String>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     crossterm::execute!(stdout, EnableMouseCapture)?;
 
-    let res = app::App::run().await;
+    let res = app::App::run(config).await;
 
     crossterm::execute!(stdout, DisableMouseCapture)?;
     disable_raw_mode()?;
-
     res
 }
 
+/// `clipctl pick`: the TUI acts as a picker whose choice is piped to a command.
+async fn pick(exec: Option<String>, id: Option<u64>) -> Result<()> {
+    let mut config = ClientConfig::load()?;
+    let command = exec
+        .or_else(|| config.send_command.clone())
+        .context("no command to run; pass --exec or set general.send_command")?;
+
+    let text = match id {
+        Some(id) => commands::text(id).await?,
+        None => {
+            // Enter then hands the entry back instead of touching the clipboard.
+            config.paste_method = PasteMethod::Stdout;
+            match run_tui(config).await? {
+                Some(text) => text,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    print!("{}", exec::pipe_to_command(&command, &text)?);
+    Ok(())
+}
+
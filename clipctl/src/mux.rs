@@ -0,0 +1,238 @@
+//! Pasting into another pane of the terminal multiplexer clipctl runs in.
+//!
+//! With `general.paste_behavior = "pane"`, a paste inside tmux or WezTerm
+//! goes straight to a pane picked from a list instead of the clipboard:
+//! through a tmux paste buffer, or `wezterm cli send-text`. Both use
+//! bracketed paste when the program in the pane asks for it, so a shell does
+//! not run a multi-line entry line by line.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState};
+use ratatui::Frame;
+use serde::Deserialize;
+
+use crate::theme::Theme;
+
+/// tmux buffer the text passes through; deleted once pasted.
+const TMUX_BUFFER: &str = "clipctl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    WezTerm,
+}
+
+/// A pane that can be pasted into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuxPane {
+    /// `%3` in tmux, `7` in WezTerm: what the paste command targets.
+    pub id: String,
+    /// Where the pane is, such as `work:1.0` or `window 0 tab 2`.
+    pub location: String,
+    /// Program running in the pane, or its title when that is unknown.
+    pub title: String,
+}
+
+/// One pane of `wezterm cli list --format json`.
+#[derive(Deserialize)]
+struct WezTermPane {
+    window_id: u64,
+    tab_id: u64,
+    pane_id: u64,
+    #[serde(default)]
+    title: String,
+}
+
+impl Multiplexer {
+    /// The multiplexer clipctl runs in, if any. tmux started inside WezTerm
+    /// wins, since its panes are the ones on screen.
+    pub fn detect() -> Option<Self> {
+        let set = |name| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+        if set("TMUX") {
+            Some(Self::Tmux)
+        } else if set("WEZTERM_PANE") {
+            Some(Self::WezTerm)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tmux => "tmux",
+            Self::WezTerm => "WezTerm",
+        }
+    }
+
+    /// Every pane but the one clipctl runs in.
+    pub fn panes(self) -> Result<Vec<MuxPane>> {
+        match self {
+            Self::Tmux => {
+                let format = "#{pane_id}\t#{session_name}:#{window_index}.#{pane_index}\t#{pane_current_command}";
+                let output = run("tmux", &["list-panes", "-a", "-F", format], None)?;
+                Ok(parse_tmux_panes(&output, &own_pane("TMUX_PANE")))
+            }
+            Self::WezTerm => {
+                let output = run("wezterm", &["cli", "list", "--format", "json"], None)?;
+                let panes: Vec<WezTermPane> =
+                    serde_json::from_str(&output).context("failed to parse wezterm cli list")?;
+                let own = own_pane("WEZTERM_PANE");
+                Ok(panes
+                    .into_iter()
+                    .filter(|pane| pane.pane_id.to_string() != own)
+                    .map(|pane| MuxPane {
+                        id: pane.pane_id.to_string(),
+                        location: format!("window {} tab {}", pane.window_id, pane.tab_id),
+                        title: pane.title,
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Paste `text` into pane `id`, as if it came from the terminal's paste.
+    pub fn paste(self, id: &str, text: &str) -> Result<()> {
+        match self {
+            Self::Tmux => {
+                run("tmux", &["load-buffer", "-b", TMUX_BUFFER, "-"], Some(text))?;
+                run("tmux", &["paste-buffer", "-d", "-p", "-b", TMUX_BUFFER, "-t", id], None)?;
+            }
+            Self::WezTerm => {
+                run("wezterm", &["cli", "send-text", "--pane-id", id], Some(text))?;
+            }
+        }
+        tracing::info!(multiplexer = self.name(), pane = id, "pasted {} chars into pane", text.len());
+        Ok(())
+    }
+}
+
+/// Id of the pane clipctl runs in, from environment variable `name`.
+fn own_pane(name: &str) -> String {
+    std::env::var(name).unwrap_or_default()
+}
+
+/// Panes in `tmux list-panes` output, one `id\tlocation\tcommand` per line,
+/// without pane `own`.
+fn parse_tmux_panes(output: &str, own: &str) -> Vec<MuxPane> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let id = fields.next()?;
+            Some(MuxPane {
+                id: id.to_string(),
+                location: fields.next()?.to_string(),
+                title: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .filter(|pane| pane.id != own)
+        .collect()
+}
+
+/// Run `program` with `args`, writing `input` to its stdin, and return its stdout.
+fn run(program: &str, args: &[&str], input: Option<&str>) -> Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start {program}"))?;
+    if let Some(input) = input {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin
+            .write_all(input.as_bytes())
+            .with_context(|| format!("failed to write to {program}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to wait for {program}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{program} {} exited with {}: {}", args[0], output.status, stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Pane picker: where a `pane` paste goes.
+pub struct PanePicker {
+    multiplexer: Multiplexer,
+    panes: Vec<MuxPane>,
+    selected: usize,
+}
+
+impl PanePicker {
+    /// The panes of `multiplexer`, with pane `last` selected if it is still there.
+    pub fn new(multiplexer: Multiplexer, panes: Vec<MuxPane>, last: Option<&str>) -> Self {
+        let selected = last
+            .and_then(|last| panes.iter().position(|pane| pane.id == last))
+            .unwrap_or(0);
+        Self {
+            multiplexer,
+            panes,
+            selected,
+        }
+    }
+
+    pub fn multiplexer(&self) -> Multiplexer {
+        self.multiplexer
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.panes.len();
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&MuxPane> {
+        self.panes.get(self.selected)
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let width = self
+            .panes
+            .iter()
+            .map(|pane| pane.location.chars().count())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<_> = self
+            .panes
+            .iter()
+            .map(|pane| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<width$}", pane.location), theme.style_list_item()),
+                    Span::styled(format!("  {:>4}  ", pane.id), theme.style_metadata_label()),
+                    Span::styled(
+                        pane.title.clone(),
+                        Style::default()
+                            .fg(theme.metadata_value)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select((!items.is_empty()).then_some(self.selected));
+
+        let title = format!(" {} panes · Enter paste · Esc cancel ", self.multiplexer.name());
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(Span::styled(title, theme.style_title()))
+                    .borders(theme.glyphs.borders())
+                    .border_style(theme.style_border_focused())
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(theme.style_list_selected())
+            .highlight_symbol(theme.glyphs.selected());
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
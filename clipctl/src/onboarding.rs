@@ -0,0 +1,213 @@
+//! First-run setup wizard shown before the history list when no config exists.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::theme::Theme;
+
+const THEMES: &[&str] = &["nord", "dracula", "tokyo-night", "gruvbox", "high-contrast"];
+const RETENTION: &[usize] = &[1_000, 10_000, 50_000, 100_000];
+/// Index into `RETENTION` matching clipd's built-in default.
+const DEFAULT_RETENTION: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Theme,
+    Retention,
+    Images,
+    Autostart,
+    Confirm,
+}
+
+const STEPS: &[Step] = &[
+    Step::Theme,
+    Step::Retention,
+    Step::Images,
+    Step::Autostart,
+    Step::Confirm,
+];
+
+/// Answers collected by the wizard; every field starts at the built-in default.
+pub struct Onboarding {
+    step: usize,
+    theme: usize,
+    retention: usize,
+    capture_images: bool,
+    autostart: bool,
+}
+
+impl Onboarding {
+    pub fn new() -> Self {
+        Self {
+            step: 0,
+            theme: 0,
+            retention: DEFAULT_RETENTION,
+            capture_images: true,
+            autostart: false,
+        }
+    }
+
+    pub fn theme_name(&self) -> &'static str {
+        THEMES[self.theme]
+    }
+
+    pub fn autostart(&self) -> bool {
+        self.autostart
+    }
+
+    /// Config keys to write, in the form `config::create` expects.
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let kinds = if self.capture_images {
+            "text,url,rtf,image,files"
+        } else {
+            "text,url,rtf,files"
+        };
+        vec![
+            ("ui.theme", self.theme_name().to_string()),
+            ("general.max_entries", RETENTION[self.retention].to_string()),
+            ("capture.allowed_kinds", kinds.to_string()),
+        ]
+    }
+
+    /// Apply a key press; returns true once the user is done.
+    ///
+    /// Esc finishes early, keeping defaults for the questions not yet answered.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => return true,
+            KeyCode::Enter if STEPS[self.step] == Step::Confirm => return true,
+            KeyCode::Enter | KeyCode::Tab => self.step = (self.step + 1).min(STEPS.len() - 1),
+            KeyCode::Backspace | KeyCode::BackTab => self.step = self.step.saturating_sub(1),
+            KeyCode::Char('j') | KeyCode::Down => self.cycle(1),
+            KeyCode::Char('k') | KeyCode::Up => self.cycle(-1),
+            KeyCode::Char('y') => self.set_flag(true),
+            KeyCode::Char('n') => self.set_flag(false),
+            _ => {}
+        }
+        false
+    }
+
+    fn cycle(&mut self, delta: isize) {
+        let wrap = |value: usize, len: usize| (value as isize + delta).rem_euclid(len as isize) as usize;
+        match STEPS[self.step] {
+            Step::Theme => self.theme = wrap(self.theme, THEMES.len()),
+            Step::Retention => self.retention = wrap(self.retention, RETENTION.len()),
+            Step::Images => self.capture_images = !self.capture_images,
+            Step::Autostart => self.autostart = !self.autostart,
+            Step::Confirm => {}
+        }
+    }
+
+    fn set_flag(&mut self, value: bool) {
+        match STEPS[self.step] {
+            Step::Images => self.capture_images = value,
+            Step::Autostart => self.autostart = value,
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = centered(frame.size(), 64, 16);
+
+        let (question, options): (&str, Vec<(String, bool)>) = match STEPS[self.step] {
+            Step::Theme => (
+                "Choose a color theme (previewed live):",
+                THEMES
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| (name.to_string(), i == self.theme))
+                    .collect(),
+            ),
+            Step::Retention => (
+                "How many entries should clipd keep?",
+                RETENTION
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| (format!("{n} entries"), i == self.retention))
+                    .collect(),
+            ),
+            Step::Images => (
+                "Capture images copied to the clipboard?",
+                yes_no(self.capture_images),
+            ),
+            Step::Autostart => (
+                "Start clipd automatically when you sign in?",
+                yes_no(self.autostart),
+            ),
+            Step::Confirm => (
+                "Save these settings?",
+                vec![
+                    (format!("theme: {}", self.theme_name()), false),
+                    (format!("keep: {} entries", RETENTION[self.retention]), false),
+                    (format!("capture images: {}", on_off(self.capture_images)), false),
+                    (format!("start at sign-in: {}", on_off(self.autostart)), false),
+                ],
+            ),
+        };
+
+        let mut lines = vec![
+            Line::styled(
+                format!("Step {} of {}", self.step + 1, STEPS.len()),
+                theme.style_metadata_label(),
+            ),
+            Line::raw(""),
+            Line::styled(question, theme.style_help_section()),
+            Line::raw(""),
+        ];
+        for (label, selected) in options {
+            lines.push(if selected {
+                Line::from(Span::styled(format!("  {}{label}", theme.glyphs.selected()), theme.style_list_selected()))
+            } else {
+                Line::from(Span::styled(format!("    {label}"), theme.style_list_item()))
+            });
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            match STEPS[self.step] {
+                Step::Confirm => "Enter save · Backspace back · Esc save",
+                _ => "j/k choose · Enter next · Backspace back · Esc keep defaults",
+            },
+            Style::default()
+                .fg(theme.metadata_label)
+                .add_modifier(Modifier::ITALIC),
+        ));
+
+        let wizard = Paragraph::new(lines).block(
+            Block::default()
+                .title(Span::styled(" Welcome to rusty-clipboard ", theme.style_title()))
+                .borders(theme.glyphs.borders())
+                .border_style(theme.style_border_focused())
+                .title_alignment(Alignment::Center),
+        );
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(wizard, area);
+    }
+}
+
+fn yes_no(value: bool) -> Vec<(String, bool)> {
+    vec![("yes".to_string(), value), ("no".to_string(), !value)]
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,694 @@
+//! Command palette (`:`): every TUI action, searchable by fuzzy match.
+
+use std::cmp::Reverse;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState};
+use ratatui::Frame;
+
+use crate::convert::Conversion;
+use crate::ipc::{Digest, EntityKind};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::transform::Transform;
+
+/// Something the user can do from the palette; most also have a key binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Paste,
+    PasteAs(Transform),
+    /// The palette narrowed to the `paste ...` transforms.
+    Transforms,
+    Search,
+    AddTag,
+    RemoveTag,
+    /// Tag or delete every entry matching the current search.
+    TagResults,
+    DeleteResults,
+    Delete,
+    /// Delete unpinned entries older than a day, week, or month.
+    ClearHistory,
+    /// Delete every entry, pinned and archived ones too, and the backups.
+    WipeHistory,
+    TogglePin,
+    /// Split the list into two panes with their own filters, or join them.
+    ToggleSplit,
+    SwitchPane,
+    /// Pin or tag the entry for the other pane, per its filter.
+    CopyToPane,
+    /// Move the selected pinned entry up (`true`) or down the snippet board.
+    MovePin(bool),
+    Export,
+    ExportRedacted,
+    ExportEncrypted,
+    Import,
+    SendToCommand,
+    SaveToFile,
+    /// Save a diff or patch entry as a `.patch` file for `git apply` or `git am`.
+    SavePatch,
+    ShowImage,
+    Calendar,
+    Sessions,
+    Profiles,
+    ApproveSource,
+    /// Move an archived entry back into the history.
+    Unarchive,
+    Checksums,
+    ViewSource,
+    ViewGroup,
+    PasteGroup,
+    Snapshot,
+    /// Stop capturing for a while, or resume if capture is paused.
+    PauseCapture,
+    CopyDigest(Digest),
+    CopyConverted(Conversion),
+    /// Copy the first entity of a kind in the entry, like an email address
+    /// or a commit hash, without the text around it.
+    CopyEntity(EntityKind),
+    /// Open a new email to the addresses in the entry.
+    ComposeMail,
+    /// Dial the first phone number in the entry.
+    Call,
+    Settings,
+    SetTheme(&'static str),
+    ToggleImageCapture,
+    ToggleGuestMode,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// Whether the action sends the selected entry's id to clipd, which for
+    /// an archived entry names nothing in the history.
+    pub fn needs_history_entry(self) -> bool {
+        matches!(
+            self,
+            Action::Paste
+                | Action::PasteAs(_)
+                | Action::AddTag
+                | Action::RemoveTag
+                | Action::Delete
+                | Action::TogglePin
+                | Action::CopyToPane
+                | Action::MovePin(_)
+                | Action::SaveToFile
+                | Action::SavePatch
+                | Action::ShowImage
+                | Action::ApproveSource
+                | Action::Checksums
+                | Action::ViewSource
+                | Action::ViewGroup
+                | Action::PasteGroup
+                | Action::CopyDigest(_)
+        )
+    }
+}
+
+pub struct ActionSpec {
+    pub action: Action,
+    /// Short name that `:name` matches first, e.g. `:set`.
+    pub command: &'static str,
+    pub title: &'static str,
+    /// Key binding shown next to the title, if any.
+    pub key: &'static str,
+}
+
+pub const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        action: Action::Paste,
+        command: "paste",
+        title: "Paste selected entry",
+        key: "Enter",
+    },
+    ActionSpec {
+        action: Action::Transforms,
+        command: "transform",
+        title: "Paste transformed...",
+        key: "x",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Sanitized),
+        command: "paste sanitized",
+        title: "Paste without zero-width, bidi, and control characters",
+        key: "L",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Lf),
+        command: "paste lf",
+        title: "Paste with LF line endings",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Crlf),
+        command: "paste crlf",
+        title: "Paste with CRLF line endings",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::WithoutBom),
+        command: "paste nobom",
+        title: "Paste as UTF-8 without BOM",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::SortLines),
+        command: "paste sorted",
+        title: "Paste with lines sorted",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::UniqueLines),
+        command: "paste unique",
+        title: "Paste without duplicate lines",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::ReverseLines),
+        command: "paste reversed",
+        title: "Paste with lines in reverse order",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::NumberLines),
+        command: "paste numbered",
+        title: "Paste with numbered lines",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Upper),
+        command: "paste upper",
+        title: "Paste in UPPER CASE",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Lower),
+        command: "paste lower",
+        title: "Paste in lower case",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::TitleCase),
+        command: "paste title",
+        title: "Paste in Title Case",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::CamelCase),
+        command: "paste camel",
+        title: "Paste as camelCase",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::SnakeCase),
+        command: "paste snake",
+        title: "Paste as snake_case",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::KebabCase),
+        command: "paste kebab",
+        title: "Paste as kebab-case",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::PasteAs(Transform::Slug),
+        command: "paste slug",
+        title: "Paste as a URL slug",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::Search,
+        command: "search",
+        title: "Search history",
+        key: "/",
+    },
+    ActionSpec {
+        action: Action::AddTag,
+        command: "tag",
+        title: "Add tag to entry",
+        key: "t",
+    },
+    ActionSpec {
+        action: Action::RemoveTag,
+        command: "untag",
+        title: "Remove tag from entry",
+        key: "T",
+    },
+    ActionSpec {
+        action: Action::Delete,
+        command: "delete",
+        title: "Delete entry",
+        key: "d",
+    },
+    ActionSpec {
+        action: Action::ClearHistory,
+        command: "clear",
+        title: "Clear entries older than a day, week, or month",
+        key: "D",
+    },
+    ActionSpec {
+        action: Action::WipeHistory,
+        command: "wipe",
+        title: "Wipe the whole history, pins, archive, and backups",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::TagResults,
+        command: "tag all",
+        title: "Tag every entry matching the search",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::DeleteResults,
+        command: "delete all",
+        title: "Delete every entry matching the search",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::ToggleSplit,
+        command: "split",
+        title: "Split the list into two panes, or join them",
+        key: "v",
+    },
+    ActionSpec {
+        action: Action::SwitchPane,
+        command: "pane",
+        title: "Focus the other pane",
+        key: "Ctrl+w",
+    },
+    ActionSpec {
+        action: Action::CopyToPane,
+        command: "copy to pane",
+        title: "Copy entry into the other pane's pins or tag",
+        key: ">",
+    },
+    ActionSpec {
+        action: Action::TogglePin,
+        command: "pin",
+        title: "Pin entry to the top, or unpin it",
+        key: "p",
+    },
+    ActionSpec {
+        action: Action::MovePin(true),
+        command: "pin up",
+        title: "Move pinned entry up",
+        key: "K",
+    },
+    ActionSpec {
+        action: Action::MovePin(false),
+        command: "pin down",
+        title: "Move pinned entry down",
+        key: "J",
+    },
+    ActionSpec {
+        action: Action::Export,
+        command: "export",
+        title: "Export history to JSON",
+        key: "e",
+    },
+    ActionSpec {
+        action: Action::ExportRedacted,
+        command: "export redacted",
+        title: "Export history with secrets masked",
+        key: "E",
+    },
+    ActionSpec {
+        action: Action::ExportEncrypted,
+        command: "export encrypted",
+        title: "Export history protected by a passphrase",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::Import,
+        command: "import",
+        title: "Import history from JSON",
+        key: "i",
+    },
+    ActionSpec {
+        action: Action::SendToCommand,
+        command: "send",
+        title: "Send entry to command",
+        key: "|",
+    },
+    ActionSpec {
+        action: Action::SaveToFile,
+        command: "save",
+        title: "Save entry to file",
+        key: "w",
+    },
+    ActionSpec {
+        action: Action::SavePatch,
+        command: "apply",
+        title: "Save diff or patch to a .patch file",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::ShowImage,
+        command: "drag",
+        title: "Show image in Explorer",
+        key: "o",
+    },
+    ActionSpec {
+        action: Action::Calendar,
+        command: "calendar",
+        title: "Browse history by day",
+        key: "c",
+    },
+    ActionSpec {
+        action: Action::Sessions,
+        command: "sessions",
+        title: "Browse capture sessions",
+        key: "S",
+    },
+    ActionSpec {
+        action: Action::Profiles,
+        command: "profile",
+        title: "Switch profile",
+        key: "P",
+    },
+    ActionSpec {
+        action: Action::ApproveSource,
+        command: "approve",
+        title: "Trust the source of a quarantined entry",
+        key: "A",
+    },
+    ActionSpec {
+        action: Action::Unarchive,
+        command: "unarchive",
+        title: "Restore archived entry to the history",
+        key: "u",
+    },
+    ActionSpec {
+        action: Action::Checksums,
+        command: "checksum",
+        title: "Show SHA-256, MD5, and CRC32 of entry",
+        key: "#",
+    },
+    ActionSpec {
+        action: Action::ViewSource,
+        command: "source",
+        title: "Toggle RTF or HTML source in preview",
+        key: "s",
+    },
+    ActionSpec {
+        action: Action::ViewGroup,
+        command: "group",
+        title: "List entries copied together with this one",
+        key: "r",
+    },
+    ActionSpec {
+        action: Action::PasteGroup,
+        command: "paste group",
+        title: "Paste entries copied together, one per line",
+        key: "R",
+    },
+    ActionSpec {
+        action: Action::Snapshot,
+        command: "snapshot",
+        title: "Store every format on the clipboard as one entry",
+        key: "z",
+    },
+    ActionSpec {
+        action: Action::PauseCapture,
+        command: "pause",
+        title: "Pause or resume capture",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyDigest(Digest::Sha256),
+        command: "copy sha256",
+        title: "Copy SHA-256 of entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyDigest(Digest::Md5),
+        command: "copy md5",
+        title: "Copy MD5 of entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyDigest(Digest::Crc32),
+        command: "copy crc32",
+        title: "Copy CRC32 of entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::LocalTime),
+        command: "copy time",
+        title: "Copy timestamp as local time",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::Decimal),
+        command: "copy decimal",
+        title: "Copy hex or binary number in decimal",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::Hex),
+        command: "copy hex",
+        title: "Copy number in hex",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::Size),
+        command: "copy size",
+        title: "Copy byte count as KiB/MiB/GiB",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::Bytes),
+        command: "copy bytes",
+        title: "Copy size in bytes",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyConverted(Conversion::Unit),
+        command: "copy unit",
+        title: "Copy quantity in metric/imperial units",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Email),
+        command: "copy email",
+        title: "Copy email address in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Phone),
+        command: "copy phone",
+        title: "Copy phone number in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Ipv4),
+        command: "copy ipv4",
+        title: "Copy IPv4 address in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Ipv6),
+        command: "copy ipv6",
+        title: "Copy IPv6 address in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Uuid),
+        command: "copy uuid",
+        title: "Copy UUID in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Sha),
+        command: "copy sha",
+        title: "Copy only the commit hash in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Container),
+        command: "copy container",
+        title: "Copy container id in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::CopyEntity(EntityKind::Issue),
+        command: "copy issue",
+        title: "Copy issue reference in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::ComposeMail,
+        command: "mail",
+        title: "Compose email to addresses in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::Call,
+        command: "call",
+        title: "Call phone number in entry",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::Settings,
+        command: "set",
+        title: "Open settings",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::SetTheme("nord"),
+        command: "theme nord",
+        title: "Change theme: Nord",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::SetTheme("dracula"),
+        command: "theme dracula",
+        title: "Change theme: Dracula",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::SetTheme("tokyo-night"),
+        command: "theme tokyo-night",
+        title: "Change theme: Tokyo Night",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::SetTheme("gruvbox"),
+        command: "theme gruvbox",
+        title: "Change theme: Gruvbox",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::SetTheme("high-contrast"),
+        command: "theme high-contrast",
+        title: "Change theme: High contrast",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::ToggleImageCapture,
+        command: "capture images",
+        title: "Toggle image capture",
+        key: "",
+    },
+    ActionSpec {
+        action: Action::ToggleGuestMode,
+        command: "guest",
+        title: "Toggle guest mode (hide content)",
+        key: "H",
+    },
+    ActionSpec {
+        action: Action::Help,
+        command: "help",
+        title: "Show keybindings",
+        key: "?",
+    },
+    ActionSpec {
+        action: Action::Quit,
+        command: "quit",
+        title: "Quit",
+        key: "Esc",
+    },
+];
+
+#[derive(Default)]
+pub struct Palette {
+    query: String,
+    selected: usize,
+}
+
+impl Palette {
+    /// A palette opened with `query` already typed.
+    pub fn with_query(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            selected: 0,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.matches().len();
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    /// Actions matching the query, best first; an exact `command` always wins.
+    pub fn matches(&self) -> Vec<&'static ActionSpec> {
+        let query = self.query.trim();
+        if query.is_empty() {
+            return ACTIONS.iter().collect();
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<_> = ACTIONS
+            .iter()
+            .filter_map(|spec| {
+                if spec.command == query {
+                    return Some((i64::MAX, spec));
+                }
+                let haystack = format!("{} {}", spec.command, spec.title);
+                matcher.fuzzy_match(&haystack, query).map(|score| (score, spec))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        scored.into_iter().map(|(_, spec)| spec).collect()
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches().get(self.selected).map(|spec| spec.action)
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme, keys: &KeyMap) {
+        let items: Vec<_> = self
+            .matches()
+            .into_iter()
+            .map(|spec| {
+                let key = keys.key_for(spec.action).unwrap_or(spec.key);
+                let spans = vec![
+                    Span::styled(format!("{:<28}", spec.title), theme.style_list_item()),
+                    Span::styled(format!("{key:<6}"), theme.style_help_key()),
+                    Span::styled(
+                    format!("  :{}", spec.command),
+                    Style::default()
+                        .fg(theme.metadata_label)
+                        .add_modifier(Modifier::ITALIC),
+                    ),
+                ];
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select((!items.is_empty()).then_some(self.selected));
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(Span::styled(" Command Palette ", theme.style_title()))
+                    .borders(theme.glyphs.borders())
+                    .border_style(theme.style_border_focused())
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(theme.style_list_selected())
+            .highlight_symbol(theme.glyphs.selected());
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -0,0 +1,68 @@
+//! Split view: a second history list beside the first, with its own filter.
+//!
+//! The focused pane lives in `TerminalUi`'s own fields; the other one is a
+//! [`Pane`] swapped in and out as focus moves.
+
+use ratatui::widgets::ListState;
+
+use crate::ipc::EntrySummary;
+
+/// Search filters clipd parses, which name no tag to copy entries into.
+const FILTER_PREFIXES: &[&str] = &["monitor:", "date:", "session:", "group:", "lang:", "kind:", "is:", "sort:"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Pane {
+    pub entries: Vec<EntrySummary>,
+    pub selected: usize,
+    pub filter: String,
+    pub list_state: ListState,
+    /// Where the next page of the history starts, while more is left.
+    pub next_cursor: Option<String>,
+}
+
+impl Pane {
+    /// Replace the listed entries, keeping the selection in range.
+    pub fn show(&mut self, entries: Vec<EntrySummary>, next_cursor: Option<String>) {
+        self.selected = self.selected.min(entries.len().saturating_sub(1));
+        self.entries = entries;
+        self.next_cursor = next_cursor;
+    }
+}
+
+/// What copying an entry into a pane does, judged by the pane's filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collection {
+    /// `is:pinned`: pin the entry.
+    Pinned,
+    /// A single tag such as `work` or `project:acme`: add the tag.
+    Tag(String),
+}
+
+impl Collection {
+    pub fn of(filter: &str) -> Option<Self> {
+        let filter = filter.trim();
+        if filter == "is:pinned" {
+            return Some(Self::Pinned);
+        }
+        let is_tag = !filter.is_empty()
+            && !filter.contains(char::is_whitespace)
+            && !filter.contains(['<', '>', '=', '!'])
+            && !FILTER_PREFIXES.iter().any(|prefix| filter.starts_with(prefix));
+        is_tag.then(|| Self::Tag(filter.to_string()))
+    }
+}
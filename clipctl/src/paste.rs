@@ -1,47 +1,464 @@
 //! Abstractions for sending paste actions to the active terminal.
 
+use std::time::Duration;
+
 use anyhow::Result;
 
-#[allow(dead_code)]
+use crate::mux::Multiplexer;
+use crate::remote;
+
+/// Registered clipboard format whose presence keeps the clipboard out of
+/// Windows clipboard history (Win+V) and cloud sync.
+#[cfg(target_os = "windows")]
+const EXCLUDE_FROM_HISTORY_FORMAT: &str = "ExcludeClipboardContentFromMonitorProcessing";
+
+/// Registered clipboard format holding the id of the pasted entry, so clipd
+/// moves that entry to the top instead of capturing the paste as a new one.
+#[cfg(target_os = "windows")]
+const PASTED_ENTRY_FORMAT: &str = "RustyClipboardEntry";
+
+/// What `clipctl hold` prints once it is about to take the clipboard.
+#[cfg(not(target_os = "windows"))]
+const HOLDER_READY: &str = "ready";
+
+/// How long `clipctl type` started by the TUI waits before typing, so the
+/// terminal is gone and the target window is in front again.
+#[cfg(target_os = "windows")]
+const TYPE_WAIT: Duration = Duration::from_millis(500);
+
+/// Registered clipboard formats of RTF and HTML documents.
+const RTF_FORMAT: &str = "Rich Text Format";
+const HTML_FORMAT: &str = "HTML Format";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PasteMethod {
     SendInput,
     Stdout,
+    /// Type the text as keystrokes, for windows that refuse Ctrl+V.
+    Type,
+    /// Paste into a tmux or WezTerm pane picked from a list; outside both,
+    /// the clipboard like `SendInput`.
+    Pane,
+}
+
+impl PasteMethod {
+    /// Parse the `general.paste_behavior` config value.
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "send_input" => Some(Self::SendInput),
+            "stdout" => Some(Self::Stdout),
+            "type" => Some(Self::Type),
+            "pane" => Some(Self::Pane),
+            _ => None,
+        }
+    }
+
+    /// The `general.paste_behavior` value that selects this method.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SendInput => "send_input",
+            Self::Stdout => "stdout",
+            Self::Type => "type",
+            Self::Pane => "pane",
+        }
+    }
+}
+
+/// An image or document entry's data, put on the clipboard in its own
+/// format rather than as its text.
+#[derive(Debug)]
+pub enum Payload {
+    /// An image, as the DIB clipd stores.
+    Dib(Vec<u8>),
+    /// An RTF or HTML document, under its registered format.
+    Document { format: &'static str, data: Vec<u8> },
+}
+
+impl Payload {
+    /// The payload of an entry of `kind` with `data`, for the kinds that have one.
+    pub fn new(kind: &str, data: Vec<u8>) -> Option<Self> {
+        match kind {
+            _ if data.is_empty() => None,
+            "image" => Some(Self::Dib(data)),
+            "rtf" => Some(Self::Document { format: RTF_FORMAT, data }),
+            "html" => Some(Self::Document { format: HTML_FORMAT, data }),
+            _ => None,
+        }
+    }
+
+    /// The entry kind `new` takes for this payload.
+    #[cfg(target_os = "linux")]
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Dib(_) => "image",
+            Self::Document { format: RTF_FORMAT, .. } => "rtf",
+            Self::Document { .. } => "html",
+        }
+    }
+
+    /// Name of the clipboard format the payload goes on as.
+    fn format_name(&self) -> &'static str {
+        match self {
+            Self::Dib(_) => "CF_DIB",
+            Self::Document { format, .. } => format,
+        }
+    }
+
+    fn data(&self) -> &[u8] {
+        match self {
+            Self::Dib(data) | Self::Document { data, .. } => data,
+        }
+    }
 }
 
 pub struct PasteEngine {
     method: PasteMethod,
+    /// `general.hide_from_windows_history`: keep pasted entries out of Win+V.
+    hide_from_history: bool,
+    /// Text held back until the TUI has left the alternate screen.
+    deferred: Option<String>,
+    /// Pane a `Pane` paste goes to, once one is picked.
+    target: Option<(Multiplexer, String)>,
 }
 
 impl PasteEngine {
-    pub fn new(method: PasteMethod) -> Self {
-        Self { method }
+    pub fn new(method: PasteMethod, hide_from_history: bool) -> Self {
+        Self {
+            method,
+            hide_from_history,
+            deferred: None,
+            target: None,
+        }
     }
 
-    pub fn paste(&self, contents: &str) -> Result<()> {
+    pub fn method(&self) -> PasteMethod {
+        self.method
+    }
+
+    pub fn hides_from_history(&self) -> bool {
+        self.hide_from_history
+    }
+
+    pub fn set_method(&mut self, method: PasteMethod) {
+        self.method = method;
+    }
+
+    pub fn set_hide_from_history(&mut self, hide: bool) {
+        self.hide_from_history = hide;
+    }
+
+    /// Pane `PasteMethod::Pane` pastes into, last picked.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_ref().map(|(_, id)| id.as_str())
+    }
+
+    pub fn set_target(&mut self, multiplexer: Multiplexer, pane: String) {
+        self.target = Some((multiplexer, pane));
+    }
+
+    /// Output for `PasteMethod::Stdout`, to print once the terminal is restored.
+    pub fn take_deferred(&mut self) -> Option<String> {
+        self.deferred.take()
+    }
+
+    /// Paste `contents`, the text of history entry `entry` if it came from one.
+    pub fn paste(&mut self, contents: &str, entry: Option<u64>) -> Result<()> {
+        // A remote entry's id would name some other entry to the clipd here.
+        let entry = entry.filter(|_| !remote::active());
         match self.method {
             PasteMethod::SendInput => {
                 // Set the clipboard so the text is available for pasting
-                set_clipboard(contents)?;
+                set_pasted(contents, None, entry, self.hide_from_history)?;
                 tracing::info!("Set clipboard with {} chars", contents.len());
                 Ok(())
             }
             PasteMethod::Stdout => {
-                print!("{contents}");
+                // Printing now would land on the alternate screen and vanish on exit.
+                self.deferred = Some(contents.to_string());
                 Ok(())
             }
+            PasteMethod::Pane => match &self.target {
+                Some((multiplexer, pane)) => multiplexer.paste(pane, contents),
+                None => set_pasted(contents, None, entry, self.hide_from_history),
+            },
+            PasteMethod::Type => {
+                // The terminal is still in front; a separate process types once it is gone.
+                spawn_typist(contents)?;
+                tracing::info!("Typing {} chars", contents.chars().count());
+                Ok(())
+            }
+        }
+    }
+
+    /// Put entry `entry` on the clipboard with its `payload`, and `text` for
+    /// apps that only take text; an image goes on without its placeholder.
+    /// Only the clipboard can carry a payload, so this is for `SendInput`
+    /// and a `Pane` paste outside tmux and WezTerm.
+    pub fn paste_payload(&mut self, text: &str, payload: &Payload, entry: u64) -> Result<()> {
+        let entry = Some(entry).filter(|_| !remote::active());
+        set_pasted(text, Some(payload), entry, self.hide_from_history)?;
+        tracing::info!(
+            ?entry,
+            format = payload.format_name(),
+            "Set clipboard with {} bytes of entry data",
+            payload.data().len()
+        );
+        Ok(())
+    }
+}
+
+/// Start `clipctl type` in the background with `text` on its stdin, outliving
+/// this process and the terminal window it runs in.
+#[cfg(target_os = "windows")]
+fn spawn_typist(text: &str) -> Result<()> {
+    use std::io::Write;
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    use anyhow::Context;
+
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let exe = std::env::current_exe().context("failed to locate clipctl")?;
+    let mut child = Command::new(exe)
+        .args(["type", "--wait-ms", &TYPE_WAIT.as_millis().to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW)
+        .spawn()
+        .context("failed to start clipctl type")?;
+    // Closing stdin tells the typist it has the whole text.
+    let mut stdin = child.stdin.take().context("clipctl type has no stdin")?;
+    stdin
+        .write_all(text.as_bytes())
+        .context("failed to hand the text to clipctl type")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn spawn_typist(_text: &str) -> Result<()> {
+    anyhow::bail!("Typing is only supported on Windows")
+}
+
+/// Type `text` into the window in front, one character every `delay`.
+///
+/// Characters go out as `KEYEVENTF_UNICODE` keystrokes, so they arrive as
+/// typed whatever the keyboard layout; line breaks and tabs are pressed as
+/// Enter and Tab, which is what consoles and form fields expect.
+#[cfg(target_os = "windows")]
+pub fn type_text(text: &str, delay: Duration) -> Result<()> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_RETURN, VK_TAB,
+    };
+
+    let key = |vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let press = |vk: VIRTUAL_KEY| vec![key(vk, 0, KEYBD_EVENT_FLAGS(0)), key(vk, 0, KEYEVENTF_KEYUP)];
+
+    let mut typed = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let inputs = match c {
+            // CRLF is one line break.
+            '\r' if chars.peek() == Some(&'\n') => continue,
+            '\r' | '\n' => press(VK_RETURN),
+            '\t' => press(VK_TAB),
+            _ => {
+                // Both halves of a surrogate pair go down before either comes up.
+                let mut buffer = [0u16; 2];
+                let units = c.encode_utf16(&mut buffer);
+                let down = units.iter().map(|&unit| key(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE));
+                let up = units
+                    .iter()
+                    .map(|&unit| key(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                down.chain(up).collect()
+            }
+        };
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            anyhow::bail!(
+                "typing stopped after {typed} characters; Windows blocks keystrokes into windows running as administrator"
+            );
         }
+        typed += 1;
+        std::thread::sleep(delay);
     }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn type_text(_text: &str, _delay: Duration) -> Result<()> {
+    anyhow::bail!("Typing is only supported on Windows")
 }
 
 #[cfg(target_os = "windows")]
-fn set_clipboard(text: &str) -> Result<()> {
+pub fn set_clipboard(text: &str) -> Result<()> {
     use clipboard_win::{formats, set_clipboard as set_clip};
     set_clip(formats::Unicode, text)
         .map_err(|e| anyhow::anyhow!("failed to set clipboard: {:?}", e))
 }
 
 #[cfg(not(target_os = "windows"))]
-fn set_clipboard(_text: &str) -> Result<()> {
-    anyhow::bail!("Clipboard setting is only supported on Windows")
+pub fn set_clipboard(text: &str) -> Result<()> {
+    set_pasted(text, None, None, false)
+}
+
+/// Set the clipboard to `text`, or `payload` with it, pasted from entry
+/// `entry`, marked with the entry's id for clipd and, with
+/// `hide_from_history`, so Windows clipboard history does not record it;
+/// entries pasted from our history would otherwise end up in both.
+#[cfg(target_os = "windows")]
+fn set_pasted(text: &str, payload: Option<&Payload>, entry: Option<u64>, hide_from_history: bool) -> Result<()> {
+    use clipboard_win::{formats, raw, Clipboard};
+    let _clip = Clipboard::new_attempts(10)
+        .map_err(|e| anyhow::anyhow!("failed to open clipboard: {:?}", e))?;
+    match payload {
+        // An image's text is only a placeholder, which apps would paste instead.
+        Some(Payload::Dib(data)) => {
+            raw::empty().map_err(|e| anyhow::anyhow!("failed to empty clipboard: {:?}", e))?;
+            raw::set_without_clear(formats::CF_DIB, data)
+                .map_err(|e| anyhow::anyhow!("failed to set clipboard image: {:?}", e))?;
+        }
+        Some(Payload::Document { format, data }) => {
+            raw::set_string(text).map_err(|e| anyhow::anyhow!("failed to set clipboard: {:?}", e))?;
+            set_marker(format, data)?;
+        }
+        None => raw::set_string(text).map_err(|e| anyhow::anyhow!("failed to set clipboard: {:?}", e))?,
+    }
+    if let Some(id) = entry {
+        set_marker(PASTED_ENTRY_FORMAT, &id.to_le_bytes())?;
+    }
+    if hide_from_history {
+        // Only the format's presence matters.
+        set_marker(EXCLUDE_FROM_HISTORY_FORMAT, &0u32.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Set the clipboard to `text`, or `payload` with it. There is no marking the
+/// entry for clipd or hiding it from history on Linux and macOS; clipd finds
+/// the text it already has and moves that entry to the top.
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn set_pasted(text: &str, payload: Option<&Payload>, _entry: Option<u64>, _hide_from_history: bool) -> Result<()> {
+    put(text, payload, || {})
+}
+
+/// Like `set_pasted` on macOS, through `clipctl hold`: on Linux only the
+/// program that set the clipboard hands it out, and clipctl is about to exit.
+#[cfg(target_os = "linux")]
+fn set_pasted(text: &str, payload: Option<&Payload>, _entry: Option<u64>, _hide_from_history: bool) -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    use anyhow::Context;
+
+    let exe = std::env::current_exe().context("failed to locate clipctl")?;
+    let mut command = Command::new(exe);
+    command.args(["hold", "--text-len", &text.len().to_string()]);
+    if let Some(payload) = payload {
+        command.args(["--kind", payload.kind()]);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        // Out of the terminal's process group, so closing the terminal leaves it be.
+        .process_group(0)
+        .spawn()
+        .context("failed to start clipctl hold")?;
+    let mut stdin = child.stdin.take().context("clipctl hold has no stdin")?;
+    stdin.write_all(text.as_bytes())?;
+    if let Some(payload) = payload {
+        stdin.write_all(payload.data())?;
+    }
+    // Closing stdin tells the holder it has everything.
+    drop(stdin);
+
+    let stdout = child.stdout.take().context("clipctl hold has no stdout")?;
+    let mut ready = String::new();
+    BufReader::new(stdout).read_line(&mut ready)?;
+    if ready.trim() != HOLDER_READY {
+        anyhow::bail!("failed to set the clipboard; is a display available?");
+    }
+    Ok(())
+}
+
+/// `clipctl hold`: put stdin on the clipboard, the first `text_len` bytes as
+/// text and the rest as an entry of `kind`, and keep it there until another
+/// program replaces it.
+#[cfg(not(target_os = "windows"))]
+pub fn hold(text_len: usize, kind: Option<&str>) -> Result<()> {
+    use std::io::Read;
+
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let (text, data) = input.split_at(text_len.min(input.len()));
+    let payload = kind.and_then(|kind| Payload::new(kind, data.to_vec()));
+    put(&String::from_utf8_lossy(text), payload.as_ref(), || println!("{HOLDER_READY}"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn hold(_text_len: usize, _kind: Option<&str>) -> Result<()> {
+    anyhow::bail!("Holding the clipboard is only needed on Linux")
+}
+
+/// Put `text`, or `payload` with it, on the clipboard, calling `ready` once
+/// only setting it is left. On Linux this returns once another program has
+/// replaced what it set.
+#[cfg(not(target_os = "windows"))]
+fn put(text: &str, payload: Option<&Payload>, ready: impl FnOnce()) -> Result<()> {
+    use anyhow::Context;
+    use arboard::{Clipboard, ImageData};
+
+    let mut clipboard = Clipboard::new().context("failed to open the clipboard")?;
+    let image = match payload {
+        Some(Payload::Dib(data)) => {
+            let (width, height, rgba) = clipd::image::dib_to_rgba(data)?;
+            Some(ImageData {
+                width: width as usize,
+                height: height as usize,
+                bytes: rgba.into(),
+            })
+        }
+        _ => None,
+    };
+    ready();
+
+    let set = clipboard.set();
+    #[cfg(target_os = "linux")]
+    let set = arboard::SetExtLinux::wait(set);
+    match (image, payload) {
+        (Some(image), _) => set.image(image),
+        (None, Some(Payload::Document { format: HTML_FORMAT, data })) => {
+            set.html(clipd::html::fragment(data), Some(text.to_string()))
+        }
+        // arboard has no RTF; its text goes on instead.
+        _ => set.text(text),
+    }
+    .context("failed to set the clipboard")
+}
+
+/// Add registered format `name` to the open clipboard.
+#[cfg(target_os = "windows")]
+fn set_marker(name: &str, data: &[u8]) -> Result<()> {
+    use anyhow::Context;
+    use clipboard_win::raw;
+    let format = raw::register_format(name)
+        .with_context(|| format!("failed to register clipboard format {name}"))?;
+    raw::set_without_clear(format.get(), data)
+        .map_err(|e| anyhow::anyhow!("failed to set clipboard format {name}: {:?}", e))
 }
 
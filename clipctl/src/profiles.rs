@@ -0,0 +1,90 @@
+//! Profile picker: every profile side by side, and which one clipd captures into.
+
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState};
+use ratatui::Frame;
+
+use crate::ipc::ProfileSummary;
+use crate::theme::Theme;
+
+#[derive(Default)]
+pub struct ProfilePicker {
+    profiles: Vec<ProfileSummary>,
+    selected: usize,
+}
+
+impl ProfilePicker {
+    /// Replace the listed profiles and select the active one.
+    pub fn update(&mut self, profiles: Vec<ProfileSummary>) {
+        self.selected = profiles.iter().position(|profile| profile.active).unwrap_or(0);
+        self.profiles = profiles;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.profiles.len();
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&ProfileSummary> {
+        self.profiles.get(self.selected)
+    }
+
+    /// `hide_content` leaves out each profile's newest entry (guest mode).
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme, hide_content: bool) {
+        let width = self
+            .profiles
+            .iter()
+            .map(|profile| profile.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<_> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                let marker = if profile.active { theme.glyphs.active() } else { "  " };
+                let latest = profile
+                    .latest
+                    .as_deref()
+                    .filter(|_| !hide_content)
+                    .and_then(|text| text.lines().next())
+                    .unwrap_or("");
+                ListItem::new(Line::from(vec![
+                    Span::styled(marker, theme.style_help_key()),
+                    Span::styled(format!("{:<width$}", profile.name), theme.style_list_item()),
+                    Span::styled(
+                        format!("  {:>6} entries  ", profile.entries),
+                        theme.style_metadata_label(),
+                    ),
+                    Span::styled(
+                        latest.to_string(),
+                        Style::default()
+                            .fg(theme.metadata_value)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select((!items.is_empty()).then_some(self.selected));
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(Span::styled(
+                        " Profiles · Enter capture into · Esc close ",
+                        theme.style_title(),
+                    ))
+                    .borders(theme.glyphs.borders())
+                    .border_style(theme.style_border_focused())
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(theme.style_list_selected())
+            .highlight_symbol(theme.glyphs.selected());
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
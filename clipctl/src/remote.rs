@@ -0,0 +1,132 @@
+//! `clipctl --remote user@host`: browsing the history of a clipd on another
+//! machine, through SSH.
+//!
+//! clipctl runs `ssh user@host clipctl bridge`, and the bridge on the other
+//! end relays the connection between its stdin and stdout and the clipd
+//! there, byte for byte; clipctl speaks the usual protocol over the SSH
+//! session. SSH has to log in without asking (keys or an agent), since the
+//! TUI owns the terminal by the time it connects.
+
+use std::io;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::task::{ready, Context as TaskContext, Poll};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use crate::ipc::{self, Transport};
+
+/// The `user@host` clipctl browses (`CLIPMGR_REMOTE`, `--remote`), if any.
+pub fn target() -> Option<String> {
+    std::env::var("CLIPMGR_REMOTE").ok().filter(|target| !target.is_empty())
+}
+
+/// Whether the history is another machine's; its entry ids mean nothing to
+/// the clipd here, and its paths name files over there.
+pub fn active() -> bool {
+    target().is_some()
+}
+
+/// A connection to the clipd at `target`, through a new SSH session.
+pub fn connect(target: &str) -> Result<SshTunnel> {
+    let mut ssh = Command::new("ssh");
+    // No prompts: there is nobody to answer them behind the TUI.
+    ssh.args(["-T", "-o", "BatchMode=yes", target, "clipctl"]);
+    // The remote clipctl finds the same daemon the local flags would.
+    if let Ok(pipe) = std::env::var("CLIPMGR_PIPE") {
+        ssh.args(["--pipe", &pipe]);
+    }
+    if let Ok(profile) = std::env::var("CLIPMGR_PROFILE") {
+        ssh.args(["--profile", &profile]);
+    }
+    let mut child = ssh
+        .arg("bridge")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start ssh; is the OpenSSH client installed?")?;
+
+    // Kept off the screen, where it would tear through the TUI, until the
+    // session ends and it explains why.
+    let stderr = Arc::new(Mutex::new(String::new()));
+    let lines = child.stderr.take().expect("ssh stderr is piped");
+    let collected = Arc::clone(&stderr);
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(lines).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::warn!("ssh: {line}");
+            let mut collected = collected.lock();
+            collected.push_str(&line);
+            collected.push('\n');
+        }
+    });
+
+    Ok(SshTunnel {
+        stdin: child.stdin.take().expect("ssh stdin is piped"),
+        stdout: child.stdout.take().expect("ssh stdout is piped"),
+        stderr,
+        _ssh: child,
+    })
+}
+
+/// The SSH session to a remote clipd; dropping it ends the session.
+pub struct SshTunnel {
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    /// What ssh and the remote clipctl printed to stderr so far.
+    stderr: Arc<Mutex<String>>,
+    _ssh: Child,
+}
+
+impl Transport for SshTunnel {}
+
+impl AsyncRead for SshTunnel {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.stdout).poll_read(cx, buf))?;
+        let stderr = this.stderr.lock();
+        if buf.filled().len() == filled && buf.remaining() > 0 && !stderr.is_empty() {
+            let message = format!("ssh closed the session: {}", stderr.trim());
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, message)));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for SshTunnel {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stdin).poll_shutdown(cx)
+    }
+}
+
+/// `clipctl bridge`: relay stdin and stdout to the local clipd until either
+/// side hangs up.
+pub async fn bridge() -> Result<()> {
+    let pipe_name = ipc::pipe_name()?;
+    let pipe = ipc::open_pipe(&pipe_name)
+        .await
+        .with_context(|| format!("failed to connect to pipe {pipe_name}; is clipd running?"))?;
+    let (mut from_clipd, mut to_clipd) = tokio::io::split(pipe);
+    let (mut stdin, mut stdout) = (tokio::io::stdin(), tokio::io::stdout());
+    let result = tokio::select! {
+        result = tokio::io::copy(&mut stdin, &mut to_clipd) => result,
+        result = tokio::io::copy(&mut from_clipd, &mut stdout) => result,
+    };
+    result.context("bridge to clipd failed")?;
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! Materializing image entries as temp files and showing them in Explorer, so
+//! they can be dragged into chats and emails, and opening `mailto:` and
+//! `tel:` links in the apps Windows has for them.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Where the PNG for image entry `id` is written; the directory is created.
+pub fn temp_png(id: u64) -> Result<PathBuf> {
+    let dir = env::temp_dir().join("rusty-clipboard");
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create temp directory: {}", dir.display()))?;
+    Ok(dir.join(format!("clip-{id}.png")))
+}
+
+/// Open an Explorer window with `path` selected, ready to be dragged out.
+#[cfg(target_os = "windows")]
+pub fn show_in_explorer(path: &Path) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    // Explorer parses `/select,"path"` itself, so the quotes must reach it as-is.
+    Command::new("explorer.exe")
+        .raw_arg(format!("/select,\"{}\"", path.display()))
+        .spawn()
+        .context("failed to start explorer.exe")?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn show_in_explorer(path: &Path) -> Result<()> {
+    anyhow::bail!("showing files is only supported on Windows ({})", path.display())
+}
+
+/// Open `uri` in the app registered for its scheme, such as the mail app
+/// for `mailto:`.
+#[cfg(target_os = "windows")]
+pub fn open(uri: &str) -> Result<()> {
+    use std::process::Command;
+
+    // Unlike `cmd /c start`, this passes the URI on without a shell parsing it.
+    Command::new("rundll32.exe")
+        .args(["url.dll,FileProtocolHandler", uri])
+        .spawn()
+        .context("failed to start rundll32.exe")?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn open(uri: &str) -> Result<()> {
+    anyhow::bail!("opening links is only supported on Windows ({uri})")
+}
@@ -0,0 +1,97 @@
+//! Bold, italic, and underline of RTF entries in the preview, from the style
+//! runs clipd sends with their plain text, and the words a search matched.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::ipc::{Highlight, StyleRun};
+
+/// A stretch of text between two byte offsets and the style it is drawn in.
+type Run = (usize, usize, Style);
+
+/// `text` as lines of spans, formatted where `styles` says and in `base` style
+/// elsewhere; at most `max_lines` lines.
+pub fn lines(text: &str, styles: &[StyleRun], base: Style, max_lines: usize) -> Vec<Line<'static>> {
+    let runs: Vec<Run> = styles
+        .iter()
+        .map(|run| (run.start, run.end, base.add_modifier(modifiers(run))))
+        .collect();
+    styled_lines(text, &runs, base, max_lines)
+}
+
+/// `text` as lines of spans, in `matched` style where `highlights` says and
+/// in `base` style elsewhere; at most `max_lines` lines.
+pub fn highlighted(
+    text: &str,
+    highlights: &[Highlight],
+    base: Style,
+    matched: Style,
+    max_lines: usize,
+) -> Vec<Line<'static>> {
+    let runs: Vec<Run> = highlights.iter().map(|h| (h.start, h.end, matched)).collect();
+    styled_lines(text, &runs, base, max_lines)
+}
+
+/// `text` as spans of one line, like [`highlighted`]. `text` may be cut
+/// short of what the highlights were found in; they are clipped to it.
+pub fn highlighted_spans(text: &str, highlights: &[Highlight], base: Style, matched: Style) -> Vec<Span<'static>> {
+    let runs: Vec<Run> = highlights
+        .iter()
+        .map(|h| (h.start, h.end.min(text.len()), matched))
+        .filter(|run| fits(text, run))
+        .collect();
+    spans(text, 0, text.len(), &runs, base)
+}
+
+fn styled_lines(text: &str, runs: &[Run], base: Style, max_lines: usize) -> Vec<Line<'static>> {
+    // Runs that do not fit the text, as from a mismatched daemon, are dropped.
+    let runs: Vec<Run> = runs.iter().copied().filter(|run| fits(text, run)).collect();
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n').take(max_lines) {
+        let end = offset + line.len();
+        lines.push(Line::from(spans(text, offset, end, &runs, base)));
+        offset = end + 1;
+    }
+    lines
+}
+
+fn fits(text: &str, &(start, end, _): &Run) -> bool {
+    start < end && text.is_char_boundary(start) && text.is_char_boundary(end)
+}
+
+/// Spans for `text[offset..end]`, styled by the runs that reach into it.
+fn spans(text: &str, offset: usize, end: usize, runs: &[Run], base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut position = offset;
+    for &(start, stop, style) in runs.iter().filter(|run| run.0 < end && run.1 > offset) {
+        let start = start.max(position);
+        let stop = stop.min(end);
+        if start >= stop {
+            continue;
+        }
+        if start > position {
+            spans.push(Span::styled(text[position..start].to_string(), base));
+        }
+        spans.push(Span::styled(text[start..stop].to_string(), style));
+        position = stop;
+    }
+    if position < end {
+        spans.push(Span::styled(text[position..end].to_string(), base));
+    }
+    spans
+}
+
+fn modifiers(run: &StyleRun) -> Modifier {
+    let mut modifiers = Modifier::empty();
+    if run.bold {
+        modifiers |= Modifier::BOLD;
+    }
+    if run.italic {
+        modifiers |= Modifier::ITALIC;
+    }
+    if run.underline {
+        modifiers |= Modifier::UNDERLINED;
+    }
+    modifiers
+}
@@ -0,0 +1,85 @@
+//! Session picker: browse, tag, or export one clipd run's captures as a unit.
+
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState};
+use ratatui::Frame;
+
+use crate::ipc::SessionSummary;
+use crate::theme::Theme;
+
+#[derive(Default)]
+pub struct SessionPicker {
+    sessions: Vec<SessionSummary>,
+    selected: usize,
+}
+
+impl SessionPicker {
+    /// Replace the listed sessions, keeping the same one selected if it is still there.
+    pub fn update(&mut self, sessions: Vec<SessionSummary>) {
+        let previous = self.selected().map(|session| session.id);
+        self.selected = previous
+            .and_then(|id| sessions.iter().position(|session| session.id == id))
+            .unwrap_or(0);
+        self.sessions = sessions;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.sessions.len();
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&SessionSummary> {
+        self.sessions.get(self.selected)
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let items: Vec<_> = self
+            .sessions
+            .iter()
+            .map(|session| {
+                let mut spans = vec![
+                    Span::styled(format!("#{:<5}", session.id), theme.style_help_key()),
+                    Span::styled(
+                        format!("{} – {}", session.started, session.ended),
+                        theme.style_list_item(),
+                    ),
+                    Span::styled(
+                        format!("  {} entries", session.entries),
+                        theme.style_metadata_label(),
+                    ),
+                ];
+                if session.current {
+                    spans.push(Span::styled(
+                        "  (current)",
+                        Style::default()
+                            .fg(theme.metadata_label)
+                            .add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select((!items.is_empty()).then_some(self.selected));
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(Span::styled(
+                        " Sessions · Enter browse · t tag · e export · Esc close ",
+                        theme.style_title(),
+                    ))
+                    .borders(theme.glyphs.borders())
+                    .border_style(theme.style_border_focused())
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(theme.style_list_selected())
+            .highlight_symbol(theme.glyphs.selected());
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
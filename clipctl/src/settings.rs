@@ -0,0 +1,144 @@
+//! In-TUI settings screen (`:set`), backed by the shared config file.
+
+use anyhow::Result;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+
+use crate::config::{self, ClientConfig};
+use crate::paste::PasteMethod;
+use crate::theme::Theme;
+
+/// Options the settings screen can change; each maps to one config key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Theme,
+    PreviewLength,
+    PasteMethod,
+    HideFromWindowsHistory,
+    ConfirmPrompts,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting::Theme,
+    Setting::PreviewLength,
+    Setting::PasteMethod,
+    Setting::HideFromWindowsHistory,
+    Setting::ConfirmPrompts,
+];
+
+impl Setting {
+    fn key(self) -> &'static str {
+        match self {
+            Setting::Theme => "ui.theme",
+            Setting::PreviewLength => "ui.preview_length",
+            Setting::PasteMethod => "general.paste_behavior",
+            Setting::HideFromWindowsHistory => "general.hide_from_windows_history",
+            Setting::ConfirmPrompts => "ui.confirm_prompts",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Theme => "Theme",
+            Setting::PreviewLength => "Preview length",
+            Setting::PasteMethod => "Paste method",
+            Setting::HideFromWindowsHistory => "Hide from Win+V",
+            Setting::ConfirmPrompts => "Confirmation prompts",
+        }
+    }
+
+    fn choices(self) -> &'static [&'static str] {
+        match self {
+            Setting::Theme => &["nord", "dracula", "tokyo-night", "gruvbox", "high-contrast"],
+            Setting::PreviewLength => &["40", "80", "120", "200"],
+            Setting::PasteMethod => &["send_input", "stdout", "type", "pane"],
+            Setting::HideFromWindowsHistory | Setting::ConfirmPrompts => &["true", "false"],
+        }
+    }
+
+    fn current(self, config: &ClientConfig) -> String {
+        match self {
+            Setting::Theme => config.theme.clone(),
+            Setting::PreviewLength => config.preview_length.to_string(),
+            Setting::PasteMethod => config.paste_method.as_str().to_string(),
+            Setting::HideFromWindowsHistory => config.hide_from_windows_history.to_string(),
+            Setting::ConfirmPrompts => config.confirm_prompts.to_string(),
+        }
+    }
+
+    fn apply(self, config: &mut ClientConfig, raw: &str) {
+        match self {
+            Setting::Theme => config.theme = raw.to_string(),
+            Setting::PreviewLength => {
+                config.preview_length = raw.parse().unwrap_or(config.preview_length)
+            }
+            Setting::PasteMethod => {
+                config.paste_method = PasteMethod::parse(raw).unwrap_or(config.paste_method)
+            }
+            Setting::HideFromWindowsHistory => config.hide_from_windows_history = raw == "true",
+            Setting::ConfirmPrompts => config.confirm_prompts = raw == "true",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SettingsView {
+    selected: usize,
+}
+
+impl SettingsView {
+    pub fn move_selection(&mut self, delta: isize) {
+        self.selected = (self.selected as isize + delta).clamp(0, SETTINGS.len() as isize - 1) as usize;
+    }
+
+    /// Step the selected option to its next (or previous) value and save it.
+    ///
+    /// Values set by hand to something outside the presets restart at the first one.
+    pub fn cycle(&mut self, config: &mut ClientConfig, delta: isize) -> Result<()> {
+        let setting = SETTINGS[self.selected];
+        let choices = setting.choices();
+        let current = setting.current(config);
+        let next = match choices.iter().position(|choice| *choice == current) {
+            Some(i) => (i as isize + delta).rem_euclid(choices.len() as isize) as usize,
+            None => 0,
+        };
+
+        config::store(setting.key(), choices[next])?;
+        setting.apply(config, choices[next]);
+        Ok(())
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, config: &ClientConfig, theme: &Theme) {
+        let mut lines = vec![
+            Line::styled("Changes are saved to config.toml immediately.", theme.style_metadata_label()),
+            Line::raw(""),
+        ];
+        for (i, setting) in SETTINGS.iter().enumerate() {
+            let row = format!("{:<22}{}", setting.label(), setting.current(config));
+            lines.push(if i == self.selected {
+                Line::from(Span::styled(format!("{}{row}", theme.glyphs.selected()), theme.style_list_selected()))
+            } else {
+                Line::from(Span::styled(format!("  {row}"), theme.style_list_item()))
+            });
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "j/k select · h/l or Enter change · Esc close",
+            Style::default()
+                .fg(theme.metadata_label)
+                .add_modifier(Modifier::ITALIC),
+        ));
+
+        let settings = Paragraph::new(lines).block(
+            Block::default()
+                .title(Span::styled(" Settings ", theme.style_title()))
+                .borders(theme.glyphs.borders())
+                .border_style(theme.style_border_focused())
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(settings, area);
+    }
+}
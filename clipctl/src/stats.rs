@@ -0,0 +1,40 @@
+//! Size of text entries, for the preview.
+
+use std::fmt;
+
+/// Roughly how many characters of English text one LLM token covers.
+const CHARS_PER_TOKEN: usize = 4;
+
+pub struct Counts {
+    pub words: usize,
+    pub chars: usize,
+    pub lines: usize,
+}
+
+impl Counts {
+    pub fn of(text: &str) -> Self {
+        Self {
+            words: text.split_whitespace().count(),
+            chars: text.chars().count(),
+            lines: text.lines().count(),
+        }
+    }
+
+    /// Approximate token count, good enough to judge whether text fits a prompt.
+    pub fn tokens(&self) -> usize {
+        self.chars.div_ceil(CHARS_PER_TOKEN)
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} words · {} chars · {} lines · ~{} tokens",
+            self.words,
+            self.chars,
+            self.lines,
+            self.tokens()
+        )
+    }
+}
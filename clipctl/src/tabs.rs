@@ -0,0 +1,75 @@
+//! Saved filter views (`[[views]]` in config.toml), shown as tabs above the list.
+
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use ratatui::widgets::Tabs;
+use ratatui::Frame;
+
+use crate::config::View;
+use crate::theme::Theme;
+
+struct Tab {
+    name: String,
+    query: String,
+    /// Entry that was selected when the tab was last left.
+    selected: Option<u64>,
+}
+
+/// "All" followed by one tab per configured view; no tabs when none are configured.
+#[derive(Default)]
+pub struct ViewTabs {
+    tabs: Vec<Tab>,
+    active: usize,
+}
+
+impl ViewTabs {
+    pub fn new(views: &[View]) -> Self {
+        if views.is_empty() {
+            return Self::default();
+        }
+        let all = Tab {
+            name: "All".to_string(),
+            query: String::new(),
+            selected: None,
+        };
+        let views = views.iter().map(|view| Tab {
+            name: view.name.clone(),
+            query: view.query.clone(),
+            selected: None,
+        });
+        Self {
+            tabs: std::iter::once(all).chain(views).collect(),
+            active: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    /// Make tab `index` active, remembering `selected` for the tab being left.
+    /// Returns the tab's query and the entry to select, if there is such a tab.
+    pub fn switch(&mut self, index: usize, selected: Option<u64>) -> Option<(&str, Option<u64>)> {
+        if index >= self.tabs.len() {
+            return None;
+        }
+        self.tabs[self.active].selected = selected;
+        self.active = index;
+        let tab = &self.tabs[index];
+        Some((&tab.query, tab.selected))
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| Line::from(format!("{} {}", index + 1, tab.name)));
+        let tabs = Tabs::new(titles)
+            .select(self.active)
+            .style(theme.style_metadata_label())
+            .highlight_style(theme.style_list_selected())
+            .divider(theme.glyphs.divider());
+        frame.render_widget(tabs, area);
+    }
+}
@@ -1,4 +1,5 @@
 use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Borders;
 
 /// Color theme for the TUI
 #[derive(Debug, Clone)]
@@ -21,6 +22,8 @@ pub struct Theme {
     pub image_icon: Color,
     pub rtf_icon: Color,
     pub code_icon: Color,
+    /// Quarantined entries and other content to be careful with.
+    pub warning: Color,
     
     // Metadata
     pub metadata_label: Color,
@@ -36,9 +39,154 @@ pub struct Theme {
     pub help_section: Color,
     pub help_key: Color,
     pub help_desc: Color,
+
+    // Symbols
+    pub glyphs: Glyphs,
+}
+
+/// Symbols drawn next to the colors: emoji and box drawing, or plain ASCII
+/// for accessible mode, where screen readers would spell out every symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    Unicode,
+    Ascii,
+}
+
+impl Glyphs {
+    /// Icon for an entry of `kind` in the history list.
+    pub fn kind(self, kind: &str) -> &'static str {
+        match (self, kind) {
+            (Glyphs::Unicode, "text") => "📝",
+            (Glyphs::Unicode, "url") => "🔗",
+            (Glyphs::Unicode, "image") => "🖼️",
+            (Glyphs::Unicode, "rtf") => "📄",
+            (Glyphs::Unicode, "html") => "🌐",
+            (Glyphs::Unicode, "snapshot") => "📦",
+            (Glyphs::Unicode, "files") => "📁",
+            (Glyphs::Unicode, _) => "❓",
+            (Glyphs::Ascii, "text") => "[TXT]",
+            (Glyphs::Ascii, "url") => "[URL]",
+            (Glyphs::Ascii, "image") => "[IMG]",
+            (Glyphs::Ascii, "rtf") => "[RTF]",
+            (Glyphs::Ascii, "html") => "[HTM]",
+            (Glyphs::Ascii, "snapshot") => "[ALL]",
+            (Glyphs::Ascii, "files") => "[DIR]",
+            (Glyphs::Ascii, _) => "[???]",
+        }
+    }
+
+    /// `emoji` decorating a prompt, or nothing in ASCII.
+    pub fn icon(self, emoji: &'static str) -> &'static str {
+        match self {
+            Glyphs::Unicode => emoji,
+            Glyphs::Ascii => "",
+        }
+    }
+
+    fn pick(self, unicode: &'static str, ascii: &'static str) -> &'static str {
+        match self {
+            Glyphs::Unicode => unicode,
+            Glyphs::Ascii => ascii,
+        }
+    }
+
+    pub fn pinned(self) -> &'static str {
+        self.pick("📌", "[PIN]")
+    }
+
+    /// Marks the entry the clipboard holds now.
+    pub fn on_clipboard(self) -> &'static str {
+        self.pick("📋", "[CB]")
+    }
+
+    /// Marks quarantined entries and anything risky to paste.
+    pub fn warning(self) -> &'static str {
+        self.pick("⚠", "[!]")
+    }
+
+    /// Marks a checksum that matches another entry.
+    pub fn matched(self) -> &'static str {
+        self.pick("✓", "[=]")
+    }
+
+    /// In front of the selected row of a list.
+    pub fn selected(self) -> &'static str {
+        self.pick("▶ ", "> ")
+    }
+
+    /// In front of the active row, such as the profile clipd captures into.
+    pub fn active(self) -> &'static str {
+        self.pick("● ", "* ")
+    }
+
+    /// After the text typed at a prompt.
+    pub fn cursor(self) -> &'static str {
+        self.pick("█", "_")
+    }
+
+    /// Stands in for each typed passphrase character.
+    pub fn mask(self) -> &'static str {
+        self.pick("•", "*")
+    }
+
+    /// Between tabs.
+    pub fn divider(self) -> &'static str {
+        self.pick("│", "|")
+    }
+
+    /// Between the items of a one-line summary.
+    pub fn separator(self) -> &'static str {
+        self.pick(" · ", " - ")
+    }
+
+    /// A calendar day with `count` of the `busiest` day's captures. ASCII
+    /// shows the amount in the symbol, not only the color.
+    pub fn day(self, count: u64, busiest: u64) -> &'static str {
+        match self {
+            Glyphs::Unicode => "■",
+            Glyphs::Ascii if count == 0 || busiest == 0 => ".",
+            Glyphs::Ascii if count * 3 <= busiest => "-",
+            Glyphs::Ascii if count * 3 <= busiest * 2 => "+",
+            Glyphs::Ascii => "#",
+        }
+    }
+
+    /// Panels draw boxes around themselves; in ASCII only their titles
+    /// separate them, so a screen reader reads each line as one text.
+    pub fn borders(self) -> Borders {
+        match self {
+            Glyphs::Unicode => Borders::ALL,
+            Glyphs::Ascii => Borders::NONE,
+        }
+    }
 }
 
 impl Theme {
+    /// Look up a theme by its config name (`nord`, `dracula`, `tokyo-night`,
+    /// `gruvbox`, `high-contrast`).
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "nord" => Some(Self::nord()),
+            "dracula" => Some(Self::dracula()),
+            "tokyo-night" => Some(Self::tokyo_night()),
+            "gruvbox" => Some(Self::gruvbox()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// The theme the config asks for: `high-contrast` with ASCII symbols in
+    /// accessible mode, whatever the theme name.
+    pub fn for_config(name: &str, accessible: bool) -> Self {
+        if accessible {
+            return Self {
+                glyphs: Glyphs::Ascii,
+                ..Self::high_contrast()
+            };
+        }
+        Self::by_name(name).unwrap_or_else(Self::nord)
+    }
+
     /// Nord-inspired theme with cool blues and purples
     pub fn nord() -> Self {
         Self {
@@ -57,6 +205,7 @@ impl Theme {
             image_icon: Color::Rgb(180, 142, 173),       // Nord15 - purple
             rtf_icon: Color::Rgb(235, 203, 139),         // Nord13 - yellow
             code_icon: Color::Rgb(163, 190, 140),        // Nord14 - green
+            warning: Color::Rgb(191, 97, 106),           // Nord11 - red
             
             metadata_label: Color::Rgb(143, 188, 187),   // Nord7 - teal
             metadata_value: Color::Rgb(229, 233, 240),   // Nord5
@@ -69,6 +218,8 @@ impl Theme {
             help_section: Color::Rgb(136, 192, 208),     // Nord8
             help_key: Color::Rgb(235, 203, 139),         // Nord13
             help_desc: Color::Rgb(216, 222, 233),        // Nord4
+
+            glyphs: Glyphs::Unicode,
         }
     }
     
@@ -90,6 +241,7 @@ impl Theme {
             image_icon: Color::Rgb(255, 121, 198),       // Dracula pink
             rtf_icon: Color::Rgb(241, 250, 140),         // Dracula yellow
             code_icon: Color::Rgb(80, 250, 123),         // Dracula green
+            warning: Color::Rgb(255, 85, 85),            // Dracula red
             
             metadata_label: Color::Rgb(98, 114, 164),    // Dracula comment
             metadata_value: Color::Rgb(248, 248, 242),   // Dracula foreground
@@ -102,6 +254,8 @@ impl Theme {
             help_section: Color::Rgb(189, 147, 249),     // Dracula purple
             help_key: Color::Rgb(255, 121, 198),         // Dracula pink
             help_desc: Color::Rgb(248, 248, 242),        // Dracula foreground
+
+            glyphs: Glyphs::Unicode,
         }
     }
     
@@ -123,6 +277,7 @@ impl Theme {
             image_icon: Color::Rgb(187, 154, 247),       // Tokyo Night purple
             rtf_icon: Color::Rgb(224, 175, 104),         // Tokyo Night yellow
             code_icon: Color::Rgb(158, 206, 106),        // Tokyo Night green
+            warning: Color::Rgb(247, 118, 142),          // Tokyo Night red
             
             metadata_label: Color::Rgb(86, 95, 137),     // Tokyo Night comment
             metadata_value: Color::Rgb(192, 202, 245),   // Tokyo Night foreground
@@ -135,6 +290,8 @@ impl Theme {
             help_section: Color::Rgb(122, 162, 247),     // Tokyo Night blue
             help_key: Color::Rgb(255, 158, 100),         // Tokyo Night orange
             help_desc: Color::Rgb(192, 202, 245),        // Tokyo Night foreground
+
+            glyphs: Glyphs::Unicode,
         }
     }
     
@@ -156,6 +313,7 @@ impl Theme {
             image_icon: Color::Rgb(211, 134, 155),       // Gruvbox purple
             rtf_icon: Color::Rgb(251, 184, 108),         // Gruvbox yellow
             code_icon: Color::Rgb(184, 187, 38),         // Gruvbox green
+            warning: Color::Rgb(251, 73, 52),            // Gruvbox red
             
             metadata_label: Color::Rgb(146, 131, 116),   // Gruvbox gray
             metadata_value: Color::Rgb(235, 219, 178),   // Gruvbox fg
@@ -168,6 +326,45 @@ impl Theme {
             help_section: Color::Rgb(254, 128, 25),      // Gruvbox orange
             help_key: Color::Rgb(251, 184, 108),         // Gruvbox yellow
             help_desc: Color::Rgb(235, 219, 178),        // Gruvbox fg
+
+            glyphs: Glyphs::Unicode,
+        }
+    }
+
+    /// Bright text on the terminal's own black, in the 16 ANSI colors so
+    /// Windows high-contrast console schemes apply
+    pub fn high_contrast() -> Self {
+        Self {
+            border: Color::White,
+            border_focused: Color::LightYellow,
+            title: Color::LightYellow,
+            background: Color::Black,
+
+            list_item: Color::White,
+            list_selected_bg: Color::Blue,
+            list_selected_fg: Color::White,
+            list_highlight_symbol: Color::LightYellow,
+
+            text_icon: Color::White,
+            url_icon: Color::LightCyan,
+            image_icon: Color::LightMagenta,
+            rtf_icon: Color::LightGreen,
+            code_icon: Color::LightGreen,
+            warning: Color::LightRed,
+
+            metadata_label: Color::LightCyan,
+            metadata_value: Color::White,
+            tag_fg: Color::Black,
+            tag_bg: Color::LightCyan,
+
+            command_prompt: Color::LightYellow,
+            command_input: Color::White,
+
+            help_section: Color::LightYellow,
+            help_key: Color::LightCyan,
+            help_desc: Color::White,
+
+            glyphs: Glyphs::Unicode,
         }
     }
     
@@ -194,6 +391,14 @@ impl Theme {
             .add_modifier(Modifier::BOLD)
     }
     
+    /// Words a search matched, in the list and the preview. Underlined, so
+    /// they still stand out in the selected row.
+    pub fn style_search_match(&self) -> Style {
+        Style::default()
+            .fg(self.list_highlight_symbol)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+    }
+    
     pub fn style_tag(&self) -> Style {
         Style::default()
             .fg(self.tag_fg)
@@ -201,6 +406,20 @@ impl Theme {
             .add_modifier(Modifier::BOLD)
     }
     
+    pub fn style_warning(&self) -> Style {
+        Style::default().fg(self.warning).add_modifier(Modifier::BOLD)
+    }
+    
+    /// Lines a diff adds, in the theme's green.
+    pub fn style_diff_added(&self) -> Style {
+        Style::default().fg(self.code_icon)
+    }
+    
+    /// Lines a diff removes, in the theme's red.
+    pub fn style_diff_removed(&self) -> Style {
+        Style::default().fg(self.warning)
+    }
+    
     pub fn style_metadata_label(&self) -> Style {
         Style::default().fg(self.metadata_label).add_modifier(Modifier::ITALIC)
     }
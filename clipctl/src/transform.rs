@@ -0,0 +1,194 @@
+//! Changes applied to an entry's text on its way to the clipboard.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+
+use crate::invisible;
+
+/// The value names are what `clipctl transform <name>` accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Transform {
+    #[default]
+    #[value(skip)]
+    Verbatim,
+    /// Without zero-width, bidi, and control characters.
+    Sanitized,
+    /// Every line break as `\n`.
+    Lf,
+    /// Every line break as `\r\n`.
+    Crlf,
+    /// Without a leading UTF-8 byte order mark.
+    #[value(name = "nobom")]
+    WithoutBom,
+    #[value(name = "sorted")]
+    SortLines,
+    /// Later repeats of a line dropped, order kept.
+    #[value(name = "unique")]
+    UniqueLines,
+    #[value(name = "reversed")]
+    ReverseLines,
+    /// `1. ` before the first line, `2. ` before the second...
+    #[value(name = "numbered")]
+    NumberLines,
+    Upper,
+    Lower,
+    /// First letter of every word upper case, the rest lower case.
+    #[value(name = "title")]
+    TitleCase,
+    /// `parseHttpResponse`; words split at spaces, punctuation, and case changes.
+    #[value(name = "camel")]
+    CamelCase,
+    /// `parse_http_response`.
+    #[value(name = "snake")]
+    SnakeCase,
+    /// `parse-http-response`.
+    #[value(name = "kebab")]
+    KebabCase,
+    /// URL path segment: lower case, words split only at spaces and
+    /// punctuation, apostrophes dropped (`It's done!` becomes `its-done`).
+    Slug,
+}
+
+impl Transform {
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Verbatim => Cow::Borrowed(text),
+            Self::Sanitized => Cow::Owned(invisible::strip(text)),
+            Self::Lf => Cow::Owned(to_lf(text)),
+            Self::Crlf => Cow::Owned(to_lf(text).replace('\n', "\r\n")),
+            Self::WithoutBom => Cow::Borrowed(text.strip_prefix('\u{FEFF}').unwrap_or(text)),
+            Self::SortLines => Cow::Owned(map_lines(text, |mut lines| {
+                lines.sort_unstable();
+                lines
+            })),
+            Self::UniqueLines => Cow::Owned(map_lines(text, |lines| {
+                let mut seen = HashSet::new();
+                lines.into_iter().filter(|line| seen.insert(line.clone())).collect()
+            })),
+            Self::ReverseLines => Cow::Owned(map_lines(text, |mut lines| {
+                lines.reverse();
+                lines
+            })),
+            Self::NumberLines => Cow::Owned(map_lines(text, |lines| {
+                let width = lines.len().to_string().len();
+                lines
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, line)| format!("{:>width$}. {line}", i + 1))
+                    .collect()
+            })),
+            Self::Upper => Cow::Owned(text.to_uppercase()),
+            Self::Lower => Cow::Owned(text.to_lowercase()),
+            Self::TitleCase => Cow::Owned(title_case(text)),
+            Self::CamelCase => Cow::Owned(per_line(text, |line| {
+                words(line)
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| match i {
+                        0 => word.to_lowercase(),
+                        _ => capitalize(word),
+                    })
+                    .collect()
+            })),
+            Self::SnakeCase => Cow::Owned(per_line(text, |line| join_lower(&words(line), "_"))),
+            Self::KebabCase => Cow::Owned(per_line(text, |line| join_lower(&words(line), "-"))),
+            Self::Slug => Cow::Owned(per_line(text, slug)),
+        }
+    }
+}
+
+/// Turn `\r\n` and lone `\r` into `\n`.
+fn to_lf(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Apply `f` to each line of `text`, leaving the line breaks alone.
+fn per_line(text: &str, f: impl Fn(&str) -> String) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let content = line.trim_end_matches(['\r', '\n']);
+            f(content) + &line[content.len()..]
+        })
+        .collect()
+}
+
+/// Words of an identifier or phrase: split at anything not alphanumeric, and
+/// where case changes (`parseHTTPResponse` is `parse`, `HTTP`, `Response`).
+/// Apostrophes are dropped, so `don't` stays one word.
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().filter(|c| !matches!(c, '\'' | '’')).collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            // `aB` starts a word at B, and so does the `R` in `HTTPResponse`.
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn join_lower(words: &[String], separator: &str) -> String {
+    words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+fn title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut start_of_word = true;
+    for c in text.chars() {
+        if start_of_word {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        // Apostrophes stay inside words: `don't`, not `Don'T`.
+        start_of_word = !(c.is_alphanumeric() || c == '\'' || c == '’');
+    }
+    result
+}
+
+fn slug(line: &str) -> String {
+    line.to_lowercase()
+        .replace(['\'', '’'], "")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Rearrange the lines of `text`, keeping its line breaks (`\r\n` if it uses
+/// them) and whether it ends with one.
+fn map_lines(text: &str, f: impl FnOnce(Vec<String>) -> Vec<String>) -> String {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let body = text.strip_suffix(newline).unwrap_or(text);
+    let lines = body.split(newline).map(str::to_string).collect();
+    let mut result = f(lines).join(newline);
+    if body.len() < text.len() {
+        result.push_str(newline);
+    }
+    result
+}
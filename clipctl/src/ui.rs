@@ -1,18 +1,74 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Stdout;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
-use ratatui::backend::CrosstermBackend;
+use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::layout::{Alignment, Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::block::{Position, Title};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
 use ratatui::Terminal;
 
-use crate::ipc::{EntrySummary, Request, RequestKind, Response};
-use crate::paste::{PasteEngine, PasteMethod};
+use crate::cache;
+use crate::calendar::Calendar;
+use crate::commands;
+use crate::config::{self, ClientConfig};
+use crate::convert;
+use crate::exec;
+use crate::i18n::t;
+use crate::input::{Motion, NormalInput};
+use crate::ipc::{
+    Checksums, Digest, Entity, EntityKind, EntryPayload, EntrySource, EntrySummary, PasteVerdict, Request, RequestKind,
+    Response, Suggestion,
+};
+use crate::jumps::{Jump, JumpList};
+use crate::macros::Macros;
+use crate::mux::{Multiplexer, PanePicker};
+use crate::onboarding::Onboarding;
+use crate::palette::{Action, Palette};
+use crate::panes::{Collection, Pane, Side};
+use crate::encoding;
+use crate::invisible;
+use crate::paste::{self, PasteEngine, PasteMethod, Payload};
+use crate::profiles::ProfilePicker;
+use crate::remote;
+use crate::reveal;
+use crate::richtext;
+use crate::sessions::SessionPicker;
+use crate::settings::SettingsView;
+use crate::git;
 use crate::syntax::{detect_code_language, highlight_code, render_formatted_text};
-use crate::theme::Theme;
+use crate::stats;
+use crate::tabs::ViewTabs;
+use crate::theme::{Glyphs, Theme};
+use crate::transform::Transform;
+
+/// Terminals narrower than this stack the list above the preview, with
+/// shorter rows and a one-line metadata summary.
+const NARROW_WIDTH: u16 = 80;
+
+/// Columns of a narrow list row taken by borders, the selection marker, and icons.
+const NARROW_ROW_CHROME: usize = 12;
+
+/// Lines PageUp and PageDown scroll the help screen by.
+const HELP_PAGE: i16 = 10;
+
+/// Entries left below the selection when the next page of the history is
+/// fetched, so scrolling rarely reaches the end of what is loaded.
+const PAGE_MARGIN: usize = 50;
+
+/// What an answer from clipd is for; clipd answers in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// The list in the pane on this side, which the answer replaces.
+    Pane(Side),
+    /// The next page of the history in the pane on this side.
+    Page(Side),
+}
 
 #[derive(Debug)]
 pub enum UiEvent {
@@ -24,16 +80,81 @@ pub struct HandleOutcome {
     pub request: Option<Request>,
 }
 
-pub struct TerminalUi {
-    terminal: Terminal<CrosstermBackend<Stdout>>,
+/// The TUI's state and the terminal it draws to: the real console by
+/// default, or a `TestBackend` buffer in the snapshot tests.
+pub struct TerminalUi<B: Backend = CrosstermBackend<Stdout>> {
+    terminal: Terminal<B>,
+    /// Entered by `new` and left on drop; other backends never enter it.
+    alternate_screen: bool,
     entries: Vec<EntrySummary>,
     selected: usize,
     filter: String,
     paste: PasteEngine,
     list_state: ListState,
     mode: UiMode,
+    /// Lines the help screen is scrolled down by; kept in range when drawn.
+    help_scroll: u16,
     input_buffer: String,
     theme: Theme,
+    status: Option<String>,
+    /// Set when clipd is unreachable; the UI then works from `cached` only.
+    offline: bool,
+    cached: Vec<EntrySummary>,
+    config: ClientConfig,
+    settings: SettingsView,
+    palette: Palette,
+    input: NormalInput,
+    /// Session marks (`m a`), remembered by entry id so they survive refreshes.
+    marks: HashMap<char, u64>,
+    jumps: JumpList,
+    macros: Macros,
+    tabs: ViewTabs,
+    /// The unfocused pane while the list is split.
+    split: Option<Pane>,
+    /// Side of the focused pane, whose state is in `entries`, `selected`, and `filter`.
+    focus: Side,
+    /// What each unanswered request is for.
+    response_targets: VecDeque<Target>,
+    /// Requests to send right after the current one, with the pane each refreshes.
+    follow_ups: Vec<(Request, Side)>,
+    /// Where the next page of the history starts, while more is left.
+    next_cursor: Option<String>,
+    /// Set while the next page is on its way; a new list cancels it.
+    loading_page: bool,
+    /// Entry to select once the list requested by a jump arrives.
+    select_after_refresh: Option<u64>,
+    /// Request held back until the user answers a confirmation prompt.
+    pending: Option<Request>,
+    /// How to paste the entry of a held-back `Paste` request.
+    pending_transform: Transform,
+    /// Image file to show in Explorer once clipd has written it.
+    reveal_after_save: Option<PathBuf>,
+    /// Set once clipd answers the daily-counts request.
+    calendar: Option<Calendar>,
+    sessions: SessionPicker,
+    profiles: ProfilePicker,
+    /// Encrypted export or import waiting for its passphrase.
+    awaiting_passphrase: Option<Request>,
+    /// First entry of a new export passphrase, until it is typed again.
+    passphrase_first: Option<String>,
+    /// Digests of the entry they name, shown in its preview.
+    checksums: Option<Checksums>,
+    /// Digest to copy once clipd sends the checksums.
+    copy_digest: Option<Digest>,
+    /// Markup shown in the preview of the entry it names instead of its text.
+    source: Option<EntrySource>,
+    /// Copy group to paste once clipd lists its entries.
+    paste_group: Option<u64>,
+    /// Entry to paste, and how, once clipd allows it.
+    awaiting_paste: Option<(u64, Transform)>,
+    /// Image or document entry to paste once clipd sends its data.
+    awaiting_payload: Option<u64>,
+    /// Set while a `pane` paste waits for its pane to be picked.
+    pane_picker: Option<PanePicker>,
+    /// Text for the picked pane, and the entry it came from.
+    pane_paste: Option<(String, Option<u64>)>,
+    /// Set when a response finished what a key started, such as a group paste.
+    exit_requested: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,16 +163,51 @@ enum UiMode {
     Search,
     AddTag,
     RemoveTag,
+    /// Tag for every entry matching the search.
+    TagResults,
     Export,
+    ExportRedacted,
+    ExportEncrypted,
     Import,
+    /// Passphrase for an encrypted export or import; the input is masked.
+    Passphrase,
+    SendCommand,
+    SaveEntry,
     Help,
+    Palette,
+    Settings,
+    Calendar,
+    Sessions,
+    TagSession,
+    ExportSession,
+    Profiles,
+    /// Which tmux or WezTerm pane a `pane` paste goes to.
+    Panes,
+    /// How much history to clear: d, w, m, or a for all of it.
+    ClearHistory,
+    Confirm,
+}
+
+enum PassphrasePrompt {
+    Export,
+    Repeat,
+    Import,
 }
 
 impl TerminalUi {
-    pub fn new() -> Result<Self> {
+    /// Take over the console, on the alternate screen.
+    pub fn new(config: &ClientConfig) -> Result<Self> {
         let mut stdout = std::io::stdout();
         crossterm::execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
+        let mut ui = Self::with_backend(config, CrosstermBackend::new(stdout))?;
+        ui.alternate_screen = true;
+        Ok(ui)
+    }
+}
+
+impl<B: Backend> TerminalUi<B> {
+    /// A UI drawing to `backend`, with an empty list in normal mode.
+    pub fn with_backend(config: &ClientConfig, backend: B) -> Result<Self> {
         let terminal = Terminal::new(backend)?;
 
         let mut list_state = ListState::default();
@@ -59,17 +215,135 @@ impl TerminalUi {
 
         Ok(Self {
             terminal,
+            alternate_screen: false,
             entries: Vec::new(),
             selected: 0,
             filter: String::new(),
-            paste: PasteEngine::new(PasteMethod::SendInput),
+            paste: PasteEngine::new(config.paste_method, config.hide_from_windows_history),
             list_state,
             mode: UiMode::Normal,
+            help_scroll: 0,
             input_buffer: String::new(),
-            theme: Theme::nord(), // Default to Nord theme, can be made configurable
+            theme: Theme::for_config(&config.theme, config.accessible),
+            status: None,
+            offline: false,
+            cached: Vec::new(),
+            config: config.clone(),
+            settings: SettingsView::default(),
+            palette: Palette::default(),
+            input: NormalInput::default(),
+            marks: HashMap::new(),
+            jumps: JumpList::default(),
+            macros: Macros::default(),
+            tabs: ViewTabs::new(&config.views),
+            split: None,
+            focus: Side::Left,
+            response_targets: VecDeque::new(),
+            follow_ups: Vec::new(),
+            next_cursor: None,
+            loading_page: false,
+            select_after_refresh: None,
+            pending: None,
+            pending_transform: Transform::Verbatim,
+            reveal_after_save: None,
+            calendar: None,
+            sessions: SessionPicker::default(),
+            profiles: ProfilePicker::default(),
+            awaiting_passphrase: None,
+            passphrase_first: None,
+            checksums: None,
+            source: None,
+            paste_group: None,
+            awaiting_paste: None,
+            awaiting_payload: None,
+            pane_picker: None,
+            pane_paste: None,
+            exit_requested: false,
+            copy_digest: None,
         })
     }
 
+    /// Switch to read-only mode, optionally replacing the list with cached entries.
+    pub fn go_offline(&mut self, cached: Option<Vec<EntrySummary>>) {
+        self.offline = true;
+        self.response_targets.clear();
+        self.follow_ups.clear();
+        self.next_cursor = None;
+        self.loading_page = false;
+        self.paste_group = None;
+        self.awaiting_paste = None;
+        self.awaiting_payload = None;
+        self.cached = cached.unwrap_or_else(|| self.entries.clone());
+        // Nobody tells an offline list what the clipboard holds.
+        self.cached.iter_mut().for_each(|entry| entry.on_clipboard = false);
+        self.filter.clear();
+        self.entries = self.cached.clone();
+        self.selected = 0;
+    }
+
+    /// Merge an entry clipd pushed into the unfiltered lists, keeping the
+    /// selected entry selected.
+    pub fn add_entry(&mut self, entry: EntrySummary) {
+        if self.offline {
+            return;
+        }
+        if let Some(pane) = &mut self.split {
+            if entry.on_clipboard {
+                pane.entries.iter_mut().for_each(|listed| listed.on_clipboard = false);
+            }
+            merge_added(&mut pane.entries, &mut pane.selected, &pane.filter, entry.clone());
+        }
+        if entry.on_clipboard {
+            self.entries.iter_mut().for_each(|listed| listed.on_clipboard = false);
+        }
+        merge_added(&mut self.entries, &mut self.selected, &self.filter, entry);
+    }
+
+    /// Select the entry clipd suggests pasting, unless the list is filtered
+    /// or the selection moved already.
+    pub fn suggest(&mut self, suggestion: Suggestion) {
+        if self.selected != 0 || !self.filter.is_empty() {
+            return;
+        }
+        let Some(index) = self.entries.iter().position(|entry| entry.id == suggestion.id) else {
+            return;
+        };
+        if index > 0 {
+            self.selected = index;
+            self.set_status(match suggestion.target {
+                Some(app) => t!("status-suggested-for", app = app),
+                None => t!("status-suggested"),
+            });
+        }
+    }
+
+    /// Show a persistent status message in the command bar.
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some(message.into());
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
+    /// Whether the UI should close after the last response.
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    /// Output held back by the stdout paste method, printed after the UI closes.
+    pub fn take_paste_output(&mut self) -> Option<String> {
+        self.paste.take_deferred()
+    }
+
+    /// Draw the first-run wizard, previewing the theme it has selected.
+    pub fn draw_onboarding(&mut self, wizard: &Onboarding) -> Result<()> {
+        self.theme = Theme::for_config(wizard.theme_name(), self.config.accessible);
+        let theme = &self.theme;
+        self.terminal.draw(|frame| wizard.render(frame, theme))?;
+        Ok(())
+    }
+
     pub fn draw(&mut self) -> Result<()> {
         self.list_state.select(if self.entries.is_empty() {
             None
@@ -77,13 +351,46 @@ impl TerminalUi {
             Some(self.selected)
         });
 
+        if let Some(pane) = &mut self.split {
+            pane.list_state.select(if pane.entries.is_empty() {
+                None
+            } else {
+                Some(pane.selected)
+            });
+        }
+
         let is_help_mode = self.mode == UiMode::Help;
+        let help_scroll = &mut self.help_scroll;
+        let split = self.split.as_mut();
+        let focus = self.focus;
         let list_state = &mut self.list_state;
         let entries = &self.entries;
         let selected = self.selected;
         let mode = &self.mode;
         let filter = &self.filter;
         let input_buffer = &self.input_buffer;
+        let status = self.status.as_deref();
+        let offline = self.offline;
+        let config = &self.config;
+        let unit_conversions = self.config.unit_conversions;
+        let guest = self.config.guest_mode;
+        let width = self.terminal.size()?.width;
+        let narrow = width < NARROW_WIDTH;
+        // Side by side, a screen reader reads a list row and a preview line as one.
+        let (panes, list_share) = if self.config.accessible {
+            (Direction::Vertical, 50)
+        } else if narrow {
+            (Direction::Vertical, 60)
+        } else {
+            (Direction::Horizontal, 70)
+        };
+        let pending_keys = self.input.pending_keys();
+        let recording = self.macros.recording();
+        let passphrase_prompt = match &self.awaiting_passphrase {
+            _ if self.passphrase_first.is_some() => PassphrasePrompt::Repeat,
+            Some(Request { kind: RequestKind::Export { .. } }) => PassphrasePrompt::Export,
+            _ => PassphrasePrompt::Import,
+        };
 
         self.terminal.draw(|frame| {
             let size = frame.size();
@@ -100,87 +407,74 @@ impl TerminalUi {
                                 .fg(theme.border_focused)
                                 .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
                         ),
-                        Span::raw(" - Keybindings"),
-                    ]),
-                    Line::raw(""),
-                    Line::styled("Navigation:", theme.style_help_section()),
-                    Line::from(vec![
-                        Span::styled("  j", theme.style_help_key()),
-                        Span::raw("/"),
-                        Span::styled("↓", theme.style_help_key()),
-                        Span::styled("         Move down", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  k", theme.style_help_key()),
-                        Span::raw("/"),
-                        Span::styled("↑", theme.style_help_key()),
-                        Span::styled("         Move up", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  g", theme.style_help_key()),
-                        Span::styled("           Go to top", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  G", theme.style_help_key()),
-                        Span::styled("           Go to bottom", theme.style_help_desc()),
+                        Span::raw(format!(" - {}", t!("help-title"))),
                     ]),
                     Line::raw(""),
-                    Line::styled("Actions:", theme.style_help_section()),
-                    Line::from(vec![
-                        Span::styled("  Enter", theme.style_help_key()),
-                        Span::raw("/"),
-                        Span::styled("l", theme.style_help_key()),
-                        Span::styled("     Paste selected entry", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  /", theme.style_help_key()),
-                        Span::styled("           Start search", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  t", theme.style_help_key()),
-                        Span::styled("           Add tag to entry", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  T", theme.style_help_key()),
-                        Span::styled("           Remove tag from entry", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  e", theme.style_help_key()),
-                        Span::styled("           Export history to JSON", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  i", theme.style_help_key()),
-                        Span::styled("           Import history from JSON", theme.style_help_desc()),
-                    ]),
+                    Line::styled(t!("help-navigation"), theme.style_help_section()),
+                    help_line(&["j", "↓"], t!("help-move-down"), theme),
+                    help_line(&["k", "↑"], t!("help-move-up"), theme),
+                    help_line(&["gg"], t!("help-top"), theme),
+                    help_line(&["G"], t!("help-bottom"), theme),
+                    help_line(&["5j", "5k"], t!("help-count"), theme),
+                    help_line(&["5G"], t!("help-go-to"), theme),
+                    help_line(&["ma", "'a"], t!("help-marks"), theme),
+                    help_line(&["Ctrl+o", "Ctrl+i"], t!("help-jumps"), theme),
                     Line::raw(""),
-                    Line::styled("General:", theme.style_help_section()),
-                    Line::from(vec![
-                        Span::styled("  ?", theme.style_help_key()),
-                        Span::styled("           Show this help", theme.style_help_desc()),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("  q", theme.style_help_key()),
-                        Span::raw("/"),
-                        Span::styled("Esc", theme.style_help_key()),
-                        Span::styled("       Quit", theme.style_help_desc()),
-                    ]),
+                    Line::styled(t!("help-actions"), theme.style_help_section()),
+                    help_line(&["Enter", "l"], t!("help-paste"), theme),
+                    help_line(&["/"], t!("help-search"), theme),
+                    help_line(&["t"], t!("help-add-tag"), theme),
+                    help_line(&["T"], t!("help-remove-tag"), theme),
+                    help_line(&["d"], t!("help-delete"), theme),
+                    help_line(&["D"], t!("help-clear"), theme),
+                    help_line(&["e"], t!("help-export"), theme),
+                    help_line(&["E"], t!("help-export-redacted"), theme),
+                    help_line(&["i"], t!("help-import"), theme),
+                    help_line(&["w"], t!("help-save"), theme),
+                    help_line(&["o"], t!("help-explorer"), theme),
+                    help_line(&["r", "R"], t!("help-copied-together"), theme),
+                    help_line(&["s"], t!("help-source"), theme),
+                    help_line(&["u"], t!("help-restore"), theme),
+                    help_line(&["z"], t!("help-snapshot"), theme),
+                    help_line(&["|"], t!("help-send"), theme),
+                    help_line(&["c"], t!("help-calendar"), theme),
+                    help_line(&["S"], t!("help-sessions"), theme),
+                    help_line(&["H"], t!("help-guest"), theme),
+                    help_line(&["P"], t!("help-profiles"), theme),
+                    help_line(&["v", "Ctrl+w"], t!("help-split"), theme),
                     Line::raw(""),
-                    Line::styled(
-                        "Press any key to close help...",
-                        Style::default().fg(theme.metadata_label).add_modifier(Modifier::ITALIC),
-                    ),
+                    Line::styled(t!("help-general"), theme.style_help_section()),
+                    help_line(&["?"], t!("help-help"), theme),
+                    help_line(&[":"], t!("help-palette"), theme),
+                    help_line(&[":set"], t!("help-settings"), theme),
+                    help_line(&["qa", "@a"], t!("help-macros"), theme),
+                    help_line(&["Esc"], t!("help-quit"), theme),
                 ];
-                
+
+                // Stop scrolling once the last line is in view; wrapped lines take more than one row.
+                let inner_width = usize::from(size.width.saturating_sub(2)).max(1);
+                let rows: usize = help_lines.iter().map(|line| line.width().div_ceil(inner_width).max(1)).sum();
+                let bottom = rows.saturating_sub(usize::from(size.height.saturating_sub(2)));
+                *help_scroll = (*help_scroll).min(u16::try_from(bottom).unwrap_or(u16::MAX));
+
+                // The hint sits on the border, so scrolling never hides it.
+                let hint = Title::from(Span::styled(
+                    format!(" {} ", t!("help-close")),
+                    Style::default().fg(theme.metadata_label).add_modifier(Modifier::ITALIC),
+                ))
+                .position(Position::Bottom);
                 let help = Paragraph::new(help_lines)
                     .block(
                         Block::default()
-                            .title(Span::styled(" Help ", theme.style_title()))
-                            .borders(Borders::ALL)
+                            .title(Span::styled(format!(" {} ", t!("help")), theme.style_title()))
+                            .title(hint)
+                            .borders(theme.glyphs.borders())
                             .border_style(theme.style_border_focused())
                             .title_alignment(Alignment::Center),
                     )
                     .alignment(Alignment::Left)
-                    .wrap(ratatui::widgets::Wrap { trim: false });
+                    .wrap(ratatui::widgets::Wrap { trim: false })
+                    .scroll((*help_scroll, 0));
                 
                 frame.render_widget(help, size);
                 return;
@@ -188,73 +482,22 @@ impl TerminalUi {
             
             let layout = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Min(5), Constraint::Length(3)])
+                .constraints([
+                    Constraint::Length(if offline { 1 } else { 0 }),
+                    Constraint::Length(if self.tabs.is_empty() { 0 } else { 1 }),
+                    Constraint::Min(5),
+                    Constraint::Length(3),
+                ])
                 .split(size);
             let main = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-                .split(layout[0]);
+                .direction(panes)
+                .constraints([Constraint::Percentage(list_share), Constraint::Percentage(100 - list_share)])
+                .split(layout[2]);
 
-            // Format history items with kind and tags
             let theme = &self.theme;
-            let history_items: Vec<_> = entries
-                .iter()
-                .map(|entry| {
-                    let (kind_icon, icon_color) = match entry.kind.as_str() {
-                        "text" => ("📝", theme.text_icon),
-                        "url" => ("🔗", theme.url_icon),
-                        "image" => ("🖼️", theme.image_icon),
-                        "rtf" => ("📄", theme.rtf_icon),
-                        _ => ("❓", theme.metadata_label),
-                    };
-                    
-                    let mut spans = vec![
-                        Span::styled(
-                            format!("{} ", kind_icon),
-                            Style::default().fg(icon_color),
-                        ),
-                    ];
-                    
-                    // Truncate preview if too long
-                    let preview_text = if entry.preview.len() > 80 {
-                        format!("{}...", &entry.preview[..77])
-                    } else {
-                        entry.preview.clone()
-                    };
-                    
-                    spans.push(Span::styled(
-                        preview_text,
-                        theme.style_list_item(),
-                    ));
-                    
-                    // Add tags with styling
-                    if !entry.tags.is_empty() {
-                        spans.push(Span::raw("  "));
-                        for (i, tag) in entry.tags.iter().enumerate() {
-                            if i > 0 {
-                                spans.push(Span::raw(" "));
-                            }
-                            spans.push(Span::styled(
-                                format!(" {} ", tag),
-                                theme.style_tag(),
-                            ));
-                        }
-                    }
-                    
-                    ListItem::new(Line::from(spans))
-                })
-                .collect();
+            let title = list_title(filter, split.is_some(), guest);
+            let list = history_list(entries, title, split.is_some(), theme, config, size.width);
 
-            let list = List::new(history_items)
-                .block(
-                    Block::default()
-                        .title(Span::styled(" History (? for help) ", theme.style_title()))
-                        .borders(Borders::ALL)
-                        .border_style(theme.style_border())
-                        .title_alignment(Alignment::Center),
-                )
-                .highlight_style(theme.style_list_selected())
-                .highlight_symbol("▶ ");
 
             // Enhanced preview with metadata and syntax highlighting
             let preview_content = entries
@@ -263,22 +506,159 @@ impl TerminalUi {
                     let theme = &self.theme;
                     let mut lines = Vec::new();
                     
-                    // Metadata header
-                    lines.push(Line::from(vec![
-                        Span::styled("Type: ", theme.style_metadata_label()),
-                        Span::styled(&e.kind, theme.style_metadata_value()),
-                    ]));
+                    // Metadata header; narrow, one line of it leaves room for the content
+                    if narrow {
+                        let tags = e.tags.join(", ");
+                        let mut summary = vec![e.kind.as_str()];
+                        summary.extend(e.source_process.as_deref().filter(|_| !guest));
+                        summary.push(e.display_time());
+                        // Narrow rows have no room left for tags
+                        summary.extend(Some(tags.as_str()).filter(|tags| !tags.is_empty() && !guest));
+                        lines.push(Line::styled(
+                            summary.join(theme.glyphs.separator()),
+                            theme.style_metadata_value(),
+                        ));
+                    } else {
+                        lines.push(Line::from(vec![
+                            Span::styled(label(t!("preview-type")), theme.style_metadata_label()),
+                            Span::styled(&e.kind, theme.style_metadata_value()),
+                        ]));
+                    }
+
+                    if guest {
+                        if !narrow {
+                            lines.push(Line::from(vec![
+                                Span::styled(label(t!("preview-time")), theme.style_metadata_label()),
+                                Span::styled(e.display_time(), theme.style_metadata_value()),
+                            ]));
+                        }
+                        lines.push(Line::raw(""));
+                        lines.push(Line::styled(
+                            t!("preview-guest"),
+                            Style::default()
+                                .fg(theme.metadata_label)
+                                .add_modifier(Modifier::ITALIC),
+                        ));
+                        return Text::from(lines);
+                    }
                     
-                    if let Some(ref proc) = e.source_process {
+                    if let Some(proc) = e.source_process.as_ref().filter(|_| !narrow) {
                         lines.push(Line::from(vec![
-                            Span::styled("Source: ", theme.style_metadata_label()),
+                            Span::styled(label(t!("preview-source")), theme.style_metadata_label()),
                             Span::styled(proc, theme.style_metadata_value()),
                         ]));
                     }
+
+                    if e.quarantined {
+                        lines.push(Line::styled(
+                            format!("{} {}", theme.glyphs.warning(), t!("preview-quarantined")),
+                            theme.style_warning(),
+                        ));
+                    }
+
+                    if e.archived {
+                        lines.push(Line::styled(
+                            t!("preview-archived"),
+                            theme.style_warning(),
+                        ));
+                    }
+
+                    if e.on_clipboard {
+                        lines.push(Line::styled(
+                            format!("{} {}", theme.glyphs.on_clipboard(), t!("preview-on-clipboard")),
+                            theme.style_help_desc(),
+                        ));
+                    }
+
+                    if !e.warnings.is_empty() {
+                        lines.push(Line::styled(
+                            format!(
+                                "{} {}",
+                                theme.glyphs.warning(),
+                                t!("preview-warnings", warnings = e.warnings.join(", "))
+                            ),
+                            theme.style_warning(),
+                        ));
+                    }
+                    
+                    // Folded into the summary line, or left out, when narrow
+                    let mut details = Vec::new();
+                    if let Some(ref lang) = e.lang {
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-language")), theme.style_metadata_label()),
+                            Span::styled(lang, theme.style_metadata_value()),
+                        ]));
+                    }
+
+                    if e.use_count > 1 {
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-copies")), theme.style_metadata_label()),
+                            Span::styled(t!("preview-copies-count", count = e.use_count), theme.style_metadata_value()),
+                        ]));
+                    }
+
+                    if let Some(group) = e.copy_group {
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-copied-together")), theme.style_metadata_label()),
+                            Span::styled(format!("group:{group}"), theme.style_metadata_value()),
+                            Span::styled(
+                                format!("  {}", t!("preview-copied-together-keys")),
+                                theme.style_help_desc(),
+                            ),
+                        ]));
+                    }
+
+                    if !e.formats.is_empty() {
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-formats")), theme.style_metadata_label()),
+                            Span::styled(e.formats.join(", "), theme.style_metadata_value()),
+                        ]));
+                    }
+
+                    for kind in EntityKind::ALL {
+                        let values: Vec<String> =
+                            e.entities.iter().filter(|entity| entity.kind == kind).map(entity_text).collect();
+                        if !values.is_empty() {
+                            details.push(Line::from(vec![
+                                Span::styled(label(entity_label(kind)), theme.style_metadata_label()),
+                                Span::styled(values.join(", "), theme.style_help_key()),
+                            ]));
+                        }
+                    }
+
+                    let diff = git::detect(&e.preview);
+                    if let Some(diff) = diff {
+                        let summary = t!(
+                            "preview-git-summary",
+                            kind = if diff.patch { "patch" } else { "diff" },
+                            files = diff.files,
+                            added = diff.added,
+                            removed = diff.removed,
+                        );
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-git")), theme.style_metadata_label()),
+                            Span::styled(summary, theme.style_metadata_value()),
+                        ]));
+                    }
+
+                    if let Some(ref image) = e.image {
+                        let mut size = format!("{}×{}", image.width, image.height);
+                        if let Some(dpi) = image.dpi {
+                            size.push_str(&format!(" {}", t!("preview-image-dpi", dpi = dpi)));
+                        }
+                        if let Some(ref monitor) = image.monitor {
+                            let monitor = t!("preview-image-monitor", monitor = monitor.as_str());
+                            size.push_str(&format!(" {monitor}"));
+                        }
+                        details.push(Line::from(vec![
+                            Span::styled(label(t!("preview-size")), theme.style_metadata_label()),
+                            Span::styled(size, theme.style_metadata_value()),
+                        ]));
+                    }
                     
                     if !e.tags.is_empty() {
                         let mut tag_spans = vec![
-                            Span::styled("Tags: ", theme.style_metadata_label()),
+                            Span::styled(label(t!("preview-tags")), theme.style_metadata_label()),
                         ];
                         for (i, tag) in e.tags.iter().enumerate() {
                             if i > 0 {
@@ -286,21 +666,104 @@ impl TerminalUi {
                             }
                             tag_spans.push(Span::styled(tag, theme.style_tag()));
                         }
-                        lines.push(Line::from(tag_spans));
+                        details.push(Line::from(tag_spans));
                     }
                     
-                    lines.push(Line::from(vec![
-                        Span::styled("Time: ", theme.style_metadata_label()),
-                        Span::styled(&e.created_at, theme.style_metadata_value()),
+                    details.push(Line::from(vec![
+                        Span::styled(label(t!("preview-time")), theme.style_metadata_label()),
+                        Span::styled(e.display_time(), theme.style_metadata_value()),
                     ]));
+                    if !narrow {
+                        lines.append(&mut details);
+                    }
+
+                    if let Some(checksums) = self.checksums.as_ref().filter(|c| c.id == e.id) {
+                        for digest in Digest::ALL {
+                            let value = checksums.get(digest);
+                            let mut spans = vec![
+                                Span::styled(format!("{}: ", digest.label()), theme.style_metadata_label()),
+                                Span::styled(value, theme.style_metadata_value()),
+                            ];
+                            // A checksum copied earlier, from a download page say
+                            if let Some(other) = entries.iter().find(|other| {
+                                other.id != e.id && other.preview.trim().eq_ignore_ascii_case(value)
+                            }) {
+                                spans.push(Span::styled(
+                                    format!(
+                                        "  {} {}",
+                                        theme.glyphs.matched(),
+                                        t!("preview-checksum-match", id = other.id)
+                                    ),
+                                    theme.style_help_key(),
+                                ));
+                            }
+                            lines.push(Line::from(spans));
+                        }
+                    }
+
+                    if !narrow && matches!(e.kind.as_str(), "text" | "url") {
+                        lines.push(Line::from(vec![
+                            Span::styled(label(t!("preview-size")), theme.style_metadata_label()),
+                            Span::styled(
+                                stats::Counts::of(&e.preview).to_string(),
+                                theme.style_metadata_value(),
+                            ),
+                        ]));
+                        if let Some(endings) = encoding::line_endings(&e.preview) {
+                            lines.push(Line::from(vec![
+                                Span::styled(label(t!("preview-line-endings")), theme.style_metadata_label()),
+                                Span::styled(endings, theme.style_metadata_value()),
+                            ]));
+                        }
+                        for (conversion, value) in convert::conversions(&e.preview, unit_conversions) {
+                            lines.push(Line::from(vec![
+                                Span::styled(format!("{}: ", conversion.label()), theme.style_metadata_label()),
+                                Span::styled(value, theme.style_help_key()),
+                            ]));
+                        }
+                        let notes = encoding::suspicious(&e.preview);
+                        if !notes.is_empty() {
+                            lines.push(Line::from(vec![
+                                Span::styled(label(t!("preview-encoding")), theme.style_metadata_label()),
+                                Span::styled(notes.join(", "), theme.style_warning()),
+                            ]));
+                        }
+                    }
                     
-                    lines.push(Line::from(Span::styled(
-                        "─".repeat(40),
-                        Style::default().fg(theme.border),
-                    )));
+                    if theme.glyphs == Glyphs::Unicode {
+                        lines.push(Line::from(Span::styled(
+                            "─".repeat(if narrow { usize::from(main[1].width.saturating_sub(2)).min(40) } else { 40 }),
+                            Style::default().fg(theme.border),
+                        )));
+                    }
                     
                     // Content with syntax highlighting or formatting
-                    if let Some(lang) = detect_code_language(&e.preview) {
+                    if let Some(source) = self.source.as_ref().filter(|source| source.id == e.id) {
+                        for line in source.text.lines().take(50) {
+                            lines.push(Line::from(Span::styled(line.to_string(), theme.style_list_item())));
+                        }
+                    } else if e.preview.chars().any(invisible::is_hidden) {
+                        // Plain, so every hidden character gets a visible badge
+                        for line in e.preview.lines().take(50) {
+                            lines.push(invisible::reveal(
+                                line,
+                                theme.style_list_item(),
+                                theme.style_warning().add_modifier(Modifier::REVERSED),
+                            ));
+                        }
+                    } else if !e.styles.is_empty() {
+                        lines.extend(richtext::lines(&e.preview, &e.styles, theme.style_list_item(), 50));
+                    } else if !e.highlights.is_empty() {
+                        lines.extend(richtext::highlighted(
+                            &e.preview,
+                            &e.highlights,
+                            theme.style_list_item(),
+                            theme.style_search_match(),
+                            50,
+                        ));
+                    } else if diff.is_some() {
+                        lines.extend(git::lines(&e.preview, theme, 50));
+                    } else if let Some(lang) = detect_code_language(&e.preview) {
                         // Syntax highlight detected code
                         let highlighted = highlight_code(&e.preview, Some(lang));
                         lines.extend(highlighted.lines);
@@ -322,7 +785,7 @@ impl TerminalUi {
                 })
                 .unwrap_or_else(|| {
                     Text::from(Line::from(Span::styled(
-                        "<no selection>",
+                        t!("preview-no-selection"),
                         Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
                     )))
                 });
@@ -330,8 +793,8 @@ impl TerminalUi {
             let preview = Paragraph::new(preview_content)
                 .block(
                     Block::default()
-                        .title(Span::styled(" Preview ", theme.style_title()))
-                        .borders(Borders::ALL)
+                        .title(Span::styled(format!(" {} ", t!("preview")), theme.style_title()))
+                        .borders(theme.glyphs.borders())
                         .border_style(theme.style_border()),
                 )
                 .alignment(Alignment::Left)
@@ -342,91 +805,333 @@ impl TerminalUi {
             let command_content = match mode {
                 UiMode::Normal => {
                     let mut spans = vec![
-                        Span::styled("Search: ", theme.style_command_prompt()),
+                        Span::styled(label(t!("prompt-search")), theme.style_command_prompt()),
                         Span::styled(filter.as_str(), theme.style_command_input()),
                     ];
                     if filter.is_empty() {
                         spans.push(Span::styled(
-                            " (press / to search)",
+                            format!(" {}", t!("prompt-search-hint")),
                             Style::default().fg(theme.metadata_label).add_modifier(Modifier::ITALIC),
                         ));
                     }
+                    if let Some(register) = recording {
+                        spans.push(Span::styled(
+                            format!("  {}", t!("prompt-recording", register = register.to_string())),
+                            theme.style_warning(),
+                        ));
+                    }
+                    if let Some(keys) = &pending_keys {
+                        spans.push(Span::styled(format!("  {keys}"), theme.style_command_input()));
+                    }
+                    if let Some(status) = status {
+                        spans.push(Span::styled(
+                            format!("  [{status}]"),
+                            Style::default().fg(theme.tag_bg).add_modifier(Modifier::BOLD),
+                        ));
+                    }
                     Line::from(spans)
                 }
-                UiMode::Search => Line::from(vec![
-                    Span::styled("🔍 Search: ", theme.style_command_prompt()),
-                    Span::styled(input_buffer.as_str(), theme.style_command_input()),
-                    Span::styled("█", Style::default().fg(theme.list_selected_fg)), // Cursor
-                ]),
-                UiMode::AddTag => Line::from(vec![
-                    Span::styled("🏷️  Add tag: ", theme.style_command_prompt()),
-                    Span::styled(input_buffer.as_str(), theme.style_command_input()),
-                    Span::styled("█", Style::default().fg(theme.list_selected_fg)),
-                ]),
-                UiMode::RemoveTag => Line::from(vec![
-                    Span::styled("🗑️  Remove tag: ", theme.style_command_prompt()),
-                    Span::styled(input_buffer.as_str(), theme.style_command_input()),
-                    Span::styled("█", Style::default().fg(theme.list_selected_fg)),
-                ]),
-                UiMode::Export => Line::from(vec![
-                    Span::styled("💾 Export to: ", theme.style_command_prompt()),
-                    Span::styled(input_buffer.as_str(), theme.style_command_input()),
-                    Span::styled("█", Style::default().fg(theme.list_selected_fg)),
-                ]),
-                UiMode::Import => Line::from(vec![
-                    Span::styled("📥 Import from: ", theme.style_command_prompt()),
-                    Span::styled(input_buffer.as_str(), theme.style_command_input()),
-                    Span::styled("█", Style::default().fg(theme.list_selected_fg)),
+                UiMode::Search => prompt_line(theme, "🔍", t!("prompt-search"), input_buffer),
+                UiMode::AddTag => prompt_line(theme, "🏷️ ", t!("prompt-add-tag"), input_buffer),
+                UiMode::RemoveTag => prompt_line(theme, "🗑️ ", t!("prompt-remove-tag"), input_buffer),
+                UiMode::Export => prompt_line(theme, "💾", t!("prompt-export"), input_buffer),
+                UiMode::ExportRedacted => prompt_line(theme, "🔒", t!("prompt-export-redacted"), input_buffer),
+                UiMode::ExportEncrypted => prompt_line(theme, "🔐", t!("prompt-export-encrypted"), input_buffer),
+                UiMode::Passphrase => prompt_line(
+                    theme,
+                    "🔑",
+                    match passphrase_prompt {
+                        PassphrasePrompt::Export => t!("prompt-passphrase-export"),
+                        PassphrasePrompt::Repeat => t!("prompt-passphrase-repeat"),
+                        PassphrasePrompt::Import => t!("prompt-passphrase"),
+                    },
+                    theme.glyphs.mask().repeat(input_buffer.chars().count()),
+                ),
+                UiMode::Import => prompt_line(theme, "📥", t!("prompt-import"), input_buffer),
+                UiMode::SaveEntry => prompt_line(theme, "💾", t!("prompt-save-entry"), input_buffer),
+                UiMode::SendCommand => prompt_line(theme, "📤", t!("prompt-send"), input_buffer),
+                UiMode::Palette => Line::from(vec![
+                    Span::styled(":", theme.style_command_prompt()),
+                    Span::styled(self.palette.query(), theme.style_command_input()),
+                    Span::styled(theme.glyphs.cursor(), Style::default().fg(theme.list_selected_fg)),
                 ]),
+                UiMode::Confirm | UiMode::ClearHistory | UiMode::Settings => Line::from(Span::styled(
+                    status.map_or_else(|| t!("mode-settings"), str::to_string),
+                    theme.style_command_prompt(),
+                )),
+                UiMode::Calendar => Line::from(Span::styled(
+                    status.map_or_else(|| t!("mode-calendar"), str::to_string),
+                    theme.style_command_prompt(),
+                )),
+                UiMode::Sessions => Line::from(Span::styled(
+                    status.map_or_else(|| t!("mode-sessions"), str::to_string),
+                    theme.style_command_prompt(),
+                )),
+                UiMode::Profiles => Line::from(Span::styled(
+                    status.map_or_else(|| t!("mode-profiles"), str::to_string),
+                    theme.style_command_prompt(),
+                )),
+                UiMode::Panes => Line::from(Span::styled(
+                    status.map_or_else(|| t!("mode-panes"), str::to_string),
+                    theme.style_command_prompt(),
+                )),
+                UiMode::TagResults => prompt_line(theme, "🏷️ ", t!("prompt-tag-results"), input_buffer),
+                UiMode::TagSession => prompt_line(theme, "🏷️ ", t!("prompt-tag-session"), input_buffer),
+                UiMode::ExportSession => prompt_line(theme, "💾", t!("prompt-export-session"), input_buffer),
                 UiMode::Help => Line::from(""),
             };
 
             let command_bar = Paragraph::new(command_content)
                 .block(
                     Block::default()
-                        .borders(Borders::ALL)
+                        .borders(theme.glyphs.borders())
                         .border_style(if *mode != UiMode::Normal {
                             theme.style_border_focused()
                         } else {
                             theme.style_border()
                         })
-                        .title(Span::styled(" Command ", theme.style_title())),
+                        .title(Span::styled(format!(" {} ", t!("command")), theme.style_title())),
                 )
                 .alignment(Alignment::Left);
 
-            frame.render_stateful_widget(list, main[0], list_state);
-            frame.render_widget(preview, main[1]);
-            frame.render_widget(command_bar, layout[1]);
+            if offline {
+                let banner = Paragraph::new(Line::from(Span::styled(
+                    format!(" {} ", t!("offline-banner")),
+                    Style::default()
+                        .fg(theme.tag_fg)
+                        .bg(theme.tag_bg)
+                        .add_modifier(Modifier::BOLD),
+                )))
+                .alignment(Alignment::Center);
+                frame.render_widget(banner, layout[0]);
+            }
+            if !self.tabs.is_empty() {
+                self.tabs.render(frame, layout[1], theme);
+            }
+
+            if *mode == UiMode::Settings {
+                self.settings.render(frame, layout[2], &self.config, theme);
+            } else if *mode == UiMode::Calendar {
+                match &self.calendar {
+                    Some(calendar) => calendar.render(frame, layout[2], theme),
+                    None => frame.render_widget(
+                        Paragraph::new(Line::styled(t!("loading"), theme.style_metadata_label()))
+                            .block(Block::default().borders(theme.glyphs.borders()).border_style(theme.style_border_focused())),
+                        layout[2],
+                    ),
+                }
+            } else if matches!(mode, UiMode::Sessions | UiMode::TagSession | UiMode::ExportSession) {
+                self.sessions.render(frame, layout[2], theme);
+            } else if *mode == UiMode::Profiles {
+                self.profiles.render(frame, layout[2], theme, guest);
+            } else if let (UiMode::Panes, Some(picker)) = (&*mode, &self.pane_picker) {
+                picker.render(frame, layout[2], theme);
+            } else if *mode == UiMode::Palette {
+                frame.render_widget(preview, main[1]);
+                self.palette.render(frame, main[0], theme, &self.config.keys);
+            } else {
+                match split {
+                    Some(other) => {
+                        let halves = Layout::default()
+                            .direction(panes)
+                            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                            .split(main[0]);
+                        let (mine, theirs) = match focus {
+                            Side::Left => (halves[0], halves[1]),
+                            Side::Right => (halves[1], halves[0]),
+                        };
+                        let title = list_title(&other.filter, true, guest);
+                        let other_list = history_list(&other.entries, title, false, theme, config, size.width);
+                        frame.render_stateful_widget(list, mine, list_state);
+                        frame.render_stateful_widget(other_list, theirs, &mut other.list_state);
+                    }
+                    None => frame.render_stateful_widget(list, main[0], list_state),
+                }
+                frame.render_widget(preview, main[1]);
+            }
+            frame.render_widget(command_bar, layout[3]);
         })?;
         Ok(())
     }
 
     pub fn handle_event(&mut self, event: UiEvent) -> Result<HandleOutcome> {
+        use crossterm::event::{KeyCode, KeyEventKind};
+
+        let UiEvent::Input(ev) = event;
+        if let crossterm::event::Event::Key(key) = ev {
+            if key.kind == KeyEventKind::Press {
+                let idle = HandleOutcome {
+                    should_exit: false,
+                    request: None,
+                };
+                // A key typed while a macro plays interrupts it.
+                if self.macros.stop_playing() {
+                    self.set_status(t!("status-macro-stopped"));
+                    return Ok(idle);
+                }
+                if self.mode == UiMode::Normal
+                    && key.code == KeyCode::Char('q')
+                    && self.input.pending_keys().is_none()
+                {
+                    if let Some((register, len)) = self.macros.stop_recording() {
+                        let register = register.to_string();
+                        self.set_status(t!("status-macro-recorded", count = len, register = register));
+                        return Ok(idle);
+                    }
+                }
+                self.macros.record(key);
+            }
+        }
+        self.handle_input(ev)
+    }
+
+    /// Whether a macro has keys left to play.
+    pub fn playing_macro(&self) -> bool {
+        self.macros.playing()
+    }
+
+    /// End the macro being played, if one is, saying so.
+    fn stop_macro(&mut self) {
+        if self.macros.stop_playing() {
+            self.set_status(t!("status-macro-stopped"));
+        }
+    }
+
+    /// Feed the next key of a playing macro, if there is one.
+    pub fn play_next(&mut self) -> Option<Result<HandleOutcome>> {
+        let key = self.macros.next_key()?;
+        let outcome = self.handle_input(crossterm::event::Event::Key(key));
+        // Nothing queued after the last key, not even by a macro it played: playback is over.
+        if !self.macros.playing() {
+            self.macros.stop_playing();
+        }
+        Some(outcome)
+    }
+
+    fn handle_input(&mut self, ev: crossterm::event::Event) -> Result<HandleOutcome> {
         let mut request = None;
         let mut should_exit = false;
 
-        let UiEvent::Input(ev) = event;
         match ev {
             crossterm::event::Event::Key(key) => {
-                use crossterm::event::{KeyCode, KeyEventKind};
+                use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
                 if key.kind == KeyEventKind::Press {
                     // Handle help mode separately
                     if self.mode == UiMode::Help {
+                        match key.code {
+                            KeyCode::Char('s') => self.open_settings(),
+                            KeyCode::Down | KeyCode::Char('j') => self.scroll_help(1),
+                            KeyCode::Up | KeyCode::Char('k') => self.scroll_help(-1),
+                            KeyCode::PageDown => self.scroll_help(HELP_PAGE),
+                            KeyCode::PageUp => self.scroll_help(-HELP_PAGE),
+                            _ => self.mode = UiMode::Normal,
+                        }
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Settings {
+                        self.handle_settings_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Calendar {
+                        request = self.handle_calendar_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Sessions {
+                        request = self.handle_sessions_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Profiles {
+                        request = self.handle_profiles_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Panes {
+                        should_exit = self.handle_panes_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::ClearHistory {
+                        request = self.handle_clear_key(key.code);
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+
+                    if self.mode == UiMode::Confirm {
                         self.mode = UiMode::Normal;
+                        if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                            request = self.pending.take();
+                            self.clear_status();
+                            match &request {
+                                Some(Request { kind: RequestKind::Paste { id, .. } }) if self.offline => {
+                                    should_exit = self.paste_entry(*id, self.pending_transform)?;
+                                }
+                                Some(Request { kind: RequestKind::Paste { id, .. } }) => {
+                                    self.awaiting_paste = Some((*id, self.pending_transform));
+                                }
+                                Some(Request { kind: RequestKind::Restore { .. } }) => should_exit = true,
+                                // clipd answers with the whole history.
+                                Some(Request {
+                                    kind: RequestKind::Delete { .. } | RequestKind::Clear { .. },
+                                }) => self.filter.clear(),
+                                _ => {}
+                            }
+                        } else {
+                            self.pending = None;
+                            self.set_status(t!("status-cancelled"));
+                        }
                         return Ok(HandleOutcome { should_exit, request });
                     }
                     
-                    // Handle input modes (AddTag, RemoveTag, Export, Import, Search)
-                    if self.mode != UiMode::Normal {
+                    if self.mode == UiMode::Palette {
+                        match key.code {
+                            KeyCode::Esc => self.mode = UiMode::Normal,
+                            KeyCode::Enter => {
+                                self.mode = UiMode::Normal;
+                                match self.palette.selected_action() {
+                                    Some(action) => {
+                                        HandleOutcome { should_exit, request } = self.perform(action)?;
+                                    }
+                                    None => {
+                                        self.set_status(t!("status-no-action", query = self.palette.query()))
+                                    }
+                                }
+                            }
+                            KeyCode::Up | KeyCode::BackTab => self.palette.move_selection(-1),
+                            KeyCode::Down | KeyCode::Tab => self.palette.move_selection(1),
+                            KeyCode::Backspace => self.palette.pop(),
+                            KeyCode::Char(c) => self.palette.push(c),
+                            _ => {}
+                        }
+                    } else if self.mode != UiMode::Normal {
+                        // Handle input modes (AddTag, RemoveTag, Export, Import, Search)
                         match key.code {
                             KeyCode::Esc => {
+                                self.mode = self.mode_after_input();
+                                self.input_buffer.clear();
+                                self.awaiting_passphrase = None;
+                                self.passphrase_first = None;
+                            }
+                            KeyCode::Enter if self.mode == UiMode::Passphrase => {
+                                request = self.submit_passphrase();
+                                self.input_buffer.clear();
+                                request = request.and_then(|req| self.confirm_overwrite(req));
+                            }
+                            KeyCode::Enter if self.mode == UiMode::SendCommand => {
                                 self.mode = UiMode::Normal;
+                                self.send_to_command();
                                 self.input_buffer.clear();
                             }
                             KeyCode::Enter => {
+                                let encrypt = self.mode == UiMode::ExportEncrypted;
                                 request = self.handle_input_mode_submit()?;
-                                self.mode = UiMode::Normal;
+                                self.mode = self.mode_after_input();
                                 self.input_buffer.clear();
+                                request = request
+                                    .and_then(|req| self.ask_passphrase(req, encrypt))
+                                    .and_then(|req| self.confirm_overwrite(req));
                             }
                             KeyCode::Backspace => {
                                 self.input_buffer.pop();
@@ -446,62 +1151,86 @@ impl TerminalUi {
                             _ => {}
                         }
                         return Ok(HandleOutcome { should_exit, request });
-                    }
-                    
-                    // Normal mode keybindings
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => should_exit = true,
-                        KeyCode::Char('?') => {
-                            self.mode = UiMode::Help;
-                        }
-                        KeyCode::Char('j') | KeyCode::Down => {
-                            if !self.entries.is_empty() {
-                                self.selected = (self.selected + 1).min(self.entries.len() - 1);
-                            }
-                        }
-                        KeyCode::Char('k') | KeyCode::Up => {
-                            if self.selected > 0 {
-                                self.selected -= 1;
-                            }
-                        }
-                        KeyCode::Char('g') => {
-                            self.selected = 0;
+                    } else if let (KeyCode::Char(digit @ '1'..='9'), true) =
+                        (key.code, key.modifiers.contains(KeyModifiers::ALT))
+                    {
+                        // Alt+1..9 switches views; plain digits are counts.
+                        request = self.switch_tab(digit as usize - '1' as usize);
+                    } else if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && key.code == KeyCode::Char('w')
+                    {
+                        HandleOutcome { should_exit, request } = self.perform(Action::SwitchPane)?;
+                    } else if (key.modifiers.contains(KeyModifiers::CONTROL)
+                        && matches!(key.code, KeyCode::Char('o') | KeyCode::Char('i')))
+                        || key.code == KeyCode::Tab
+                    {
+                        // Terminals deliver Ctrl+i as Tab.
+                        let jump = if key.code == KeyCode::Char('o') {
+                            self.current_jump().and_then(|current| self.jumps.back(current))
+                        } else {
+                            self.jumps.forward()
+                        };
+                        match jump {
+                            Some(jump) => request = self.go_to_jump(jump),
+                            None => self.set_status(t!("status-no-more-jumps")),
                         }
-                        KeyCode::Char('G') => {
-                            if !self.entries.is_empty() {
-                                self.selected = self.entries.len() - 1;
+                    } else if let Some(action) = self.config.keys.action(key.code, key.modifiers) {
+                        // `[keys]` bindings come before counts, marks, and the
+                        // built-in keys, so they can replace any of them.
+                        HandleOutcome { should_exit, request } = self.perform(action)?;
+                    } else {
+                        // Normal mode: counts, `gg`, and marks first, then plain bindings
+                        let code = match self.input.feed(key.code) {
+                            Motion::Key(code) => code,
+                            motion => {
+                                self.apply_motion(motion);
+                                let request = self.next_page();
+                                return Ok(HandleOutcome { should_exit, request });
                             }
-                        }
-                        KeyCode::Enter | KeyCode::Char('l') => {
-                            if let Some(entry) = self.entries.get(self.selected) {
-                                self.paste.paste(&entry.preview)?;
-                                request = Some(Request {
-                                    kind: RequestKind::Paste { id: entry.id },
-                                });
-                                should_exit = true;
+                        };
+                        let action = match code {
+                            KeyCode::Esc => Some(Action::Quit),
+                            KeyCode::Char('?') => Some(Action::Help),
+                            KeyCode::Enter | KeyCode::Char('l') => Some(Action::Paste),
+                            KeyCode::Char('L') => Some(Action::PasteAs(Transform::Sanitized)),
+                            KeyCode::Char('x') => Some(Action::Transforms),
+                            KeyCode::Char(':') => {
+                                self.palette = Palette::default();
+                                self.mode = UiMode::Palette;
+                                None
                             }
+                            KeyCode::Char('/') => Some(Action::Search),
+                            KeyCode::Char('t') => Some(Action::AddTag),
+                            KeyCode::Char('T') => Some(Action::RemoveTag),
+                            KeyCode::Char('d') => Some(Action::Delete),
+                            KeyCode::Char('D') => Some(Action::ClearHistory),
+                            KeyCode::Char('p') => Some(Action::TogglePin),
+                            KeyCode::Char('v') => Some(Action::ToggleSplit),
+                            KeyCode::Char('>') => Some(Action::CopyToPane),
+                            KeyCode::Char('K') => Some(Action::MovePin(true)),
+                            KeyCode::Char('J') => Some(Action::MovePin(false)),
+                            KeyCode::Char('e') => Some(Action::Export),
+                            KeyCode::Char('E') => Some(Action::ExportRedacted),
+                            KeyCode::Char('i') => Some(Action::Import),
+                            KeyCode::Char('|') => Some(Action::SendToCommand),
+                            KeyCode::Char('w') => Some(Action::SaveToFile),
+                            KeyCode::Char('o') => Some(Action::ShowImage),
+                            KeyCode::Char('c') => Some(Action::Calendar),
+                            KeyCode::Char('S') => Some(Action::Sessions),
+                            KeyCode::Char('P') => Some(Action::Profiles),
+                            KeyCode::Char('A') => Some(Action::ApproveSource),
+                            KeyCode::Char('u') => Some(Action::Unarchive),
+                            KeyCode::Char('#') => Some(Action::Checksums),
+                            KeyCode::Char('s') => Some(Action::ViewSource),
+                            KeyCode::Char('r') => Some(Action::ViewGroup),
+                            KeyCode::Char('R') => Some(Action::PasteGroup),
+                            KeyCode::Char('z') => Some(Action::Snapshot),
+                            KeyCode::Char('H') => Some(Action::ToggleGuestMode),
+                            _ => None,
+                        };
+                        if let Some(action) = action {
+                            HandleOutcome { should_exit, request } = self.perform(action)?;
                         }
-                        KeyCode::Char('/') => {
-                            self.mode = UiMode::Search;
-                            self.input_buffer = self.filter.clone();
-                        }
-                        KeyCode::Char('t') => {
-                            self.mode = UiMode::AddTag;
-                            self.input_buffer.clear();
-                        }
-                        KeyCode::Char('T') => {
-                            self.mode = UiMode::RemoveTag;
-                            self.input_buffer.clear();
-                        }
-                        KeyCode::Char('e') => {
-                            self.mode = UiMode::Export;
-                            self.input_buffer = "clipboard_export.json".to_string();
-                        }
-                        KeyCode::Char('i') => {
-                            self.mode = UiMode::Import;
-                            self.input_buffer = "clipboard_export.json".to_string();
-                        }
-                        _ => {}
                     }
                 }
             }
@@ -509,12 +1238,1128 @@ impl TerminalUi {
             _ => {}
         }
 
+        if self.offline {
+            request = request.and_then(|req| self.handle_offline_request(req));
+            self.follow_ups.clear();
+        }
+        if request.is_some() {
+            self.response_targets.push_back(Target::Pane(self.focus));
+            self.response_targets.extend(self.follow_ups.iter().map(|(_, side)| Target::Pane(*side)));
+        } else {
+            request = self.next_page();
+        }
+
         Ok(HandleOutcome { should_exit, request })
     }
-    
-    fn handle_input_mode_submit(&self) -> Result<Option<Request>> {
-        if self.input_buffer.is_empty() {
-            return Ok(None);
+
+    /// A request for the next page of the history once the selection nears
+    /// the end of the unfiltered list, unless one is on its way already.
+    fn next_page(&mut self) -> Option<Request> {
+        if self.offline || self.loading_page || !self.filter.is_empty() {
+            return None;
+        }
+        if self.selected + PAGE_MARGIN < self.entries.len() {
+            return None;
+        }
+        let cursor = self.next_cursor.clone()?;
+        self.loading_page = true;
+        self.response_targets.push_back(Target::Page(self.focus));
+        Some(Request {
+            kind: RequestKind::ListPage {
+                cursor: Some(cursor),
+                limit: None,
+            },
+        })
+    }
+
+    /// Scroll the help screen down by `lines`, or up when negative.
+    fn scroll_help(&mut self, lines: i16) {
+        self.help_scroll = self.help_scroll.saturating_add_signed(lines);
+    }
+
+    /// Requests to send right after the one `handle_event` returned.
+    pub fn take_follow_ups(&mut self) -> Vec<Request> {
+        self.follow_ups.drain(..).map(|(request, _)| request).collect()
+    }
+
+    fn apply_motion(&mut self, motion: Motion) {
+        if matches!(
+            motion,
+            Motion::Top | Motion::Bottom | Motion::Line(_) | Motion::JumpMark(_)
+        ) {
+            self.record_jump();
+        }
+
+        let last = self.entries.len().saturating_sub(1);
+        match motion {
+            Motion::Pending | Motion::Cancelled | Motion::Key(_) => {}
+            // Moving past either end does nothing, which ends a playing macro as in Vim.
+            Motion::Down(_) if self.selected >= last => self.stop_macro(),
+            Motion::Up(_) if self.selected == 0 => self.stop_macro(),
+            Motion::Down(n) => self.selected = (self.selected + n).min(last),
+            Motion::Up(n) => self.selected = self.selected.saturating_sub(n),
+            Motion::Top => self.selected = 0,
+            Motion::Bottom => self.selected = last,
+            Motion::Line(n) => self.selected = n.saturating_sub(1).min(last),
+            Motion::SetMark(name) => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    self.marks.insert(name, entry.id);
+                    self.set_status(t!("status-mark-set", name = name.to_string()));
+                }
+            }
+            Motion::Record(register) => {
+                self.macros.start_recording(register);
+                self.set_status(t!("status-macro-recording", register = register.to_string()));
+            }
+            Motion::Play(register, count) => {
+                if let Err(err) = self.macros.play(register, count) {
+                    self.macros.stop_playing();
+                    self.set_status(format!("{err:#}"));
+                }
+            }
+            Motion::JumpMark(name) => {
+                let Some(id) = self.marks.get(&name).copied() else {
+                    self.macros.stop_playing();
+                    self.set_status(t!("status-mark-not-set", name = name.to_string()));
+                    return;
+                };
+                match self.entries.iter().position(|entry| entry.id == id) {
+                    Some(index) => self.selected = index,
+                    None => {
+                        self.macros.stop_playing();
+                        self.set_status(t!("status-mark-not-listed", name = name.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn current_jump(&self) -> Option<Jump> {
+        self.entries.get(self.selected).map(|entry| Jump {
+            id: entry.id,
+            filter: self.filter.clone(),
+        })
+    }
+
+    fn record_jump(&mut self) {
+        if let Some(jump) = self.current_jump() {
+            self.jumps.record(jump);
+        }
+    }
+
+    /// Show a jump-list position, re-running its search if the filter differs.
+    fn go_to_jump(&mut self, jump: Jump) -> Option<Request> {
+        if jump.filter == self.filter {
+            self.select_after_refresh = Some(jump.id);
+            self.select_pending();
+            return None;
+        }
+
+        self.filter = jump.filter;
+        self.select_after_refresh = Some(jump.id);
+        Some(Request {
+            kind: RequestKind::Search {
+                query: self.filter.clone(),
+            },
+        })
+    }
+
+    /// Show saved view `index`, selecting the entry it had when it was left.
+    fn switch_tab(&mut self, index: usize) -> Option<Request> {
+        let current = self.entries.get(self.selected).map(|entry| entry.id);
+        let (query, selected) = self.tabs.switch(index, current)?;
+        let query = query.to_string();
+        self.record_jump();
+        self.filter = query.clone();
+        self.select_after_refresh = selected;
+        self.selected = 0;
+        Some(Request {
+            kind: RequestKind::Search { query },
+        })
+    }
+
+    /// Apply `select_after_refresh` to the current list.
+    fn select_pending(&mut self) {
+        let Some(id) = self.select_after_refresh.take() else {
+            return;
+        };
+        match self.entries.iter().position(|entry| entry.id == id) {
+            Some(index) => self.selected = index,
+            None => self.set_status(t!("status-entry-not-listed", id = id)),
+        }
+    }
+
+    /// Run an action from a key binding or the command palette.
+    fn perform(&mut self, action: Action) -> Result<HandleOutcome> {
+        let mut request = None;
+        let mut should_exit = false;
+
+        let archived = self.entries.get(self.selected).is_some_and(|entry| entry.archived);
+        if archived && action.needs_history_entry() {
+            self.set_status(t!("status-entry-archived"));
+            return Ok(HandleOutcome { should_exit, request });
+        }
+
+        match action {
+            Action::Quit => should_exit = true,
+            Action::Help => {
+                self.help_scroll = 0;
+                self.mode = UiMode::Help;
+            }
+            Action::Paste => return self.paste_selected(Transform::Verbatim),
+            Action::PasteAs(transform) => return self.paste_selected(transform),
+            Action::Transforms => {
+                self.palette = Palette::with_query("paste ");
+                self.mode = UiMode::Palette;
+            }
+            Action::Search => {
+                self.record_jump();
+                self.mode = UiMode::Search;
+                self.input_buffer = self.filter.clone();
+            }
+            Action::AddTag => {
+                self.mode = UiMode::AddTag;
+                self.input_buffer.clear();
+            }
+            Action::RemoveTag => {
+                self.mode = UiMode::RemoveTag;
+                self.input_buffer.clear();
+            }
+            Action::Export => {
+                self.mode = UiMode::Export;
+                self.input_buffer = "clipboard_export.json".to_string();
+            }
+            Action::ExportRedacted => {
+                self.mode = UiMode::ExportRedacted;
+                self.input_buffer = "clipboard_export_redacted.json".to_string();
+            }
+            Action::ExportEncrypted => {
+                self.mode = UiMode::ExportEncrypted;
+                self.input_buffer = "clipboard_export.json.age".to_string();
+            }
+            Action::Import => {
+                self.mode = UiMode::Import;
+                self.input_buffer = "clipboard_export.json".to_string();
+            }
+            Action::TagResults => {
+                self.mode = UiMode::TagResults;
+                self.input_buffer.clear();
+            }
+            Action::DeleteResults => {
+                if self.filter.trim().is_empty() {
+                    self.set_status(t!("status-search-first"));
+                } else {
+                    // Always ask: this can remove far more than the listed entries.
+                    self.set_status(t!("confirm-delete-matching", query = self.filter.as_str()));
+                    self.pending = Some(Request {
+                        kind: RequestKind::DeleteMatching {
+                            query: self.filter.clone(),
+                        },
+                    });
+                    self.mode = UiMode::Confirm;
+                }
+            }
+            Action::Delete => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    let delete = Request {
+                        kind: RequestKind::Delete { id: entry.id },
+                    };
+                    if self.config.confirm_prompts {
+                        let label = match entry.preview.lines().next() {
+                            Some(line) if !self.config.guest_mode => line.chars().take(40).collect(),
+                            _ => entry.kind.clone(),
+                        };
+                        self.set_status(t!("confirm-delete", label = label));
+                        self.pending = Some(delete);
+                        self.mode = UiMode::Confirm;
+                    } else {
+                        // clipd answers with the whole history.
+                        self.filter.clear();
+                        request = Some(delete);
+                    }
+                }
+            }
+            Action::ClearHistory => {
+                self.set_status(t!("prompt-clear"));
+                self.mode = UiMode::ClearHistory;
+            }
+            Action::WipeHistory => self.confirm_wipe(),
+            Action::ToggleSplit => match self.split.take() {
+                Some(_) => self.set_status(t!("status-joined")),
+                None => {
+                    // The new pane starts as a copy; search either one to change it.
+                    self.split = Some(Pane {
+                        entries: self.entries.clone(),
+                        selected: self.selected,
+                        filter: self.filter.clone(),
+                        list_state: ListState::default(),
+                        next_cursor: self.next_cursor.clone(),
+                    });
+                    self.set_status(t!("status-split"));
+                }
+            },
+            Action::SwitchPane => match &mut self.split {
+                Some(other) => {
+                    std::mem::swap(&mut self.entries, &mut other.entries);
+                    std::mem::swap(&mut self.selected, &mut other.selected);
+                    std::mem::swap(&mut self.filter, &mut other.filter);
+                    std::mem::swap(&mut self.list_state, &mut other.list_state);
+                    std::mem::swap(&mut self.next_cursor, &mut other.next_cursor);
+                    // A page on its way is for the pane left behind; it is dropped.
+                    self.loading_page = false;
+                    self.focus = self.focus.other();
+                }
+                None => self.set_status(t!("status-not-split")),
+            },
+            Action::CopyToPane => {
+                let Some(other_filter) = self.split.as_ref().map(|other| other.filter.clone()) else {
+                    self.set_status(t!("status-not-split"));
+                    return Ok(HandleOutcome { should_exit, request });
+                };
+                let Some(id) = self.entries.get(self.selected).map(|entry| entry.id) else {
+                    return Ok(HandleOutcome { should_exit, request });
+                };
+                let kind = match Collection::of(&other_filter) {
+                    Some(Collection::Pinned) => RequestKind::SetPinned { id, pinned: true },
+                    Some(Collection::Tag(tag)) => RequestKind::AddTag { id, tag },
+                    None => {
+                        self.set_status(t!("status-other-pane"));
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+                };
+                // clipd answers with the whole history; re-run both panes' searches.
+                self.follow_ups = vec![
+                    (
+                        Request {
+                            kind: RequestKind::Search {
+                                query: self.filter.clone(),
+                            },
+                        },
+                        self.focus,
+                    ),
+                    (
+                        Request {
+                            kind: RequestKind::Search { query: other_filter },
+                        },
+                        self.focus.other(),
+                    ),
+                ];
+                request = Some(Request { kind });
+            }
+            Action::TogglePin => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    self.select_after_refresh = Some(entry.id);
+                    request = Some(Request {
+                        kind: RequestKind::SetPinned {
+                            id: entry.id,
+                            pinned: !entry.pinned,
+                        },
+                    });
+                }
+            }
+            Action::MovePin(up) => match self.entries.get(self.selected) {
+                Some(entry) if entry.pinned => {
+                    // Keep the entry selected as it moves.
+                    self.select_after_refresh = Some(entry.id);
+                    request = Some(Request {
+                        kind: RequestKind::MovePin { id: entry.id, up },
+                    });
+                }
+                Some(_) => self.set_status(t!("status-pinned-only")),
+                None => {}
+            },
+            Action::SendToCommand => {
+                self.mode = UiMode::SendCommand;
+                self.input_buffer = self.config.send_command.clone().unwrap_or_default();
+            }
+            Action::SaveToFile => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    let extension = match entry.kind.as_str() {
+                        "image" => "png",
+                        "rtf" => "rtf",
+                        "html" => "html",
+                        "snapshot" => "bin",
+                        _ => "txt",
+                    };
+                    self.mode = UiMode::SaveEntry;
+                    self.input_buffer = format!("clip-{}.{extension}", entry.id);
+                }
+            }
+            Action::SavePatch => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    if git::detect(&entry.preview).is_some() {
+                        self.mode = UiMode::SaveEntry;
+                        self.input_buffer = format!("clip-{}.patch", entry.id);
+                    } else {
+                        self.set_status(t!("status-no-diff"));
+                    }
+                }
+            }
+            Action::ShowImage => match self.entries.get(self.selected) {
+                // clipd would write the file on the other machine.
+                Some(_) if remote::active() => self.set_status(t!("status-remote-image")),
+                Some(entry) if entry.kind == "image" => match reveal::temp_png(entry.id) {
+                    Ok(path) => {
+                        request = Some(Request {
+                            kind: RequestKind::SaveEntry {
+                                id: entry.id,
+                                path: path.display().to_string(),
+                            },
+                        });
+                        self.reveal_after_save = Some(path);
+                    }
+                    Err(err) => self.set_status(format!("{err:#}")),
+                },
+                Some(_) => self.set_status(t!("status-images-only")),
+                None => {}
+            },
+            Action::Calendar => {
+                self.calendar = None;
+                self.mode = UiMode::Calendar;
+                request = Some(Request {
+                    kind: RequestKind::DailyCounts,
+                });
+            }
+            Action::Sessions => {
+                self.mode = UiMode::Sessions;
+                request = Some(Request {
+                    kind: RequestKind::Sessions,
+                });
+            }
+            Action::Profiles => {
+                self.mode = UiMode::Profiles;
+                request = Some(Request {
+                    kind: RequestKind::Profiles,
+                });
+            }
+            Action::ApproveSource => match self.entries.get(self.selected) {
+                Some(entry) if entry.quarantined => {
+                    let Some(process) = entry.source_process.clone() else {
+                        self.set_status(t!("status-source-unknown"));
+                        return Ok(HandleOutcome { should_exit, request });
+                    };
+                    // Persist first so the approval survives clipd restarts.
+                    if let Err(err) = config::trust_process(&process) {
+                        self.set_status(t!("status-trust-failed", error = format!("{err:#}")));
+                        return Ok(HandleOutcome { should_exit, request });
+                    }
+                    self.set_status(t!("status-trusted", process = process.as_str()));
+                    request = Some(Request {
+                        kind: RequestKind::ApproveSource { process },
+                    });
+                }
+                Some(_) => self.set_status(t!("status-not-quarantined")),
+                None => {}
+            },
+            Action::Unarchive => match self.entries.get(self.selected) {
+                Some(entry) if entry.archived => {
+                    request = Some(Request {
+                        kind: RequestKind::Unarchive { id: entry.id },
+                    });
+                    // clipd answers with the history, restored entry on top.
+                    self.record_jump();
+                    self.filter.clear();
+                    self.selected = 0;
+                    self.set_status(t!("status-restored"));
+                }
+                Some(_) => self.set_status(t!("status-not-archived")),
+                None => {}
+            },
+            Action::Checksums => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    request = Some(Request {
+                        kind: RequestKind::Checksums { id: entry.id },
+                    });
+                }
+            }
+            Action::ViewSource => match self.entries.get(self.selected) {
+                Some(entry) if self.source.as_ref().is_some_and(|source| source.id == entry.id) => {
+                    self.source = None;
+                }
+                Some(entry) if matches!(entry.kind.as_str(), "rtf" | "html") => {
+                    request = Some(Request {
+                        kind: RequestKind::Source { id: entry.id },
+                    });
+                }
+                Some(_) => self.set_status(t!("status-no-source")),
+                None => {}
+            },
+            Action::ViewGroup | Action::PasteGroup => {
+                let Some(entry) = self.entries.get(self.selected) else {
+                    return Ok(HandleOutcome { should_exit, request });
+                };
+                let id = entry.id;
+                let Some(group) = entry.copy_group else {
+                    self.set_status(t!("status-copied-alone"));
+                    return Ok(HandleOutcome { should_exit, request });
+                };
+                if action == Action::PasteGroup {
+                    self.paste_group = Some(group);
+                } else {
+                    self.record_jump();
+                    self.filter = format!("group:{group}");
+                    self.select_after_refresh = Some(id);
+                }
+                request = Some(Request {
+                    kind: RequestKind::Search {
+                        query: format!("group:{group}"),
+                    },
+                });
+            }
+            Action::Snapshot => {
+                request = Some(Request {
+                    kind: RequestKind::Snapshot,
+                });
+                self.set_status(t!("status-snapshot-stored"));
+            }
+            Action::PauseCapture => {
+                request = Some(Request {
+                    kind: RequestKind::PauseCapture { minutes: None },
+                });
+            }
+            Action::CopyDigest(digest) => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    match &self.checksums {
+                        Some(checksums) if checksums.id == entry.id => {
+                            let value = checksums.get(digest).to_string();
+                            self.copy_to_clipboard(digest.label(), &value);
+                        }
+                        _ => {
+                            self.copy_digest = Some(digest);
+                            request = Some(Request {
+                                kind: RequestKind::Checksums { id: entry.id },
+                            });
+                        }
+                    }
+                }
+            }
+            Action::CopyConverted(conversion) => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    let value = convert::conversions(&entry.preview, self.config.unit_conversions)
+                        .into_iter()
+                        .find(|(kind, _)| *kind == conversion)
+                        .map(|(_, value)| value);
+                    match value {
+                        Some(value) => self.copy_to_clipboard(conversion.label(), &value),
+                        None => self.set_status(t!(
+                            "status-no-conversion",
+                            conversion = conversion.label().to_lowercase()
+                        )),
+                    }
+                }
+            }
+            Action::CopyEntity(kind) => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    match entity_values(entry, kind).first() {
+                        Some(value) => {
+                            let value = value.to_string();
+                            self.copy_to_clipboard(kind.name(), &value);
+                        }
+                        None => self.set_status(no_entity(kind)),
+                    }
+                }
+            }
+            Action::ComposeMail | Action::Call => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    let uri = if action == Action::ComposeMail {
+                        let emails = entity_values(entry, EntityKind::Email);
+                        (!emails.is_empty()).then(|| format!("mailto:{}", emails.join(",")))
+                    } else {
+                        // tel: takes the digits, with the + of an international number
+                        entity_values(entry, EntityKind::Phone).first().map(|phone| {
+                            let digits: String =
+                                phone.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+                            format!("tel:{digits}")
+                        })
+                    };
+                    match uri {
+                        Some(uri) => match reveal::open(&uri) {
+                            Ok(()) => self.set_status(t!("status-opened", uri = uri.as_str())),
+                            Err(err) => self.set_status(format!("{err:#}")),
+                        },
+                        None if action == Action::ComposeMail => self.set_status(no_entity(EntityKind::Email)),
+                        None => self.set_status(no_entity(EntityKind::Phone)),
+                    }
+                }
+            }
+            Action::Settings => self.open_settings(),
+            Action::SetTheme(name) => match config::store("ui.theme", name) {
+                Ok(()) => {
+                    self.config.theme = name.to_string();
+                    self.theme = Theme::for_config(name, self.config.accessible);
+                }
+                Err(err) => self.set_status(t!("status-theme-failed", error = format!("{err:#}"))),
+            },
+            Action::ToggleGuestMode => {
+                self.config.guest_mode = !self.config.guest_mode;
+                self.set_status(if self.config.guest_mode {
+                    t!("status-guest-on")
+                } else {
+                    t!("status-guest-off")
+                });
+            }
+            Action::ToggleImageCapture => match config::toggle_image_capture() {
+                Ok(enabled) => {
+                    self.set_status(if enabled {
+                        t!("status-image-capture-on")
+                    } else {
+                        t!("status-image-capture-off")
+                    });
+                    request = Some(Request {
+                        kind: RequestKind::ReloadConfig,
+                    });
+                }
+                Err(err) => self.set_status(t!("status-image-capture-failed", error = format!("{err:#}"))),
+            },
+        }
+
+        Ok(HandleOutcome { should_exit, request })
+    }
+
+    /// Put a derived value on the clipboard without leaving the TUI.
+    fn copy_to_clipboard(&mut self, label: &str, value: &str) {
+        match paste::set_clipboard(value) {
+            Ok(()) => self.set_status(t!("status-copied", label = label, value = value)),
+            Err(err) => self.set_status(format!("{err:#}")),
+        }
+    }
+
+    /// Paste the selected entry, asking first if it looks dangerous.
+    fn paste_selected(&mut self, transform: Transform) -> Result<HandleOutcome> {
+        let mut outcome = HandleOutcome {
+            should_exit: false,
+            request: None,
+        };
+        let Some(entry) = self.entries.get(self.selected) else {
+            return Ok(outcome);
+        };
+        if self.offline && !matches!(entry.kind.as_str(), "text" | "url") {
+            self.set_status(t!("status-offline-text-only"));
+            return Ok(outcome);
+        }
+        // clipd puts a snapshot's formats back itself; its text alone would lose them.
+        let restore = entry.kind == "snapshot";
+        let paste = Request {
+            kind: if restore {
+                RequestKind::Restore { id: entry.id }
+            } else {
+                self.paste_request(entry.id, transform, false)
+            },
+        };
+        let warnings: Vec<_> = entry
+            .warnings
+            .iter()
+            .filter(|warning| transform != Transform::Sanitized || *warning != invisible::WARNING)
+            .cloned()
+            .collect();
+        // Asked even with confirm_prompts off: this is the pastejacking guard.
+        if !warnings.is_empty() {
+            let question = t!("confirm-paste-anyway", reason = warnings.join(", "));
+            self.set_status(format!("{} {question}", self.theme.glyphs.warning()));
+            self.pending = Some(paste);
+            self.pending_transform = transform;
+            self.mode = UiMode::Confirm;
+            return Ok(outcome);
+        }
+        // The clipboard holds it already: Ctrl+V pastes it without a round
+        // trip through clipd. A remote clipd reports its own clipboard.
+        if entry.on_clipboard
+            && transform == Transform::Verbatim
+            && self.paste.method() == PasteMethod::SendInput
+            && !self.offline
+            && !remote::active()
+        {
+            tracing::info!(id = entry.id, "entry is on the clipboard already; not pasting it again");
+            outcome.should_exit = true;
+            return Ok(outcome);
+        }
+        if restore || self.offline {
+            outcome.should_exit = restore || self.paste_entry(entry.id, transform)?;
+        } else {
+            // clipd checks the `[paste]` rules first; its answer pastes or asks.
+            self.awaiting_paste = Some((entry.id, transform));
+        }
+        outcome.request = Some(paste);
+        Ok(outcome)
+    }
+
+    /// Paste the entry clipd allowed, or ask before overriding a `[paste]` rule.
+    fn answer_paste(&mut self, verdict: PasteVerdict) -> Result<()> {
+        let Some((id, transform)) = self.awaiting_paste.filter(|(id, _)| *id == verdict.id) else {
+            return Ok(());
+        };
+        self.awaiting_paste = None;
+        match verdict.blocked {
+            Some(reason) => {
+                let question = t!("confirm-paste-anyway", reason = reason);
+                self.set_status(format!("{} {question}", self.theme.glyphs.warning()));
+                self.pending = Some(Request {
+                    kind: self.paste_request(id, transform, true),
+                });
+                self.pending_transform = transform;
+                self.mode = UiMode::Confirm;
+            }
+            None if verdict.pasted => self.exit_requested = true,
+            None if self.wants_payload(id, transform) => {
+                // The list only has its text; the image or document comes with `Get`.
+                self.awaiting_payload = Some(id);
+                self.follow_ups.push((
+                    Request {
+                        kind: RequestKind::Get { id, payload: true },
+                    },
+                    self.focus,
+                ));
+                self.response_targets.push_back(Target::Pane(self.focus));
+            }
+            None => self.exit_requested = self.paste_entry(id, transform)?,
+        }
+        Ok(())
+    }
+
+    /// Whether pasting entry `id` takes its data rather than its text: an
+    /// image put on the clipboard, or a document clipd on another machine
+    /// could not put on this one's.
+    fn wants_payload(&self, id: u64, transform: Transform) -> bool {
+        let Some(entry) = self.entries.iter().find(|entry| entry.id == id) else {
+            return false;
+        };
+        let to_clipboard = match self.paste.method() {
+            PasteMethod::SendInput => true,
+            PasteMethod::Pane => Multiplexer::detect().is_none(),
+            PasteMethod::Stdout | PasteMethod::Type => false,
+        };
+        match entry.kind.as_str() {
+            "image" => to_clipboard,
+            "rtf" | "html" => to_clipboard && transform == Transform::Verbatim && remote::active(),
+            _ => false,
+        }
+    }
+
+    /// Paste the entry whose data clipd sent, then close.
+    fn paste_payload(&mut self, payload: EntryPayload) -> Result<()> {
+        if self.awaiting_payload != Some(payload.id) {
+            return Ok(());
+        }
+        self.awaiting_payload = None;
+        let Some(entry) = self.entries.iter().find(|entry| entry.id == payload.id) else {
+            return Ok(());
+        };
+        match Payload::new(&entry.kind, payload.data) {
+            Some(data) => self.paste.paste_payload(&entry.preview, &data, payload.id)?,
+            None => self.paste.paste(&entry.preview, Some(payload.id))?,
+        }
+        self.exit_requested = true;
+        Ok(())
+    }
+
+    /// `Paste` for entry `id`. clipd may paste RTF and HTML entries with their
+    /// formatting, unless the text is transformed, printed, or typed instead.
+    fn paste_request(&self, id: u64, transform: Transform, force: bool) -> RequestKind {
+        RequestKind::Paste {
+            id,
+            force,
+            // clipd would paste a remote entry onto the other machine's clipboard.
+            plain: transform != Transform::Verbatim
+                || self.paste.method() != PasteMethod::SendInput
+                || remote::active(),
+            hide_from_history: self.paste.hides_from_history(),
+        }
+    }
+
+    /// Paste the text of a copy group, one entry per line in the order copied,
+    /// and close; `entries` is the group as clipd listed it.
+    fn paste_copied_together(&mut self, entries: Vec<EntrySummary>) -> Result<()> {
+        if entries.iter().any(|entry| !entry.warnings.is_empty()) {
+            self.set_status(t!("status-group-dangerous"));
+            return Ok(());
+        }
+        let texts: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.kind != "image")
+            .map(|entry| entry.preview.as_str())
+            .collect();
+        if texts.is_empty() {
+            self.set_status(t!("status-group-empty"));
+            return Ok(());
+        }
+        self.exit_requested = self.hand_over(texts.join("\n"), None)?;
+        Ok(())
+    }
+
+    /// Paste entry `id` from the list; returns whether it was pasted, rather
+    /// than missing or waiting for a pane to be picked.
+    fn paste_entry(&mut self, id: u64, transform: Transform) -> Result<bool> {
+        match self.entries.iter().find(|entry| entry.id == id) {
+            Some(entry) => self.hand_over(transform.apply(&entry.preview).into_owned(), Some(id)),
+            None => Ok(false),
+        }
+    }
+
+    /// Paste `text` from entry `entry`, first asking which pane it goes to
+    /// for a `pane` paste inside tmux or WezTerm; returns whether it was pasted.
+    fn hand_over(&mut self, text: String, entry: Option<u64>) -> Result<bool> {
+        let multiplexer = Multiplexer::detect().filter(|_| self.paste.method() == PasteMethod::Pane);
+        let Some(multiplexer) = multiplexer else {
+            self.paste.paste(&text, entry)?;
+            return Ok(true);
+        };
+        match multiplexer.panes() {
+            Ok(panes) if panes.is_empty() => {
+                self.set_status(t!("status-no-panes", multiplexer = multiplexer.name()));
+            }
+            Ok(panes) => {
+                self.pane_picker = Some(PanePicker::new(multiplexer, panes, self.paste.target()));
+                self.pane_paste = Some((text, entry));
+                self.mode = UiMode::Panes;
+            }
+            Err(err) => self.set_status(format!("{err:#}")),
+        }
+        Ok(false)
+    }
+
+    /// Pipe the selected entry into the command in `input_buffer`.
+    ///
+    /// Runs to completion before the UI redraws; the first line of output (a
+    /// pastebin URL, say) ends up in the status bar.
+    fn send_to_command(&mut self) {
+        let command = self.input_buffer.trim().to_string();
+        let Some(entry) = self.entries.get(self.selected) else {
+            return;
+        };
+        if command.is_empty() {
+            return;
+        }
+        if !matches!(entry.kind.as_str(), "text" | "url") {
+            self.set_status(t!("status-send-text-only", kind = entry.kind.as_str()));
+            return;
+        }
+
+        let status = match exec::pipe_to_command(&command, &entry.preview) {
+            Ok(output) => match output.lines().map(str::trim).find(|line| !line.is_empty()) {
+                Some(line) if !self.config.guest_mode => line.to_string(),
+                _ => t!("status-sent", id = entry.id, command = command.as_str()),
+            },
+            Err(err) => format!("{err:#}"),
+        };
+        self.set_status(status);
+    }
+
+    fn open_settings(&mut self) {
+        self.settings = SettingsView::default();
+        self.clear_status();
+        self.mode = UiMode::Settings;
+    }
+
+    fn handle_settings_key(&mut self, code: crossterm::event::KeyCode) {
+        use crossterm::event::KeyCode;
+
+        let delta = match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.clear_status();
+                self.mode = UiMode::Normal;
+                return;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.settings.move_selection(1);
+                return;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.settings.move_selection(-1);
+                return;
+            }
+            KeyCode::Char('h') | KeyCode::Left => -1,
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') => 1,
+            _ => return,
+        };
+
+        match self.settings.cycle(&mut self.config, delta) {
+            Ok(()) => {
+                self.theme = Theme::for_config(&self.config.theme, self.config.accessible);
+                self.paste.set_method(self.config.paste_method);
+                self.paste.set_hide_from_history(self.config.hide_from_windows_history);
+                self.clear_status();
+            }
+            Err(err) => self.set_status(t!("status-setting-failed", error = format!("{err:#}"))),
+        }
+    }
+
+    fn handle_calendar_key(&mut self, code: crossterm::event::KeyCode) -> Option<Request> {
+        use crossterm::event::KeyCode;
+
+        let calendar = self.calendar.as_mut();
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = UiMode::Normal,
+            KeyCode::Char('h') | KeyCode::Left => calendar?.move_cursor(-7),
+            KeyCode::Char('l') | KeyCode::Right => calendar?.move_cursor(7),
+            KeyCode::Char('k') | KeyCode::Up => calendar?.move_cursor(-1),
+            KeyCode::Char('j') | KeyCode::Down => calendar?.move_cursor(1),
+            KeyCode::Enter => {
+                let day = calendar?.selected();
+                self.record_jump();
+                self.mode = UiMode::Normal;
+                self.filter = format!("date:{}", day.format("%Y-%m-%d"));
+                return Some(Request {
+                    kind: RequestKind::Search {
+                        query: self.filter.clone(),
+                    },
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_sessions_key(&mut self, code: crossterm::event::KeyCode) -> Option<Request> {
+        use crossterm::event::KeyCode;
+
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = UiMode::Normal,
+            KeyCode::Char('j') | KeyCode::Down => self.sessions.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => self.sessions.move_selection(-1),
+            KeyCode::Enter => {
+                let id = self.sessions.selected()?.id;
+                self.record_jump();
+                self.mode = UiMode::Normal;
+                self.filter = format!("session:{id}");
+                return Some(Request {
+                    kind: RequestKind::Search {
+                        query: self.filter.clone(),
+                    },
+                });
+            }
+            KeyCode::Char('t') if self.sessions.selected().is_some() => {
+                self.mode = UiMode::TagSession;
+                self.input_buffer.clear();
+            }
+            KeyCode::Char('e') => {
+                let id = self.sessions.selected()?.id;
+                self.mode = UiMode::ExportSession;
+                self.input_buffer = format!("session-{id}.json");
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_profiles_key(&mut self, code: crossterm::event::KeyCode) -> Option<Request> {
+        use crossterm::event::KeyCode;
+
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = UiMode::Normal,
+            KeyCode::Char('j') | KeyCode::Down => self.profiles.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => self.profiles.move_selection(-1),
+            KeyCode::Enter => {
+                let name = self.profiles.selected()?.name.clone();
+                self.mode = UiMode::Normal;
+                self.filter.clear();
+                // Entry ids belong to one profile's database.
+                self.marks.clear();
+                self.jumps = JumpList::default();
+                self.set_status(t!("status-profile", name = name.as_str()));
+                return Some(Request {
+                    kind: RequestKind::SwitchProfile { name },
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_clear_key(&mut self, code: crossterm::event::KeyCode) -> Option<Request> {
+        use crossterm::event::KeyCode;
+
+        self.mode = UiMode::Normal;
+        let days = match code {
+            KeyCode::Char('d') => 1,
+            KeyCode::Char('w') => 7,
+            KeyCode::Char('m') => 30,
+            KeyCode::Char('a') => {
+                self.confirm_wipe();
+                return None;
+            }
+            _ => {
+                self.set_status(t!("status-cancelled"));
+                return None;
+            }
+        };
+        self.clear_status();
+        self.filter.clear();
+        Some(Request {
+            kind: RequestKind::Clear {
+                older_than_days: Some(days),
+            },
+        })
+    }
+
+    fn confirm_wipe(&mut self) {
+        // Always asked: not even a backup is left to undo a wipe.
+        self.set_status(t!("confirm-wipe"));
+        self.pending = Some(Request {
+            kind: RequestKind::Clear { older_than_days: None },
+        });
+        self.mode = UiMode::Confirm;
+    }
+
+    /// Returns whether the text was pasted into the picked pane.
+    fn handle_panes_key(&mut self, code: crossterm::event::KeyCode) -> bool {
+        use crossterm::event::KeyCode;
+
+        let Some(picker) = &mut self.pane_picker else {
+            self.mode = UiMode::Normal;
+            return false;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = UiMode::Normal;
+                self.pane_paste = None;
+                self.set_status(t!("status-cancelled"));
+            }
+            KeyCode::Char('j') | KeyCode::Down => picker.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => picker.move_selection(-1),
+            KeyCode::Enter => {
+                let (Some(pane), Some((text, entry))) = (picker.selected(), self.pane_paste.take()) else {
+                    return false;
+                };
+                self.mode = UiMode::Normal;
+                self.paste.set_target(picker.multiplexer(), pane.id.clone());
+                match self.paste.paste(&text, entry) {
+                    Ok(()) => return true,
+                    Err(err) => self.set_status(format!("{err:#}")),
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Prompts opened from the session picker return to it.
+    fn mode_after_input(&self) -> UiMode {
+        match self.mode {
+            UiMode::TagSession | UiMode::ExportSession => UiMode::Sessions,
+            _ => UiMode::Normal,
+        }
+    }
+
+    /// Hold back an encrypted export, or the import of an encrypted archive,
+    /// until the user types its passphrase.
+    fn ask_passphrase(&mut self, request: Request, encrypt: bool) -> Option<Request> {
+        let needed = match &request.kind {
+            RequestKind::Export { .. } => encrypt,
+            RequestKind::Import { path, .. } => commands::is_encrypted_archive(Path::new(path)),
+            _ => false,
+        };
+        if !needed {
+            return Some(request);
+        }
+        self.awaiting_passphrase = Some(request);
+        self.passphrase_first = None;
+        self.mode = UiMode::Passphrase;
+        None
+    }
+
+    /// Fill in the passphrase of the held-back request; new export passphrases
+    /// are asked for twice, since a typo would lock the archive for good.
+    /// Leaving an export's passphrase empty seals it with this Windows user's key.
+    fn submit_passphrase(&mut self) -> Option<Request> {
+        let typed = std::mem::take(&mut self.input_buffer);
+        let mut request = self.awaiting_passphrase.take()?;
+        match &mut request.kind {
+            RequestKind::Export { user_key, .. } if typed.is_empty() && self.passphrase_first.is_none() => {
+                *user_key = true;
+            }
+            _ if typed.is_empty() => {
+                self.passphrase_first = None;
+                self.mode = UiMode::Normal;
+                return None;
+            }
+            RequestKind::Export { passphrase, .. } => match self.passphrase_first.take() {
+                None => {
+                    self.passphrase_first = Some(typed);
+                    self.awaiting_passphrase = Some(request);
+                    return None;
+                }
+                Some(first) if first != typed => {
+                    self.set_status(t!("status-passphrase-mismatch"));
+                    self.mode = UiMode::Normal;
+                    return None;
+                }
+                Some(_) => *passphrase = Some(typed),
+            },
+            RequestKind::Import { passphrase, .. } => *passphrase = Some(typed),
+            _ => {}
+        }
+        self.mode = UiMode::Normal;
+        Some(request)
+    }
+
+    /// Hold back an export or save that would overwrite a file until the user confirms.
+    fn confirm_overwrite(&mut self, request: Request) -> Option<Request> {
+        let (RequestKind::Export { path, .. }
+        | RequestKind::SaveEntry { path, .. }
+        | RequestKind::ExportSession { path, .. }) = &request.kind
+        else {
+            return Some(request);
+        };
+        if !self.config.confirm_prompts || !Path::new(path).exists() {
+            return Some(request);
+        }
+
+        self.set_status(t!("confirm-overwrite", path = path));
+        self.pending = Some(request);
+        self.mode = UiMode::Confirm;
+        None
+    }
+
+    /// Serve what we can from the cache; everything else needs the daemon.
+    fn handle_offline_request(&mut self, request: Request) -> Option<Request> {
+        match request.kind {
+            RequestKind::Search { query } if query.starts_with("group:") => {
+                self.paste_group = None;
+                self.set_status(t!("offline-groups"));
+            }
+            RequestKind::Search { query } if query.split_whitespace().any(|word| word == "in:archive") => {
+                self.set_status(t!("offline-archive"));
+            }
+            RequestKind::Search { query } => {
+                let needle = query.to_lowercase();
+                self.entries = self
+                    .cached
+                    .iter()
+                    .filter(|entry| {
+                        entry.preview.to_lowercase().contains(&needle)
+                            || entry.tags.iter().any(|tag| tag.to_lowercase().contains(&needle))
+                    })
+                    .cloned()
+                    .collect();
+                self.selected = 0;
+                self.select_pending();
+            }
+            // Config changes are already on disk; clipd reads them when it starts.
+            RequestKind::Paste { .. } | RequestKind::ReloadConfig => {}
+            RequestKind::SaveEntry { .. } => {
+                self.reveal_after_save = None;
+                self.set_status(t!("offline-save"));
+            }
+            RequestKind::DailyCounts | RequestKind::Sessions | RequestKind::Profiles => {
+                self.mode = UiMode::Normal;
+                self.set_status(t!("offline-view"));
+            }
+            RequestKind::Checksums { .. } => {
+                self.copy_digest = None;
+                self.set_status(t!("offline-checksums"));
+            }
+            RequestKind::Source { .. } => self.set_status(t!("offline-source")),
+            RequestKind::Snapshot | RequestKind::Restore { .. } => {
+                self.set_status(t!("offline-snapshots"))
+            }
+            _ => self.set_status(t!("offline-read-only")),
+        }
+        None
+    }
+    
+    fn handle_input_mode_submit(&self) -> Result<Option<Request>> {
+        if self.input_buffer.is_empty() {
+            return Ok(None);
         }
         
         let current_entry = self.entries.get(self.selected);
@@ -551,17 +2396,74 @@ impl TerminalUi {
                     Ok(None)
                 }
             }
-            UiMode::Export => {
+            UiMode::Export | UiMode::ExportRedacted => {
                 Ok(Some(Request {
                     kind: RequestKind::Export {
                         path: self.input_buffer.clone(),
+                        redact: self.mode == UiMode::ExportRedacted,
+                        passphrase: None,
+                        user_key: false,
+                    },
+                }))
+            }
+            UiMode::ExportEncrypted => {
+                let path = commands::absolute(Path::new(&self.input_buffer))?;
+                Ok(Some(Request {
+                    kind: RequestKind::Export {
+                        path: path.display().to_string(),
+                        redact: false,
+                        passphrase: None,
+                        user_key: false,
                     },
                 }))
             }
             UiMode::Import => {
+                // Absolute, so the encrypted-archive check reads the file clipd will.
+                let path = commands::absolute(Path::new(&self.input_buffer))?;
                 Ok(Some(Request {
                     kind: RequestKind::Import {
-                        path: self.input_buffer.clone(),
+                        path: path.display().to_string(),
+                        passphrase: None,
+                    },
+                }))
+            }
+            UiMode::SaveEntry => {
+                if let Some(entry) = current_entry {
+                    let path = commands::absolute(Path::new(&self.input_buffer))?;
+                    Ok(Some(Request {
+                        kind: RequestKind::SaveEntry {
+                            id: entry.id,
+                            path: path.display().to_string(),
+                        },
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+            UiMode::TagResults => Ok(Some(Request {
+                kind: RequestKind::TagMatching {
+                    query: self.filter.clone(),
+                    tag: self.input_buffer.clone(),
+                },
+            })),
+            UiMode::TagSession => {
+                let session = self.sessions.selected().map(|session| session.id);
+                Ok(session.map(|session| Request {
+                    kind: RequestKind::TagSession {
+                        session,
+                        tag: self.input_buffer.clone(),
+                    },
+                }))
+            }
+            UiMode::ExportSession => {
+                let Some(session) = self.sessions.selected().map(|session| session.id) else {
+                    return Ok(None);
+                };
+                let path = commands::absolute(Path::new(&self.input_buffer))?;
+                Ok(Some(Request {
+                    kind: RequestKind::ExportSession {
+                        session,
+                        path: path.display().to_string(),
                     },
                 }))
             }
@@ -569,21 +2471,397 @@ impl TerminalUi {
         }
     }
 
+    /// Add the next page of the history to the end of the list, unless the
+    /// list was replaced or left since it was asked for.
+    fn add_page(&mut self, side: Side, response: Response) {
+        if side != self.focus || !std::mem::take(&mut self.loading_page) {
+            return;
+        }
+        let listed: HashSet<u64> = self.entries.iter().map(|entry| entry.id).collect();
+        self.entries
+            .extend(response.entries.into_iter().filter(|entry| !listed.contains(&entry.id)));
+        self.next_cursor = response.next_cursor;
+    }
+
     pub fn ingest_response(&mut self, response: Response) -> Result<()> {
+        let target = self.response_targets.pop_front().unwrap_or(Target::Pane(self.focus));
+        if let Some(error) = response.error {
+            // Nothing arrives for whatever waited on the failed request.
+            self.copy_digest = None;
+            self.reveal_after_save = None;
+            if target == Target::Page(self.focus) {
+                self.loading_page = false;
+            }
+            self.set_status(t!("status-request-failed", error = error.message));
+            return Ok(());
+        }
+        let side = match target {
+            Target::Pane(side) => side,
+            Target::Page(side) => {
+                self.add_page(side, response);
+                return Ok(());
+            }
+        };
+        if side != self.focus {
+            // Dropped if the pane was closed in the meantime.
+            if let Some(other) = &mut self.split {
+                other.show(response.entries, response.next_cursor);
+            }
+            return Ok(());
+        }
+
+        if let Some(days) = response.days {
+            self.calendar = Some(Calendar::new(days));
+            return Ok(());
+        }
+        if let Some(sessions) = response.sessions {
+            self.sessions.update(sessions);
+            return Ok(());
+        }
+        if let Some(profiles) = response.profiles {
+            self.profiles.update(profiles);
+            return Ok(());
+        }
+        if let Some(checksums) = response.checksums {
+            if let Some(digest) = self.copy_digest.take() {
+                let value = checksums.get(digest).to_string();
+                self.copy_to_clipboard(digest.label(), &value);
+            }
+            self.checksums = Some(checksums);
+            return Ok(());
+        }
+        if let Some(source) = response.source {
+            self.source = Some(source);
+            return Ok(());
+        }
+        if let Some(verdict) = response.paste {
+            return self.answer_paste(verdict);
+        }
+        if let Some(payload) = response.payload {
+            return self.paste_payload(payload);
+        }
+        if let Some(capture) = response.capture {
+            self.set_status(match capture.paused_until {
+                Some(until) => t!("status-capture-paused", until = until),
+                None => t!("status-capture-resumed"),
+            });
+            return Ok(());
+        }
+
+        if let Some(group) = self.paste_group {
+            // Answers to requests sent before the group's are not the group.
+            let is_group = !response.entries.is_empty()
+                && response.entries.iter().all(|entry| entry.copy_group == Some(group));
+            if is_group {
+                self.paste_group = None;
+                return self.paste_copied_together(response.entries);
+            }
+        }
+        if let Some(changed) = response.changed {
+            self.set_status(t!("status-changed", count = changed));
+        }
         if response.entries.is_empty() {
             self.selected = 0;
         } else if self.selected >= response.entries.len() {
             self.selected = response.entries.len() - 1;
         }
         self.entries = response.entries;
+        self.next_cursor = response.next_cursor;
+        self.loading_page = false;
+        self.select_pending();
+
+        if let Some(path) = self.reveal_after_save.take() {
+            match reveal::show_in_explorer(&path) {
+                Ok(()) => self.set_status(t!("status-drag", path = path.display().to_string())),
+                Err(err) => self.set_status(format!("{err:#}")),
+            }
+        }
+
+        if self.filter.is_empty() {
+            if let Err(err) = cache::save(&self.entries) {
+                tracing::warn!(%err, "failed to update history cache");
+            }
+        }
         Ok(())
     }
 }
 
-impl Drop for TerminalUi {
+impl<B: Backend> Drop for TerminalUi<B> {
     fn drop(&mut self) {
         let _ = self.terminal.show_cursor();
-        let _ = crossterm::execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        if self.alternate_screen {
+            let _ = crossterm::execute!(std::io::stdout(), LeaveAlternateScreen);
+        }
+    }
+}
+
+/// One row of the history list: kind icon, badges, label, time, and tags.
+/// One history row; in a terminal `width` columns wide, narrow rows drop the
+/// timestamp and cut the preview to fit.
+fn history_item<'a>(entry: &'a EntrySummary, theme: &Theme, config: &ClientConfig, width: u16) -> ListItem<'a> {
+    let guest = config.guest_mode;
+    let narrow = width < NARROW_WIDTH;
+    let preview_length = if narrow {
+        config.preview_length.min(usize::from(width).saturating_sub(NARROW_ROW_CHROME))
+    } else {
+        config.preview_length
+    };
+    let icon_color = match entry.kind.as_str() {
+        "text" => theme.text_icon,
+        "url" => theme.url_icon,
+        "image" => theme.image_icon,
+        "rtf" | "html" | "snapshot" | "files" => theme.rtf_icon,
+        _ => theme.metadata_label,
+    };
+    
+    let mut spans = vec![
+        Span::styled(
+            format!("{} ", theme.glyphs.kind(&entry.kind)),
+            Style::default().fg(icon_color),
+        ),
+    ];
+    if entry.pinned {
+        spans.push(Span::raw(format!("{} ", theme.glyphs.pinned())));
+    }
+    if entry.on_clipboard {
+        spans.push(Span::raw(format!("{} ", theme.glyphs.on_clipboard())));
+    }
+    if entry.quarantined {
+        spans.push(Span::styled(format!("{} quarantined ", theme.glyphs.warning()), theme.style_warning()));
+    } else if !entry.warnings.is_empty() {
+        spans.push(Span::styled(format!("{} ", theme.glyphs.warning()), theme.style_warning()));
+    }
+
+    // Guest mode: kind and time only, nothing copied shows on screen
+    if guest {
+        spans.push(Span::styled(
+            format!("{:<6}", entry.kind),
+            theme.style_list_item(),
+        ));
+        spans.push(Span::styled(
+            format!("  {}", entry.display_time()),
+            theme.style_metadata_label(),
+        ));
+        return ListItem::new(Line::from(spans));
+    }
+    
+    // Prefer the daemon's title; truncate if too long
+    let label = entry.title.as_deref().unwrap_or(&entry.preview);
+    let (preview_text, ellipsis) = if label.chars().count() > preview_length {
+        let cut: String = label
+            .chars()
+            .take(preview_length.saturating_sub(3))
+            .collect();
+        (cut, "...")
+    } else {
+        (label.to_string(), "")
+    };
+    
+    // Search matches are offsets into the preview, so a title shows none
+    let highlights = if entry.title.is_none() { entry.highlights.as_slice() } else { &[] };
+    spans.extend(richtext::highlighted_spans(
+        &preview_text,
+        highlights,
+        theme.style_list_item(),
+        theme.style_search_match(),
+    ));
+    if !ellipsis.is_empty() {
+        spans.push(Span::styled(ellipsis, theme.style_list_item()));
+    }
+    
+    if config.show_timestamps && !narrow {
+        spans.push(Span::styled(
+            format!("  {}", entry.display_time()),
+            theme.style_metadata_label(),
+        ));
+    }
+    
+    // Add tags with styling
+    if !entry.tags.is_empty() {
+        spans.push(Span::raw("  "));
+        for (i, tag) in entry.tags.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            // A tag's background is all that sets it apart, except in ASCII
+            let tag = match theme.glyphs {
+                Glyphs::Unicode => format!(" {} ", tag),
+                Glyphs::Ascii => format!("#{tag}"),
+            };
+            spans.push(Span::styled(tag, theme.style_tag()));
+        }
+    }
+    
+    ListItem::new(Line::from(spans))
+}
+
+/// Put a pushed entry at the top of a list, below its pinned entries, unless
+/// the list is filtered; one the list has already moves up from where it was.
+/// `selected` stays with the entry it points at.
+fn merge_added(entries: &mut Vec<EntrySummary>, selected: &mut usize, filter: &str, entry: EntrySummary) {
+    if !filter.is_empty() {
+        return;
+    }
+    let selected_id = entries.get(*selected).map(|listed| listed.id);
+    // Copied again: a pinned entry keeps its place, others move to the top.
+    if let Some(index) = entries.iter().position(|listed| listed.id == entry.id) {
+        if entries[index].pinned {
+            return;
+        }
+        entries.remove(index);
+    }
+    let at = entries.iter().take_while(|listed| listed.pinned).count();
+    entries.insert(at, entry);
+    if let Some(index) = selected_id.and_then(|id| entries.iter().position(|listed| listed.id == id)) {
+        *selected = index;
+    }
+}
+
+/// Values of `entry`'s entities of `kind`, in the order they appear.
+fn entity_values(entry: &EntrySummary, kind: EntityKind) -> Vec<&str> {
+    entry
+        .entities
+        .iter()
+        .filter(|entity| entity.kind == kind)
+        .map(|entity| entity.value.as_str())
+        .collect()
+}
+
+/// An entity as the preview lists it: an issue with its title, once clipd
+/// has looked it up.
+fn entity_text(entity: &Entity) -> String {
+    match &entity.title {
+        Some(title) => format!("{} \u{201c}{title}\u{201d}", entity.value),
+        None => entity.value.clone(),
+    }
+}
+
+/// Preview label for the entities of `kind`.
+fn entity_label(kind: EntityKind) -> String {
+    match kind {
+        EntityKind::Email => t!("preview-emails"),
+        EntityKind::Phone => t!("preview-phones"),
+        EntityKind::Ipv4 => t!("preview-ipv4"),
+        EntityKind::Ipv6 => t!("preview-ipv6"),
+        EntityKind::Uuid => t!("preview-uuids"),
+        EntityKind::Sha => t!("preview-commits"),
+        EntityKind::Container => t!("preview-containers"),
+        EntityKind::Issue => t!("preview-issues"),
+    }
+}
+
+fn no_entity(kind: EntityKind) -> String {
+    match kind {
+        EntityKind::Email => t!("status-no-email"),
+        EntityKind::Phone => t!("status-no-phone"),
+        EntityKind::Ipv4 => t!("status-no-ipv4"),
+        EntityKind::Ipv6 => t!("status-no-ipv6"),
+        EntityKind::Uuid => t!("status-no-uuid"),
+        EntityKind::Sha => t!("status-no-commit"),
+        EntityKind::Container => t!("status-no-container"),
+        EntityKind::Issue => t!("status-no-issue"),
+    }
+}
+
+/// The history list for one pane; `focused` highlights the pane keys go to in a split.
+fn history_list<'a>(
+    entries: &'a [EntrySummary],
+    title: String,
+    focused: bool,
+    theme: &Theme,
+    config: &ClientConfig,
+    width: u16,
+) -> List<'a> {
+    let items: Vec<_> = entries
+        .iter()
+        .map(|entry| history_item(entry, theme, config, width))
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .title(Span::styled(title, theme.style_title()))
+                .borders(theme.glyphs.borders())
+                .border_style(if focused {
+                    theme.style_border_focused()
+                } else {
+                    theme.style_border()
+                })
+                .title_alignment(Alignment::Center),
+        )
+        .highlight_style(theme.style_list_selected())
+        .highlight_symbol(theme.glyphs.selected())
+}
+
+/// Title of a history list; split panes are told apart by their filters.
+fn list_title(filter: &str, split: bool, guest: bool) -> String {
+    if guest {
+        format!(" {} ", t!("list-history-guest"))
+    } else if split && !filter.is_empty() {
+        format!(" {filter} ")
+    } else if split {
+        format!(" {} ", t!("list-history"))
+    } else {
+        format!(" {} ", t!("list-history-help"))
+    }
+}
+
+/// A label or prompt from the catalog, followed by what it labels.
+fn label(text: String) -> String {
+    text + " "
+}
+
+/// The command bar while typing `input` at `prompt`, after its `emoji`.
+fn prompt_line<'a>(
+    theme: &Theme,
+    emoji: &'static str,
+    prompt: String,
+    input: impl Into<Cow<'a, str>>,
+) -> Line<'a> {
+    let prompt = match theme.glyphs.icon(emoji) {
+        "" => label(prompt),
+        icon => format!("{icon} {}", label(prompt)),
+    };
+    Line::from(vec![
+        Span::styled(prompt, theme.style_command_prompt()),
+        Span::styled(input, theme.style_command_input()),
+        Span::styled(theme.glyphs.cursor(), Style::default().fg(theme.list_selected_fg)),
+    ])
+}
+
+/// A help overlay row: `keys` joined by "/", then `description` aligned
+/// with the other rows' descriptions.
+fn help_line(keys: &[&str], description: String, theme: &Theme) -> Line<'static> {
+    const DESCRIPTION_COLUMN: usize = 14;
+    if theme.glyphs == Glyphs::Ascii {
+        // A plain "keys: description" sentence, without arrows or alignment
+        let keys: Vec<_> = keys
+            .iter()
+            .map(|key| match *key {
+                "↓" => "Down",
+                "↑" => "Up",
+                key => key,
+            })
+            .collect();
+        return Line::from(vec![
+            Span::styled(keys.join("/"), theme.style_help_key()),
+            Span::styled(format!(": {description}"), theme.style_help_desc()),
+        ]);
+    }
+    let mut spans = Vec::new();
+    let mut width = 0;
+    for (i, key) in keys.iter().enumerate() {
+        let key = if i == 0 { format!("  {key}") } else { key.to_string() };
+        if i > 0 {
+            spans.push(Span::raw("/"));
+            width += 1;
+        }
+        width += key.chars().count();
+        spans.push(Span::styled(key, theme.style_help_key()));
     }
+    let padding = " ".repeat(DESCRIPTION_COLUMN.saturating_sub(width).max(1));
+    spans.push(Span::styled(padding + &description, theme.style_help_desc()));
+    Line::from(spans)
 }
 
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,377 @@
+//! Snapshots of whole screens, drawn into a `TestBackend` from injected
+//! entries and modes. Run `cargo insta review` after an intended change to
+//! the layout or a theme to accept the new snapshots.
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+
+use super::*;
+use crate::daemon::SpawnPolicy;
+use crate::keymap::KeyMap;
+use crate::mux::{Multiplexer, MuxPane, PanePicker};
+use clipcore::ImageInfo;
+
+const THEMES: &[&str] = &["nord", "dracula", "tokyo-night", "gruvbox", "high-contrast"];
+
+fn config(theme: &str) -> ClientConfig {
+    ClientConfig {
+        theme: theme.to_string(),
+        spawn_daemon: SpawnPolicy::Never,
+        paste_method: PasteMethod::Stdout,
+        preview_length: 60,
+        confirm_prompts: true,
+        show_timestamps: true,
+        guest_mode: false,
+        unit_conversions: true,
+        suggestions: true,
+        accessible: false,
+        language: String::new(),
+        send_command: None,
+        hide_from_windows_history: false,
+        type_delay_ms: 0,
+        views: Vec::new(),
+        keys: KeyMap::default(),
+        first_run: false,
+    }
+}
+
+fn entry(id: u64, kind: &str, preview: &str, minutes_ago: u32) -> EntrySummary {
+    EntrySummary {
+        id,
+        preview: preview.to_string(),
+        created_at: format!("2026-10-16T09:{:02}:00+00:00", 59 - minutes_ago),
+        created_display: Some(format!("{minutes_ago} min ago")),
+        kind: kind.to_string(),
+        source_process: Some("notepad.exe".to_string()),
+        tags: Vec::new(),
+        image: None,
+        title: None,
+        quarantined: false,
+        warnings: Vec::new(),
+        lang: None,
+        pinned: false,
+        styles: Vec::new(),
+        copy_group: None,
+        formats: Vec::new(),
+        archived: false,
+        highlights: Vec::new(),
+        entities: Vec::new(),
+        on_clipboard: false,
+        use_count: 1,
+    }
+}
+
+/// A history with one entry of each common kind, the first one selected.
+fn entries() -> Vec<EntrySummary> {
+    let mut note = entry(1, "text", "Meeting moved to 3pm\nRoom 4.12, bring the Q3 numbers", 1);
+    note.tags = vec!["work".to_string()];
+    note.pinned = true;
+    let mut image = entry(4, "image", "<non-text entry>", 12);
+    image.image = Some(ImageInfo {
+        width: 1920,
+        height: 1080,
+        dpi: Some(96),
+        monitor: Some(r"\\.\DISPLAY1".to_string()),
+    });
+    image.source_process = Some("SnippingTool.exe".to_string());
+    vec![
+        note,
+        entry(2, "url", "https://github.com/surajfale/rusty-clipboard", 3),
+        entry(3, "text", "fn main() {\n    println!(\"hello\");\n}", 7),
+        image,
+    ]
+}
+
+fn ui(theme: &str, width: u16, height: u16) -> TerminalUi<TestBackend> {
+    let mut ui = TerminalUi::with_backend(&config(theme), TestBackend::new(width, height)).unwrap();
+    ui.entries = entries();
+    ui
+}
+
+fn press(ui: &mut TerminalUi<TestBackend>, code: KeyCode) -> Option<Request> {
+    let key = KeyEvent::new(code, KeyModifiers::NONE);
+    ui.handle_event(UiEvent::Input(Event::Key(key))).unwrap().request
+}
+
+/// The cursor a request for the next page of the history asks from.
+fn page_cursor(request: Option<Request>) -> Option<String> {
+    match request?.kind {
+        RequestKind::ListPage { cursor, .. } => cursor,
+        _ => None,
+    }
+}
+
+/// The characters on screen, one line per row.
+fn screen(ui: &mut TerminalUi<TestBackend>) -> String {
+    ui.draw().unwrap();
+    let buffer = ui.terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..buffer.area.height {
+        let row: String = (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect();
+        screen.push_str(row.trim_end());
+        screen.push('\n');
+    }
+    screen
+}
+
+/// The screen with every cell's colors and modifiers, for theme snapshots.
+fn styled_screen(ui: &mut TerminalUi<TestBackend>) -> String {
+    ui.draw().unwrap();
+    format!("{:?}", ui.terminal.backend().buffer())
+}
+
+#[test]
+fn list_and_preview_in_every_theme() {
+    for theme in THEMES {
+        let mut ui = ui(theme, 100, 30);
+        insta::assert_snapshot!(format!("list_{theme}"), styled_screen(&mut ui));
+    }
+}
+
+#[test]
+fn list_and_preview_at_every_size() {
+    for (width, height) in [(60, 20), (80, 24), (160, 48)] {
+        let mut ui = ui("nord", width, height);
+        insta::assert_snapshot!(format!("list_{width}x{height}"), screen(&mut ui));
+    }
+}
+
+#[test]
+fn preview_follows_the_selection() {
+    let mut ui = ui("nord", 100, 30);
+    press(&mut ui, KeyCode::Char('j'));
+    press(&mut ui, KeyCode::Char('j'));
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn empty_history() {
+    let mut ui = ui("nord", 80, 24);
+    ui.entries.clear();
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn help() {
+    for (width, height) in [(80, 24), (120, 60)] {
+        let mut ui = ui("nord", width, height);
+        press(&mut ui, KeyCode::Char('?'));
+        insta::assert_snapshot!(format!("help_{width}x{height}"), screen(&mut ui));
+    }
+}
+
+#[test]
+fn scrolling_near_the_end_loads_the_next_page() {
+    let mut ui = ui("nord", 100, 30);
+    ui.entries = (1..=60).map(|id| entry(id, "text", &format!("entry {id}"), 1)).collect();
+    ui.next_cursor = Some("page-2".to_string());
+
+    assert_eq!(page_cursor(press(&mut ui, KeyCode::Char('j'))), None);
+    assert_eq!(page_cursor(press(&mut ui, KeyCode::Char('G'))), Some("page-2".to_string()));
+    // Asked for once, however the selection moves until it arrives.
+    assert_eq!(page_cursor(press(&mut ui, KeyCode::Char('k'))), None);
+
+    // Entry 60 was bumped to the top of the next page meanwhile; it stays where it is.
+    let page = [60, 61, 62].map(|id| entry(id, "text", &format!("entry {id}"), 2));
+    ui.ingest_response(Response {
+        entries: page.to_vec(),
+        next_cursor: Some("page-3".to_string()),
+        ..Response::default()
+    })
+    .unwrap();
+    let ids: Vec<u64> = ui.entries.iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, (1..=62).collect::<Vec<_>>());
+
+    // A page that arrives after the panes swap was for the other list; it is dropped.
+    assert_eq!(page_cursor(press(&mut ui, KeyCode::Char('G'))), Some("page-3".to_string()));
+    press(&mut ui, KeyCode::Char('v'));
+    ui.perform(Action::SwitchPane).unwrap();
+    ui.ingest_response(Response {
+        entries: vec![entry(63, "text", "entry 63", 3)],
+        ..Response::default()
+    })
+    .unwrap();
+    assert_eq!(ui.entries.len(), 62);
+    assert!(ui.split.as_ref().is_some_and(|other| other.entries.len() == 62));
+}
+
+/// Type `keys` one at a time, then play every macro key they queued, and
+/// return how many were played.
+fn type_and_play(ui: &mut TerminalUi<TestBackend>, keys: &str) -> usize {
+    for c in keys.chars() {
+        press(ui, KeyCode::Char(c));
+    }
+    let mut played = 0;
+    while ui.play_next().is_some() {
+        played += 1;
+        assert!(played <= 20_000, "macro never stopped");
+    }
+    played
+}
+
+#[test]
+fn a_macro_that_replays_itself_stops() {
+    // Stopped by `j` failing at the bottom of the list, as in Vim.
+    let mut at_bottom = ui("nord", 100, 30);
+    type_and_play(&mut at_bottom, "qaj@aq@a");
+    assert_eq!(at_bottom.selected, at_bottom.entries.len() - 1);
+    assert_eq!(at_bottom.status.as_deref(), Some(t!("status-macro-stopped").as_str()));
+    assert!(!at_bottom.playing_macro());
+
+    // Nothing fails in `@a` alone; the count of keys played stops it.
+    let mut looping = ui("nord", 100, 30);
+    let played = type_and_play(&mut looping, "qa@aq@a");
+    assert!(played <= 10_000, "{played} keys played");
+    let status = looping.status.as_deref().unwrap_or_default();
+    assert!(status.contains("more than"), "{status}");
+    assert!(!looping.playing_macro());
+}
+
+#[test]
+fn help_scrolls_to_its_last_line() {
+    let mut ui = ui("nord", 80, 24);
+    press(&mut ui, KeyCode::Char('?'));
+    for _ in 0..3 {
+        press(&mut ui, KeyCode::PageDown);
+    }
+    insta::assert_snapshot!(screen(&mut ui));
+    // Drawing stopped the scroll at the last line, so one up and one down cancel out.
+    press(&mut ui, KeyCode::Up);
+    let scrolled = ui.help_scroll;
+    press(&mut ui, KeyCode::Down);
+    assert_eq!(ui.help_scroll, scrolled + 1);
+}
+
+#[test]
+fn command_palette() {
+    let mut ui = ui("nord", 100, 30);
+    press(&mut ui, KeyCode::Char(':'));
+    press(&mut ui, KeyCode::Char('e'));
+    press(&mut ui, KeyCode::Char('x'));
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn key_bindings_from_the_config() {
+    let mut config = config("nord");
+    let table = [("X".to_string(), "delete".to_string())].into_iter().collect();
+    config.keys = KeyMap::parse(&table).0;
+    let mut ui = TerminalUi::with_backend(&config, TestBackend::new(100, 30)).unwrap();
+    ui.entries = entries();
+    press(&mut ui, KeyCode::Char(':'));
+    for c in "del".chars() {
+        press(&mut ui, KeyCode::Char(c));
+    }
+    insta::assert_snapshot!(screen(&mut ui));
+    press(&mut ui, KeyCode::Esc);
+    press(&mut ui, KeyCode::Char('X'));
+    assert_eq!(ui.mode, UiMode::Confirm);
+}
+
+#[test]
+fn search_prompt() {
+    let mut ui = ui("nord", 100, 30);
+    press(&mut ui, KeyCode::Char('/'));
+    for c in "meet".chars() {
+        press(&mut ui, KeyCode::Char(c));
+    }
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn delete_confirmation() {
+    let mut ui = ui("nord", 100, 30);
+    ui.filter = "kind:url".to_string();
+    ui.perform(Action::DeleteResults).unwrap();
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn diff_preview() {
+    let mut ui = ui("nord", 100, 30);
+    let diff = "diff --git a/src/main.rs b/src/main.rs\n\
+                index 83db48f..bf269f4 100644\n\
+                --- a/src/main.rs\n\
+                +++ b/src/main.rs\n\
+                @@ -1,3 +1,3 @@\n \
+                fn main() {\n\
+                -    println!(\"helo\");\n\
+                +    println!(\"hello\");\n \
+                }";
+    ui.entries[0] = entry(5, "text", diff, 1);
+    insta::assert_snapshot!(styled_screen(&mut ui));
+}
+
+#[test]
+fn entry_on_the_clipboard() {
+    let mut ui = ui("nord", 100, 30);
+    ui.entries[0].on_clipboard = true;
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn split_panes() {
+    let mut ui = ui("dracula", 120, 30);
+    press(&mut ui, KeyCode::Char('v'));
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn accessible_mode() {
+    let mut config = config("nord");
+    config.accessible = true;
+    let mut ui = TerminalUi::with_backend(&config, TestBackend::new(80, 30)).unwrap();
+    ui.entries = entries();
+    ui.entries[2].warnings = vec!["hidden characters".to_string()];
+    insta::assert_snapshot!("accessible_list", screen(&mut ui));
+    press(&mut ui, KeyCode::Char('?'));
+    insta::assert_snapshot!("accessible_help", screen(&mut ui));
+}
+
+#[test]
+fn offline_banner() {
+    let mut ui = ui("gruvbox", 100, 30);
+    ui.go_offline(Some(entries()));
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn pane_picker() {
+    let mut ui = ui("nord", 100, 30);
+    let pane = |id: &str, location: &str, title: &str| MuxPane {
+        id: id.to_string(),
+        location: location.to_string(),
+        title: title.to_string(),
+    };
+    let panes = vec![
+        pane("%0", "work:0.0", "nvim"),
+        pane("%2", "work:1.0", "pwsh"),
+        pane("%5", "logs:0.1", "tail"),
+    ];
+    ui.pane_picker = Some(PanePicker::new(Multiplexer::Tmux, panes, Some("%2")));
+    ui.mode = UiMode::Panes;
+    insta::assert_snapshot!(screen(&mut ui));
+}
+
+#[test]
+fn catalogs_parse_and_translate_only_english_messages() {
+    use fluent_bundle::FluentResource;
+    use fluent_syntax::ast::Entry;
+
+    let ids = |source: &str| -> Vec<String> {
+        FluentResource::try_new(source.to_string())
+            .expect("catalog parses")
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    };
+    let english = ids(crate::i18n::CATALOGS[0].1);
+    for (tag, source) in crate::i18n::CATALOGS {
+        crate::i18n::bundle(tag, source);
+        let unknown: Vec<_> = ids(source).into_iter().filter(|id| !english.contains(id)).collect();
+        assert!(unknown.is_empty(), "{tag} translates messages en-US lacks: {unknown:?}");
+    }
+}
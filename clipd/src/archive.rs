@@ -0,0 +1,54 @@
+//! Encrypted export archives: passphrase-protected ones in the age format, so
+//! they can also be opened with `age --decrypt`, or sealed with this Windows
+//! user's key for backups that stay on one account.
+
+use age::scrypt;
+use age::secrecy::SecretString;
+use anyhow::{bail, Context, Result};
+
+use crate::keys::{self, KeyRing};
+
+/// First line of every binary age file.
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1\n";
+
+/// How an export is encrypted.
+pub enum Protection<'a> {
+    Passphrase(&'a str),
+    /// The current key in `keys.json`.
+    UserKey(&'a KeyRing),
+}
+
+impl Protection<'_> {
+    pub fn apply(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Passphrase(passphrase) => encrypt(plaintext, passphrase),
+            Self::UserKey(keys) => keys.seal(plaintext),
+        }
+    }
+}
+
+/// The JSON inside an export file, decrypting it if needed.
+pub fn open(contents: Vec<u8>, passphrase: Option<&str>, keys: &KeyRing) -> Result<Vec<u8>> {
+    if keys::is_sealed(&contents) {
+        return keys.unseal(&contents);
+    }
+    match (is_encrypted(&contents), passphrase) {
+        (true, Some(passphrase)) => decrypt(&contents, passphrase),
+        (true, None) => bail!("the archive is encrypted; a passphrase is needed to import it"),
+        (false, _) => Ok(contents),
+    }
+}
+
+fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(AGE_HEADER)
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let recipient = scrypt::Recipient::new(SecretString::from(passphrase.to_owned()));
+    age::encrypt(&recipient, plaintext).context("failed to encrypt export")
+}
+
+fn decrypt(ciphertext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let identity = scrypt::Identity::new(SecretString::from(passphrase.to_owned()));
+    age::decrypt(&identity, ciphertext).context("failed to decrypt archive; is the passphrase right?")
+}
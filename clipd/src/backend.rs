@@ -0,0 +1,89 @@
+//! Access to the system clipboard and the window in front.
+//!
+//! The watcher only talks to the clipboard through [`ClipboardBackend`], so it
+//! runs the same against Windows ([`WindowsClipboard`]), against Linux and
+//! macOS ([`UnixClipboard`]), and against the in-memory
+//! [`MockClipboard`](crate::mock::MockClipboard) the integration tests
+//! script, which needs no desktop session.
+
+use anyhow::Result;
+
+use crate::snapshot::Format;
+
+#[cfg(not(target_os = "windows"))]
+mod unix;
+#[cfg(target_os = "windows")]
+mod win32;
+
+#[cfg(not(target_os = "windows"))]
+pub use unix::UnixClipboard;
+#[cfg(target_os = "windows")]
+pub use win32::WindowsClipboard;
+
+/// The clipboard of the desktop clipd runs on.
+#[cfg(target_os = "windows")]
+pub type SystemClipboard = WindowsClipboard;
+#[cfg(not(target_os = "windows"))]
+pub type SystemClipboard = UnixClipboard;
+
+/// Id of `CF_UNICODETEXT`: UTF-16 text ending in a NUL.
+pub const CF_UNICODETEXT: u32 = 13;
+
+/// Id of `CF_DIB`: a `BITMAPINFOHEADER` followed by the pixels.
+pub const CF_DIB: u32 = 8;
+
+/// Id of `CF_HDROP`: files copied in Explorer, as a `DROPFILES` list of paths.
+pub const CF_HDROP: u32 = 15;
+
+/// A clipboard format to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatId<'a> {
+    /// Predefined by Windows (`CF_*`).
+    Standard(u32),
+    /// Registered by name, such as `HTML Format`.
+    Registered(&'a str),
+}
+
+/// The clipboard and window queries clipd needs.
+///
+/// Windows are identified by their handle value, 0 meaning none; reads that
+/// fail, such as while another process holds the clipboard open, return `None`.
+pub trait ClipboardBackend: Send + Sync + std::fmt::Debug {
+    /// Number that changes whenever the clipboard's contents do; 0 when unknown.
+    fn sequence(&self) -> u32;
+
+    /// Data of one format, if the clipboard holds it.
+    fn read(&self, format: FormatId<'_>) -> Option<Vec<u8>>;
+
+    /// Size in bytes of one format's data, without copying it.
+    fn size(&self, format: FormatId<'_>) -> Option<usize>;
+
+    /// Every format on the clipboard whose data can be copied.
+    fn read_all(&self) -> Result<Vec<Format>>;
+
+    /// Replace the clipboard with `formats`, calling `written` with the new
+    /// sequence number before the watcher can see it.
+    fn write(&self, formats: &[Format], written: &dyn Fn(u32)) -> Result<()>;
+
+    /// Executable name of the process whose window last wrote the clipboard.
+    fn owner_process(&self) -> Option<String>;
+
+    /// The window in front.
+    fn foreground_window(&self) -> usize;
+
+    /// Executable name of the process that owns `window`, such as `Code.exe`.
+    fn window_process(&self, window: usize) -> Option<String>;
+
+    /// Title bar text of `window`.
+    fn window_title(&self, window: usize) -> Option<String>;
+
+    /// Device name and effective DPI of the monitor showing `window`.
+    fn window_monitor(&self, window: usize) -> Option<(String, Option<u32>)>;
+
+    /// Whether the workstation is locked, so nobody is copying anything.
+    fn workstation_locked(&self) -> bool;
+
+    /// Battery charge in percent while running on battery; `None` on AC
+    /// power, without a battery, or when the charge is unknown.
+    fn battery_percent(&self) -> Option<u8>;
+}
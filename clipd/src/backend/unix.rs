@@ -0,0 +1,233 @@
+//! The Linux and macOS clipboard, through arboard: X11 or Wayland on Linux,
+//! the general pasteboard on macOS.
+//!
+//! Only text, HTML, images, and copied files go through arboard, so they are
+//! all clipd captures and restores there; files are handed to the watcher as
+//! `CF_HDROP` data, as on Windows. Nothing tells clipd when the clipboard
+//! changes, so the sequence number is counted here, going up whenever a check
+//! finds other contents than the last one. Which app copied and which window
+//! is in front are not known, so rules that name processes do not apply.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use arboard::{Clipboard, ImageData};
+use parking_lot::Mutex;
+
+use super::{ClipboardBackend, FormatId, CF_DIB, CF_HDROP, CF_UNICODETEXT};
+use crate::{files, image};
+use crate::snapshot::Format;
+
+/// The name clipd reads HTML under, as on Windows; arboard hands it out
+/// without the CF_HTML header, which the HTML parser does without.
+const HTML_FORMAT: &str = "HTML Format";
+
+/// Where Linux lists batteries and power supplies.
+const POWER_SUPPLIES: &str = "/sys/class/power_supply";
+
+/// The clipboard of the desktop session clipd runs in.
+#[derive(Default)]
+pub struct UnixClipboard {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Opened on first use and kept open: on Linux, what clipd writes is
+    /// only on the clipboard while clipd holds it.
+    clipboard: Option<Clipboard>,
+    sequence: u32,
+    /// Checksum of the contents `sequence` was counted for.
+    contents: Option<u32>,
+}
+
+impl std::fmt::Debug for UnixClipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnixClipboard").finish_non_exhaustive()
+    }
+}
+
+impl State {
+    fn clipboard(&mut self) -> Result<&mut Clipboard> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("failed to open the clipboard")?);
+        }
+        Ok(self.clipboard.as_mut().expect("clipboard was just opened"))
+    }
+
+    fn text(&mut self) -> Option<String> {
+        self.clipboard().ok()?.get().text().ok()
+    }
+
+    fn html(&mut self) -> Option<String> {
+        self.clipboard().ok()?.get().html().ok()
+    }
+
+    fn image(&mut self) -> Option<ImageData<'static>> {
+        self.clipboard().ok()?.get().image().ok()
+    }
+
+    /// Paths of copied files, as `CF_HDROP` data.
+    fn files(&mut self) -> Option<Vec<u8>> {
+        let paths = self.clipboard().ok()?.get().file_list().ok()?;
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        (!paths.is_empty()).then(|| files::encode_drop(&paths))
+    }
+
+    /// The sequence number, counted up when the contents changed since the
+    /// last check. Files and images are only read when there is no text,
+    /// which is how they are copied; reading an image costs the most.
+    fn sequence(&mut self) -> u32 {
+        let contents = match self.text() {
+            Some(text) => Some(crc32fast::hash(text.as_bytes())),
+            None => match self.files() {
+                Some(files) => Some(crc32fast::hash(&files)),
+                None => self.image().map(|image| crc32fast::hash(&image.bytes)),
+            },
+        };
+        if contents != self.contents {
+            self.contents = contents;
+            // 0 means unknown to the watcher.
+            self.sequence = self.sequence.wrapping_add(1).max(1);
+        }
+        self.sequence
+    }
+
+    fn read(&mut self, format: FormatId<'_>) -> Option<Vec<u8>> {
+        match format {
+            FormatId::Standard(CF_UNICODETEXT) => {
+                let text = self.text()?;
+                Some(text.encode_utf16().chain(Some(0)).flat_map(u16::to_le_bytes).collect())
+            }
+            FormatId::Standard(CF_DIB) => {
+                let image = self.image()?;
+                Some(image::rgba_to_dib(image.width as u32, image.height as u32, &image.bytes))
+            }
+            FormatId::Standard(CF_HDROP) => self.files(),
+            FormatId::Registered(HTML_FORMAT) => self.html().map(String::into_bytes),
+            _ => None,
+        }
+    }
+}
+
+impl ClipboardBackend for UnixClipboard {
+    fn sequence(&self) -> u32 {
+        self.state.lock().sequence()
+    }
+
+    fn read(&self, format: FormatId<'_>) -> Option<Vec<u8>> {
+        self.state.lock().read(format)
+    }
+
+    fn size(&self, format: FormatId<'_>) -> Option<usize> {
+        self.read(format).map(|data| data.len())
+    }
+
+    fn read_all(&self) -> Result<Vec<Format>> {
+        let mut state = self.state.lock();
+        let formats = [
+            (CF_UNICODETEXT, None, FormatId::Standard(CF_UNICODETEXT)),
+            (0, Some(HTML_FORMAT), FormatId::Registered(HTML_FORMAT)),
+            (CF_DIB, None, FormatId::Standard(CF_DIB)),
+            (CF_HDROP, None, FormatId::Standard(CF_HDROP)),
+        ];
+        Ok(formats
+            .into_iter()
+            .filter_map(|(id, name, format)| {
+                Some(Format {
+                    id,
+                    name: name.map(str::to_string),
+                    data: state.read(format)?,
+                })
+            })
+            .collect())
+    }
+
+    fn write(&self, formats: &[Format], written: &dyn Fn(u32)) -> Result<()> {
+        let mut text = None;
+        let mut html = None;
+        let mut image = None;
+        let mut paths = None;
+        for format in formats {
+            match (format.name.as_deref(), format.id) {
+                (None, CF_UNICODETEXT) => {
+                    let units: Vec<u16> = format
+                        .data
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                        .take_while(|&unit| unit != 0)
+                        .collect();
+                    text = Some(String::from_utf16_lossy(&units));
+                }
+                (Some(HTML_FORMAT), _) => html = Some(crate::html::fragment(&format.data)),
+                (None, CF_DIB) => {
+                    let (width, height, rgba) = image::dib_to_rgba(&format.data)?;
+                    image = Some(ImageData {
+                        width: width as usize,
+                        height: height as usize,
+                        bytes: rgba.into(),
+                    });
+                }
+                (None, CF_HDROP) => paths = files::parse_drop(&format.data),
+                // Markers such as the pasted entry's id have nowhere to go.
+                _ => tracing::debug!(format = %format.label(), "format not supported by this clipboard"),
+            }
+        }
+
+        // Held throughout, so the watcher cannot count the change first.
+        let mut state = self.state.lock();
+        let clipboard = state.clipboard()?;
+        match (paths, html, image, text) {
+            // The paths' text has no place next to the files.
+            (Some(paths), ..) => clipboard.set().file_list(&paths),
+            (None, Some(html), _, text) => clipboard.set().html(html, text),
+            (None, None, Some(image), _) => clipboard.set().image(image),
+            (None, None, None, Some(text)) => clipboard.set().text(text),
+            (None, None, None, None) => clipboard.clear(),
+        }
+        .context("failed to set the clipboard")?;
+        written(state.sequence());
+        Ok(())
+    }
+
+    fn owner_process(&self) -> Option<String> {
+        None
+    }
+
+    fn foreground_window(&self) -> usize {
+        0
+    }
+
+    fn window_process(&self, _window: usize) -> Option<String> {
+        None
+    }
+
+    fn window_title(&self, _window: usize) -> Option<String> {
+        None
+    }
+
+    fn window_monitor(&self, _window: usize) -> Option<(String, Option<u32>)> {
+        None
+    }
+
+    fn workstation_locked(&self) -> bool {
+        false
+    }
+
+    fn battery_percent(&self) -> Option<u8> {
+        // Missing on macOS, which is taken to be on AC power.
+        let supplies = fs::read_dir(POWER_SUPPLIES).ok()?;
+        for supply in supplies.flatten() {
+            let path = supply.path();
+            let read = |name: &str| fs::read_to_string(path.join(name)).ok();
+            if read("type").as_deref().map(str::trim) != Some("Battery") {
+                continue;
+            }
+            if read("status")?.trim() != "Discharging" {
+                return None;
+            }
+            return read("capacity")?.trim().parse().ok().filter(|percent| *percent <= 100);
+        }
+        None
+    }
+}
@@ -0,0 +1,287 @@
+//! The Windows clipboard, through the Win32 clipboard and window APIs.
+
+use std::ffi::c_void;
+
+use anyhow::{Context, Result};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, GlobalFree, HANDLE, HGLOBAL, HWND};
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST,
+};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, GetClipboardFormatNameW,
+    GetClipboardOwner, GetClipboardSequenceNumber, IsClipboardFormatAvailable, OpenClipboard,
+    RegisterClipboardFormatW, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE};
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+use super::{ClipboardBackend, FormatId};
+use crate::session;
+use crate::snapshot::Format;
+
+/// Formats whose data is a GDI handle or private to the owner rather than
+/// global memory, so a snapshot cannot copy them. Windows synthesizes the
+/// bitmap and metafile formats again from the DIB and enhanced metafile data
+/// that is kept.
+const UNCOPYABLE_FORMATS: &[u32] = &[
+    2,    // CF_BITMAP
+    3,    // CF_METAFILEPICT
+    9,    // CF_PALETTE
+    14,   // CF_ENHMETAFILE
+    0x80, // CF_OWNERDISPLAY
+    0x82, // CF_DSPBITMAP
+    0x83, // CF_DSPMETAFILEPICT
+    0x8E, // CF_DSPENHMETAFILE
+];
+
+/// `CF_PRIVATEFIRST..=CF_GDIOBJLAST`: handles only the owner understands.
+const PRIVATE_FORMATS: std::ops::RangeInclusive<u32> = 0x200..=0x3FF;
+
+/// The Windows clipboard of the session clipd runs in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WindowsClipboard;
+
+impl ClipboardBackend for WindowsClipboard {
+    fn sequence(&self) -> u32 {
+        unsafe { GetClipboardSequenceNumber() }
+    }
+
+    fn read(&self, format: FormatId<'_>) -> Option<Vec<u8>> {
+        with_format(format, |id| unsafe { read_global(id) })
+    }
+
+    fn size(&self, format: FormatId<'_>) -> Option<usize> {
+        with_format(format, |id| unsafe { global_size(id) })
+    }
+
+    fn read_all(&self) -> Result<Vec<Format>> {
+        unsafe {
+            OpenClipboard(HWND::default()).context("failed to open the clipboard")?;
+            let formats = read_formats();
+            let _ = CloseClipboard();
+            Ok(formats)
+        }
+    }
+
+    fn write(&self, formats: &[Format], written: &dyn Fn(u32)) -> Result<()> {
+        unsafe {
+            OpenClipboard(HWND::default()).context("failed to open the clipboard")?;
+            let result = write_formats(formats);
+            // Reported before closing, so the watcher cannot see the new sequence first.
+            written(GetClipboardSequenceNumber());
+            let _ = CloseClipboard();
+            result
+        }
+    }
+
+    fn owner_process(&self) -> Option<String> {
+        let hwnd = unsafe { GetClipboardOwner() }.ok()?;
+        window_process_name(hwnd)
+    }
+
+    fn foreground_window(&self) -> usize {
+        unsafe { GetForegroundWindow() }.0 as usize
+    }
+
+    fn window_process(&self, window: usize) -> Option<String> {
+        window_process_name(hwnd(window))
+    }
+
+    fn window_title(&self, window: usize) -> Option<String> {
+        if window == 0 {
+            return None;
+        }
+        let mut buffer = [0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd(window), &mut buffer) };
+        (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+
+    fn window_monitor(&self, window: usize) -> Option<(String, Option<u32>)> {
+        if window == 0 {
+            return None;
+        }
+        unsafe {
+            let monitor = MonitorFromWindow(hwnd(window), MONITOR_DEFAULTTONEAREST);
+            let mut info = MONITORINFOEXW::default();
+            info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+            if !GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+                return None;
+            }
+            let len = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+            let name = String::from_utf16_lossy(&info.szDevice[..len]);
+
+            let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+            let dpi = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y)
+                .ok()
+                .map(|()| dpi_x);
+
+            Some((name, dpi))
+        }
+    }
+
+    fn workstation_locked(&self) -> bool {
+        session::locked()
+    }
+
+    fn battery_percent(&self) -> Option<u8> {
+        let mut status = SYSTEM_POWER_STATUS::default();
+        unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+        // ACLineStatus is 0 when unplugged; BatteryLifePercent is 255 when unknown.
+        (status.ACLineStatus == 0 && status.BatteryLifePercent <= 100).then_some(status.BatteryLifePercent)
+    }
+}
+
+fn hwnd(window: usize) -> HWND {
+    HWND(window as *mut c_void)
+}
+
+/// Id of the format registered as `name`.
+fn register_format(name: &str) -> Option<u32> {
+    let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+    let id = unsafe { RegisterClipboardFormatW(PCWSTR(name.as_ptr())) };
+    (id != 0).then_some(id)
+}
+
+/// Data of each format on the open clipboard held in global memory.
+unsafe fn read_formats() -> Vec<Format> {
+    let mut formats = Vec::new();
+    let mut id = EnumClipboardFormats(0);
+    while id != 0 {
+        if !UNCOPYABLE_FORMATS.contains(&id) && !PRIVATE_FORMATS.contains(&id) {
+            if let Some(data) = read_global(id) {
+                formats.push(Format {
+                    id,
+                    name: format_name(id),
+                    data,
+                });
+            }
+        }
+        id = EnumClipboardFormats(id);
+    }
+    formats
+}
+
+/// Copy of the global memory behind format `id` on the open clipboard.
+/// Open the clipboard and apply `f` to `format`, if the clipboard holds it.
+fn with_format<T>(format: FormatId<'_>, f: impl FnOnce(u32) -> Option<T>) -> Option<T> {
+    let id = match format {
+        FormatId::Standard(id) => id,
+        FormatId::Registered(name) => register_format(name)?,
+    };
+    unsafe {
+        if IsClipboardFormatAvailable(id).is_err() {
+            return None;
+        }
+        // Clipboard might be locked by another process, this is normal
+        OpenClipboard(HWND::default()).ok()?;
+        let result = f(id);
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Size of format `id` on the open clipboard.
+unsafe fn global_size(id: u32) -> Option<usize> {
+    let handle = GetClipboardData(id).ok().filter(|handle| !handle.is_invalid())?;
+    Some(GlobalSize(HGLOBAL(handle.0)))
+}
+
+unsafe fn read_global(id: u32) -> Option<Vec<u8>> {
+    // Delay-rendered formats are produced by their owner here, and may fail.
+    let handle = GetClipboardData(id).ok().filter(|handle| !handle.is_invalid())?;
+    let hglobal = HGLOBAL(handle.0);
+    let ptr = GlobalLock(hglobal) as *const u8;
+    if ptr.is_null() {
+        return None;
+    }
+    let data = std::slice::from_raw_parts(ptr, GlobalSize(hglobal)).to_vec();
+    let _ = GlobalUnlock(hglobal);
+    Some(data)
+}
+
+/// Registered name of format `id`; `None` for the predefined formats.
+fn format_name(id: u32) -> Option<String> {
+    let mut name = [0u16; 256];
+    let len = unsafe { GetClipboardFormatNameW(id, &mut name) };
+    (len > 0).then(|| String::from_utf16_lossy(&name[..len as usize]))
+}
+
+/// Put `formats` on the open clipboard in place of what it holds. Formats
+/// that cannot be set are skipped with a warning.
+unsafe fn write_formats(formats: &[Format]) -> Result<()> {
+    EmptyClipboard().context("failed to empty the clipboard")?;
+    for format in formats {
+        let id = match &format.name {
+            Some(name) => register_format(name),
+            None => Some(format.id),
+        };
+        let Some(id) = id else {
+            tracing::warn!(format = %format.label(), "failed to register clipboard format");
+            continue;
+        };
+        let hglobal = GlobalAlloc(GMEM_MOVEABLE, format.data.len().max(1))
+            .context("failed to allocate clipboard memory")?;
+        let ptr = GlobalLock(hglobal) as *mut u8;
+        if ptr.is_null() {
+            let _ = GlobalFree(hglobal);
+            continue;
+        }
+        std::ptr::copy_nonoverlapping(format.data.as_ptr(), ptr, format.data.len());
+        let _ = GlobalUnlock(hglobal);
+        // The clipboard owns the memory once set; it is only ours to free on failure.
+        if let Err(err) = SetClipboardData(id, HANDLE(hglobal.0)) {
+            tracing::warn!(format = %format.label(), %err, "failed to restore clipboard format");
+            let _ = GlobalFree(hglobal);
+        }
+    }
+    Ok(())
+}
+
+/// Executable name of the process that owns `hwnd`.
+fn window_process_name(hwnd: HWND) -> Option<String> {
+    unsafe {
+        if hwnd.0.is_null() {
+            return None;
+        }
+
+        // Get the process ID
+        let mut process_id: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        if process_id == 0 {
+            return None;
+        }
+
+        // Open the process
+        let process_handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) {
+            Ok(handle) => handle,
+            Err(_) => return None,
+        };
+
+        // Get the process name
+        let mut buffer = vec![0u16; 260];
+        let mut size = buffer.len() as u32;
+
+        let result = QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_FORMAT(0),
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size
+        );
+        let _: () = CloseHandle(process_handle).map(|_| ()).unwrap_or(());
+
+        if result.is_err() {
+            return None;
+        }
+
+        // Convert the path to a string and extract just the filename
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        let filename = path.rsplit('\\').next().unwrap_or(&path);
+        Some(filename.to_string())
+    }
+}
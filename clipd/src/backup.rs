@@ -0,0 +1,159 @@
+//! Copies of a history database taken before operations that change many
+//! entries at once: imports, schema migrations, bulk deletes, and restores.
+//! Wiping the history deletes them too.
+//!
+//! `history.db` is backed up to `backups/history/<time>-<reason>.db` next to
+//! it, one folder per database, with `VACUUM INTO` so the copy is consistent
+//! while clipd keeps the database open. The newest `general.backups` copies
+//! of each database are kept.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::{params, Connection};
+
+/// Timestamp at the start of each backup's file name, in UTC.
+const NAME_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// One backup of a database, as listed by [`list`].
+pub struct Backup {
+    /// File name, which is how backups are named to `restore`.
+    pub name: String,
+    pub created: DateTime<Utc>,
+    /// What was about to happen: `import`, `migration`, `delete`, `clear`, `restore`, or `manual`.
+    pub reason: String,
+    pub bytes: u64,
+}
+
+/// Folder holding the backups of the database at `db_path`.
+fn dir(db_path: &Path) -> PathBuf {
+    let stem = db_path.file_stem().unwrap_or_default();
+    db_path.with_file_name("backups").join(stem)
+}
+
+/// Copy the database open as `conn` at `db_path` before `reason`, keeping
+/// the newest `keep` backups.
+pub fn create(conn: &Connection, db_path: &Path, reason: &str, keep: usize) -> Result<PathBuf> {
+    let dir = dir(db_path);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create backup folder {}", dir.display()))?;
+    let stamp = Utc::now().format(NAME_TIME_FORMAT);
+    let mut path = dir.join(format!("{stamp}-{reason}.db"));
+    // Two backups within a second, such as a restore right after an import.
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{stamp}-{reason}-{n}.db"));
+        n += 1;
+    }
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+        .with_context(|| format!("failed to back up {} to {}", db_path.display(), path.display()))?;
+    tracing::info!(path = %path.display(), reason, "backed up database");
+    prune(db_path, keep)?;
+    Ok(path)
+}
+
+/// Backups of the database at `db_path`, newest first.
+pub fn list(db_path: &Path) -> Result<Vec<Backup>> {
+    let dir = dir(db_path);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to list {}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(stem) = name.strip_suffix(".db") else {
+            continue;
+        };
+        // `20261016-153000-import`, or `...-import-2` for a same-second repeat.
+        let Some((time, reason)) = stem.get(..15).zip(stem.get(16..)) else {
+            continue;
+        };
+        let Ok(created) = NaiveDateTime::parse_from_str(time, NAME_TIME_FORMAT) else {
+            continue;
+        };
+        let reason = reason.trim_end_matches(|c: char| c.is_ascii_digit() || c == '-');
+        backups.push(Backup {
+            reason: reason.to_string(),
+            created: created.and_utc(),
+            bytes: entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+            name,
+        });
+    }
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Delete every backup of the database at `db_path`.
+pub fn remove_all(db_path: &Path) -> Result<()> {
+    prune(db_path, 0)
+}
+
+/// Delete all but the newest `keep` backups of the database at `db_path`.
+fn prune(db_path: &Path, keep: usize) -> Result<()> {
+    let dir = dir(db_path);
+    for backup in list(db_path)?.into_iter().skip(keep) {
+        let path = dir.join(&backup.name);
+        std::fs::remove_file(&path)
+            .with_context(|| format!("failed to delete old backup {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Replace every entry in the database open as `conn` with those in backup
+/// `name`. Backups from before a migration lack newer columns, which stay empty.
+pub fn restore(conn: &Connection, db_path: &Path, name: &str) -> Result<()> {
+    if name.contains(['/', '\\']) || !name.ends_with(".db") {
+        bail!("'{name}' is not a backup name; `clipctl backup` lists them");
+    }
+    let path = dir(db_path).join(name);
+    if !path.exists() {
+        bail!("no backup named {name}");
+    }
+
+    conn.execute("ATTACH DATABASE ?1 AS backup", params![path.to_string_lossy()])
+        .with_context(|| format!("failed to open backup {}", path.display()))?;
+    let result = (|| -> Result<()> {
+        let columns = |schema: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('entries', ?1)")?;
+            let names = stmt
+                .query_map(params![schema], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(names)
+        };
+        let current = columns("main")?;
+        let shared: Vec<String> = columns("backup")?
+            .into_iter()
+            .filter(|column| current.contains(column))
+            .collect();
+        if shared.is_empty() {
+            bail!("{name} holds no clipboard history");
+        }
+        let shared = shared.join(", ");
+        // Backups from before images were shared keep them in `entries`.
+        let has_blobs: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM backup.sqlite_master WHERE type = 'table' AND name = 'blobs')",
+            [],
+            |row| row.get(0),
+        )?;
+        let blobs = if has_blobs {
+            "INSERT OR IGNORE INTO blobs (hash, data, refs) SELECT hash, data, 0 FROM backup.blobs;"
+        } else {
+            ""
+        };
+        conn.execute_batch(&format!(
+            "BEGIN;
+             DELETE FROM entries;
+             {blobs}
+             INSERT INTO entries ({shared}) SELECT {shared} FROM backup.entries;
+             COMMIT;"
+        ))
+        .inspect_err(|_| {
+            let _ = conn.execute_batch("ROLLBACK");
+        })
+        .with_context(|| format!("failed to restore {name}"))
+    })();
+    conn.execute_batch("DETACH DATABASE backup")?;
+    result
+}
@@ -1,323 +1,816 @@
 //! Clipboard listener and normalization.
 
-use anyhow::Result;
-use chrono::Utc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use tokio::sync::mpsc::Sender;
-use tokio::time::{sleep, Duration};
-use windows::Win32::Foundation::{HWND, HGLOBAL, CloseHandle};
-use windows::Win32::System::DataExchange::{
-    CloseClipboard, GetClipboardData, GetClipboardSequenceNumber, IsClipboardFormatAvailable, OpenClipboard,
-};
-use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
-use windows::Win32::System::Ole::{CF_UNICODETEXT, CF_DIB};
-use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
-use windows::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION};
-use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
 
+use crate::backend::{ClipboardBackend, FormatId, CF_DIB, CF_HDROP, CF_UNICODETEXT};
+use crate::ignore::IgnoreRules;
+use crate::files;
+use crate::image;
 use crate::model::{Entry, EntryKind};
+use crate::paste::{PasteFormat, PastePolicy};
+use crate::project;
+use crate::queue::{CaptureQueue, QueueStats};
+use crate::snapshot::{self, Format};
+
+/// Registered format clipctl adds to its pastes, holding the pasted entry's
+/// id as a little-endian u64.
+pub const PASTED_ENTRY_FORMAT: &str = "RustyClipboardEntry";
+
+/// Registered format that keeps the clipboard out of Windows clipboard history.
+const EXCLUDE_FROM_HISTORY_FORMAT: &str = "ExcludeClipboardContentFromMonitorProcessing";
+
+/// Registered formats password managers add to mark a copy as private; clipd
+/// does not read a clipboard holding one of them.
+const PRIVATE_FORMATS: &[&str] = &[EXCLUDE_FROM_HISTORY_FORMAT, "Clipboard Viewer Ignore"];
+
+/// Registered format whose DWORD is 0 when the copy must stay out of
+/// clipboard history.
+const INCLUDE_IN_HISTORY_FORMAT: &str = "CanIncludeInClipboardHistory";
+
+/// Longest `PauseCapture` pause, so a forgotten one still ends.
+const MAX_PAUSE_MINUTES: u32 = 24 * 60;
+
+/// Without a clipboard change or a switch to another app for this long, the
+/// watcher counts the user as idle and starts backing off.
+const IDLE_AFTER: Duration = Duration::from_secs(30);
+
+/// How often the battery is checked between copies; every copy checks it too.
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+const RTF_FORMAT: &str = "Rich Text Format";
+const HTML_FORMAT: &str = "HTML Format";
+
+/// Registered format whose DWORD tells Explorer whether pasted files are
+/// copied (1) or moved (2).
+const DROP_EFFECT_FORMAT: &str = "Preferred DropEffect";
+
+/// The apps most recently in front, for telling where a paste will land.
+#[derive(Debug, Default)]
+struct Foreground {
+    /// Foreground window when last checked, as its handle value.
+    window: usize,
+    current: Option<String>,
+    previous: Option<String>,
+}
 
-/// Watches the Windows clipboard for changes and forwards normalized entries.
-#[derive(Debug, Clone)]
-pub struct ClipboardWatcher;
+/// Which stored content the clipboard holds, for marking the entry pasting
+/// would change nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OnClipboard {
+    /// Something not stored, or not read, such as a copy made while paused.
+    #[default]
+    Unknown,
+    /// Entry `id`, pasted by clipctl or restored by clipd.
+    Entry(u64),
+    /// A copy with this content hash, stored or deduplicated into an entry.
+    Content(String),
+}
 
-impl ClipboardWatcher {
-    pub fn new() -> Self {
-        Self
+/// How often the watcher checks the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Polling {
+    /// Between checks while the user is active (`capture.poll_interval_ms`).
+    pub interval: Duration,
+    /// Longest gap idle checks back off to, and the gap while the workstation
+    /// is locked (`capture.idle_poll_interval_ms`).
+    pub idle_interval: Duration,
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(250),
+            idle_interval: Duration::from_secs(2),
+        }
     }
+}
 
-    /// Start listening to clipboard changes using polling.
-    /// This uses GetClipboardSequenceNumber to detect changes efficiently.
-    pub async fn run(self, tx: Sender<Entry>) -> Result<()> {
-        tracing::info!("starting clipboard watcher with polling strategy");
-        
-        let mut last_sequence: u32 = 0;
-        let mut last_hash: Option<String> = None;
-        
-        loop {
-            // Check if clipboard has changed
-            let current_sequence = unsafe { GetClipboardSequenceNumber() };
-            
-            if current_sequence != last_sequence && current_sequence != 0 {
-                last_sequence = current_sequence;
-                tracing::debug!("clipboard sequence changed to {}", current_sequence);
-                
-                // Try to read in priority order: image, RTF, then text
-                let entry_opt = read_clipboard_image()
-                    .ok()
-                    .flatten()
-                    .or_else(|| read_clipboard_rtf().ok().flatten())
-                    .or_else(|| {
-                        read_clipboard_text().ok().flatten().map(|(text, _)| Entry {
-                            id: None,
-                            created_at: Utc::now(),
-                            kind: EntryKind::Text,
-                            text: Some(text.clone()),
-                            data: None,
-                            bytes_len: text.len(),
-                            hash: hash_data(text.as_bytes()),
-                            source_process: None,
-                            tags: Vec::new(),
-                        })
-                    });
-                
-                if let Some(mut entry) = entry_opt {
-                    // Skip if content hash is the same
-                    if Some(&entry.hash) != last_hash.as_ref() {
-                        last_hash = Some(entry.hash.clone());
-                        
-                        // Try to get the source process
-                        entry.source_process = get_foreground_process_name();
-                        
-                        let bytes = entry.bytes_len;
-                        let kind = entry.kind.clone();
-                        let process = entry.source_process.clone();
-                        if let Err(e) = tx.send(entry).await {
-                            tracing::error!("failed to send clipboard entry: {}", e);
-                        } else {
-                            tracing::info!(
-                                "captured clipboard {:?} ({} bytes) from {:?}", 
-                                kind, bytes, process
-                            );
-                        }
-                    }
-                } else {
-                    tracing::debug!("clipboard contains no supported content");
-                }
-            }
-            
-            // Poll every 250ms - this is efficient and responsive
-            sleep(Duration::from_millis(250)).await;
+impl Polling {
+    /// Gap before the next check, given the last one: the interval while
+    /// active, then doubling up to the idle interval.
+    fn next(&self, previous: Duration, idle: bool) -> Duration {
+        if idle {
+            (previous * 2).clamp(self.interval, self.idle_interval)
+        } else {
+            self.interval
         }
     }
 }
 
-/// Read text from the Windows clipboard
-fn read_clipboard_text() -> Result<Option<(String, Vec<u8>)>> {
-    unsafe {
-        if let Err(_) = OpenClipboard(HWND::default()) {
-            // Clipboard might be locked by another process, this is normal
-            return Ok(None);
+/// What changes while a laptop runs on a low battery (`[battery]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryPolicy {
+    /// Charge, in percent, at or below which clipd saves power; 0 never does.
+    pub low_percent: u8,
+    /// Leave images out of the history while saving power.
+    pub skip_images: bool,
+}
+
+impl Default for BatteryPolicy {
+    fn default() -> Self {
+        Self {
+            low_percent: 20,
+            skip_images: false,
         }
+    }
+}
 
-        let result = (|| -> Result<Option<(String, Vec<u8>)>> {
-            let handle = match GetClipboardData(CF_UNICODETEXT.0 as u32) {
-                Ok(h) => h,
-                Err(_) => return Ok(None),
-            };
-            
-            if handle.is_invalid() {
-                return Ok(None);
-            }
+/// What the watcher saw on the clipboard.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Capture {
+    /// A new copy to store.
+    Entry(Box<Entry>),
+    /// Entry `id` pasted again by clipctl, to move to the top rather than store twice.
+    Pasted(u64),
+}
 
-            // Cast HANDLE to HGLOBAL for GlobalLock
-            let hglobal = HGLOBAL(handle.0);
-            let ptr = GlobalLock(hglobal) as *const u16;
-            if ptr.is_null() {
-                return Ok(None);
+impl Capture {
+    /// Rough memory the capture holds: its text and data.
+    pub(crate) fn memory_size(&self) -> usize {
+        match self {
+            Self::Entry(entry) => {
+                let text = entry.text.as_ref().map_or(0, String::len);
+                let extracted = entry.text_extracted.as_ref().map_or(0, String::len);
+                text + extracted + entry.data.as_ref().map_or(0, Vec::len)
             }
+            Self::Pasted(_) => 0,
+        }
+    }
+}
 
-            // Find the null terminator
-            let mut len = 0;
-            while *ptr.offset(len) != 0 {
-                len += 1;
-            }
+/// Watches the Windows clipboard for changes and forwards normalized entries.
+#[derive(Debug, Clone)]
+pub struct ClipboardWatcher {
+    /// The clipboard itself; Windows, or a mock in tests.
+    backend: Arc<dyn ClipboardBackend>,
+    /// Shared with the IPC server so `ReloadConfig` takes effect immediately.
+    allowed_kinds: Arc<RwLock<Vec<EntryKind>>>,
+    /// Whether captures get a `project:<name>` tag from the foreground window.
+    project_tags: Arc<AtomicBool>,
+    /// Executables trusted while quarantine is on; `None` when it is off.
+    trusted_processes: Arc<RwLock<Option<Vec<String>>>>,
+    /// `capture.group_window_secs`, read when each capture is stored.
+    group_window_secs: Arc<AtomicU64>,
+    /// Clipboard sequence number left by the last restored snapshot, which is
+    /// not captured again.
+    restored_sequence: Arc<AtomicU32>,
+    /// Updated on every poll, so pastes can be judged by their target app.
+    foreground: Arc<RwLock<Foreground>>,
+    /// `[paste]` rules, replaced on `ReloadConfig`.
+    paste_policy: Arc<RwLock<PastePolicy>>,
+    polling: Arc<RwLock<Polling>>,
+    battery: Arc<RwLock<BatteryPolicy>>,
+    /// Formats bigger than this are not read (`capture.max_entry_mb`).
+    max_entry_bytes: Arc<AtomicUsize>,
+    /// `[ignore]` rules, replaced on `ReloadConfig`.
+    ignore: Arc<RwLock<IgnoreRules>>,
+    /// Nothing is captured until then, after `PauseCapture`.
+    paused_until: Arc<RwLock<Option<DateTime<Utc>>>>,
+    on_clipboard: Arc<RwLock<OnClipboard>>,
+    /// Where captures wait for the database writer.
+    queue: CaptureQueue,
+}
 
-            let slice = std::slice::from_raw_parts(ptr, len as usize);
-            let text = String::from_utf16_lossy(slice);
-            let bytes = text.as_bytes().to_vec();
+impl ClipboardWatcher {
+    pub fn new(
+        backend: Arc<dyn ClipboardBackend>,
+        allowed_kinds: Vec<EntryKind>,
+        project_tags: bool,
+        trusted_processes: Option<Vec<String>>,
+        group_window_secs: u64,
+        paste_policy: PastePolicy,
+        queue: CaptureQueue,
+    ) -> Self {
+        Self {
+            backend,
+            allowed_kinds: Arc::new(RwLock::new(allowed_kinds)),
+            project_tags: Arc::new(AtomicBool::new(project_tags)),
+            trusted_processes: Arc::new(RwLock::new(trusted_processes)),
+            group_window_secs: Arc::new(AtomicU64::new(group_window_secs)),
+            restored_sequence: Arc::new(AtomicU32::new(0)),
+            foreground: Arc::new(RwLock::new(Foreground::default())),
+            paste_policy: Arc::new(RwLock::new(paste_policy)),
+            polling: Arc::new(RwLock::new(Polling::default())),
+            battery: Arc::new(RwLock::new(BatteryPolicy::default())),
+            max_entry_bytes: Arc::new(AtomicUsize::new(usize::MAX)),
+            ignore: Arc::new(RwLock::new(IgnoreRules::default())),
+            paused_until: Arc::new(RwLock::new(None)),
+            on_clipboard: Arc::new(RwLock::new(OnClipboard::Unknown)),
+            queue,
+        }
+    }
 
-            let _ = GlobalUnlock(hglobal);
+    /// The queue captures are pushed to, for the writer to drain.
+    pub fn queue(&self) -> &CaptureQueue {
+        &self.queue
+    }
 
-            Ok(Some((text, bytes)))
-        })();
+    pub fn queue_stats(&self) -> QueueStats {
+        self.queue.stats()
+    }
 
-        let _ = CloseClipboard();
+    pub fn set_paste_policy(&self, policy: PastePolicy) {
+        *self.paste_policy.write() = policy;
+    }
 
-        result
+    pub fn set_polling(&self, polling: Polling) {
+        let previous = std::mem::replace(&mut *self.polling.write(), polling);
+        if previous != polling {
+            tracing::info!(?polling, "clipboard polling changed");
+        }
     }
-}
 
-/// Read image from the Windows clipboard (CF_DIB format)
-fn read_clipboard_image() -> Result<Option<Entry>> {
-    unsafe {
-        // Check if image format is available
-        if IsClipboardFormatAvailable(CF_DIB.0 as u32).is_err() {
-            return Ok(None);
+    pub fn set_battery_policy(&self, policy: BatteryPolicy) {
+        let previous = std::mem::replace(&mut *self.battery.write(), policy);
+        if previous != policy {
+            tracing::info!(?policy, "battery policy changed");
         }
-        
-        if let Err(_) = OpenClipboard(HWND::default()) {
-            return Ok(None);
+    }
+
+    pub fn set_max_entry_bytes(&self, bytes: usize) {
+        if self.max_entry_bytes.swap(bytes, Ordering::Relaxed) != bytes {
+            tracing::info!(bytes, "largest capture changed");
         }
+    }
 
-        let result = (|| -> Result<Option<Entry>> {
-            let handle = match GetClipboardData(CF_DIB.0 as u32) {
-                Ok(h) => h,
-                Err(_) => return Ok(None),
-            };
-            
-            if handle.is_invalid() {
-                return Ok(None);
-            }
+    pub fn set_ignore_rules(&self, rules: IgnoreRules) {
+        *self.ignore.write() = rules;
+    }
 
-            let hglobal = HGLOBAL(handle.0);
-            let ptr = GlobalLock(hglobal) as *const u8;
-            if ptr.is_null() {
-                return Ok(None);
-            }
+    /// Pause capture for `minutes`, or resume it when 0; `None` resumes a
+    /// paused capture and pauses for `ignore.pause_minutes` otherwise.
+    /// Returns when capture resumes by itself, while it is paused.
+    pub fn pause(&self, minutes: Option<u32>) -> Option<DateTime<Utc>> {
+        let mut paused_until = self.paused_until.write();
+        let paused = paused_until.is_some_and(|until| until > Utc::now());
+        let minutes = match minutes {
+            Some(minutes) => minutes,
+            None if paused => 0,
+            None => self.ignore.read().pause_minutes,
+        };
+        *paused_until = (minutes > 0)
+            .then(|| Utc::now() + chrono::Duration::minutes(minutes.min(MAX_PAUSE_MINUTES).into()));
+        match *paused_until {
+            Some(until) => tracing::info!(%until, "capture paused"),
+            None => tracing::info!("capture resumed"),
+        }
+        *paused_until
+    }
 
-            let size = GlobalSize(hglobal);
-            let data = std::slice::from_raw_parts(ptr, size).to_vec();
+    /// When capture resumes, while it is paused.
+    pub fn paused_until(&self) -> Option<DateTime<Utc>> {
+        self.paused_until.read().filter(|until| *until > Utc::now())
+    }
+
+    /// Whether the app that made the copy marked it private, as password
+    /// managers do for Windows clipboard history.
+    fn marked_private(&self) -> bool {
+        PRIVATE_FORMATS
+            .iter()
+            .any(|name| self.backend.size(FormatId::Registered(name)).is_some())
+            || self
+                .backend
+                .read(FormatId::Registered(INCLUDE_IN_HISTORY_FORMAT))
+                .is_some_and(|data| data.get(..4) == Some(&[0; 4]))
+    }
 
-            let _ = GlobalUnlock(hglobal);
+    /// Data of `format`, unless it is bigger than `capture.max_entry_mb`;
+    /// checked before reading, so a huge copy never reaches memory.
+    fn read(&self, format: FormatId<'_>) -> Option<Vec<u8>> {
+        let limit = self.max_entry_bytes.load(Ordering::Relaxed);
+        if let Some(size) = self.backend.size(format).filter(|&size| size > limit) {
+            tracing::warn!(?format, size, limit, "clipboard data is over capture.max_entry_mb; not captured");
+            self.queue.reject();
+            return None;
+        }
+        self.backend.read(format)
+    }
 
-            let hash = hash_data(&data);
-            let bytes_len = data.len();
+    /// Whether to save power: on battery at or below `battery.low_percent`.
+    fn low_battery(&self) -> bool {
+        let low_percent = self.battery.read().low_percent;
+        low_percent > 0 && self.backend.battery_percent().is_some_and(|percent| percent <= low_percent)
+    }
 
-            Ok(Some(Entry {
-                id: None,
-                created_at: Utc::now(),
-                kind: EntryKind::Image,
-                text: Some(format!("<image {} bytes>", bytes_len)),
-                data: Some(data),
-                bytes_len,
-                hash,
-                source_process: None,
-                tags: Vec::new(),
-            }))
-        })();
+    /// What the clipboard held when the watcher last looked.
+    pub fn on_clipboard(&self) -> OnClipboard {
+        self.on_clipboard.read().clone()
+    }
 
-        let _ = CloseClipboard();
+    /// Note that clipd put entry `id` back on the clipboard itself.
+    pub fn set_on_clipboard(&self, id: u64) {
+        *self.on_clipboard.write() = OnClipboard::Entry(id);
+    }
 
-        result
+    /// The app a paste from clipctl lands in: the one that was in front
+    /// before the terminal clipctl runs in.
+    pub fn paste_target(&self) -> Option<String> {
+        self.foreground.read().previous.clone()
     }
-}
 
-/// Read RTF from the Windows clipboard
-fn read_clipboard_rtf() -> Result<Option<Entry>> {
-    unsafe {
-        use windows::Win32::System::DataExchange::RegisterClipboardFormatW;
-        use windows::core::PCWSTR;
-        
-        // Register RTF format
-        let format_name: Vec<u16> = "Rich Text Format\0".encode_utf16().collect();
-        let rtf_format = RegisterClipboardFormatW(PCWSTR(format_name.as_ptr()));
-        
-        if rtf_format == 0 {
-            return Ok(None);
+    /// Why `entry` may not be pasted into `target`, if a `[paste]` rule forbids it.
+    pub fn blocked_paste(&self, entry: &Entry, target: &str) -> Option<String> {
+        self.paste_policy.read().blocked(entry, target)
+    }
+
+    /// Format `[paste]` rules choose for documents pasted into `target`.
+    pub fn paste_format(&self, target: Option<&str>) -> PasteFormat {
+        self.paste_policy.read().format(target)
+    }
+
+    /// Put an RTF or HTML entry on the clipboard in its own format, with its
+    /// text for apps that only take text. Returns false for other entries.
+    pub fn paste_document(&self, entry: &Entry, hide_from_history: bool) -> Result<bool> {
+        let name = match entry.kind {
+            EntryKind::Rtf => RTF_FORMAT,
+            EntryKind::Html => HTML_FORMAT,
+            _ => return Ok(false),
+        };
+        let Some(data) = entry.data.clone() else {
+            return Ok(false);
+        };
+        self.paste_with_text(entry, vec![Format { id: 0, name: Some(name.to_string()), data }], hide_from_history)
+    }
+
+    /// Put a files entry back on the clipboard as the copied files, to be
+    /// pasted again as copies, with their paths for apps that only take text.
+    /// Returns false for other entries.
+    pub fn paste_files(&self, entry: &Entry, hide_from_history: bool) -> Result<bool> {
+        if entry.kind != EntryKind::Files {
+            return Ok(false);
         }
-        
-        // Check if RTF format is available
-        if IsClipboardFormatAvailable(rtf_format).is_err() {
-            return Ok(None);
+        let Some(data) = entry.data.clone() else {
+            return Ok(false);
+        };
+        let formats = vec![
+            Format { id: CF_HDROP, name: None, data },
+            Format { id: 0, name: Some(DROP_EFFECT_FORMAT.to_string()), data: 1u32.to_le_bytes().to_vec() },
+        ];
+        self.paste_with_text(entry, formats, hide_from_history)
+    }
+
+    /// Put `formats` on the clipboard with the entry's text; returns true.
+    fn paste_with_text(&self, entry: &Entry, mut formats: Vec<Format>, hide_from_history: bool) -> Result<bool> {
+        let text: Vec<u8> = entry
+            .plain_text()
+            .unwrap_or_default()
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        formats.insert(0, Format { id: CF_UNICODETEXT, name: None, data: text });
+        if hide_from_history {
+            formats.push(Format {
+                id: 0,
+                name: Some(EXCLUDE_FROM_HISTORY_FORMAT.to_string()),
+                data: 0u32.to_le_bytes().to_vec(),
+            });
         }
-        
-        if let Err(_) = OpenClipboard(HWND::default()) {
-            return Ok(None);
+        self.restore(&formats)?;
+        Ok(true)
+    }
+
+    /// Note the foreground app if another window came to the front; true if
+    /// it did since the last poll.
+    fn track_foreground(&self) -> bool {
+        let window = self.backend.foreground_window();
+        if window == self.foreground.read().window {
+            return false;
         }
+        let process = self.backend.window_process(window);
+        let mut foreground = self.foreground.write();
+        foreground.window = window;
+        if process.is_some() && process != foreground.current {
+            foreground.previous = std::mem::replace(&mut foreground.current, process);
+        }
+        true
+    }
 
-        let result = (|| -> Result<Option<Entry>> {
-            let handle = match GetClipboardData(rtf_format) {
-                Ok(h) => h,
-                Err(_) => return Ok(None),
-            };
-            
-            if handle.is_invalid() {
-                return Ok(None);
-            }
+    /// Replace the clipboard with `formats`, such as a snapshot's, without
+    /// capturing them again.
+    pub fn restore(&self, formats: &[Format]) -> Result<()> {
+        self.backend.write(formats, &|sequence| {
+            self.restored_sequence.store(sequence, Ordering::Relaxed);
+        })
+    }
 
-            let hglobal = HGLOBAL(handle.0);
-            let ptr = GlobalLock(hglobal) as *const u8;
-            if ptr.is_null() {
-                return Ok(None);
-            }
+    /// Store every format on the clipboard whose data can be copied as one
+    /// snapshot entry.
+    pub fn snapshot(&self) -> Result<Entry> {
+        let formats = self.backend.read_all()?;
+        if formats.is_empty() {
+            bail!("the clipboard holds no formats that can be stored");
+        }
+        tracing::info!(
+            formats = ?formats.iter().map(Format::label).collect::<Vec<_>>(),
+            "snapshotting clipboard"
+        );
+
+        let text_extracted = snapshot::text(&formats);
+        let data = snapshot::encode(&formats);
+        Ok(Entry {
+            id: None,
+            created_at: Utc::now(),
+            kind: EntryKind::Snapshot,
+            text: None,
+            bytes_len: data.len(),
+            hash: hash_data(&data),
+            data: Some(data),
+            source_process: self.backend.owner_process(),
+            tags: Vec::new(),
+            image: None,
+            title: None,
+            session: None,
+            quarantined: false,
+            lang: None,
+            pinned: false,
+            text_extracted,
+            copy_group: None,
+            entities: Vec::new(),
+            use_count: 1,
+        })
+    }
 
-            let size = GlobalSize(hglobal);
-            let data = std::slice::from_raw_parts(ptr, size).to_vec();
+    /// Id of the history entry clipctl put on the clipboard, if it did.
+    fn read_pasted_entry(&self) -> Option<u64> {
+        let data = self.backend.read(FormatId::Registered(PASTED_ENTRY_FORMAT))?;
+        // Global memory may be rounded up past the eight bytes written.
+        let id: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        Some(u64::from_le_bytes(id))
+    }
 
-            let _ = GlobalUnlock(hglobal);
+    /// The clipboard's text as an entry.
+    fn read_text(&self) -> Option<Entry> {
+        let data = self.read(FormatId::Standard(CF_UNICODETEXT))?;
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        Some(text_entry(String::from_utf16_lossy(&units)))
+    }
 
-            let hash = hash_data(&data);
-            let bytes_len = data.len();
-            
-            // Try to convert to text for preview
-            let preview_text = String::from_utf8_lossy(&data).to_string();
-            let preview = if preview_text.len() > 100 {
-                format!("{} ...", &preview_text[..100])
-            } else {
-                preview_text
-            };
-
-            Ok(Some(Entry {
-                id: None,
-                created_at: Utc::now(),
-                kind: EntryKind::Rtf,
-                text: Some(format!("<rtf {} bytes> {}", bytes_len, preview)),
-                data: Some(data),
-                bytes_len,
-                hash,
-                source_process: None,
-                tags: Vec::new(),
-            }))
-        })();
-
-        let _ = CloseClipboard();
-
-        result
+    /// The clipboard's image (`CF_DIB`) as an entry.
+    fn read_image(&self) -> Option<Entry> {
+        let data = self.read(FormatId::Standard(CF_DIB))?;
+        let hash = hash_data(&data);
+        let bytes_len = data.len();
+        Some(Entry {
+            id: None,
+            created_at: Utc::now(),
+            kind: EntryKind::Image,
+            text: Some(format!("<image {} bytes>", bytes_len)),
+            image: image::dib_info(&data),
+            data: Some(data),
+            bytes_len,
+            hash,
+            source_process: None,
+            tags: Vec::new(),
+            title: None,
+            session: None,
+            quarantined: false,
+            lang: None,
+            pinned: false,
+            text_extracted: None,
+            copy_group: None,
+            entities: Vec::new(),
+            use_count: 1,
+        })
     }
-}
 
-/// Get the name of the foreground process
-fn get_foreground_process_name() -> Option<String> {
-    unsafe {
-        // Get the foreground window
-        let hwnd = GetForegroundWindow();
-        if hwnd.0.is_null() {
-            return None;
+    /// Files copied in Explorer (`CF_HDROP`) as an entry. The data is stored
+    /// re-encoded, so copying the same files again is a duplicate however the
+    /// app that copied them laid out the list.
+    fn read_files(&self) -> Option<Entry> {
+        let paths = files::parse_drop(&self.read(FormatId::Standard(CF_HDROP))?)?;
+        let data = files::encode_drop(&paths);
+        let text = paths.join("\n");
+        Some(Entry {
+            id: None,
+            created_at: Utc::now(),
+            kind: EntryKind::Files,
+            hash: hash_data(&data),
+            bytes_len: text.len(),
+            text: Some(text),
+            data: Some(data),
+            source_process: None,
+            tags: Vec::new(),
+            image: None,
+            title: None,
+            session: None,
+            quarantined: false,
+            lang: None,
+            pinned: false,
+            text_extracted: None,
+            copy_group: None,
+            entities: Vec::new(),
+            use_count: 1,
+        })
+    }
+
+    /// A document in the registered clipboard format `format_name`, such as
+    /// RTF, keeping the document and its plain text.
+    fn read_document(&self, format_name: &str, kind: EntryKind) -> Option<Entry> {
+        let data = self.read(FormatId::Registered(format_name))?;
+        let hash = hash_data(&data);
+        let bytes_len = data.len();
+        let text_extracted = kind.extract_text(&data);
+        Some(Entry {
+            id: None,
+            created_at: Utc::now(),
+            kind,
+            text: None,
+            data: Some(data),
+            bytes_len,
+            hash,
+            source_process: None,
+            tags: Vec::new(),
+            image: None,
+            title: None,
+            session: None,
+            quarantined: false,
+            lang: None,
+            pinned: false,
+            text_extracted,
+            copy_group: None,
+            entities: Vec::new(),
+            use_count: 1,
+        })
+    }
+
+    /// Longest gap between copies from one app that are linked as copied together.
+    pub fn group_window(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.group_window_secs.load(Ordering::Relaxed) as i64)
+    }
+
+    pub fn set_group_window(&self, secs: u64) {
+        if self.group_window_secs.swap(secs, Ordering::Relaxed) != secs {
+            tracing::info!(secs, "copy grouping window changed");
         }
+    }
 
-        // Get the process ID
-        let mut process_id: u32 = 0;
-        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
-        if process_id == 0 {
-            return None;
+    /// Quarantine captures from processes outside `trusted`, or stop when `None`.
+    pub fn set_quarantine(&self, trusted: Option<Vec<String>>) {
+        tracing::info!(?trusted, "quarantine policy changed");
+        *self.trusted_processes.write() = trusted;
+    }
+
+    /// Stop quarantining captures from `process`.
+    pub fn trust(&self, process: &str) {
+        if let Some(trusted) = self.trusted_processes.write().as_mut() {
+            if !trusted.iter().any(|name| name.eq_ignore_ascii_case(process)) {
+                trusted.push(process.to_string());
+            }
+        }
+    }
+
+    /// Whether a capture written by `process` goes to quarantine.
+    fn is_untrusted(&self, process: Option<&str>) -> bool {
+        match self.trusted_processes.read().as_ref() {
+            None => false,
+            Some(trusted) => !process.is_some_and(|process| {
+                trusted.iter().any(|name| name.eq_ignore_ascii_case(process))
+            }),
         }
+    }
 
-        // Open the process
-        let process_handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) {
-            Ok(handle) => handle,
-            Err(_) => return None,
-        };
+    pub fn set_project_tags(&self, enabled: bool) {
+        if self.project_tags.swap(enabled, Ordering::Relaxed) != enabled {
+            tracing::info!(enabled, "project tagging changed");
+        }
+    }
 
-        // Get the process name
-        use windows::core::PWSTR;
-        let mut buffer = vec![0u16; 260];
-        let mut size = buffer.len() as u32;
+    pub fn set_allowed_kinds(&self, allowed_kinds: Vec<EntryKind>) {
+        tracing::info!(?allowed_kinds, "capture filter changed");
+        *self.allowed_kinds.write() = allowed_kinds;
+    }
+
+    /// Start listening to clipboard changes using polling.
+    /// This uses GetClipboardSequenceNumber to detect changes efficiently,
+    /// polling less often while the user is idle or the battery is low and
+    /// pausing while the workstation is locked.
+    /// Returns once `shutdown` fires, closing the queue so the writer can finish.
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
+        tracing::info!("starting clipboard watcher with polling strategy");
         
-        let result = QueryFullProcessImageNameW(
-            process_handle, 
-            PROCESS_NAME_FORMAT(0), 
-            PWSTR(buffer.as_mut_ptr()), 
-            &mut size
-        );
-        let _: () = CloseHandle(process_handle).map(|_| ()).unwrap_or(());
+        let mut last_sequence: u32 = 0;
+        let mut last_hash: Option<String> = None;
+        let mut last_activity = Instant::now();
+        let mut delay = Duration::ZERO;
+        let mut was_locked = false;
+        let mut saving_power = self.low_battery();
+        let mut power_checked = Instant::now();
+        if saving_power {
+            tracing::info!("battery low; saving power");
+        }
+        
+        loop {
+            let locked = self.backend.workstation_locked();
+            if locked != was_locked {
+                was_locked = locked;
+                if locked {
+                    tracing::info!("workstation locked; capture paused");
+                } else {
+                    tracing::info!("workstation unlocked; capture resumed");
+                    last_activity = Instant::now();
+                }
+            }
+            if locked || self.paused_until().is_some() {
+                // The clipboard is not read while locked, so nothing typed
+                // into the lock screen is captured; following the sequence
+                // number keeps it from being captured after unlocking, too.
+                // A pause works the same way.
+                let sequence = self.backend.sequence();
+                if sequence != last_sequence {
+                    *self.on_clipboard.write() = OnClipboard::Unknown;
+                }
+                last_sequence = sequence;
+                delay = self.polling.read().idle_interval;
+                tokio::select! {
+                    _ = sleep(delay) => continue,
+                    _ = shutdown.changed() => break,
+                }
+            }
 
-        if result.is_err() {
-            return None;
+            if self.track_foreground() {
+                last_activity = Instant::now();
+            }
+
+            // Check if clipboard has changed
+            let current_sequence = self.backend.sequence();
+            let changed = current_sequence != last_sequence && current_sequence != 0;
+
+            if changed || power_checked.elapsed() >= POWER_CHECK_INTERVAL {
+                power_checked = Instant::now();
+                if self.low_battery() != saving_power {
+                    saving_power = !saving_power;
+                    if saving_power {
+                        tracing::info!("battery low; saving power");
+                    } else {
+                        tracing::info!("battery no longer low; polling normally");
+                    }
+                }
+            }
+            
+            if changed {
+                last_sequence = current_sequence;
+                last_activity = Instant::now();
+                tracing::debug!("clipboard sequence changed to {}", current_sequence);
+                if current_sequence == self.restored_sequence.load(Ordering::Relaxed) {
+                    tracing::debug!("skipping restored snapshot");
+                    continue;
+                }
+                *self.on_clipboard.write() = OnClipboard::Unknown;
+                if let Some(id) = self.read_pasted_entry() {
+                    tracing::debug!(id, "clipboard holds an entry pasted by clipctl");
+                    *self.on_clipboard.write() = OnClipboard::Entry(id);
+                    self.queue.push(Capture::Pasted(id)).await;
+                    continue;
+                }
+                if self.marked_private() {
+                    tracing::info!("clipboard is marked private; not captured");
+                    continue;
+                }
+                // A background process can write the clipboard while something else
+                // is in front, so the clipboard's owner counts too.
+                let window = self.backend.foreground_window();
+                let source = self.backend.window_process(window);
+                let writer = self.backend.owner_process().or(source.clone());
+                let ignored = self.ignore.read().ignored_process(writer.iter().chain(&source).map(String::as_str));
+                if let Some(process) = ignored {
+                    tracing::info!(process, "copy from a process in ignore.processes; not captured");
+                    continue;
+                }
+                
+                let mut allowed = self.allowed_kinds.read().clone();
+                if saving_power && self.battery.read().skip_images {
+                    allowed.retain(|kind| *kind != EntryKind::Image);
+                }
+
+                // Try to read in priority order: image, files, RTF, HTML, then text.
+                // Disallowed formats are not even read so the next one can match.
+                let entry_opt = allowed
+                    .contains(&EntryKind::Image)
+                    .then(|| self.read_image())
+                    .flatten()
+                    .or_else(|| allowed.contains(&EntryKind::Files).then(|| self.read_files()).flatten())
+                    .or_else(|| {
+                        allowed
+                            .contains(&EntryKind::Rtf)
+                            .then(|| self.read_document(RTF_FORMAT, EntryKind::Rtf))
+                            .flatten()
+                    })
+                    .or_else(|| {
+                        allowed
+                            .contains(&EntryKind::Html)
+                            .then(|| self.read_document(HTML_FORMAT, EntryKind::Html))
+                            .flatten()
+                    })
+                    .or_else(|| self.read_text())
+                    .filter(|entry| allowed.contains(&entry.kind));
+                
+                if let Some(mut entry) = entry_opt {
+                    *self.on_clipboard.write() = OnClipboard::Content(entry.hash.clone());
+                    // Skip if content hash is the same
+                    if Some(&entry.hash) == last_hash.as_ref() {
+                        continue;
+                    }
+                    last_hash = Some(entry.hash.clone());
+                    let ignored = self.ignore.read().ignored_content(&entry);
+                    if let Some(reason) = ignored {
+                        tracing::info!(reason, "copy matches an [ignore] rule; not captured");
+                    } else {
+                        entry.source_process = source;
+                        // Quarantine goes by the clipboard's owner.
+                        if self.is_untrusted(writer.as_deref()) {
+                            tracing::warn!(process = ?writer, "quarantining capture from untrusted process");
+                            entry.quarantined = true;
+                            entry.source_process = writer;
+                        }
+                        if self.project_tags.load(Ordering::Relaxed) {
+                            let title = self.backend.window_title(window).unwrap_or_default();
+                            if let Some(name) = project::detect(entry.source_process.as_deref(), &title) {
+                                entry.tags.push(format!("project:{name}"));
+                            }
+                        }
+                        if let Some(info) = entry.image.as_mut() {
+                            if let Some((monitor, dpi)) = self.backend.window_monitor(window) {
+                                info.monitor = Some(monitor);
+                                info.dpi = info.dpi.or(dpi);
+                            }
+                        }
+                        
+                        let bytes = entry.bytes_len;
+                        let kind = entry.kind.clone();
+                        let process = entry.source_process.clone();
+                        self.queue.push(Capture::Entry(Box::new(entry))).await;
+                        tracing::info!(
+                            "captured clipboard {:?} ({} bytes) from {:?}", 
+                            kind, bytes, process
+                        );
+                    }
+                } else {
+                    tracing::debug!("clipboard contains no supported content");
+                }
+            }
+            
+            let idle = last_activity.elapsed() >= IDLE_AFTER;
+            let polling = *self.polling.read();
+            let next = if saving_power { polling.idle_interval } else { polling.next(delay, idle) };
+            if next != delay {
+                tracing::debug!(?next, idle, "clipboard polling interval changed");
+                delay = next;
+            }
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = shutdown.changed() => break,
+            }
         }
 
-        // Convert the path to a string and extract just the filename
-        let path = String::from_utf16_lossy(&buffer[..size as usize]);
-        let filename = path.rsplit('\\').next().unwrap_or(&path);
-        Some(filename.to_string())
+        self.queue.close();
+        tracing::info!("clipboard watcher stopped");
+        Ok(())
     }
 }
 
 /// Hash data using SHA256 for deduplication
+/// A text entry for `text`, as if it had just been copied.
+pub(crate) fn text_entry(text: String) -> Entry {
+    Entry {
+        id: None,
+        created_at: Utc::now(),
+        kind: EntryKind::Text,
+        bytes_len: text.len(),
+        hash: hash_data(text.as_bytes()),
+        text: Some(text),
+        data: None,
+        source_process: None,
+        tags: Vec::new(),
+        image: None,
+        title: None,
+        session: None,
+        quarantined: false,
+        lang: None,
+        pinned: false,
+        text_extracted: None,
+        copy_group: None,
+        entities: Vec::new(),
+        use_count: 1,
+    }
+}
+
 fn hash_data(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
     let result = hasher.finalize();
     format!("{:x}", result)
 }
-
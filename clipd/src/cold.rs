@@ -0,0 +1,152 @@
+//! Cold storage: with `general.archive` on, entries retention would delete
+//! move into an archive database next to the history instead.
+//!
+//! The archive is attached to the history's connection as `archive`, so
+//! moving entries is one transaction and searches run the same SQL against
+//! `archive.entries`. Its rows keep the searchable columns as they are and
+//! hold image, document, and snapshot data deflate-compressed.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, Connection};
+
+/// Column list matching `Database::entry_from_row`, over `archive.entries`.
+pub(crate) const ENTRY_COLUMNS: &str = "id, created_at, kind, text, inflate(data), \
+     bytes_len, hash, source_process, tags, \
+     image_width, image_height, image_dpi, image_monitor, title, session, quarantined, lang, pinned, text_extracted, copy_group, \
+     entities, 1";
+
+/// The columns searches filter and sort on, the content, and when the entry
+/// was archived. A hash archived twice keeps the later copy.
+const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS archive.entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        archived_at TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        text TEXT,
+        data BLOB,
+        bytes_len INTEGER NOT NULL,
+        hash TEXT NOT NULL UNIQUE,
+        source_process TEXT,
+        tags TEXT,
+        image_width INTEGER,
+        image_height INTEGER,
+        image_dpi INTEGER,
+        image_monitor TEXT,
+        title TEXT,
+        session INTEGER,
+        quarantined INTEGER,
+        search_text TEXT,
+        lang TEXT,
+        pinned INTEGER,
+        pin_order INTEGER,
+        text_extracted TEXT,
+        copy_group INTEGER,
+        last_used_at TEXT,
+        entities TEXT
+    );
+
+    CREATE INDEX IF NOT EXISTS archive.idx_created_at ON entries(created_at DESC);
+"#;
+
+/// The archive of the history at `db_path`: `history.db` keeps its archive
+/// in `history.archive.db`.
+pub(crate) fn path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("archive.db")
+}
+
+/// Attach the archive of the history at `db_path` unless it already is,
+/// creating it if `create`. Returns whether it is attached.
+pub(crate) fn attach(conn: &Connection, db_path: &Path, create: bool) -> Result<bool> {
+    let attached: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_database_list WHERE name = 'archive')",
+        [],
+        |row| row.get(0),
+    )?;
+    if attached {
+        return Ok(true);
+    }
+    let path = path(db_path);
+    if !create && !path.exists() {
+        return Ok(false);
+    }
+    conn.execute("ATTACH DATABASE ?1 AS archive", params![path.to_string_lossy()])
+        .with_context(|| format!("failed to open the archive at {}", path.display()))?;
+    conn.execute_batch(SCHEMA).context("failed to create the archive table")?;
+    // Archives from before entities were found lack the column.
+    let has_entities: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('entries', 'archive') WHERE name = 'entities')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_entities {
+        conn.execute("ALTER TABLE archive.entries ADD COLUMN entities TEXT", [])
+            .context("failed to add column entities to the archive")?;
+    }
+    tracing::info!(path = %path.display(), "attached archive");
+    Ok(true)
+}
+
+/// Copy the entries listed in `temp.retiring` into the archive, returning
+/// how many were copied. The caller deletes them from the history.
+pub(crate) fn store(conn: &Connection) -> Result<usize> {
+    let stored = conn.execute(
+        r#"
+        INSERT OR REPLACE INTO archive.entries (
+            archived_at, created_at, kind, text, data, bytes_len, hash, source_process, tags,
+            image_width, image_height, image_dpi, image_monitor, title, session, quarantined,
+            search_text, lang, pinned, pin_order, text_extracted, copy_group, last_used_at, entities)
+        SELECT ?1, created_at, kind, text,
+               deflate(COALESCE(data, (SELECT blobs.data FROM main.blobs WHERE blobs.hash = entries.blob_hash))),
+               bytes_len, hash, source_process, tags,
+               image_width, image_height, image_dpi, image_monitor, title, session, quarantined,
+               search_text, lang, 0, NULL, text_extracted, copy_group, last_used_at, entities
+        FROM main.entries WHERE id IN (SELECT id FROM temp.retiring)
+        "#,
+        params![Utc::now().to_rfc3339()],
+    )?;
+    Ok(stored)
+}
+
+/// Register `deflate(x)` and `inflate(x)`, which the archive stores and
+/// reads its data with; NULL stays NULL.
+pub(crate) fn register_functions(conn: &Connection) -> Result<()> {
+    let codecs: [(&str, Codec); 2] = [("deflate", deflate), ("inflate", inflate)];
+    for (name, function) in codecs {
+        conn.create_scalar_function(
+            name,
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            move |ctx| {
+                let Some(data) = ctx.get::<Option<Vec<u8>>>(0)? else {
+                    return Ok(None);
+                };
+                function(&data).map(Some).map_err(|err| rusqlite::Error::UserFunctionError(err.into()))
+            },
+        )
+        .with_context(|| format!("failed to register SQL function {name}"))?;
+    }
+    Ok(())
+}
+
+type Codec = fn(&[u8]) -> std::io::Result<Vec<u8>>;
+
+fn deflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn inflate(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    DeflateDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
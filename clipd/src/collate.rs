@@ -0,0 +1,42 @@
+//! Locale-aware alphabetical order for `sort:title` and `sort:tag`, so that
+//! `Ä` sorts with `A` in German and `Å` after `Z` in Swedish instead of by
+//! code point.
+
+use std::panic::AssertUnwindSafe;
+
+use anyhow::{Context, Result};
+use icu_collator::{Collator, CollatorOptions};
+use icu_provider::DataLocale;
+use rusqlite::Connection;
+
+/// Name of the SQL collation, as in `ORDER BY title COLLATE locale`.
+pub const COLLATION: &str = "locale";
+
+/// A collator for `locale` (`general.sort_locale`), the system locale when
+/// it is empty, or the root order when neither has collation data.
+fn collator(locale: &str) -> Collator {
+    let name = match locale {
+        "" => sys_locale::get_locale().unwrap_or_default(),
+        name => name.to_string(),
+    };
+    let data_locale = name.parse::<DataLocale>().unwrap_or_else(|_| {
+        tracing::warn!(locale = %name, "ignoring unknown sort locale; using the root order");
+        DataLocale::default()
+    });
+    Collator::try_new(&data_locale, CollatorOptions::new())
+        .or_else(|_| Collator::try_new(&DataLocale::default(), CollatorOptions::new()))
+        .expect("root collation data is compiled in")
+}
+
+/// Whether `locale` names a locale, as `general.sort_locale` must.
+pub(crate) fn is_known(locale: &str) -> bool {
+    locale.parse::<DataLocale>().is_ok()
+}
+
+/// Register [`COLLATION`] on `conn`, ordering like `locale`; replaces the
+/// one registered before.
+pub(crate) fn register(conn: &Connection, locale: &str) -> Result<()> {
+    let collator = AssertUnwindSafe(collator(locale));
+    conn.create_collation(COLLATION, move |a, b| collator.compare(a, b))
+        .context("failed to register the sort collation")
+}
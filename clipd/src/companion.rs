@@ -0,0 +1,288 @@
+//! The companion endpoint: a phone app on the same network sends text into
+//! the history and fetches the newest entries. Off unless
+//! `companion.enabled` is set.
+//!
+//! An app pairs by taking the address and key `clipctl companion` prints.
+//! The key is 32 random bytes, kept in `keys.json` wrapped by DPAPI like the
+//! other keys. On the TCP connection every frame is a u32 LE length, then a
+//! 12-byte nonce and a JSON message sealed with AES-256-GCM under the key,
+//! so nobody on the network can read the history or write to it without
+//! pairing. Requests and responses are sealed with different associated
+//! data, so one cannot pass for the other; a request more than
+//! [`MAX_CLOCK_SKEW`] off clipd's clock, or with a nonce seen before, is
+//! refused. A frame that does not open with the key ends the connection
+//! unanswered.
+//!
+//! Requests are `{"sent_at": "2026-10-16T09:00:00Z", "kind": "push", "text": "..."}`
+//! or `{"sent_at": ..., "kind": "latest", "limit": 10}`; responses are
+//! `{"entries": [{"id": 7, "text": "...", "created_at": "..."}]}`, with an
+//! `error` instead when the request failed.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clipcore::MAX_REQUEST_LEN;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::clipboard::text_entry;
+use crate::ipc::Server;
+use crate::keys;
+
+/// Associated data requests are sealed with.
+pub const REQUEST_AAD: &[u8] = b"clipmgr-companion-v1 request";
+/// Associated data responses are sealed with.
+pub const RESPONSE_AAD: &[u8] = b"clipmgr-companion-v1 response";
+pub const NONCE_LEN: usize = 12;
+/// How far a request's `sent_at` may be from clipd's clock.
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(120);
+/// Most entries one `latest` request gets.
+const MAX_LATEST: usize = 50;
+/// Source process pushed entries are recorded with.
+const SOURCE: &str = "companion";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompanionRequest {
+    /// When the app sent the request, by its own clock.
+    pub sent_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: CompanionRequestKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompanionRequestKind {
+    /// Add `text` to the history, as if it had been copied on the PC.
+    Push { text: String },
+    /// The newest entries with text, newest first.
+    Latest {
+        #[serde(default = "default_limit")]
+        limit: usize,
+    },
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CompanionResponse {
+    pub entries: Vec<CompanionEntry>,
+    /// Why the request failed; `entries` is empty when it is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionEntry {
+    pub id: u64,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Seal `message` under `key` with `nonce` and `aad`, as the body of a frame.
+pub fn seal(key: &[u8; 32], aad: &[u8], nonce: [u8; NONCE_LEN], message: &[u8]) -> Result<Vec<u8>> {
+    let ciphertext = cipher(key)
+        .encrypt(&Nonce::from(nonce), Payload { msg: message, aad })
+        .map_err(|_| anyhow!("failed to seal companion message"))?;
+    let mut body = nonce.to_vec();
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// The nonce and message of a frame body from [`seal`]; fails unless it was
+/// sealed under `key` with `aad`.
+pub fn open(key: &[u8; 32], aad: &[u8], body: &[u8]) -> Result<([u8; NONCE_LEN], Vec<u8>)> {
+    if body.len() < NONCE_LEN {
+        bail!("companion frame is truncated");
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at NONCE_LEN bytes");
+    let message = cipher(key)
+        .decrypt(&Nonce::from(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| anyhow!("companion frame is not sealed with the pairing key"))?;
+    Ok((nonce, message))
+}
+
+fn cipher(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new_from_slice(key).expect("companion keys are 32 bytes")
+}
+
+/// Read one frame body; `None` once the other end hangs up.
+pub async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Option<Vec<u8>>> {
+    let len = match stream.read_u32_le().await {
+        Ok(len) => len,
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if len > MAX_REQUEST_LEN {
+        bail!("companion frame of {len} bytes is over the {MAX_REQUEST_LEN}-byte limit");
+    }
+    let mut body = vec![0; len as usize];
+    stream.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+pub async fn write_frame<S: AsyncWrite + Unpin>(stream: &mut S, body: &[u8]) -> Result<()> {
+    stream.write_u32_le(body.len() as u32).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Nonces of recent requests and when they came, so none is answered twice.
+type Seen = Mutex<HashMap<[u8; NONCE_LEN], Instant>>;
+
+/// The companion endpoint, answering apps paired with a server's key.
+pub struct Companion {
+    server: Server,
+    listener: TcpListener,
+    seen: Arc<Seen>,
+}
+
+impl Companion {
+    pub async fn bind(server: Server, address: SocketAddr) -> Result<Self> {
+        let listener = TcpListener::bind(address)
+            .await
+            .with_context(|| format!("failed to listen for companion apps on {address}"))?;
+        Ok(Self {
+            server,
+            listener,
+            seen: Arc::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Answer paired apps until `shutdown` fires.
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
+        let address = self.local_addr()?;
+        self.server.set_companion_address(Some(address));
+        tracing::info!(%address, "companion endpoint listening");
+
+        let mut clients = JoinSet::new();
+        while !*shutdown.borrow() {
+            let (stream, peer) = tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        tracing::warn!(%err, "failed to accept companion connection");
+                        continue;
+                    }
+                },
+                _ = shutdown.changed() => break,
+            };
+            let server = self.server.clone();
+            let seen = Arc::clone(&self.seen);
+            clients.spawn(async move {
+                if let Err(err) = serve(&server, &seen, stream).await {
+                    tracing::warn!(%peer, "companion connection closed: {err:#}");
+                }
+            });
+            while clients.try_join_next().is_some() {}
+        }
+
+        clients.abort_all();
+        self.server.set_companion_address(None);
+        Ok(())
+    }
+}
+
+/// Answer one app until it hangs up or sends a frame it could not have
+/// sealed without the key.
+async fn serve(server: &Server, seen: &Seen, mut stream: TcpStream) -> Result<()> {
+    // Read per connection, so resetting the key shuts out apps from then on.
+    let key = server.companion_key()?;
+    while let Some(body) = read_frame(&mut stream).await? {
+        let (nonce, message) = open(&key, REQUEST_AAD, &body)?;
+        let response = match serde_json::from_slice(&message) {
+            Ok(request) => answer(server, seen, nonce, request).await,
+            Err(err) => Err(anyhow!("malformed request: {err}")),
+        }
+        .unwrap_or_else(|err| CompanionResponse {
+            entries: Vec::new(),
+            error: Some(format!("{err:#}")),
+        });
+        let body = seal(&key, RESPONSE_AAD, keys::random_bytes()?, &serde_json::to_vec(&response)?)?;
+        write_frame(&mut stream, &body).await?;
+    }
+    Ok(())
+}
+
+async fn answer(
+    server: &Server,
+    seen: &Seen,
+    nonce: [u8; NONCE_LEN],
+    request: CompanionRequest,
+) -> Result<CompanionResponse> {
+    let skew = (Utc::now() - request.sent_at).abs();
+    if skew.to_std().unwrap_or(Duration::MAX) > MAX_CLOCK_SKEW {
+        bail!("sent_at is {}s off clipd's clock; check the phone's time", skew.num_seconds());
+    }
+    {
+        // A nonce older than this came with a sent_at refused above.
+        let mut seen = seen.lock();
+        seen.retain(|_, at| at.elapsed() <= 2 * MAX_CLOCK_SKEW);
+        if seen.insert(nonce, Instant::now()).is_some() {
+            bail!("request was already answered");
+        }
+    }
+
+    match request.kind {
+        CompanionRequestKind::Push { text } => {
+            if text.is_empty() {
+                bail!("nothing to push");
+            }
+            let mut entry = text_entry(text);
+            entry.source_process = Some(SOURCE.to_string());
+            tracing::info!(bytes = entry.bytes_len, "companion pushed text");
+            server.capture(entry).await;
+            Ok(CompanionResponse::default())
+        }
+        CompanionRequestKind::Latest { limit } => {
+            let entries = server
+                .latest_text(limit.min(MAX_LATEST))?
+                .into_iter()
+                .filter_map(|entry| {
+                    Some(CompanionEntry {
+                        id: entry.id?,
+                        text: entry.plain_text()?.to_string(),
+                        created_at: entry.created_at,
+                    })
+                })
+                .collect();
+            Ok(CompanionResponse { entries, error: None })
+        }
+    }
+}
+
+/// `address` as an app on the network reaches it: with the PC's LAN address
+/// in place of an unspecified one.
+pub(crate) fn advertised(address: SocketAddr) -> SocketAddr {
+    if !address.ip().is_unspecified() {
+        return address;
+    }
+    // Connecting a UDP socket sends nothing; it only picks the interface
+    // traffic to the outside would leave through.
+    let local = UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| {
+        socket.connect(("192.0.2.1", 9))?;
+        socket.local_addr()
+    });
+    match local {
+        Ok(local) => SocketAddr::new(local.ip(), address.port()),
+        Err(_) => address,
+    }
+}
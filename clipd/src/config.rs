@@ -1,42 +1,481 @@
 //! Configuration loading for clipd.
+//!
+//! Values come from the layers in [`crate::layers`]: the machine-wide file,
+//! `config.toml` in the user's config directory, the `CLIPMGR_*` environment
+//! variables, and `--set` flags, each taking precedence over the ones before.
 
-use std::env;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
+use serde::Deserialize;
 
+use crate::clipboard::{BatteryPolicy, Polling};
+use crate::db::{Duplicates, Eviction};
+use crate::ignore::{IgnoreRules, DEFAULT_PAUSE_MINUTES, DEFAULT_PROCESSES};
+use crate::issues::{GitHub, IssueTrackers, Jira, GITHUB_API};
+use crate::model::EntryKind;
+use crate::paste::PastePolicy;
+use crate::queue::Backpressure;
+use crate::redact::Redactor;
+use crate::layers::{self, Sources};
+use crate::timefmt::TimeDisplay;
+
+#[cfg(target_os = "windows")]
 const PIPE_NAME: &str = r"\\.\pipe\clipmgr";
+/// Unix domain socket clipd listens on in its data directory, on Linux and
+/// macOS, where there are no named pipes.
+#[cfg(not(target_os = "windows"))]
+const SOCKET_FILE: &str = "clipmgr.sock";
+const CONFIG_FILE: &str = "config.toml";
+const DEFAULT_MAX_ENTRIES: usize = 10000;
+const DEFAULT_BACKUPS: usize = 5;
+/// Copies from one app this close together are linked as copied together.
+const DEFAULT_GROUP_WINDOW_SECS: u64 = 10;
+/// Clipboard formats bigger than this are not captured.
+const DEFAULT_MAX_ENTRY_MB: usize = 64;
+/// Text and data the capture queue holds before `capture.backpressure` applies.
+const DEFAULT_MEMORY_BUDGET_MB: usize = 256;
+/// Port the companion endpoint listens on unless `companion.port` says otherwise.
+const DEFAULT_COMPANION_PORT: u16 = 47800;
+/// Profile that uses `history.db`; it always exists.
+pub const DEFAULT_PROFILE: &str = "default";
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub db_path: PathBuf,
     pub pipe_name: String,
+    /// Retention for the default profile, and for others that do not set their own.
+    pub max_entries: usize,
+    /// Every profile, `default` first and the rest by name.
+    pub profiles: Vec<Profile>,
+    /// Profile clipd captures into when it starts.
+    pub active_profile: String,
+    /// Clipboard formats clipd records; everything but HTML by default.
+    pub allowed_kinds: Vec<EntryKind>,
+    /// Tag captures with the project of the window they were copied from.
+    pub project_tags: bool,
+    /// Longest gap, in seconds, between copies from the same app that links
+    /// them as copied together; 0 turns linking off.
+    pub group_window_secs: u64,
+    /// With quarantine on, the executables whose captures go straight into
+    /// the history; `None` when quarantine is off.
+    pub trusted_processes: Option<Vec<String>>,
+    /// What a copy does while the capture queue is full.
+    pub backpressure: Backpressure,
+    /// Clipboard formats bigger than this many bytes are not read.
+    pub max_entry_bytes: usize,
+    /// Bytes of text and data the capture queue holds before it counts as full.
+    pub memory_budget_bytes: usize,
+    /// How often the clipboard is checked, while active and while idle.
+    pub polling: Polling,
+    /// What changes while running on a low battery.
+    pub battery: BatteryPolicy,
+    /// Where the `spill` backpressure policy writes captures.
+    pub spill_dir: PathBuf,
+    /// Captures not yet committed to the database, replayed after a crash.
+    pub journal_path: PathBuf,
+    /// How timestamps are shown to clipctl and in exports.
+    pub time: TimeDisplay,
+    /// Mask secrets in every export, not only those that ask for it.
+    pub redact_exports: bool,
+    /// Secret detectors used by redacted exports.
+    pub redactor: Redactor,
+    /// Which entries may be pasted into which apps, and in which format.
+    pub paste: PastePolicy,
+    /// Copies not captured, by the app they came from or what they hold.
+    pub ignore: IgnoreRules,
+    /// Trackers asked for the titles of issues entries mention.
+    pub issues: IssueTrackers,
+    /// DPAPI-protected encryption keys (`keys.json` next to the databases).
+    pub keys_path: PathBuf,
+    /// Where the companion endpoint listens; `None` while it is off.
+    pub companion: Option<SocketAddr>,
+    /// Where clipd announces its pipe while it listens: `clipd.json`, or
+    /// `clipd-<profile>.json` in a profile with a pipe of its own.
+    pub runtime_path: PathBuf,
+}
+
+/// A named history with its own database file.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub db_path: PathBuf,
     pub max_entries: usize,
+    /// Backups taken before risky operations that are kept (`general.backups`).
+    pub keep_backups: usize,
+    /// Content bytes kept before entries are evicted; 0 keeps any amount.
+    pub max_bytes: u64,
+    /// Which entries go first when over `max_bytes`.
+    pub eviction: Eviction,
+    /// Move entries retention drops into the archive instead of deleting them.
+    pub archive: bool,
+    /// Whether content copied again moves to the top.
+    pub duplicates: Duplicates,
+    /// Locale of alphabetical sorts; empty follows the system locale.
+    pub sort_locale: String,
+}
+
+/// The subset of `config.toml` the daemon reads; other sections belong to clipctl.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FileConfig {
+    general: GeneralSection,
+    capture: CaptureSection,
+    time: TimeSection,
+    profiles: ProfilesSection,
+    export: ExportSection,
+    paste: PastePolicy,
+    ignore: IgnoreSection,
+    issues: IssuesSection,
+    battery: BatterySection,
+    companion: CompanionSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GeneralSection {
+    pipe_name: Option<String>,
+    max_entries: Option<usize>,
+    backups: Option<usize>,
+    max_db_bytes: Option<u64>,
+    eviction: Option<String>,
+    archive: Option<bool>,
+    duplicates: Option<String>,
+    sort_locale: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CaptureSection {
+    allowed_kinds: Option<Vec<String>>,
+    project_tags: Option<bool>,
+    group_window_secs: Option<u64>,
+    quarantine: Option<bool>,
+    trusted_processes: Vec<String>,
+    backpressure: Option<String>,
+    max_entry_mb: Option<usize>,
+    memory_budget_mb: Option<usize>,
+    poll_interval_ms: Option<u64>,
+    idle_poll_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IgnoreSection {
+    /// Executables whose copies are dropped; password managers when unset.
+    processes: Option<Vec<String>>,
+    /// Regular expressions for text that is dropped.
+    patterns: Vec<String>,
+    cards: Option<bool>,
+    max_entropy: Option<f64>,
+    pause_minutes: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IssuesSection {
+    github_token: Option<String>,
+    /// `owner/repo` that a bare `#456` refers to.
+    github_repo: Option<String>,
+    github_api: Option<String>,
+    jira_url: Option<String>,
+    jira_user: Option<String>,
+    jira_token: Option<String>,
+    jira_projects: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BatterySection {
+    low_percent: Option<u8>,
+    skip_images: Option<bool>,
+}
+
+/// `[profiles]`: `active = "work"` plus one `name = { max_entries = ... }` per profile.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProfilesSection {
+    active: Option<String>,
+    #[serde(flatten)]
+    named: BTreeMap<String, ProfileSection>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProfileSection {
+    max_entries: Option<usize>,
+    max_db_bytes: Option<u64>,
+    /// Pipe a clipd started in this profile listens on, as a daemon of its own.
+    pipe_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ExportSection {
+    redact: Option<bool>,
+    /// Regular expressions for secrets beyond the built-in detectors.
+    redact_patterns: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CompanionSection {
+    enabled: Option<bool>,
+    port: Option<u16>,
+    /// Address to listen on; every interface by default.
+    bind: Option<IpAddr>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TimeSection {
+    format: Option<String>,
+    timezone: Option<String>,
+    locale: Option<String>,
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let dirs = ProjectDirs::from("com", "rusty-clipboard", "clipmgr")
-            .context("failed to determine application directories")?;
+        let dirs = project_dirs()?;
+        Self::load_layers(&Self::sources()?, dirs.data_local_dir().to_path_buf())
+    }
+
+    /// Every layer this process reads its configuration from.
+    pub fn sources() -> Result<Sources> {
+        Ok(Sources::system(Self::file_path()?))
+    }
+
+    /// `config.toml` in the user's config directory.
+    pub fn file_path() -> Result<PathBuf> {
+        Ok(project_dirs()?.config_dir().join(CONFIG_FILE))
+    }
+
+    /// Read only `config_file`, keeping the databases and the rest of clipd's
+    /// state in `data_dir`. Tests point both at a temporary directory.
+    pub fn load_from(config_file: &Path, data_dir: PathBuf) -> Result<Self> {
+        Self::load_layers(&Sources::file(config_file), data_dir)
+    }
 
-        let mut db_path = dirs.data_local_dir().to_path_buf();
-        std::fs::create_dir_all(&db_path).with_context(|| {
+    /// Merge the layers in `sources`, failing with every problem they have,
+    /// and keep clipd's state in `data_dir`.
+    pub fn load_layers(sources: &Sources, data_dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&data_dir).with_context(|| {
             format!(
                 "failed to create data directory: {}",
-                db_path.display()
+                data_dir.display()
             )
         })?;
-        db_path.push("history.db");
 
-        let pipe_name = env::var("CLIPMGR_PIPE").unwrap_or_else(|_| PIPE_NAME.to_string());
-        
-        let max_entries = env::var("CLIPMGR_MAX_ENTRIES")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(10000);
+        let layered = layers::gather(sources)?.check()?;
+        let file: FileConfig = toml::from_str(&layered.document().to_string())
+            .context("failed to read the merged configuration")?;
+
+        let max_entries = file.general.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES);
+
+        // The schema check let only known names through.
+        let allowed_kinds = match file.capture.allowed_kinds {
+            Some(names) => names.iter().filter_map(|name| EntryKind::from_name(name)).collect(),
+            None => EntryKind::DEFAULT.to_vec(),
+        };
+        let backpressure = file
+            .capture
+            .backpressure
+            .and_then(|name| Backpressure::parse(&name))
+            .unwrap_or_default();
+
+        let defaults = Polling::default();
+        let interval = file
+            .capture
+            .poll_interval_ms
+            .map_or(defaults.interval, Duration::from_millis);
+        let polling = Polling {
+            interval,
+            idle_interval: file
+                .capture
+                .idle_poll_interval_ms
+                .map_or(defaults.idle_interval, Duration::from_millis)
+                .max(interval),
+        };
+
+        let battery = BatteryPolicy {
+            low_percent: file
+                .battery
+                .low_percent
+                .unwrap_or(BatteryPolicy::default().low_percent),
+            skip_images: file.battery.skip_images.unwrap_or(false),
+        };
+
+        let time = TimeDisplay::new(file.time.format, file.time.timezone, file.time.locale);
+
+        let eviction = file
+            .general
+            .eviction
+            .and_then(|name| Eviction::parse(&name))
+            .unwrap_or_default();
 
-        Ok(Self { db_path, pipe_name, max_entries })
+        let keep_backups = file.general.backups.unwrap_or(DEFAULT_BACKUPS);
+        let max_bytes = file.general.max_db_bytes.unwrap_or(0);
+        let archive = file.general.archive.unwrap_or(false);
+        let duplicates = file
+            .general
+            .duplicates
+            .and_then(|name| Duplicates::parse(&name))
+            .unwrap_or_default();
+        let sort_locale = file.general.sort_locale.unwrap_or_default();
+        let mut profile_pipes = BTreeMap::new();
+        let mut profiles = vec![Profile {
+            name: DEFAULT_PROFILE.to_string(),
+            db_path: data_dir.join("history.db"),
+            max_entries,
+            keep_backups,
+            max_bytes,
+            eviction,
+            archive,
+            duplicates,
+            sort_locale: sort_locale.clone(),
+        }];
+        for (name, section) in file.profiles.named {
+            if name == DEFAULT_PROFILE {
+                profiles[0].max_entries = section.max_entries.unwrap_or(max_entries);
+                profiles[0].max_bytes = section.max_db_bytes.unwrap_or(max_bytes);
+                continue;
+            }
+            if let Some(pipe_name) = section.pipe_name {
+                profile_pipes.insert(name.clone(), pipe_name);
+            }
+            profiles.push(Profile {
+                db_path: data_dir.join(format!("history-{name}.db")),
+                max_entries: section.max_entries.unwrap_or(max_entries),
+                keep_backups,
+                max_bytes: section.max_db_bytes.unwrap_or(max_bytes),
+                eviction,
+                archive,
+                duplicates,
+                sort_locale: sort_locale.clone(),
+                name,
+            });
+        }
+
+        let mut active_profile = file.profiles.active.unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        if !profiles.iter().any(|profile| profile.name == active_profile) {
+            // Only CLIPMGR_PROFILE or a flag can name one; the schema checks the files'.
+            tracing::warn!(profile = %active_profile, "unknown active profile; using default");
+            active_profile = DEFAULT_PROFILE.to_string();
+        }
+
+        // Started in a profile with a pipe of its own, clipd runs beside the
+        // default daemon, so the files only one daemon may use are its own.
+        let own_pipe = profile_pipes.remove(&active_profile);
+        let suffix = match own_pipe {
+            Some(_) => format!("-{active_profile}"),
+            None => String::new(),
+        };
+        // A pipe named in the environment or by a flag still wins over the profile's.
+        let (overridden, configured) = match file.general.pipe_name {
+            Some(pipe) if layered.origin("general.pipe_name").is_override() => (Some(pipe), None),
+            configured => (None, configured),
+        };
+        let pipe_name = overridden
+            .or(own_pipe)
+            .or(configured)
+            .unwrap_or_else(|| default_pipe_name(&data_dir));
+
+        let issues = file.issues;
+        let token = |configured: Option<String>| configured.filter(|token| !token.is_empty());
+        let github = token(issues.github_token).map(|token| GitHub {
+            api: issues.github_api.unwrap_or_else(|| GITHUB_API.to_string()),
+            token,
+            repo: issues.github_repo.filter(|repo| !repo.is_empty()),
+        });
+        let jira_url = issues.jira_url.filter(|url| !url.is_empty());
+        let jira = match (token(issues.jira_token), jira_url) {
+            (Some(token), Some(url)) => Some(Jira {
+                url,
+                user: issues.jira_user,
+                token,
+                projects: issues.jira_projects,
+            }),
+            (Some(_), None) => {
+                tracing::warn!("issues.jira_token is set without issues.jira_url; not looking up Jira issues");
+                None
+            }
+            (None, _) => None,
+        };
+
+        Ok(Self {
+            pipe_name,
+            max_entries,
+            profiles,
+            active_profile,
+            allowed_kinds,
+            project_tags: file.capture.project_tags.unwrap_or(true),
+            group_window_secs: file.capture.group_window_secs.unwrap_or(DEFAULT_GROUP_WINDOW_SECS),
+            trusted_processes: file
+                .capture
+                .quarantine
+                .unwrap_or(false)
+                .then_some(file.capture.trusted_processes),
+            backpressure,
+            max_entry_bytes: megabytes(file.capture.max_entry_mb.unwrap_or(DEFAULT_MAX_ENTRY_MB)),
+            memory_budget_bytes: megabytes(file.capture.memory_budget_mb.unwrap_or(DEFAULT_MEMORY_BUDGET_MB)),
+            polling,
+            battery,
+            spill_dir: data_dir.join(format!("spill{suffix}")),
+            journal_path: data_dir.join(format!("captures{suffix}.journal")),
+            time,
+            redact_exports: file.export.redact.unwrap_or(false),
+            redactor: Redactor::new(&file.export.redact_patterns),
+            paste: file.paste,
+            ignore: IgnoreRules::new(
+                file.ignore
+                    .processes
+                    .unwrap_or_else(|| DEFAULT_PROCESSES.iter().map(|name| name.to_string()).collect()),
+                &file.ignore.patterns,
+                file.ignore.cards.unwrap_or(true),
+                file.ignore.max_entropy,
+                file.ignore.pause_minutes.unwrap_or(DEFAULT_PAUSE_MINUTES),
+            ),
+            issues: IssueTrackers { github, jira },
+            keys_path: data_dir.join("keys.json"),
+            companion: file.companion.enabled.unwrap_or(false).then(|| {
+                SocketAddr::new(
+                    file.companion.bind.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                    file.companion.port.unwrap_or(DEFAULT_COMPANION_PORT),
+                )
+            }),
+            runtime_path: data_dir.join(format!("clipd{suffix}.json")),
+        })
     }
 }
 
+/// `mb` megabytes in bytes, at least one megabyte.
+fn megabytes(mb: usize) -> usize {
+    mb.max(1).saturating_mul(1024 * 1024)
+}
+
+pub(crate) fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Where clipd listens unless told otherwise: a named pipe on Windows, a
+/// socket in `data_dir` elsewhere.
+#[cfg(target_os = "windows")]
+fn default_pipe_name(_data_dir: &Path) -> String {
+    PIPE_NAME.to_string()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn default_pipe_name(data_dir: &Path) -> String {
+    data_dir.join(SOCKET_FILE).to_string_lossy().into_owned()
+}
+
+fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("com", "rusty-clipboard", "clipmgr")
+        .context("failed to determine application directories")
+}
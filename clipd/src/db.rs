@@ -1,31 +1,307 @@
 //! SQLite persistence layer.
 
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clipcore::{Entity, EntityKind};
 use parking_lot::Mutex;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
-use crate::model::{Entry, EntryKind};
+use crate::archive::{self, Protection};
+use crate::backup::{self, Backup};
+use crate::cold;
+use crate::collate;
+use crate::entity;
+use crate::fold;
+use crate::keys::KeyRing;
+use crate::lang;
+use crate::model::{Entry, EntryKind, ImageInfo};
+use crate::query::Query;
+use crate::redact::Redactor;
+use crate::suggest::{self, Paste};
+use crate::timefmt::TimeDisplay;
+use crate::title;
+
+/// Column list matching [`Database::entry_from_row`]. Image data comes
+/// from the shared blob the entry refers to.
+const ENTRY_COLUMNS: &str = "id, created_at, kind, text, \
+     COALESCE(data, (SELECT blobs.data FROM blobs WHERE blobs.hash = entries.blob_hash)), \
+     bytes_len, hash, source_process, tags, \
+     image_width, image_height, image_dpi, image_monitor, title, session, quarantined, lang, pinned, text_extracted, copy_group, \
+     entities, COALESCE(use_count, 1)";
+
+/// Columns in a row of [`ENTRY_COLUMNS`]; queries that select more after
+/// them read those from this index on.
+const ENTRY_COLUMN_COUNT: usize = column_count(ENTRY_COLUMNS);
+
+/// Columns added after the first release, created on open when missing.
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("image_width", "INTEGER"),
+    ("image_height", "INTEGER"),
+    ("image_dpi", "INTEGER"),
+    ("image_monitor", "TEXT"),
+    ("title", "TEXT"),
+    ("session", "INTEGER"),
+    ("quarantined", "INTEGER"),
+    ("search_text", "TEXT"),
+    ("lang", "TEXT"),
+    ("pinned", "INTEGER"),
+    ("pin_order", "INTEGER"),
+    ("text_extracted", "TEXT"),
+    ("copy_group", "INTEGER"),
+    ("last_used_at", "TEXT"),
+    ("blob_hash", "TEXT"),
+    ("entities", "TEXT"),
+    // Times the content was copied; NULL for once.
+    ("use_count", "INTEGER"),
+];
+
+/// Image data, stored once however many entries hold it. Triggers keep
+/// `refs` equal to the number of entries pointing at a blob and delete it
+/// with the last one, whichever way entries are deleted.
+const BLOB_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS blobs (
+        hash TEXT PRIMARY KEY,
+        data BLOB NOT NULL,
+        refs INTEGER NOT NULL
+    );
+
+    CREATE TRIGGER IF NOT EXISTS blob_ref AFTER INSERT ON entries
+    WHEN NEW.blob_hash IS NOT NULL BEGIN
+        UPDATE blobs SET refs = refs + 1 WHERE hash = NEW.blob_hash;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS blob_unref AFTER DELETE ON entries
+    WHEN OLD.blob_hash IS NOT NULL BEGIN
+        UPDATE blobs SET refs = refs - 1 WHERE hash = OLD.blob_hash;
+        DELETE FROM blobs WHERE hash = OLD.blob_hash AND refs <= 0;
+    END;
+"#;
+
+/// Full-text index over the folded text and the tags of entries, for
+/// searches. It reads the text from `entries` instead of keeping a copy;
+/// triggers index each entry as it is inserted, updated, and deleted.
+const FTS_SCHEMA: &str = r#"
+    CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+        search_text, tags, content = 'entries', content_rowid = 'id'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS fts_insert AFTER INSERT ON entries BEGIN
+        INSERT INTO entries_fts (rowid, search_text, tags) VALUES (NEW.id, NEW.search_text, NEW.tags);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS fts_delete AFTER DELETE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, search_text, tags)
+        VALUES ('delete', OLD.id, OLD.search_text, OLD.tags);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS fts_update AFTER UPDATE OF search_text, tags ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, search_text, tags)
+        VALUES ('delete', OLD.id, OLD.search_text, OLD.tags);
+        INSERT INTO entries_fts (rowid, search_text, tags) VALUES (NEW.id, NEW.search_text, NEW.tags);
+    END;
+"#;
+
+/// The entities of each entry, one row apiece, for `has:` filters. Triggers
+/// keep it in step with the `entities` column, which summaries read.
+const ENTITY_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS entities (
+        entry_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_entities_kind ON entities(kind, value);
+    CREATE INDEX IF NOT EXISTS idx_entities_entry ON entities(entry_id);
+
+    CREATE TRIGGER IF NOT EXISTS entities_insert AFTER INSERT ON entries
+    WHEN NEW.entities IS NOT NULL BEGIN
+        INSERT INTO entities (entry_id, kind, value)
+        SELECT NEW.id, json_extract(value, '$.kind'), json_extract(value, '$.value') FROM json_each(NEW.entities);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS entities_delete AFTER DELETE ON entries BEGIN
+        DELETE FROM entities WHERE entry_id = OLD.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS entities_update AFTER UPDATE OF entities ON entries BEGIN
+        DELETE FROM entities WHERE entry_id = OLD.id;
+        INSERT INTO entities (entry_id, kind, value)
+        SELECT NEW.id, json_extract(value, '$.kind'), json_extract(value, '$.value')
+        FROM json_each(COALESCE(NEW.entities, '[]'));
+    END;
+"#;
+
+/// Titles of issues their trackers were asked about, by reference such as
+/// `owner/repo#12` or `OPS-7`, so each is looked up once.
+const ISSUE_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS issues (
+        reference TEXT PRIMARY KEY,
+        title TEXT NOT NULL
+    );
+"#;
+
+/// Pastes, for suggesting what to paste next: the app each went into and
+/// when. A trigger forgets an entry's pastes with the entry.
+const PASTE_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS pastes (
+        entry_id INTEGER NOT NULL,
+        target TEXT,
+        pasted_at TEXT NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_pastes_pasted_at ON pastes(pasted_at);
+
+    CREATE TRIGGER IF NOT EXISTS pastes_delete AFTER DELETE ON entries BEGIN
+        DELETE FROM pastes WHERE entry_id = OLD.id;
+    END;
+"#;
+
+/// Quarantined entries are deleted this long after capture unless their
+/// source is approved first.
+const QUARANTINE_TTL: chrono::Duration = chrono::Duration::hours(1);
+
+/// Condition that hides quarantined entries from lists and searches.
+pub const NOT_QUARANTINED: &str = "COALESCE(quarantined, 0) = 0";
+
+/// Pinned entries first, in the order the user arranged them.
+const PINNED_FIRST: &str = "COALESCE(pinned, 0) DESC, pin_order ASC";
+
+/// Most recently copied or pasted first.
+pub const RECENT_FIRST: &str = "COALESCE(last_used_at, created_at) DESC";
+
+/// Bytes an entry's content takes in `entries`: text, document or snapshot
+/// data, and extracted text.
+const ROW_BYTES: &str = "COALESCE(LENGTH(CAST(text AS BLOB)), 0) + COALESCE(LENGTH(data), 0) \
+     + COALESCE(LENGTH(CAST(text_extracted AS BLOB)), 0)";
+
+/// Which unpinned entries go first when the history is over
+/// `general.max_db_bytes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Eviction {
+    /// Least recently used first, like pruning by count.
+    #[default]
+    Oldest,
+    /// Biggest first, so one huge image goes before many snippets.
+    Largest,
+}
+
+impl Eviction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "oldest" => Some(Self::Oldest),
+            "largest" => Some(Self::Largest),
+            _ => None,
+        }
+    }
+
+    /// Order of the entries to evict, over `last_used_at`, `created_at`, and `size`.
+    fn order(self) -> &'static str {
+        match self {
+            Self::Oldest => "COALESCE(last_used_at, created_at) ASC",
+            Self::Largest => "size DESC, COALESCE(last_used_at, created_at) ASC",
+        }
+    }
+}
+
+/// What happens when something already in the history is copied again
+/// (`general.duplicates`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Its entry moves to the top, as if just copied.
+    #[default]
+    Bump,
+    /// Its entry stays where it is.
+    Skip,
+}
+
+impl Duplicates {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "bump" => Some(Self::Bump),
+            "skip" => Some(Self::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// What [`Database::insert_entry`] did with a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stored {
+    /// Stored as the new entry with this id.
+    Added(u64),
+    /// Already stored as this entry, which moved to the top.
+    Bumped(u64),
+    /// Already stored, and left where it was.
+    Skipped,
+}
 
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
-    max_entries: usize,
+    path: Arc<PathBuf>,
+    max_entries: Arc<AtomicUsize>,
+    /// Backups kept before risky operations (`general.backups`); 0 takes none.
+    keep_backups: Arc<AtomicUsize>,
+    /// Content bytes kept (`general.max_db_bytes`); 0 keeps any amount.
+    max_bytes: Arc<AtomicU64>,
+    eviction: Arc<Mutex<Eviction>>,
+    /// Move entries into the archive instead of deleting them (`general.archive`).
+    archive: Arc<AtomicBool>,
+    duplicates: Arc<Mutex<Duplicates>>,
+    /// Session stamped on entries captured by this clipd run.
+    session: u64,
+}
+
+/// Entries captured during one session, as listed by [`Database::sessions`].
+pub struct Session {
+    pub id: u64,
+    pub started: DateTime<Utc>,
+    pub ended: DateTime<Utc>,
+    pub entries: u64,
+}
+
+/// How much storage sharing identical images saves, for `clipctl doctor`.
+pub use clipcore::BlobStats;
+
+/// Outcome of [`Database::check_health`].
+pub struct Health {
+    pub entries: u64,
+    /// Lines reported by `PRAGMA quick_check`; empty when the database is intact.
+    pub problems: Vec<String>,
+}
+
+/// What was captured over a stretch of time, as [`Database::usage`] counts it.
+pub struct Usage {
+    pub entries: u64,
+    /// Apps, kinds, and tags with their numbers of entries, most first.
+    pub apps: Vec<(String, u64)>,
+    pub kinds: Vec<(String, u64)>,
+    pub tags: Vec<(String, u64)>,
+    /// Entries per hour of the day.
+    pub hours: [u64; 24],
+    pub biggest: Vec<Entry>,
 }
 
 impl Database {
-    pub fn open(path: PathBuf, max_entries: usize) -> Result<Self> {
+    pub fn open(path: PathBuf, max_entries: usize, keep_backups: usize) -> Result<Self> {
         tracing::info!("opening sqlite database at {} (max_entries: {})", path.display(), max_entries);
         
+        let existed = path.exists();
         let conn = Connection::open(&path)
             .with_context(|| format!("failed to open database at {}", path.display()))?;
         
         // Enable WAL mode for better concurrency
         conn.pragma_update(None, "journal_mode", "WAL")?;
+        cold::register_functions(&conn)?;
+        collate::register(&conn, "")?;
         
         // Create schema
         conn.execute_batch(
@@ -46,57 +322,220 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_hash ON entries(hash);
             "#,
         )?;
+        if existed && keep_backups > 0 && needs_migration(&conn)? {
+            backup::create(&conn, &path, "migration", keep_backups)
+                .context("refusing to migrate without a backup; free some disk space or set general.backups = 0")?;
+        }
+        let added = migrate(&conn)?;
+        conn.execute_batch(BLOB_SCHEMA).context("failed to create the blob table")?;
+        if added.contains(&"blob_hash") {
+            move_images_to_blobs(&conn)?;
+        }
+        if added.contains(&"title") {
+            backfill_titles(&conn)?;
+        }
+        if added.contains(&"search_text") {
+            backfill_search_text(&conn)?;
+        }
+        if added.contains(&"lang") {
+            backfill_languages(&conn)?;
+        }
+        if added.contains(&"text_extracted") {
+            backfill_extracted_text(&conn)?;
+        }
+        // After the backfills, which the triggers would index into tables
+        // that do not hold the entries yet.
+        create_entity_table(&conn)?;
+        create_fts(&conn)?;
+        conn.execute_batch(PASTE_SCHEMA).context("failed to create the paste table")?;
         
         tracing::info!("database schema initialized");
+
+        // Each start is a new session; clipd starts at logon, so sessions
+        // usually follow logins.
+        let last_session: Option<i64> =
+            conn.query_row("SELECT MAX(session) FROM entries", [], |row| row.get(0))?;
+        let session = last_session.map_or(1, |last| last as u64 + 1);
+        tracing::info!(session, "starting capture session");
         
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
-            max_entries,
+            path: Arc::new(path),
+            max_entries: Arc::new(AtomicUsize::new(max_entries)),
+            keep_backups: Arc::new(AtomicUsize::new(keep_backups)),
+            max_bytes: Arc::new(AtomicU64::new(0)),
+            eviction: Arc::new(Mutex::new(Eviction::default())),
+            archive: Arc::new(AtomicBool::new(false)),
+            duplicates: Arc::new(Mutex::new(Duplicates::default())),
+            session,
+        })
+    }
+
+    /// The session new captures are recorded under.
+    pub fn current_session(&self) -> u64 {
+        self.session
+    }
+
+    /// Change the retention limit at runtime and prune immediately.
+    pub fn set_max_entries(&self, max_entries: usize) -> Result<()> {
+        let previous = self.max_entries.swap(max_entries, Ordering::Relaxed);
+        if previous != max_entries {
+            tracing::info!(previous, max_entries, "retention limit changed");
+            self.cleanup_old_entries()?;
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Change the size budget at runtime and evict immediately.
+    pub fn set_max_bytes(&self, max_bytes: u64, eviction: Eviction) -> Result<()> {
+        let previous = self.max_bytes.swap(max_bytes, Ordering::Relaxed);
+        let previous_eviction = std::mem::replace(&mut *self.eviction.lock(), eviction);
+        if previous != max_bytes || previous_eviction != eviction {
+            tracing::info!(previous, max_bytes, ?eviction, "size budget changed");
+            self.cleanup_old_entries()?;
+        }
+        Ok(())
+    }
+
+    /// Order `sort:title` and `sort:tag` like `locale` (`general.sort_locale`);
+    /// empty follows the system locale.
+    pub fn set_sort_locale(&self, locale: &str) -> Result<()> {
+        collate::register(&self.conn.lock(), locale)?;
+        tracing::debug!(locale, "sort locale set");
+        Ok(())
+    }
+
+    /// Archive entries retention drops from now on, or go back to deleting
+    /// them. Entries archived before stay searchable either way.
+    pub fn set_archive(&self, archive: bool) -> Result<()> {
+        if archive {
+            cold::attach(&self.conn.lock(), &self.path, true)?;
+        }
+        if self.archive.swap(archive, Ordering::Relaxed) != archive {
+            tracing::info!(archive, "archiving changed");
+        }
+        Ok(())
+    }
+
+    /// Bump or skip content copied again from now on.
+    pub fn set_duplicates(&self, duplicates: Duplicates) {
+        let previous = std::mem::replace(&mut *self.duplicates.lock(), duplicates);
+        if previous != duplicates {
+            tracing::info!(?duplicates, "duplicate handling changed");
+        }
+    }
+
+    /// Bytes of content the history holds, each shared image counted once.
+    /// The file itself can be larger until SQLite reuses the freed pages.
+    pub fn content_bytes(&self) -> Result<u64> {
+        content_bytes(&self.conn.lock())
+    }
+
+    pub fn set_keep_backups(&self, keep: usize) {
+        self.keep_backups.store(keep, Ordering::Relaxed);
+    }
+
+    /// Back the database up before `reason`, unless backups are turned off.
+    pub fn backup(&self, reason: &str) -> Result<Option<PathBuf>> {
+        let keep = self.keep_backups.load(Ordering::Relaxed);
+        if keep == 0 {
+            return Ok(None);
+        }
+        let conn = self.conn.lock();
+        backup::create(&conn, &self.path, reason, keep).map(Some)
+    }
+
+    /// Backups of this database, newest first.
+    pub fn backups(&self) -> Result<Vec<Backup>> {
+        backup::list(&self.path)
+    }
+
+    /// Replace the history with backup `name`, backing up the current one first.
+    pub fn restore_backup(&self, name: &str) -> Result<()> {
+        self.backup("restore")?;
+        let conn = self.conn.lock();
+        backup::restore(&conn, &self.path, name)?;
+        tracing::info!(backup = name, "restored database from backup");
+        Ok(())
+    }
+
+    /// Run SQLite's quick integrity check and count stored entries.
+    pub fn check_health(&self) -> Result<Health> {
+        let conn = self.conn.lock();
+
+        let mut stmt = conn.prepare("PRAGMA quick_check(10)")?;
+        let problems = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|line| line != "ok")
+            .collect();
+        drop(stmt);
+
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+
+        Ok(Health {
+            entries: entries as u64,
+            problems,
+        })
+    }
+
+    /// How many entries share how many image blobs.
+    pub fn blob_stats(&self) -> Result<BlobStats> {
+        let conn = self.conn.lock();
+        let (images, blobs, stored, saved): (i64, i64, i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(refs), 0), COUNT(*), COALESCE(SUM(LENGTH(data)), 0),
+                    COALESCE(SUM((refs - 1) * LENGTH(data)), 0)
+             FROM blobs",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        Ok(BlobStats {
+            images: images as u64,
+            blobs: blobs as u64,
+            stored_bytes: stored as u64,
+            saved_bytes: saved as u64,
         })
     }
 
-    pub fn insert_entry(&self, entry: &Entry) -> Result<()> {
+    pub fn count(&self) -> Result<u64> {
+        let conn = self.conn.lock();
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+        Ok(entries as u64)
+    }
+
+    /// Store a capture, linking it with the one before when both were copied
+    /// from the same app less than `group_window` apart. Content already
+    /// stored is bumped to the top or skipped, per `general.duplicates`; a
+    /// `replayed` capture the crashed run stored after all is always skipped.
+    pub fn insert_entry(&self, entry: &Entry, group_window: chrono::Duration, replayed: bool) -> Result<Stored> {
         let conn = self.conn.lock();
         
         // Check if entry with this hash already exists
-        let exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM entries WHERE hash = ?1",
-                params![&entry.hash],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+        let existing: Option<i64> = conn
+            .query_row("SELECT id FROM entries WHERE hash = ?1", params![&entry.hash], |row| row.get(0))
+            .optional()?;
         
-        if exists {
-            tracing::debug!(hash = %entry.hash, "skipping duplicate entry");
-            return Ok(());
+        if let Some(id) = existing {
+            if replayed || *self.duplicates.lock() == Duplicates::Skip {
+                tracing::debug!(hash = %entry.hash, "skipping duplicate entry");
+                return Ok(Stored::Skipped);
+            }
+            conn.execute(
+                "UPDATE entries SET last_used_at = ?1, use_count = COALESCE(use_count, 1) + 1 WHERE id = ?2",
+                params![Utc::now().to_rfc3339(), id],
+            )?;
+            tracing::debug!(hash = %entry.hash, id, "bumped duplicate entry");
+            return Ok(Stored::Bumped(id as u64));
         }
         
-        let kind_str = match entry.kind {
-            EntryKind::Text => "text",
-            EntryKind::Url => "url",
-            EntryKind::Image => "image",
-            EntryKind::Rtf => "rtf",
-        };
-        
-        let tags_json = serde_json::to_string(&entry.tags)?;
-        
-        conn.execute(
-            r#"
-            INSERT INTO entries (created_at, kind, text, data, bytes_len, hash, source_process, tags)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#,
-            params![
-                entry.created_at.to_rfc3339(),
-                kind_str,
-                &entry.text,
-                &entry.data,
-                entry.bytes_len as i64,
-                &entry.hash,
-                &entry.source_process,
-                tags_json,
-            ],
-        )?;
+        let copy_group = link_to_previous(&conn, entry, group_window)?;
+        insert_row(&conn, entry, Some(self.session), copy_group)?;
+        let id = conn.last_insert_rowid() as u64;
         
         tracing::info!(hash = %entry.hash, "inserted new entry");
         
@@ -106,20 +545,15 @@ impl Database {
         // Clean up old entries if we've exceeded the limit
         self.cleanup_old_entries()?;
         
-        Ok(())
+        Ok(Stored::Added(id))
     }
 
     pub fn list_recent(&self, limit: usize) -> Result<Vec<Entry>> {
         let conn = self.conn.lock();
         
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, created_at, kind, text, data, bytes_len, hash, source_process, tags
-            FROM entries
-            ORDER BY created_at DESC
-            LIMIT ?1
-            "#,
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {ENTRY_COLUMNS} FROM entries WHERE {NOT_QUARANTINED} ORDER BY {PINNED_FIRST}, {RECENT_FIRST} LIMIT ?1"
+        ))?;
         
         let entries = stmt
             .query_map(params![limit as i64], |row| {
@@ -130,23 +564,110 @@ impl Database {
         Ok(entries)
     }
 
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Entry>> {
+    /// A page of the history: with no `cursor`, every pinned entry and then
+    /// the newest `limit` others; with one, the `limit` entries after the
+    /// page it came from. Also returns the cursor of the next page, if any.
+    ///
+    /// A cursor is the last entry's last use and id, so entries copied while
+    /// a client pages neither shift the pages after nor repeat in them.
+    pub fn list_page(&self, cursor: Option<&str>, limit: usize) -> Result<(Vec<Entry>, Option<String>)> {
+        let conn = self.conn.lock();
+        let mut entries = Vec::new();
+
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        let after = match cursor {
+            Some(cursor) => {
+                let (used, id) = cursor
+                    .rsplit_once('|')
+                    .and_then(|(used, id)| Some((used, id.parse::<i64>().ok()?)))
+                    .with_context(|| format!("invalid list cursor: {cursor}"))?;
+                values.push(used.to_string().into());
+                values.push(id.into());
+                "AND (COALESCE(last_used_at, created_at), id) < (?, ?)"
+            }
+            None => {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {ENTRY_COLUMNS} FROM entries WHERE {NOT_QUARANTINED} AND COALESCE(pinned, 0) != 0 \
+                     ORDER BY pin_order ASC"
+                ))?;
+                entries = stmt
+                    .query_map([], |row| self.entry_from_row(row))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                ""
+            }
+        };
+        // One more than asked for, to tell whether there is a next page.
+        values.push((limit as i64 + 1).into());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {ENTRY_COLUMNS}, COALESCE(last_used_at, created_at) FROM entries \
+             WHERE {NOT_QUARANTINED} AND COALESCE(pinned, 0) = 0 {after} \
+             ORDER BY {RECENT_FIRST}, id DESC LIMIT ?"
+        ))?;
+        let mut rows = stmt
+            .query_map(rusqlite::params_from_iter(values), |row| {
+                let used = row.get::<_, String>(ENTRY_COLUMN_COUNT)?;
+                Ok((self.entry_from_row(row)?, used, row.get::<_, i64>(0)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let more = rows.len() > limit;
+        rows.truncate(limit);
+        let next = match rows.last() {
+            Some((_, used, id)) if more => Some(format!("{used}|{id}")),
+            _ => None,
+        };
+        entries.extend(rows.into_iter().map(|(entry, _, _)| entry));
+        Ok((entries, next))
+    }
+
+    pub fn get_entry(&self, id: u64) -> Result<Option<Entry>> {
+        let conn = self.conn.lock();
+
+        let mut stmt = conn.prepare(&format!("SELECT {ENTRY_COLUMNS} FROM entries WHERE id = ?1"))?;
+
+        let entry = stmt
+            .query_map(params![id as i64], |row| self.entry_from_row(row))?
+            .next()
+            .transpose()?;
+
+        Ok(entry)
+    }
+
+    /// Entries matching the query's free text and every field filter.
+    ///
+    /// `day` is the SQL expression for an entry's calendar day (see `TimeDisplay::day_sql`).
+    /// With `in:archive`, the archive is searched instead of the history.
+    pub fn search(&self, query: &Query, day: &str, limit: usize) -> Result<Vec<Entry>> {
         let conn = self.conn.lock();
         
-        let search_pattern = format!("%{}%", query);
+        let (condition, condition_values) = query.to_sql(day);
+        let order = query.order_by();
+        let mut values = Vec::new();
+        let ranked = match query.relevance() {
+            Some((subquery, value)) => {
+                values.push(value);
+                format!("JOIN {subquery} ON match_id = entries.id")
+            }
+            None => String::new(),
+        };
+        values.extend(condition_values);
+        values.push((limit as i64).into());
         
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, created_at, kind, text, data, bytes_len, hash, source_process, tags
-            FROM entries
-            WHERE text LIKE ?1 OR tags LIKE ?1
-            ORDER BY created_at DESC
-            LIMIT ?2
-            "#,
-        )?;
+        let (columns, table) = if query.archived() {
+            if !cold::attach(&conn, &self.path, false)? {
+                return Ok(Vec::new());
+            }
+            (cold::ENTRY_COLUMNS, "archive.entries")
+        } else {
+            (ENTRY_COLUMNS, "entries")
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {columns} FROM {table} {ranked} WHERE {condition} ORDER BY {order} LIMIT ?"
+        ))?;
         
         let entries = stmt
-            .query_map(params![search_pattern, limit as i64], |row| {
+            .query_map(rusqlite::params_from_iter(values), |row| {
                 self.entry_from_row(row)
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -154,6 +675,213 @@ impl Database {
         Ok(entries)
     }
 
+    /// Sessions that still have entries, newest first.
+    pub fn sessions(&self) -> Result<Vec<Session>> {
+        let conn = self.conn.lock();
+
+        let mut stmt = conn.prepare(
+            "SELECT session, MIN(created_at), MAX(created_at), COUNT(*) FROM entries
+             WHERE session IS NOT NULL GROUP BY session ORDER BY session DESC",
+        )?;
+        let sessions = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(|(id, started, ended, entries)| Session {
+                id: id as u64,
+                started: parse_timestamp(&started),
+                ended: parse_timestamp(&ended),
+                entries: entries as u64,
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Add `tag` to every entry of a session, returning how many entries there were.
+    pub fn tag_session(&self, session: u64, tag: &str) -> Result<usize> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT id FROM entries WHERE session = ?1")?;
+        let ids = stmt
+            .query_map(params![session as i64], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+        drop(conn);
+
+        for &id in &ids {
+            self.add_tag(id as u64, tag)?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Add `tag` to every entry matching `query` in one transaction, returning
+    /// how many did not have it yet.
+    pub fn tag_matching(&self, query: &Query, day: &str, tag: &str) -> Result<usize> {
+        if query.archived() {
+            bail!("archived entries cannot be tagged; restore them first");
+        }
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+
+        let (condition, values) = query.to_sql(day);
+        let mut stmt = tx.prepare(&format!("SELECT id, tags FROM entries WHERE {condition}"))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut tagged = 0;
+        for (id, tags_json) in rows {
+            let mut tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+            if tags.iter().any(|existing| existing == tag) {
+                continue;
+            }
+            tags.push(tag.to_string());
+            tx.execute(
+                "UPDATE entries SET tags = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&tags)?, id],
+            )?;
+            tagged += 1;
+        }
+        tx.commit()?;
+        Ok(tagged)
+    }
+
+    /// Delete every entry matching `query`, returning how many there were.
+    pub fn delete_matching(&self, query: &Query, day: &str) -> Result<usize> {
+        if query.archived() {
+            bail!("archived entries cannot be deleted from a search");
+        }
+        self.backup("delete")?;
+        let conn = self.conn.lock();
+        let (condition, values) = query.to_sql(day);
+        let deleted = conn.execute(
+            &format!("DELETE FROM entries WHERE {condition}"),
+            rusqlite::params_from_iter(values),
+        )?;
+        Ok(deleted)
+    }
+
+    /// Delete entry `id`, returning whether there was one.
+    pub fn delete_entry(&self, id: u64) -> Result<bool> {
+        let conn = self.conn.lock();
+        let deleted = conn.execute("DELETE FROM entries WHERE id = ?1", params![id as i64])?;
+        Ok(deleted > 0)
+    }
+
+    /// Delete the unpinned entries captured more than `older_than` ago, or
+    /// with `None` wipe everything, returning how many entries went. A wipe
+    /// takes pinned and archived entries and the backups too, and compacts
+    /// the files so no copied content is left in free pages.
+    pub fn clear(&self, older_than: Option<chrono::Duration>) -> Result<usize> {
+        let Some(older_than) = older_than else {
+            return self.wipe();
+        };
+        self.backup("clear")?;
+        let conn = self.conn.lock();
+        let cutoff = (Utc::now() - older_than).to_rfc3339();
+        let cleared = conn.execute(
+            "DELETE FROM entries WHERE COALESCE(pinned, 0) = 0 AND created_at < ?1",
+            params![cutoff],
+        )?;
+        Ok(cleared)
+    }
+
+    fn wipe(&self) -> Result<usize> {
+        let conn = self.conn.lock();
+        let mut wiped = conn.execute("DELETE FROM entries", [])?;
+        if cold::attach(&conn, &self.path, false)? {
+            wiped += conn.execute("DELETE FROM archive.entries", [])?;
+            conn.execute_batch("VACUUM archive")?;
+        }
+        conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .context("failed to compact the wiped history")?;
+        drop(conn);
+        backup::remove_all(&self.path)?;
+        tracing::info!(wiped, "wiped history");
+        Ok(wiped)
+    }
+
+    /// Number of entries captured on each day that has any, oldest first.
+    pub fn daily_counts(&self, day: &str) -> Result<Vec<(String, u64)>> {
+        let conn = self.conn.lock();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {day} AS day, COUNT(*) FROM entries GROUP BY day ORDER BY day"
+        ))?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// Entries captured in the last `within`, quarantined ones left out: the
+    /// `top` apps, kinds, tags, and largest entries, and the hours of the day
+    /// per `hour` (see `TimeDisplay::hour_sql`).
+    pub fn usage(&self, within: chrono::Duration, hour: &str, top: usize) -> Result<Usage> {
+        let conn = self.conn.lock();
+        let since = (Utc::now() - within).to_rfc3339();
+        let filter = format!("created_at >= ?1 AND {NOT_QUARANTINED}");
+        let counts = |sql: &str| -> Result<Vec<(String, u64)>> {
+            let mut stmt = conn.prepare(sql)?;
+            let counts = stmt
+                .query_map(params![since, top as i64], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(counts)
+        };
+
+        let entries: i64 =
+            conn.query_row(&format!("SELECT COUNT(*) FROM entries WHERE {filter}"), params![since], |row| row.get(0))?;
+        let apps = counts(&format!(
+            "SELECT source_process, COUNT(*) AS n FROM entries WHERE {filter} AND source_process IS NOT NULL \
+             GROUP BY source_process ORDER BY n DESC, source_process LIMIT ?2"
+        ))?;
+        let kinds = counts(&format!(
+            "SELECT kind, COUNT(*) AS n FROM entries WHERE {filter} GROUP BY kind ORDER BY n DESC, kind LIMIT ?2"
+        ))?;
+        let tags = counts(&format!(
+            "SELECT tag.value, COUNT(*) AS n FROM entries, json_each(COALESCE(entries.tags, '[]')) AS tag \
+             WHERE {filter} GROUP BY tag.value ORDER BY n DESC, tag.value LIMIT ?2"
+        ))?;
+
+        let mut hours = [0; 24];
+        let mut stmt =
+            conn.prepare(&format!("SELECT {hour} AS hour, COUNT(*) FROM entries WHERE {filter} GROUP BY hour"))?;
+        let rows = stmt.query_map(params![since], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?;
+        for row in rows {
+            let (hour, count) = row?;
+            if let Some(slot) = hours.get_mut(hour as usize) {
+                *slot = count as u64;
+            }
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {ENTRY_COLUMNS} FROM entries WHERE {filter} ORDER BY bytes_len DESC, id DESC LIMIT ?2"
+        ))?;
+        let biggest = stmt
+            .query_map(params![since, top as i64], |row| self.entry_from_row(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Usage {
+            entries: entries as u64,
+            apps,
+            kinds,
+            tags,
+            hours,
+            biggest,
+        })
+    }
+
     pub fn add_tag(&self, id: u64, tag: &str) -> Result<()> {
         let conn = self.conn.lock();
         
@@ -207,81 +935,413 @@ impl Database {
         Ok(())
     }
 
-    /// Remove old entries if the database exceeds max_entries
+    /// Remember that `id` was pasted into `target` just now, and forget
+    /// pastes too old to count for suggestions.
+    pub fn record_paste(&self, id: u64, target: Option<&str>) -> Result<()> {
+        let now = Utc::now();
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT INTO pastes (entry_id, target, pasted_at) VALUES (?1, ?2, ?3)",
+            params![id as i64, target, now.to_rfc3339()],
+        )?;
+        let forgotten = now - chrono::Duration::days(suggest::LOOKBACK_DAYS);
+        conn.execute("DELETE FROM pastes WHERE pasted_at < ?1", params![forgotten.to_rfc3339()])?;
+        Ok(())
+    }
+
+    /// Pastes since `since` of entries in the history and out of quarantine.
+    pub fn pastes_since(&self, since: DateTime<Utc>) -> Result<Vec<Paste>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT entry_id, target, pasted_at FROM pastes JOIN entries ON entries.id = pastes.entry_id
+             WHERE pasted_at >= ?1 AND {NOT_QUARANTINED}"
+        ))?;
+        let rows = stmt
+            .query_map(params![since.to_rfc3339()], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, target, at)| {
+                Ok(Paste {
+                    entry_id: id as u64,
+                    target,
+                    at: DateTime::parse_from_rfc3339(&at)
+                        .with_context(|| format!("bad paste time in database: {at}"))?
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect()
+    }
+
+    /// Move `id` to the top of the history, as if just copied, when it is
+    /// pasted again.
+    pub fn touch_entry(&self, id: u64) -> Result<()> {
+        let conn = self.conn.lock();
+        let changed = conn.execute(
+            "UPDATE entries SET last_used_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id as i64],
+        )?;
+        tracing::info!(id, changed, "bumped pasted entry");
+        Ok(())
+    }
+
+    /// Pin `id` below the other pinned entries, or unpin it.
+    pub fn set_pinned(&self, id: u64, pinned: bool) -> Result<()> {
+        let conn = self.conn.lock();
+        let changed = if pinned {
+            conn.execute(
+                "UPDATE entries SET pinned = 1, \
+                 pin_order = (SELECT COALESCE(MAX(pin_order), 0) + 1 FROM entries WHERE pinned = 1) \
+                 WHERE id = ?1 AND COALESCE(pinned, 0) = 0",
+                params![id as i64],
+            )?
+        } else {
+            conn.execute(
+                "UPDATE entries SET pinned = 0, pin_order = NULL WHERE id = ?1",
+                params![id as i64],
+            )?
+        };
+        tracing::info!(id, pinned, changed, "set entry pin");
+        Ok(())
+    }
+
+    /// Swap pinned entry `id` with the pinned entry above it, or below it
+    /// when `up` is false; nothing happens at either end of the board or if
+    /// `id` is not pinned.
+    pub fn move_pin(&self, id: u64, up: bool) -> Result<()> {
+        let conn = self.conn.lock();
+        let order: Option<i64> = conn
+            .query_row(
+                "SELECT pin_order FROM entries WHERE id = ?1 AND pinned = 1",
+                params![id as i64],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        let Some(order) = order else {
+            tracing::debug!(id, "not moving unpinned entry");
+            return Ok(());
+        };
+
+        let neighbour = if up {
+            "SELECT id, pin_order FROM entries WHERE pinned = 1 AND pin_order < ?1 \
+             ORDER BY pin_order DESC LIMIT 1"
+        } else {
+            "SELECT id, pin_order FROM entries WHERE pinned = 1 AND pin_order > ?1 \
+             ORDER BY pin_order ASC LIMIT 1"
+        };
+        let Some((other, other_order)) = conn
+            .query_row(neighbour, params![order], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+            .optional()?
+        else {
+            return Ok(());
+        };
+
+        conn.execute("UPDATE entries SET pin_order = ?1 WHERE id = ?2", params![other_order, id as i64])?;
+        conn.execute("UPDATE entries SET pin_order = ?1 WHERE id = ?2", params![order, other])?;
+        tracing::info!(id, up, "moved pinned entry");
+        Ok(())
+    }
+
+    /// Move archived entry `id` back into the history, at the top as if just
+    /// pasted, returning its id there; `None` if it is not archived.
+    pub fn unarchive(&self, id: u64) -> Result<Option<u64>> {
+        let conn = self.conn.lock();
+        if !cold::attach(&conn, &self.path, false)? {
+            return Ok(None);
+        }
+        let entry = conn
+            .query_row(
+                &format!("SELECT {} FROM archive.entries WHERE id = ?1", cold::ENTRY_COLUMNS),
+                params![id as i64],
+                |row| self.entry_from_row(row),
+            )
+            .optional()?;
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        let tx = conn.unchecked_transaction()?;
+        // Copied again since it was archived: the history has it already.
+        let existing: Option<i64> = tx
+            .query_row("SELECT id FROM entries WHERE hash = ?1", params![&entry.hash], |row| row.get(0))
+            .optional()?;
+        let restored = match existing {
+            Some(restored) => restored,
+            None => {
+                // Groups name ids the entries had before they were archived.
+                insert_row(&tx, &entry, entry.session, None)?;
+                tx.last_insert_rowid()
+            }
+        };
+        tx.execute(
+            "UPDATE entries SET last_used_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), restored],
+        )?;
+        tx.execute("DELETE FROM archive.entries WHERE id = ?1", params![id as i64])?;
+        tx.commit()?;
+        drop(conn);
+        tracing::info!(id, restored, "restored archived entry");
+
+        self.cleanup_old_entries()?;
+        Ok(Some(restored as u64))
+    }
+
+    /// Let quarantined entries from `process` into the history, returning how many there were.
+    pub fn release_quarantine(&self, process: &str) -> Result<usize> {
+        let conn = self.conn.lock();
+        let released = conn.execute(
+            "UPDATE entries SET quarantined = 0 WHERE quarantined = 1 AND source_process = ?1 COLLATE NOCASE",
+            params![process],
+        )?;
+        tracing::info!(process, released, "released quarantined entries");
+        Ok(released)
+    }
+
+    /// The title looked up before for the issue `reference`.
+    pub fn issue_title(&self, reference: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        conn.query_row("SELECT title FROM issues WHERE reference = ?1", params![reference], |row| row.get(0))
+            .optional()
+            .context("failed to read an issue title")
+    }
+
+    /// Remember `title` for the issue `reference`, and show it with the
+    /// issues written `written` in entries, whose searches then find it too.
+    pub fn set_issue_title(&self, written: &str, reference: &str, title: &str) -> Result<usize> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO issues (reference, title) VALUES (?1, ?2)",
+            params![reference, title],
+        )?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, entities, search_text FROM entries
+             WHERE id IN (SELECT entry_id FROM entities WHERE kind = 'issue' AND value = ?1)",
+        )?;
+        let rows = stmt
+            .query_map(params![written], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut updated = 0;
+        for (id, json, search_text) in rows {
+            let mut entities: Vec<Entity> = serde_json::from_str(&json)?;
+            let untitled = entities
+                .iter_mut()
+                .filter(|entity| entity.kind == EntityKind::Issue && entity.value == written)
+                .filter(|entity| entity.title.as_deref() != Some(title));
+            let mut changed = false;
+            for entity in untitled {
+                entity.title = Some(title.to_string());
+                changed = true;
+            }
+            if !changed {
+                continue;
+            }
+            let search_text = format!("{}\n{}", search_text.unwrap_or_default(), fold::fold(title));
+            conn.execute(
+                "UPDATE entries SET entities = ?1, search_text = ?2 WHERE id = ?3",
+                params![serde_json::to_string(&entities)?, search_text, id],
+            )?;
+            updated += 1;
+        }
+        tracing::info!(reference, updated, "added issue title to entries");
+        Ok(updated)
+    }
+
+    /// Remove quarantined entries past [`QUARANTINE_TTL`], and retire old
+    /// entries if the database exceeds max_entries or max_bytes; pinned
+    /// entries are kept and not counted
     fn cleanup_old_entries(&self) -> Result<()> {
         let conn = self.conn.lock();
+
+        let cutoff = (Utc::now() - QUARANTINE_TTL).to_rfc3339();
+        let purged = conn.execute(
+            "DELETE FROM entries WHERE quarantined = 1 AND created_at < ?1",
+            params![cutoff],
+        )?;
+        if purged > 0 {
+            tracing::info!(purged, "purged quarantined entries");
+        }
         
         // Count total entries
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM entries",
+            "SELECT COUNT(*) FROM entries WHERE COALESCE(pinned, 0) = 0",
             [],
             |row| row.get(0),
         )?;
         
-        if count as usize > self.max_entries {
-            let to_delete = count as usize - self.max_entries;
+        let max_entries = self.max_entries.load(Ordering::Relaxed);
+        if count as usize > max_entries {
+            let to_delete = count as usize - max_entries;
             
-            conn.execute(
+            self.retire(
+                &conn,
                 r#"
-                DELETE FROM entries WHERE id IN (
-                    SELECT id FROM entries 
-                    ORDER BY created_at ASC 
-                    LIMIT ?1
-                )
+                SELECT id FROM entries WHERE COALESCE(pinned, 0) = 0
+                ORDER BY COALESCE(last_used_at, created_at) ASC
+                LIMIT ?1
                 "#,
                 params![to_delete as i64],
             )?;
             
             tracing::info!(
                 deleted = to_delete, 
-                remaining = self.max_entries,
+                remaining = max_entries,
                 "cleaned up old entries"
             );
         }
+
+        let max_bytes = self.max_bytes.load(Ordering::Relaxed);
+        if max_bytes > 0 {
+            let order = self.eviction.lock().order();
+            let mut evicted = 0;
+            loop {
+                let used = content_bytes(&conn)?;
+                if used <= max_bytes {
+                    break;
+                }
+                // Enough entries, in eviction order, to free the excess; an
+                // image shared with a kept entry frees nothing, hence the loop.
+                let deleted = self.retire(
+                    &conn,
+                    &format!(
+                        r#"
+                        SELECT id FROM (
+                            SELECT id, size, SUM(size) OVER (ORDER BY {order} ROWS UNBOUNDED PRECEDING) AS freed
+                            FROM (
+                                SELECT id, last_used_at, created_at, {ROW_BYTES}
+                                    + COALESCE((SELECT LENGTH(blobs.data) FROM blobs WHERE blobs.hash = entries.blob_hash), 0) AS size
+                                FROM entries WHERE COALESCE(pinned, 0) = 0
+                            )
+                        )
+                        WHERE freed - size < ?1
+                        "#
+                    ),
+                    params![(used - max_bytes) as i64],
+                )?;
+                if deleted == 0 {
+                    tracing::warn!(used, max_bytes, "pinned entries alone exceed general.max_db_bytes");
+                    break;
+                }
+                evicted += deleted;
+            }
+            if evicted > 0 {
+                tracing::info!(evicted, max_bytes, "evicted entries over the size budget");
+            }
+        }
         
         Ok(())
     }
 
-    /// Export all entries to a JSON file
-    pub fn export_to_json(&self, path: &str) -> Result<()> {
+    /// Delete the entries `victims` selects the ids of, archiving them first
+    /// when archiving is on, and return how many there were.
+    fn retire(&self, conn: &Connection, victims: &str, params: impl rusqlite::Params) -> Result<usize> {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS retiring (id INTEGER PRIMARY KEY);
+             DELETE FROM temp.retiring;",
+        )?;
+        tx.execute(&format!("INSERT INTO temp.retiring (id) {victims}"), params)?;
+        if self.archive.load(Ordering::Relaxed) {
+            let archived = cold::store(&tx)?;
+            tracing::debug!(archived, "archived retired entries");
+        }
+        let retired = tx.execute("DELETE FROM entries WHERE id IN (SELECT id FROM temp.retiring)", [])?;
+        tx.commit()?;
+        Ok(retired)
+    }
+
+    /// Export all entries, or only those of `session`, to a JSON file,
+    /// masking secrets when a `redactor` is given and encrypting the file
+    /// when a `protection` is given.
+    pub fn export_to_json(
+        &self,
+        path: &str,
+        time: &TimeDisplay,
+        session: Option<u64>,
+        redactor: Option<&Redactor>,
+        protection: Option<Protection>,
+    ) -> Result<()> {
         let conn = self.conn.lock();
         
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, created_at, kind, text, data, bytes_len, hash, source_process, tags
-            FROM entries
-            ORDER BY created_at ASC
-            "#,
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {ENTRY_COLUMNS} FROM entries WHERE ?1 IS NULL OR session = ?1 ORDER BY created_at ASC"
+        ))?;
         
-        let entries = stmt
-            .query_map([], |row| {
+        let mut entries = stmt
+            .query_map(params![session.map(|session| session as i64)], |row| {
                 self.entry_from_row(row)
             })?
             .collect::<Result<Vec<_>, _>>()?;
         
         drop(stmt);
         drop(conn);
+
+        if let Some(redactor) = redactor {
+            let redacted = entries
+                .iter_mut()
+                .map(|entry| redactor.redact(entry))
+                .filter(|&redacted| redacted)
+                .count();
+            tracing::info!(redacted, "redacted secrets from export");
+        }
         
-        let file = File::create(path)
-            .with_context(|| format!("failed to create export file: {}", path))?;
-        let writer = BufWriter::new(file);
-        
-        serde_json::to_writer_pretty(writer, &entries)
-            .with_context(|| "failed to write JSON")?;
+        let exported: Vec<_> = entries
+            .iter()
+            .map(|entry| ExportedEntry {
+                entry,
+                created_display: time.format(entry.created_at),
+            })
+            .collect();
+
+        let encrypted = protection.is_some();
+        if let Some(protection) = protection {
+            let json = serde_json::to_vec_pretty(&exported)
+                .with_context(|| "failed to serialize JSON")?;
+            std::fs::write(path, protection.apply(&json)?)
+                .with_context(|| format!("failed to write export file: {}", path))?;
+        } else {
+            let file = File::create(path)
+                .with_context(|| format!("failed to create export file: {}", path))?;
+            let writer = BufWriter::new(file);
+            serde_json::to_writer_pretty(writer, &exported)
+                .with_context(|| "failed to write JSON")?;
+        }
         
-        tracing::info!(count = entries.len(), "exported entries to {}", path);
+        tracing::info!(count = entries.len(), encrypted, "exported entries to {}", path);
         Ok(())
     }
 
-    /// Import entries from a JSON file
-    pub fn import_from_json(&self, path: &str) -> Result<()> {
-        let file = File::open(path)
+    /// Import entries from a JSON file, or from an encrypted export with its
+    /// `passphrase` or one of our `keys`.
+    pub fn import_from_json(&self, path: &str, passphrase: Option<&str>, keys: &KeyRing) -> Result<()> {
+        let contents = std::fs::read(path)
             .with_context(|| format!("failed to open import file: {}", path))?;
-        let reader = BufReader::new(file);
+        let contents = archive::open(contents, passphrase, keys)
+            .with_context(|| format!("failed to read {path}"))?;
+        let (imported, skipped) = self.import_json(&contents)?;
+        
+        tracing::info!(
+            imported, 
+            skipped, 
+            "imported entries from {}", 
+            path
+        );
         
-        let entries: Vec<Entry> = serde_json::from_reader(reader)
+        Ok(())
+    }
+
+    /// Add the entries of an export's JSON that are not stored yet, returning
+    /// how many were added and how many were already there.
+    pub fn import_json(&self, json: &[u8]) -> Result<(usize, usize)> {
+        let entries: Vec<Entry> = serde_json::from_slice(json)
             .with_context(|| "failed to parse JSON")?;
+        self.backup("import")?;
         
         let mut imported = 0;
         let mut skipped = 0;
@@ -304,64 +1364,36 @@ impl Database {
                 continue;
             }
             
-            let kind_str = match entry.kind {
-                EntryKind::Text => "text",
-                EntryKind::Url => "url",
-                EntryKind::Image => "image",
-                EntryKind::Rtf => "rtf",
-            };
-            
-            let tags_json = serde_json::to_string(&entry.tags)?;
-            
-            conn.execute(
-                r#"
-                INSERT INTO entries (created_at, kind, text, data, bytes_len, hash, source_process, tags)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-                "#,
-                params![
-                    entry.created_at.to_rfc3339(),
-                    kind_str,
-                    &entry.text,
-                    &entry.data,
-                    entry.bytes_len as i64,
-                    &entry.hash,
-                    &entry.source_process,
-                    tags_json,
-                ],
-            )?;
+            // Session ids are local to the database they were captured in.
+            // Groups name ids of the database the entries came from.
+            insert_row(&conn, &entry, None, None)?;
             
             imported += 1;
             drop(conn);
         }
         
-        tracing::info!(
-            imported, 
-            skipped, 
-            "imported entries from {}", 
-            path
-        );
-        
-        Ok(())
+        Ok((imported, skipped))
     }
 
     fn entry_from_row(&self, row: &rusqlite::Row) -> rusqlite::Result<Entry> {
         let kind_str: String = row.get(2)?;
-        let kind = match kind_str.as_str() {
-            "text" => EntryKind::Text,
-            "url" => EntryKind::Url,
-            "image" => EntryKind::Image,
-            "rtf" => EntryKind::Rtf,
-            _ => EntryKind::Text,
-        };
+        let kind = EntryKind::from_name(&kind_str).unwrap_or(EntryKind::Text);
         
-        let created_at_str: String = row.get(1)?;
-        let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .unwrap_or_else(|_| chrono::Utc::now());
+        let created_at = parse_timestamp(&row.get::<_, String>(1)?);
         
         let tags_json: String = row.get(8)?;
         let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
         
+        let image = match (row.get::<_, Option<u32>>(9)?, row.get::<_, Option<u32>>(10)?) {
+            (Some(width), Some(height)) => Some(ImageInfo {
+                width,
+                height,
+                dpi: row.get(11)?,
+                monitor: row.get(12)?,
+            }),
+            _ => None,
+        };
+        
         Ok(Entry {
             id: Some(row.get(0)?),
             created_at,
@@ -372,7 +1404,389 @@ impl Database {
             hash: row.get(6)?,
             source_process: row.get(7)?,
             tags,
+            image,
+            title: row.get(13)?,
+            session: row.get::<_, Option<i64>>(14)?.map(|session| session as u64),
+            quarantined: row.get::<_, Option<bool>>(15)?.unwrap_or(false),
+            lang: row.get(16)?,
+            pinned: row.get::<_, Option<bool>>(17)?.unwrap_or(false),
+            text_extracted: row.get(18)?,
+            copy_group: row.get::<_, Option<i64>>(19)?.map(|group| group as u64),
+            entities: row
+                .get::<_, Option<String>>(20)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            use_count: row.get(21)?,
         })
     }
 }
 
+/// An exported entry: the importable fields plus a human-readable timestamp,
+/// which `import_from_json` ignores.
+#[derive(Serialize)]
+struct ExportedEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a Entry,
+    created_display: String,
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn existing_columns(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('entries')")?;
+    let existing = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(existing)
+}
+
+/// Whether [`migrate`] will change the schema.
+fn needs_migration(conn: &Connection) -> Result<bool> {
+    let existing = existing_columns(conn)?;
+    Ok(ADDED_COLUMNS.iter().any(|(column, _)| !existing.iter().any(|name| name == column)))
+}
+
+/// Bring databases created by older versions up to the current schema,
+/// returning the columns that had to be added.
+fn migrate(conn: &Connection) -> Result<Vec<&'static str>> {
+    let existing = existing_columns(conn)?;
+
+    let mut added = Vec::new();
+    for &(column, sql_type) in ADDED_COLUMNS {
+        if !existing.iter().any(|name| name == column) {
+            tracing::info!(column, "adding column to entries table");
+            conn.execute(&format!("ALTER TABLE entries ADD COLUMN {column} {sql_type}"), [])
+                .with_context(|| format!("failed to add column {column}"))?;
+            added.push(column);
+        }
+    }
+    Ok(added)
+}
+
+/// Derive titles for entries stored before titles existed.
+fn backfill_titles(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, kind, text FROM entries WHERE text IS NOT NULL")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut updated = 0;
+    for (id, kind, text) in rows {
+        let kind = EntryKind::from_name(&kind).unwrap_or(EntryKind::Text);
+        if let Some(title) = title::derive(&kind, &text) {
+            conn.execute("UPDATE entries SET title = ?1 WHERE id = ?2", params![title, id])?;
+            updated += 1;
+        }
+    }
+    tracing::info!(updated, "derived titles for existing entries");
+    Ok(())
+}
+
+/// Create the full-text index, filling it from the entries when it is new.
+fn create_fts(conn: &Connection) -> Result<()> {
+    let existed: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'entries_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(FTS_SCHEMA)
+        .context("failed to create the search index")?;
+    if !existed {
+        conn.execute("INSERT INTO entries_fts (entries_fts) VALUES ('rebuild')", [])
+            .context("failed to build the search index")?;
+        tracing::info!("built the search index");
+    }
+    Ok(())
+}
+
+/// Create the entities table, finding the entities of every entry first
+/// when it is new: entries stored before entities were have none, and the
+/// ones before the ops kinds lack those. Entries stored before issues were
+/// are searched again when the issue table is new.
+fn create_entity_table(conn: &Connection) -> Result<()> {
+    let table_exists = |name: &str| -> Result<bool> {
+        let exists = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            params![name],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    };
+    let existed = table_exists("entities")?;
+    let had_issues = table_exists("issues")?;
+    if !existed {
+        backfill_entities(conn)?;
+    }
+    conn.execute_batch(ENTITY_SCHEMA)
+        .context("failed to create the entities table")?;
+    if !existed {
+        conn.execute(
+            "INSERT INTO entities (entry_id, kind, value)
+             SELECT entries.id, json_extract(value, '$.kind'), json_extract(value, '$.value')
+             FROM entries, json_each(entries.entities)",
+            [],
+        )
+        .context("failed to fill the entities table")?;
+    } else if !had_issues {
+        // The triggers keep the table in step.
+        backfill_entities(conn)?;
+    }
+    conn.execute_batch(ISSUE_SCHEMA)
+        .context("failed to create the issue table")?;
+    Ok(())
+}
+
+/// Fold the text of entries stored before searches were folded.
+fn backfill_search_text(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, text FROM entries WHERE text IS NOT NULL")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    for (id, text) in &rows {
+        conn.execute(
+            "UPDATE entries SET search_text = ?1 WHERE id = ?2",
+            params![fold::fold(text), id],
+        )?;
+    }
+    tracing::info!(updated = rows.len(), "folded text of existing entries for search");
+    Ok(())
+}
+
+/// Detect the language of entries stored before languages were.
+fn backfill_languages(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, kind, text FROM entries WHERE text IS NOT NULL")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut updated = 0;
+    for (id, kind, text) in rows {
+        let kind = EntryKind::from_name(&kind).unwrap_or(EntryKind::Text);
+        if let Some(lang) = lang::detect(&kind, &text) {
+            conn.execute("UPDATE entries SET lang = ?1 WHERE id = ?2", params![lang, id])?;
+            updated += 1;
+        }
+    }
+    tracing::info!(updated, "detected languages of existing entries");
+    Ok(())
+}
+
+/// Extract the text of RTF and HTML entries stored before it was, and make
+/// them searchable by it.
+fn backfill_extracted_text(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, data FROM entries WHERE kind IN ('rtf', 'html') AND data IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Vec<u8>>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut updated = 0;
+    for (id, kind, data) in rows {
+        let kind = EntryKind::from_name(&kind).unwrap_or(EntryKind::Rtf);
+        if let Some(text) = kind.extract_text(&data) {
+            conn.execute(
+                "UPDATE entries SET text_extracted = ?1, search_text = ?2, lang = ?3 WHERE id = ?4",
+                params![&text, fold::fold(&text), lang::detect(&kind, &text), id],
+            )?;
+            updated += 1;
+        }
+    }
+    tracing::info!(updated, "extracted text of existing documents");
+    Ok(())
+}
+
+/// Find the entities of existing entries again, with every kind found now.
+fn backfill_entities(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id, COALESCE(text_extracted, text) FROM entries WHERE COALESCE(text_extracted, text) IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut found = 0;
+    for (id, text) in rows {
+        let entities = entities_json(Some(&text))?;
+        found += usize::from(entities.is_some());
+        conn.execute("UPDATE entries SET entities = ?1 WHERE id = ?2", params![entities, id])?;
+    }
+    tracing::info!(found, "found entities in existing entries");
+    Ok(())
+}
+
+/// The `entities` column for an entry with `text`: a JSON array, or NULL
+/// when there are none.
+fn entities_json(text: Option<&str>) -> Result<Option<String>> {
+    let entities = text.map(entity::extract).unwrap_or_default();
+    if entities.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::to_string(&entities)?))
+}
+
+/// The copy group `entry` joins: that of the newest entry if it came from the
+/// same process within `window`, started by that entry if it has none yet.
+fn link_to_previous(conn: &Connection, entry: &Entry, window: chrono::Duration) -> Result<Option<u64>> {
+    let Some(source) = entry.source_process.as_deref() else {
+        return Ok(None);
+    };
+    if window <= chrono::Duration::zero() {
+        return Ok(None);
+    }
+    let previous = conn
+        .query_row(
+            "SELECT id, created_at, source_process, copy_group FROM entries ORDER BY id DESC LIMIT 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((id, created_at, previous_source, group)) = previous else {
+        return Ok(None);
+    };
+    let gap = entry.created_at - parse_timestamp(&created_at);
+    let same_source = previous_source.is_some_and(|previous| previous.eq_ignore_ascii_case(source));
+    if !same_source || gap < chrono::Duration::zero() || gap > window {
+        return Ok(None);
+    }
+    if group.is_none() {
+        conn.execute("UPDATE entries SET copy_group = ?1 WHERE id = ?1", params![id])?;
+    }
+    Ok(Some(group.unwrap_or(id) as u64))
+}
+
+fn insert_row(conn: &Connection, entry: &Entry, session: Option<u64>, copy_group: Option<u64>) -> Result<()> {
+    let tags_json = serde_json::to_string(&entry.tags)?;
+    let (data, blob_hash) = match &entry.data {
+        Some(data) if entry.kind == EntryKind::Image => (None, Some(store_blob(conn, data)?)),
+        data => (data.as_ref(), None),
+    };
+    let image = entry.image.as_ref();
+    // Imported entries from older exports carry only the document.
+    let extracted = entry
+        .text_extracted
+        .clone()
+        .or_else(|| entry.data.as_deref().and_then(|data| entry.kind.extract_text(data)));
+    let plain_text = extracted.as_deref().or(entry.text.as_deref());
+    
+    conn.execute(
+        r#"
+        INSERT INTO entries (created_at, kind, text, data, bytes_len, hash, source_process, tags,
+                             image_width, image_height, image_dpi, image_monitor, title, session,
+                             quarantined, search_text, lang, pinned, pin_order, text_extracted,
+                             copy_group, blob_hash, entities, use_count)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                CASE WHEN ?18 THEN (SELECT COALESCE(MAX(pin_order), 0) + 1 FROM entries WHERE pinned = 1) END,
+                ?19, ?20, ?21, ?22, ?23)
+        "#,
+        params![
+            entry.created_at.to_rfc3339(),
+            entry.kind.name(),
+            &entry.text,
+            data,
+            entry.bytes_len as i64,
+            &entry.hash,
+            &entry.source_process,
+            tags_json,
+            image.map(|image| image.width),
+            image.map(|image| image.height),
+            image.and_then(|image| image.dpi),
+            image.and_then(|image| image.monitor.as_deref()),
+            entry.text.as_deref().and_then(|text| title::derive(&entry.kind, text)),
+            session.map(|session| session as i64),
+            entry.quarantined,
+            plain_text.map(fold::fold),
+            plain_text.and_then(|text| lang::detect(&entry.kind, text)),
+            entry.pinned,
+            extracted,
+            copy_group.map(|group| group as i64),
+            blob_hash,
+            entities_json(plain_text)?,
+            entry.use_count,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Columns in an SQL column list: its commas outside parentheses, plus one.
+const fn column_count(columns: &str) -> usize {
+    let bytes = columns.as_bytes();
+    let (mut count, mut depth, mut i) = (1, 0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => count += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    count
+}
+
+fn content_bytes(conn: &Connection) -> Result<u64> {
+    let bytes: i64 = conn.query_row(
+        &format!(
+            "SELECT (SELECT COALESCE(SUM({ROW_BYTES}), 0) FROM entries)
+                  + (SELECT COALESCE(SUM(LENGTH(data)), 0) FROM blobs)"
+        ),
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(bytes as u64)
+}
+
+/// Make sure a blob holding `data` exists, returning its hash; the entry
+/// inserted with that hash adds the reference.
+fn store_blob(conn: &Connection, data: &[u8]) -> Result<String> {
+    let hash = format!("{:x}", Sha256::digest(data));
+    conn.execute(
+        "INSERT OR IGNORE INTO blobs (hash, data, refs) VALUES (?1, ?2, 0)",
+        params![hash, data],
+    )?;
+    Ok(hash)
+}
+
+/// Move the data of images stored before blobs existed into shared blobs.
+fn move_images_to_blobs(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, data FROM entries WHERE kind = 'image' AND data IS NOT NULL")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    for (id, data) in &rows {
+        let hash = store_blob(conn, data)?;
+        conn.execute(
+            "UPDATE entries SET data = NULL, blob_hash = ?1 WHERE id = ?2",
+            params![hash, id],
+        )?;
+        conn.execute("UPDATE blobs SET refs = refs + 1 WHERE hash = ?1", params![hash])?;
+    }
+    tracing::info!(moved = rows.len(), "moved existing images into shared blobs");
+    Ok(())
+}
+
@@ -0,0 +1,93 @@
+//! Plain text converted from RTF and HTML, with the formatting the preview
+//! can show.
+
+use serde::{Deserialize, Serialize};
+
+/// Character formatting the preview can show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Style {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+}
+
+/// Formatted text between byte offsets `start` and `end` of the plain text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleRun {
+    pub start: usize,
+    pub end: usize,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+impl From<StyleRun> for clipcore::StyleRun {
+    fn from(run: StyleRun) -> Self {
+        Self {
+            start: run.start,
+            end: run.end,
+            bold: run.style.bold,
+            italic: run.style.italic,
+            underline: run.style.underline,
+        }
+    }
+}
+
+/// Plain text of a document and where it is formatted.
+#[derive(Default)]
+pub struct Document {
+    pub text: String,
+    /// Formatted stretches in order; unformatted text has none.
+    pub styles: Vec<StyleRun>,
+}
+
+impl Document {
+    pub fn push(&mut self, c: char, style: Style) {
+        let start = self.text.len();
+        self.text.push(c);
+        if style == Style::default() {
+            return;
+        }
+        match self.styles.last_mut() {
+            Some(run) if run.end == start && run.style == style => run.end = self.text.len(),
+            _ => self.styles.push(StyleRun {
+                start,
+                end: self.text.len(),
+                style,
+            }),
+        }
+    }
+
+    pub fn push_str(&mut self, text: &str, style: Style) {
+        for c in text.chars() {
+            self.push(c, style);
+        }
+    }
+
+    /// Drop trailing whitespace, and formatting of the text dropped.
+    pub fn trim_end(&mut self) {
+        self.truncate(self.text.trim_end().len());
+    }
+
+    /// Drop trailing spaces and tabs, keeping line breaks.
+    pub fn trim_line_end(&mut self) {
+        self.truncate(self.text.trim_end_matches([' ', '\t']).len());
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.text.truncate(len);
+        // Runs are in order, so only the last few can reach past `len`.
+        while let Some(run) = self.styles.last_mut() {
+            if run.end <= len {
+                break;
+            }
+            if run.start < len {
+                run.end = len;
+                break;
+            }
+            self.styles.pop();
+        }
+    }
+}
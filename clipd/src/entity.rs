@@ -0,0 +1,198 @@
+//! Things in copied text worth acting on by themselves, found when an entry
+//! is stored, for `has:` filters and the TUI's copy, mail, and call actions:
+//! email addresses and phone numbers, for ops work IP addresses, UUIDs,
+//! commit hashes, and container ids, and Jira and GitHub issue references.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::{Range, RangeInclusive};
+
+use clipcore::{Entity, EntityKind};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Entities kept per entry; past this the text is a directory dump, not
+/// something to act on address by address.
+const MAX_ENTITIES: usize = 20;
+
+/// Digits a phone number has, its country code included; E.164 allows 15.
+const PHONE_DIGITS: RangeInclusive<usize> = 7..=15;
+
+/// Characters that join hex ids into longer tokens, like file names and
+/// the groups of a UUID.
+const HEX_JOINS: &str = "-_.";
+
+/// Names written like Jira keys that are standards and algorithms instead:
+/// `UTF-8`, `SHA-256`, `ISO-9001`, `RFC-2119`.
+const NOT_JIRA: &[&str] = &["UTF", "SHA", "ISO", "RFC", "CVE", "AES", "TLS", "PEP", "COVID"];
+
+static EMAIL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b[a-z0-9][a-z0-9._%+-]*@[a-z0-9-]+(\.[a-z0-9-]+)*\.[a-z]{2,}\b").expect("email pattern is valid")
+});
+
+/// Digits with the separators phone numbers are written with; [`is_phone`]
+/// tells which of these are phone numbers.
+static PHONE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\+?\(?\d[\d ().-]{5,20}\d").expect("phone pattern is valid"));
+
+/// Dates and dotted quads (IP addresses, versions), which are written much
+/// like phone numbers.
+static NOT_PHONE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{1,4}[-./]\d{1,2}[-./]\d{1,4}|\d{1,3}(\.\d{1,3}){3})$").expect("date pattern is valid")
+});
+
+static UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b").expect("uuid pattern is valid")
+});
+
+static IPV4: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d{1,3}(\.\d{1,3}){3}\b").expect("ipv4 pattern is valid"));
+
+/// Colon-separated hex groups, with an IPv4 address at the end for mapped
+/// addresses; `Ipv6Addr` tells which are addresses rather than times.
+static IPV6: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)[0-9a-f]{0,4}(:[0-9a-f]{0,4}){2,7}(\.\d{1,3}){0,3}").expect("ipv6 pattern is valid")
+});
+
+/// A Jira key: the project in capitals, then the issue's number.
+static JIRA: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b[A-Z][A-Z0-9_]{1,9}-[1-9]\d{0,6}\b").expect("jira pattern is valid"));
+
+/// A GitHub issue or pull request, in its own repository (`#456`) or
+/// another (`owner/repo#789`).
+static GITHUB_ISSUE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:\b[A-Za-z0-9][A-Za-z0-9-]*/[A-Za-z0-9._-]+)?#[1-9]\d{0,6}\b").expect("issue pattern is valid")
+});
+
+/// Lowercase hex, as git and Docker print ids; [`hex_kind`] tells which
+/// lengths are which.
+static HEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[0-9a-f]{7,64}\b").expect("hex pattern is valid"));
+
+/// The entities in `text`, in order, each once.
+pub fn extract(text: &str) -> Vec<Entity> {
+    let emails = EMAIL
+        .find_iter(text)
+        .map(|found| (found.range(), EntityKind::Email));
+    let jira = JIRA
+        .find_iter(text)
+        .filter(|found| standalone(text, found.range(), "-") && !is_standard(found.as_str()))
+        .map(|found| (found.range(), EntityKind::Issue));
+    let github = GITHUB_ISSUE
+        .find_iter(text)
+        .filter(|found| is_issue_reference(text, found.start()))
+        .map(|found| (found.range(), EntityKind::Issue));
+    let uuids = UUID
+        .find_iter(text)
+        .filter(|found| standalone(text, found.range(), HEX_JOINS))
+        .map(|found| (found.range(), EntityKind::Uuid));
+    // `::` by itself is Haskell or C++ far more often than an address.
+    let ipv6 = IPV6
+        .find_iter(text)
+        .filter(|found| found.as_str().bytes().any(|b| b.is_ascii_hexdigit()))
+        .filter(|found| standalone(text, found.range(), ":.") && found.as_str().parse::<Ipv6Addr>().is_ok())
+        .map(|found| (found.range(), EntityKind::Ipv6));
+    let ipv4 = IPV4
+        .find_iter(text)
+        .filter(|found| standalone(text, found.range(), ".") && found.as_str().parse::<Ipv4Addr>().is_ok())
+        .map(|found| (found.range(), EntityKind::Ipv4));
+    let hashes = HEX
+        .find_iter(text)
+        .filter(|found| standalone(text, found.range(), HEX_JOINS) && !in_diff_index(text, found.start()))
+        .filter_map(|found| Some((found.range(), hex_kind(found.as_str())?)));
+    let phones = PHONE
+        .find_iter(text)
+        .filter(|found| is_phone(text, found.start(), found.as_str()))
+        .map(|found| (found.range(), EntityKind::Phone));
+
+    // Where matches overlap the earlier kind wins, so the digits of a UUID
+    // or an address are not read as a phone number too, nor the repository
+    // of an issue as a commit.
+    let mut found: Vec<(Range<usize>, EntityKind)> = Vec::new();
+    let issues = jira.chain(github);
+    for (range, kind) in emails.chain(issues).chain(uuids).chain(ipv6).chain(ipv4).chain(hashes).chain(phones) {
+        if !found.iter().any(|(taken, _)| range.start < taken.end && taken.start < range.end) {
+            found.push((range, kind));
+        }
+    }
+    found.sort_by_key(|(range, _)| range.start);
+
+    let mut entities: Vec<Entity> = Vec::new();
+    for (range, kind) in found {
+        if entities.len() == MAX_ENTITIES {
+            break;
+        }
+        let value = &text[range];
+        if !entities.iter().any(|entity| entity.kind == kind && entity.value == value) {
+            entities.push(Entity {
+                kind,
+                value: value.to_string(),
+                title: None,
+            });
+        }
+    }
+    entities
+}
+
+/// Whether `candidate`, found at byte `start` of `text`, reads as a phone
+/// number: enough digits, written with a `+` or separators, and standing on
+/// its own rather than inside an id, path, or address.
+fn is_phone(text: &str, start: usize, candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    let before = text[..start].chars().next_back();
+    let after = text[start + candidate.len()..].chars().next();
+    let inside_token = before.is_some_and(|c| c.is_alphanumeric() || "-./:_#=&@".contains(c))
+        || after.is_some_and(|c| c.is_alphanumeric() || "/_@".contains(c));
+    // A bare run of digits is an id or a timestamp far more often.
+    let written_as_phone =
+        candidate.starts_with('+') || candidate.contains([' ', '-', '(']) || candidate.matches('.').count() >= 2;
+    PHONE_DIGITS.contains(&digits) && written_as_phone && !inside_token && !NOT_PHONE.is_match(candidate)
+}
+
+/// What a run of hex is by its length: 12 and 64 digits are container ids,
+/// the short one `docker ps` lists and the full one; 40 digits and 7 to 11
+/// are commits, full and abbreviated. Words spelled in hex (`effaced`) and
+/// plain numbers are neither, so it needs a letter and a digit.
+fn hex_kind(hex: &str) -> Option<EntityKind> {
+    if !hex.bytes().any(|b| b.is_ascii_digit()) || !hex.bytes().any(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    match hex.len() {
+        12 | 64 => Some(EntityKind::Container),
+        7..=11 | 40 => Some(EntityKind::Sha),
+        _ => None,
+    }
+}
+
+/// Whether `key`, written like a Jira key, names a standard rather than an issue.
+fn is_standard(key: &str) -> bool {
+    key.split_once('-').is_some_and(|(project, _)| NOT_JIRA.contains(&project))
+}
+
+/// Whether the GitHub reference found at byte `start` of `text` begins a
+/// word, rather than ending a URL or path (`page.html#12`) or an HTML
+/// character reference (`&#38;`).
+fn is_issue_reference(text: &str, start: usize) -> bool {
+    text[..start]
+        .chars()
+        .next_back()
+        .is_none_or(|c| c.is_whitespace() || "([{,;:'\"".contains(c))
+}
+
+/// Whether byte `at` of `text` is on the `index` line of a git diff, whose
+/// ids name file contents rather than commits.
+fn in_diff_index(text: &str, at: usize) -> bool {
+    let line = text[..at].rfind('\n').map_or(0, |newline| newline + 1);
+    text[line..].starts_with("index ")
+}
+
+/// Whether `range` of `text` stands on its own rather than inside a longer
+/// token: no letter or digit on either side, nor one of `joins` leading to one.
+fn standalone(text: &str, range: Range<usize>, joins: &str) -> bool {
+    !continues(text[..range.start].chars().rev(), joins) && !continues(text[range.end..].chars(), joins)
+}
+
+/// Whether `chars`, read away from a match, carry on its token.
+fn continues(mut chars: impl Iterator<Item = char>, joins: &str) -> bool {
+    match chars.next() {
+        Some(c) if c.is_alphanumeric() => true,
+        Some(c) if joins.contains(c) => chars.next().is_some_and(char::is_alphanumeric),
+        _ => false,
+    }
+}
@@ -0,0 +1,61 @@
+//! Files copied in Explorer: `CF_HDROP` data, a `DROPFILES` header followed
+//! by the paths, each ending in a NUL and the list in another.
+//!
+//! The header is five 32-bit fields: the offset of the paths, the drop
+//! point's x and y, whether that point is in the non-client area, and whether
+//! the paths are UTF-16 rather than the ANSI code page.
+
+/// Size of `DROPFILES`.
+const HEADER_LEN: usize = 20;
+
+/// Paths in `CF_HDROP` data; `None` when it is malformed or lists none.
+///
+/// ANSI paths are read as Latin-1, which only differs from the code page for
+/// names Explorer would have sent as UTF-16 anyway.
+pub fn parse_drop(data: &[u8]) -> Option<Vec<String>> {
+    let field = |index: usize| {
+        let bytes = data.get(index * 4..index * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let offset = usize::try_from(field(0)?).ok()?;
+    let wide = field(4)? != 0;
+    let list = data.get(offset.max(HEADER_LEN)..)?;
+
+    let paths: Vec<String> = if wide {
+        let units: Vec<u16> = list
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        units
+            .split(|&unit| unit == 0)
+            .take_while(|path| !path.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect()
+    } else {
+        list.split(|&byte| byte == 0)
+            .take_while(|path| !path.is_empty())
+            .map(|path| path.iter().map(|&byte| char::from(byte)).collect())
+            .collect()
+    };
+    (!paths.is_empty()).then_some(paths)
+}
+
+/// `CF_HDROP` data listing `paths` as UTF-16, as Explorer writes it.
+pub fn encode_drop<S: AsRef<str>>(paths: &[S]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_LEN);
+    data.extend_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+    // Drop point and non-client flag, which mean nothing on the clipboard.
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    for path in paths {
+        data.extend(path.as_ref().encode_utf16().chain(Some(0)).flat_map(u16::to_le_bytes));
+    }
+    data.extend_from_slice(&[0, 0]);
+    data
+}
+
+/// Last component of `path`, with either separator, for showing a file by name.
+pub fn file_name(path: &str) -> &str {
+    let path = path.trim_end_matches(['\\', '/']);
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
@@ -0,0 +1,33 @@
+//! Search folding: text reduced to a form where "cafe" matches "Café" and
+//! fullwidth "ＡＢＣ" matches "abc".
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Compatibility-decompose `text` (NFKD), drop the accents that splits off,
+/// recompose (NFKC), and case-fold.
+///
+/// Stored in `entries.search_text` and applied to queries, so both sides of a
+/// `LIKE` are compared in the same form.
+pub fn fold(text: &str) -> String {
+    text.nfkd()
+        .filter(|&c| !is_combining_mark(c))
+        .nfkc()
+        .flat_map(case_fold)
+        .collect()
+}
+
+/// Full case folding for the characters where it differs from lowercasing.
+fn case_fold(c: char) -> impl Iterator<Item = char> {
+    let folded: Option<&'static str> = match c {
+        'ß' | 'ẞ' => Some("ss"),
+        'ς' => Some("σ"),
+        'ſ' => Some("s"),
+        _ => None,
+    };
+    let (special, lower) = match folded {
+        Some(folded) => (Some(folded.chars()), None),
+        None => (None, Some(c.to_lowercase())),
+    };
+    special.into_iter().flatten().chain(lower.into_iter().flatten())
+}
@@ -0,0 +1,58 @@
+//! Entry points for the cargo-fuzz targets in `fuzz/`.
+//!
+//! Each takes arbitrary bytes in place of input clipd does not control (a
+//! client's request frames, an import file, or a document another app put on
+//! the clipboard) and runs them through the same code the daemon does. Errors
+//! are expected; panics, hangs, and runaway allocations are the bugs.
+
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use tokio::runtime::Runtime;
+
+use crate::db::Database;
+use crate::ipc;
+use crate::model::EntryKind;
+use crate::timefmt::TimeDisplay;
+
+/// Drives the async frame reader; reads from a byte slice never wait.
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to build the fuzzing runtime")
+});
+
+/// One request frame as a client sends it: a little-endian length, then JSON.
+pub fn request_frame(data: &[u8]) {
+    let Some((len, mut body)) = data.get(..4).zip(data.get(4..)) else {
+        return;
+    };
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]);
+    let _ = RUNTIME.block_on(clipcore::read_request(&mut body, len));
+}
+
+/// The JSON of an export file, imported into an empty history and then listed
+/// and saved the way clients do with each entry.
+pub fn import(data: &[u8]) {
+    // A fresh database per input keeps crashes reproducible from one file.
+    let db = Database::open(PathBuf::from(":memory:"), 1000, 0)
+        .expect("failed to open an in-memory database");
+    if db.import_json(data).is_err() {
+        return;
+    }
+    let time = TimeDisplay::default();
+    for entry in db.list_recent(1000).expect("failed to list imported entries") {
+        let _ = entry.file_contents();
+        ipc::entry_summary(entry, &time);
+    }
+}
+
+/// Clipboard data in the "HTML Format" (CF_HTML).
+pub fn html(data: &[u8]) {
+    EntryKind::Html.parse_document(data);
+}
+
+/// Clipboard data in the "Rich Text Format".
+pub fn rtf(data: &[u8]) {
+    EntryKind::Rtf.parse_document(data);
+}
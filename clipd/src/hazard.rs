@@ -0,0 +1,66 @@
+//! Heuristics for copied text that is dangerous to paste into a shell
+//! ("pastejacking"): hidden characters, piped installers, lookalike URLs.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `curl ... | sh`, `iwr ... | iex` and friends: a download run unseen.
+static PIPE_TO_SHELL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(curl|wget|iwr|irm|invoke-webrequest|invoke-restmethod)\b[^|\n]*\|\s*(sudo\s+)?(sh|bash|zsh|dash|python3?|perl|iex|invoke-expression|powershell|pwsh)\b",
+    )
+    .expect("pipe-to-shell pattern is valid")
+});
+
+/// `sudo rm` at the start of a command, including one chained after another.
+static SUDO_RM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)(^|[;&|]\s*)\s*sudo\s+rm\b").expect("sudo rm pattern is valid")
+});
+
+static URL_HOST: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[a-z][a-z0-9+.-]*://([^/\s:?#@]+)").expect("URL pattern is valid"));
+
+/// Reasons `text` looks dangerous to paste, in a fixed order; empty if none apply.
+pub fn scan(text: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if text.chars().any(is_invisible) {
+        warnings.push("invisible characters".to_string());
+    }
+    if PIPE_TO_SHELL.is_match(text) {
+        warnings.push("pipes a download into a shell".to_string());
+    }
+    if URL_HOST
+        .captures_iter(text)
+        .any(|captures| is_lookalike_host(&captures[1]))
+    {
+        warnings.push("URL with lookalike characters".to_string());
+    }
+    if SUDO_RM.is_match(text) {
+        warnings.push("runs sudo rm".to_string());
+    }
+    warnings
+}
+
+/// Characters that render as nothing, or reorder the text around them.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'                      // soft hyphen
+            | '\u{180E}'                // Mongolian vowel separator
+            | '\u{200B}'..='\u{200F}'   // zero-width space/joiners, LRM, RLM
+            | '\u{202A}'..='\u{202E}'   // bidi embeddings and overrides
+            | '\u{2060}'..='\u{2064}'   // word joiner, invisible operators
+            | '\u{2066}'..='\u{2069}'   // bidi isolates
+            | '\u{FEFF}'                // zero-width no-break space (BOM)
+            | '\u{E0000}'..='\u{E007F}' // tag characters
+    )
+}
+
+/// Hosts spelled with non-ASCII letters, or their punycode form, can pass for
+/// a familiar domain (`аpple.com` with a Cyrillic `а`).
+fn is_lookalike_host(host: &str) -> bool {
+    !host.is_ascii()
+        || host
+            .split('.')
+            .any(|label| label.len() > 4 && label[..4].eq_ignore_ascii_case("xn--"))
+}
@@ -0,0 +1,235 @@
+//! Plain text of HTML copied from browsers and Office, for search and preview.
+//!
+//! The clipboard's "HTML Format" is a CF_HTML header followed by a document
+//! whose copied part is marked with `<!--StartFragment-->` comments; only that
+//! part is converted. Tags are dropped, block elements end lines, list items
+//! get bullets or numbers, table cells are separated by tabs, and character
+//! references are decoded. Headings and bold, italic, underlined, and linked
+//! text keep their formatting for the preview.
+
+use crate::document::{Document, Style};
+
+/// Elements whose content is never shown.
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "head", "title", "template", "noscript"];
+
+/// Elements that start and end a line.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption",
+    "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav",
+    "ol", "p", "pre", "section", "table", "tr", "ul",
+];
+
+/// Elements shown bold.
+const BOLD_ELEMENTS: &[&str] = &["b", "strong", "h1", "h2", "h3", "h4", "h5", "h6", "th"];
+
+/// Elements shown italic.
+const ITALIC_ELEMENTS: &[&str] = &["i", "em", "cite", "var"];
+
+/// Elements shown underlined; links are what they usually mark.
+const UNDERLINE_ELEMENTS: &[&str] = &["u", "ins", "a"];
+
+/// Deepest list nesting that is indented further; deeper lists line up with it.
+const MAX_LIST_INDENT: usize = 8;
+
+/// Longest character reference decoded, `&#x10FFFF;` without the `&`.
+const MAX_ENTITY_LEN: usize = 10;
+
+/// Named character references common enough to be worth decoding.
+const ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{A0}'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("hellip", '…'),
+    ("bull", '•'),
+    ("middot", '·'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("deg", '°'),
+    ("times", '×'),
+    ("shy", '\u{AD}'),
+];
+
+/// The copied HTML inside clipboard data in CF_HTML format, or all of `data`
+/// when it has no fragment markers.
+pub fn fragment(data: &[u8]) -> String {
+    let html = String::from_utf8_lossy(data);
+    let html = html.trim_end_matches('\0');
+    let start = html.find("<!--StartFragment-->").map(|i| i + "<!--StartFragment-->".len());
+    let end = html.rfind("<!--EndFragment-->");
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => html[start..end].to_string(),
+        // Without markers, skip the CF_HTML header up to the document itself.
+        _ => html[html.find('<').unwrap_or(0)..].to_string(),
+    }
+}
+
+/// Open formatting elements, counted so nesting and stray end tags balance.
+#[derive(Default)]
+struct Formatting {
+    bold: usize,
+    italic: usize,
+    underline: usize,
+}
+
+impl Formatting {
+    fn update(&mut self, name: &str, closing: bool) {
+        let counters = [
+            (BOLD_ELEMENTS, &mut self.bold),
+            (ITALIC_ELEMENTS, &mut self.italic),
+            (UNDERLINE_ELEMENTS, &mut self.underline),
+        ];
+        for (elements, count) in counters {
+            if elements.contains(&name) {
+                *count = if closing { count.saturating_sub(1) } else { *count + 1 };
+            }
+        }
+    }
+
+    fn style(&self) -> Style {
+        Style {
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            underline: self.underline > 0,
+        }
+    }
+}
+
+/// The text of `html`, one line per block with runs of spaces collapsed, and
+/// its headings, emphasis, and links formatted.
+pub fn parse(html: &str) -> Document {
+    let mut document = Document::default();
+    let mut formatting = Formatting::default();
+    let mut hidden: Option<String> = None;
+    // Open lists: `None` for bullets, the last number for ordered lists.
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        if c == '<' {
+            // An unterminated tag at the end is cut off mid-copy.
+            let Some(end) = rest.find('>') else { break };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let closing = tag.starts_with('/');
+            let name = tag
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if let Some(element) = &hidden {
+                if closing && name == *element {
+                    hidden = None;
+                }
+                continue;
+            }
+            if HIDDEN_ELEMENTS.contains(&name.as_str()) && !closing && !tag.ends_with('/') {
+                hidden = Some(name);
+                continue;
+            }
+            formatting.update(&name, closing);
+            match name.as_str() {
+                "ul" | "ol" if closing => {
+                    lists.pop();
+                }
+                "ul" if !closing => lists.push(None),
+                "ol" if !closing => lists.push(Some(0)),
+                _ => {}
+            }
+            if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                new_line(&mut document);
+            }
+            if name == "li" && !closing {
+                let marker = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{number}. ")
+                    }
+                    _ => "• ".to_string(),
+                };
+                let depth = lists.len().saturating_sub(1).min(MAX_LIST_INDENT);
+                let indent = "  ".repeat(depth);
+                document.push_str(&indent, Style::default());
+                document.push_str(&marker, Style::default());
+            } else if matches!(name.as_str(), "td" | "th") && !closing {
+                document.trim_line_end();
+                if !document.text.is_empty() && !document.text.ends_with('\n') {
+                    document.push('\t', Style::default());
+                }
+            }
+            continue;
+        }
+
+        rest = &rest[c.len_utf8()..];
+        if hidden.is_some() {
+            continue;
+        }
+        let c = if c == '&' {
+            match entity(rest) {
+                Some((decoded, len)) => {
+                    rest = &rest[len..];
+                    decoded
+                }
+                None => c,
+            }
+        } else if c.is_whitespace() {
+            // Source whitespace is insignificant outside <pre>, which is rare
+            // on the clipboard and still readable collapsed.
+            if document.text.is_empty() || document.text.ends_with([' ', '\n', '\t']) {
+                continue;
+            }
+            ' '
+        } else {
+            c
+        };
+        // A no-break space is kept however many there are.
+        let c = if c == '\u{A0}' { ' ' } else { c };
+        document.push(c, formatting.style());
+    }
+
+    document.trim_end();
+    document
+}
+
+/// End the current line, unless it is empty.
+fn new_line(document: &mut Document) {
+    document.trim_line_end();
+    if !document.text.is_empty() && !document.text.ends_with('\n') {
+        document.push('\n', Style::default());
+    }
+}
+
+/// The character referenced after a `&`, and the length of the reference.
+fn entity(rest: &str) -> Option<(char, usize)> {
+    // Only the next few bytes are searched, so runs of bare `&` stay linear.
+    let end = rest.bytes().take(MAX_ENTITY_LEN + 1).position(|b| b == b';')?;
+    let name = &rest[..end];
+    let c = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code)?
+    } else {
+        ENTITIES.iter().find(|(entity, _)| *entity == name)?.1
+    };
+    Some((c, end + 1))
+}
@@ -0,0 +1,178 @@
+//! Copies clipd drops before storing them (`[ignore]` in config.toml): those
+//! made in password managers, and text that looks like a password or a card
+//! number. Copies the app that made them marks as private never get here;
+//! the watcher does not read them at all.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::model::{Entry, EntryKind};
+
+/// Password managers ignored unless `ignore.processes` names others.
+pub const DEFAULT_PROCESSES: &[&str] = &[
+    "KeePass*.exe",
+    "Bitwarden.exe",
+    "1Password.exe",
+    "Dashlane*.exe",
+    "Enpass.exe",
+    "RoboForm.exe",
+];
+
+/// How long `PauseCapture` pauses unless `ignore.pause_minutes` says otherwise.
+pub const DEFAULT_PAUSE_MINUTES: u32 = 15;
+
+/// Length of a copy that may be a password: shorter is a word or a PIN, and
+/// longer is a token or a key, which `ignore.patterns` can name.
+const PASSWORD_LEN: RangeInclusive<usize> = 8..=64;
+
+/// Digits in a card number, from old 13-digit Visa numbers to 19-digit ones.
+const CARD_DIGITS: RangeInclusive<usize> = 13..=19;
+
+/// Runs of digits written like card numbers: in groups split by spaces or dashes.
+static CARD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("card pattern is valid"));
+
+/// What clipd does not capture.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    /// Executable names, with `*` and `?` wildcards.
+    processes: Vec<String>,
+    patterns: Vec<Regex>,
+    /// Whether text with a card number in it is dropped.
+    cards: bool,
+    /// Bits of entropy per character above which a single word with letters
+    /// and digits is taken for a password; `None` leaves words alone.
+    max_entropy: Option<f64>,
+    /// How long a pause from the TUI lasts.
+    pub pause_minutes: u32,
+}
+
+impl IgnoreRules {
+    /// Rules from the `[ignore]` settings; invalid patterns are skipped with a warning.
+    pub fn new(
+        processes: Vec<String>,
+        patterns: &[String],
+        cards: bool,
+        max_entropy: Option<f64>,
+        pause_minutes: u32,
+    ) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    tracing::warn!(%pattern, %err, "ignoring invalid pattern in ignore.patterns");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            processes,
+            patterns,
+            cards,
+            max_entropy: max_entropy.filter(|bits| *bits > 0.0),
+            pause_minutes: pause_minutes.max(1),
+        }
+    }
+
+    /// The first of `processes` whose copies are ignored, if any.
+    pub fn ignored_process<'a>(&self, processes: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+        processes
+            .into_iter()
+            .find(|process| self.processes.iter().any(|glob| glob_matches(glob, process)))
+    }
+
+    /// Why `entry`'s content is not captured, if a rule says so.
+    pub fn ignored_content(&self, entry: &Entry) -> Option<&'static str> {
+        let text = entry.plain_text()?;
+        if self.patterns.iter().any(|regex| regex.is_match(text)) {
+            return Some("matches ignore.patterns");
+        }
+        if self.cards && CARD.find_iter(text).any(|found| is_card_number(found.as_str())) {
+            return Some("holds a card number");
+        }
+        let max_entropy = self.max_entropy?;
+        (entry.kind == EntryKind::Text && looks_like_password(text.trim(), max_entropy))
+            .then_some("looks like a password")
+    }
+}
+
+/// Whether `name` matches `glob`, ignoring case: `*` stands for any run of
+/// characters and `?` for one.
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    // Where the last `*` was and the name position it matched up to, to
+    // backtrack to when the rest does not match.
+    let (mut g, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    g = star_g + 1;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Whether the digits of `candidate` make a card number: the right count,
+/// with a valid Luhn check digit.
+fn is_card_number(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !CARD_DIGITS.contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Whether `text` reads like a generated password: one word of a password's
+/// length, mixing letters and digits, with characters as varied as
+/// `max_entropy` bits each.
+fn looks_like_password(text: &str, max_entropy: f64) -> bool {
+    let len = text.chars().count();
+    if !PASSWORD_LEN.contains(&len)
+        || text.chars().any(char::is_whitespace)
+        || !text.chars().any(|c| c.is_alphabetic())
+        || !text.chars().any(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+    let mut counts = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    let entropy: f64 = counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len as f64;
+            -p * p.log2()
+        })
+        .sum();
+    entropy > max_entropy
+}
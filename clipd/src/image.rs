@@ -0,0 +1,123 @@
+//! Conversion of captured CF_DIB clipboard data into PNG files, and between
+//! DIBs and the RGBA pixels the Linux and macOS clipboards deal in.
+
+use anyhow::{bail, Context, Result};
+
+use crate::model::ImageInfo;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .context("bitmap header is truncated")
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .context("bitmap header is truncated")
+}
+
+/// Dimensions and resolution from a DIB header, if it is readable.
+pub fn dib_info(dib: &[u8]) -> Option<ImageInfo> {
+    let width = read_u32(dib, 4).ok()? as i32;
+    let height = read_u32(dib, 8).ok()? as i32;
+    let pixels_per_meter = read_u32(dib, 24).ok()? as i32;
+    if width <= 0 || height == 0 {
+        return None;
+    }
+    Some(ImageInfo {
+        width: width as u32,
+        height: height.unsigned_abs(),
+        // Most screenshots leave this at 0; the monitor's DPI is used instead.
+        dpi: (pixels_per_meter > 0).then(|| (pixels_per_meter as f64 * 0.0254).round() as u32),
+        monitor: None,
+    })
+}
+
+/// Encode a packed DIB (BITMAPINFOHEADER followed by pixels) as PNG.
+pub fn dib_to_png(dib: &[u8]) -> Result<Vec<u8>> {
+    let (width, height, rgba) = dib_to_rgba(dib)?;
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().context("failed to write PNG header")?;
+    writer
+        .write_image_data(&rgba)
+        .context("failed to encode PNG image data")?;
+    writer.finish().context("failed to finish PNG")?;
+    Ok(png)
+}
+
+/// Width, height, and top-down RGBA pixels of a packed DIB.
+///
+/// Handles the uncompressed 24- and 32-bit layouts Windows puts on the
+/// clipboard for screenshots; 32-bit data with an all-zero alpha channel is
+/// treated as opaque, as Windows does.
+pub fn dib_to_rgba(dib: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    let header_size = read_u32(dib, 0)? as usize;
+    let width = read_u32(dib, 4)? as i32;
+    let height = read_u32(dib, 8)? as i32;
+    let bit_count = read_u16(dib, 14)?;
+    let compression = read_u32(dib, 16)?;
+    let colors_used = read_u32(dib, 32)? as usize;
+
+    if width <= 0 || height == 0 {
+        bail!("bitmap has invalid dimensions {width}x{height}");
+    }
+    if !matches!(bit_count, 24 | 32) || !matches!(compression, BI_RGB | BI_BITFIELDS) {
+        bail!("unsupported bitmap format ({bit_count} bpp, compression {compression})");
+    }
+
+    // A plain BITMAPINFOHEADER is followed by the three channel masks for
+    // BI_BITFIELDS; the V4/V5 headers include them.
+    let masks = if compression == BI_BITFIELDS && header_size == 40 { 12 } else { 0 };
+    let width = width as usize;
+    let rows = height.unsigned_abs() as usize;
+    let bytes_per_pixel = bit_count as usize / 8;
+    let stride = (width * bytes_per_pixel + 3) & !3;
+    // Header fields come from whoever wrote the clipboard or the import file,
+    // so sizes that overflow are as truncated as ones that point past the end.
+    let pixels = colors_used
+        .checked_mul(4)
+        .and_then(|colors| (header_size + masks).checked_add(colors))
+        .zip(stride.checked_mul(rows))
+        .and_then(|(pixels_at, len)| dib.get(pixels_at..pixels_at.checked_add(len)?))
+        .context("bitmap pixel data is truncated")?;
+
+    let has_alpha = bytes_per_pixel == 4 && pixels.chunks_exact(4).any(|px| px[3] != 0);
+    let mut rgba = Vec::with_capacity(width * rows * 4);
+    for row in 0..rows {
+        // Positive heights are stored bottom-up.
+        let source_row = if height > 0 { rows - 1 - row } else { row };
+        let line = &pixels[source_row * stride..source_row * stride + width * bytes_per_pixel];
+        for px in line.chunks_exact(bytes_per_pixel) {
+            let alpha = if has_alpha { px[3] } else { 255 };
+            rgba.extend_from_slice(&[px[2], px[1], px[0], alpha]);
+        }
+    }
+    Ok((width as u32, rows as u32, rgba))
+}
+
+/// A packed 32-bit DIB of `width` by `height` top-down RGBA pixels, as
+/// [`dib_to_png`] and [`dib_info`] read them.
+pub fn rgba_to_dib(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut dib = Vec::with_capacity(40 + rgba.len());
+    dib.extend_from_slice(&40u32.to_le_bytes());
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    // A negative height stores the rows top-down, as they come.
+    dib.extend_from_slice(&(-(height as i32)).to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&32u16.to_le_bytes());
+    dib.extend_from_slice(&BI_RGB.to_le_bytes());
+    dib.extend_from_slice(&(rgba.len() as u32).to_le_bytes());
+    // Resolution and palette: none.
+    dib.extend_from_slice(&[0; 16]);
+    for px in rgba.chunks_exact(4) {
+        dib.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+    }
+    dib
+}
@@ -1,45 +1,75 @@
-//! Named pipe IPC server.
+//! IPC server, on the named pipe or the Unix domain socket.
 
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use anyhow::{bail, Context, Result};
+use clipcore::{read_request, write_frame, FrameError};
+use md5::Md5;
+use parking_lot::{Mutex, RwLock};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, DuplexStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 
-use crate::db::Database;
-use crate::model::Entry;
+use crate::archive::Protection;
+use crate::clipboard::{Capture, ClipboardWatcher, OnClipboard};
+use crate::companion;
+use crate::config::Config;
+use crate::db::{Database, Stored};
+use crate::hazard;
+use crate::issues::{self, IssueTrackers};
+use crate::keys::{self, KeyRing};
+use crate::listener::{self, Listener};
+use crate::model::{Entry, EntryKind};
+use crate::paste::PasteFormat;
+use crate::profiles::Profiles;
+use crate::query::Query;
+use crate::redact::Redactor;
+use crate::runtime;
+use crate::snapshot;
+use crate::suggest;
+use crate::timefmt::TimeDisplay;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Request {
-    pub kind: RequestKind,
-}
+pub use clipcore::{
+    BackupSummary, CaptureStatus, Checksums, CompanionInfo, DayCount, Diagnostics, EntryPayload, EntrySource,
+    EntrySummary, ErrorCode, ErrorResponse, KeySummary, LargeEntry, PasteVerdict, ProfileSummary, Request,
+    RequestKind, Response, ServerEvent, ServerInfo, SessionSummary, Suggestion, UsageCount, UsageStats,
+    PROTOCOL_VERSION,
+};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum RequestKind {
-    List,
-    Search { query: String },
-    Paste { id: u64 },
-    AddTag { id: u64, tag: String },
-    RemoveTag { id: u64, tag: String },
-    Export { path: String },
-    Import { path: String },
-}
+/// How long in-flight client requests may run once shutdown starts.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Response {
-    pub entries: Vec<EntrySummary>,
-}
+/// Bytes buffered in each direction of an in-memory connection.
+const IN_MEMORY_BUFFER: usize = 64 * 1024;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EntrySummary {
-    pub id: u64,
-    pub preview: String,
-    pub created_at: String,
-    pub kind: String,
-    pub source_process: Option<String>,
-    pub tags: Vec<String>,
-}
+/// New entries queued for a subscribed client that is busy; one that falls
+/// further behind misses the oldest.
+const ADDED_BACKLOG: usize = 64;
+
+/// Entries in a page of `ListPage` when the client does not say, besides the
+/// pinned ones, and the most it may ask for.
+const LIST_PAGE: u32 = 256;
+const LIST_PAGE_MAX: u32 = 1000;
+
+/// Apps, kinds, tags, and largest entries `Stats` answers with.
+const STATS_TOP: usize = 10;
+/// Characters of a large entry's text `Stats` labels it with.
+const STATS_LABEL_CHARS: usize = 80;
+
+/// A connection clients send requests over: the named pipe or socket, or an
+/// in-memory duplex from a client in the same process (tests, `clipctl
+/// --embedded`).
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl Transport for listener::Connection {}
+
+impl Transport for DuplexStream {}
 
 #[derive(Clone)]
 pub struct Server {
@@ -48,159 +78,1002 @@ pub struct Server {
 
 struct ServerInner {
     pipe_name: String,
-    db: Database,
+    profiles: Profiles,
+    clipboard: ClipboardWatcher,
+    /// Replaced on `ReloadConfig`.
+    time: RwLock<TimeDisplay>,
+    /// `export.redact`: mask secrets even when the request does not ask to.
+    redact_exports: AtomicBool,
+    redactor: RwLock<Redactor>,
+    keys: Mutex<KeyRing>,
+    /// Where the companion endpoint listens, while it does.
+    companion: Mutex<Option<SocketAddr>>,
+    /// Ids of entries as they are stored, for subscribed clients.
+    added: broadcast::Sender<u64>,
+    /// Replaced on `ReloadConfig`.
+    issues: RwLock<IssueTrackers>,
 }
 
 impl Server {
-    pub fn new(pipe_name: String, db: Database) -> Self {
+    pub fn new(
+        pipe_name: String,
+        profiles: Profiles,
+        clipboard: ClipboardWatcher,
+        time: TimeDisplay,
+        redact_exports: bool,
+        redactor: Redactor,
+        keys: KeyRing,
+    ) -> Self {
         Self {
-            inner: Arc::new(ServerInner { pipe_name, db }),
+            inner: Arc::new(ServerInner {
+                pipe_name,
+                profiles,
+                clipboard,
+                time: RwLock::new(time),
+                redact_exports: AtomicBool::new(redact_exports),
+                redactor: RwLock::new(redactor),
+                keys: Mutex::new(keys),
+                companion: Mutex::new(None),
+                added: broadcast::channel(ADDED_BACKLOG).0,
+                issues: RwLock::new(IssueTrackers::default()),
+            }),
         }
     }
 
-    pub async fn run(&self) -> Result<()> {
-        loop {
-            let pipe = self.create_pipe()?;
-            if let Err(err) = pipe.connect().await {
-                tracing::warn!(%err, "failed to connect named pipe client");
-                continue;
-            }
+    /// Accept clients until `shutdown` fires, then drain connected clients.
+    /// The pipe is announced in the runtime file at `runtime_path` once it
+    /// exists, until this returns.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>, runtime_path: &Path) -> Result<()> {
+        let mut clients = JoinSet::new();
+        let mut listener = Listener::bind(&self.inner.pipe_name)?;
+        let _announcement = runtime::announce(runtime_path, &self.inner.pipe_name)?;
+
+        while !*shutdown.borrow() {
+            let pipe = tokio::select! {
+                accepted = listener.accept() => match accepted? {
+                    Some(pipe) => pipe,
+                    None => continue,
+                },
+                _ = shutdown.changed() => break,
+            };
 
             let inner = self.inner.clone();
-            tokio::spawn(async move {
-                if let Err(err) = inner.handle_client(pipe).await {
+            let client_shutdown = shutdown.clone();
+            clients.spawn(async move {
+                let mut pipe = pipe;
+                if let Err(err) = inner.handle_client(&mut pipe, client_shutdown).await {
                     tracing::warn!(%err, "client handler failed");
                 }
             });
+
+            // Reap handlers of clients that already disconnected.
+            while clients.try_join_next().is_some() {}
         }
+
+        tracing::info!(clients = clients.len(), "pipe server draining connected clients");
+        let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+            while clients.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                remaining = clients.len(),
+                "drain deadline elapsed, aborting remaining client handlers"
+            );
+            clients.abort_all();
+        }
+
+        Ok(())
     }
 
-    fn create_pipe(&self) -> Result<NamedPipeServer> {
-        // Create named pipe with default security attributes
-        // This grants access to the same user that created the pipe
-        ServerOptions::new()
-            .create(&self.inner.pipe_name)
-            .with_context(|| {
-                format!(
-                    "failed to create named pipe {}. \
-                    If you're getting 'Access is denied', ensure clipd is running \
-                    and was started by the same user.",
-                    self.inner.pipe_name
-                )
-            })
+    /// Answer one client on `stream` until it disconnects or `shutdown` fires.
+    /// `run` does this for every pipe client.
+    pub async fn serve<S: Transport>(&self, stream: &mut S, shutdown: watch::Receiver<bool>) -> Result<()> {
+        self.inner.handle_client(stream, shutdown).await
+    }
+
+    /// Connect a client in this process, without the pipe: the server answers
+    /// on one end of an in-memory duplex and the client gets the other.
+    pub fn connect_in_memory(&self, shutdown: watch::Receiver<bool>) -> DuplexStream {
+        let (client, mut stream) = tokio::io::duplex(IN_MEMORY_BUFFER);
+        let server = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = server.serve(&mut stream, shutdown).await {
+                tracing::warn!(%err, "in-memory client handler failed");
+            }
+        });
+        client
+    }
+
+    /// The key companion apps pair with.
+    pub(crate) fn companion_key(&self) -> Result<[u8; 32]> {
+        self.inner.keys.lock().companion_key()
+    }
+
+    /// Note where the companion endpoint listens, for `Companion` requests.
+    pub(crate) fn set_companion_address(&self, address: Option<SocketAddr>) {
+        *self.inner.companion.lock() = address;
+    }
+
+    /// Tell subscribed clients about entry `id`, just stored.
+    pub fn entry_added(&self, id: u64) {
+        // An error only means nobody is subscribed.
+        let _ = self.inner.added.send(id);
+    }
+
+    pub(crate) fn set_issue_trackers(&self, trackers: IssueTrackers) {
+        *self.inner.issues.write() = trackers;
+    }
+
+    /// Look up the titles of the issues entry `id` mentions in the
+    /// background, when `[issues]` has a token.
+    pub(crate) fn add_issue_titles(&self, id: u64) {
+        let trackers = self.inner.issues.read().clone();
+        if trackers.is_empty() {
+            return;
+        }
+        let db = self.inner.db();
+        tokio::spawn(async move {
+            if let Err(err) = issues::add_titles(db, trackers, id).await {
+                tracing::warn!(id, "failed to add issue titles: {err:#}");
+            }
+        });
+    }
+
+    /// Store `entry` as if it had been copied.
+    pub(crate) async fn capture(&self, entry: Entry) {
+        self.inner.clipboard.queue().push(Capture::Entry(Box::new(entry))).await;
+    }
+
+    /// The newest `limit` entries with text, newest first, with secrets
+    /// masked when `export.redact` is on: what leaves the PC for a companion.
+    pub(crate) fn latest_text(&self, limit: usize) -> Result<Vec<Entry>> {
+        let redactor = self
+            .inner
+            .redact_exports
+            .load(Ordering::Relaxed)
+            .then(|| self.inner.redactor.read().clone());
+        let mut entries: Vec<_> = self
+            .inner
+            .db()
+            .list_recent(256)?
+            .into_iter()
+            .filter(|entry| !matches!(entry.kind, EntryKind::Image | EntryKind::Snapshot))
+            .take(limit)
+            .collect();
+        if let Some(redactor) = redactor {
+            for entry in &mut entries {
+                redactor.redact(entry);
+            }
+        }
+        Ok(entries)
     }
 }
 
 impl ServerInner {
-    async fn handle_client(&self, mut pipe: NamedPipeServer) -> Result<()> {
+    async fn handle_client<S: Transport>(&self, pipe: &mut S, mut shutdown: watch::Receiver<bool>) -> Result<()> {
         tracing::info!("client connected");
+        let mut greeted = false;
+        let mut added = None;
+        // The length of the next request, read a piece at a time so that a
+        // push in between loses none of it.
+        let mut len = [0u8; 4];
+        let mut filled = 0;
         loop {
-            let len = match pipe.read_u32_le().await {
-                Ok(len) => len,
+            if *shutdown.borrow() {
+                self.send_shutting_down(pipe).await;
+                break;
+            }
+
+            let read = tokio::select! {
+                read = pipe.read(&mut len[filled..]) => read,
+                id = next_added(&mut added) => {
+                    self.send_added(pipe, id).await?;
+                    continue;
+                }
+                _ = shutdown.changed() => {
+                    self.send_shutting_down(pipe).await;
+                    break;
+                }
+            };
+            match read {
+                Ok(0) => {
+                    tracing::debug!("client disconnected");
+                    break;
+                }
+                Ok(read) => filled += read,
                 Err(err) => {
                     tracing::debug!(%err, "client disconnected");
                     break;
                 }
-            };
+            }
+            if filled < len.len() {
+                continue;
+            }
+            filled = 0;
 
-            let mut buf = vec![0u8; len as usize];
-            pipe.read_exact(&mut buf).await?;
+            let response = match read_request(pipe, u32::from_le_bytes(len)).await {
+                Ok(request) => {
+                    let subscribe = matches!(request.kind, RequestKind::Subscribe);
+                    let response = self.answer(request, &mut greeted).await;
+                    if subscribe && response.error.is_none() && added.is_none() {
+                        added = Some(self.added.subscribe());
+                    }
+                    response
+                }
+                Err(FrameError::Malformed(err)) => {
+                    tracing::warn!(%err, "malformed request");
+                    let message = format!("malformed request: {err}");
+                    Response::failed(ErrorResponse::new(ErrorCode::BadRequest, message))
+                }
+                Err(err @ FrameError::TooLong(_)) => {
+                    // The body is still unread, so no later frame would line up.
+                    let error = ErrorResponse::new(ErrorCode::BadRequest, err.to_string());
+                    write_frame(pipe, &Response::failed(error)).await?;
+                    return Err(err.into());
+                }
+                Err(FrameError::Io(err)) => return Err(err.into()),
+            };
+            write_frame(pipe, &response).await?;
+        }
 
-            let request: Request = serde_json::from_slice(&buf)?;
-            let response = self.dispatch(request).await?;
+        Ok(())
+    }
 
-            let payload = serde_json::to_vec(&response)?;
-            pipe.write_u32_le(payload.len() as u32).await?;
-            pipe.write_all(&payload).await?;
-            pipe.flush().await?;
+    /// Answer `request`, or say why it failed. Until a `Hello` succeeds,
+    /// every other request is refused.
+    async fn answer(&self, request: Request, greeted: &mut bool) -> Response {
+        let hello = matches!(request.kind, RequestKind::Hello { .. });
+        if !hello && !*greeted {
+            let error = ErrorResponse::new(ErrorCode::HandshakeRequired, "send Hello before any other request");
+            return Response::failed(error);
+        }
+        match self.dispatch(request).await {
+            Ok(response) => {
+                *greeted |= hello;
+                response
+            }
+            Err(err) => {
+                let message = format!("{err:#}");
+                tracing::warn!(%message, "request failed");
+                let error = err
+                    .downcast_ref::<ErrorResponse>()
+                    .cloned()
+                    .unwrap_or_else(|| ErrorResponse::new(ErrorCode::Failed, message));
+                Response::failed(error)
+            }
         }
+    }
 
+    /// Push entry `id` to a subscribed client, unless it is already gone or
+    /// quarantined, which lists leave out too.
+    async fn send_added<S: Transport>(&self, pipe: &mut S, id: u64) -> Result<()> {
+        let Some(entry) = self.db().get_entry(id)?.filter(|entry| !entry.quarantined) else {
+            return Ok(());
+        };
+        let on_clipboard = self.on_clipboard(&entry);
+        let mut summary = entry_summary(entry, &self.time.read());
+        summary.on_clipboard = on_clipboard;
+        let response = Response {
+            event: Some(ServerEvent::EntryAdded(Box::new(summary))),
+            ..Response::default()
+        };
+        write_frame(pipe, &response).await?;
         Ok(())
     }
 
+    /// Tell the client the daemon is exiting.
+    async fn send_shutting_down<S: Transport>(&self, pipe: &mut S) {
+        let response = Response {
+            event: Some(ServerEvent::ShuttingDown),
+            ..Response::default()
+        };
+        if let Err(err) = write_frame(pipe, &response).await {
+            tracing::debug!(%err, "failed to notify client about shutdown");
+        }
+    }
+
     async fn dispatch(&self, request: Request) -> Result<Response> {
         match request.kind {
+            RequestKind::Hello {
+                client_version,
+                protocol,
+            } => self.handle_hello(client_version, protocol).await,
             RequestKind::List => self.handle_list().await,
+            RequestKind::ListPage { cursor, limit } => self.handle_list_page(cursor, limit).await,
+            // handle_client starts the pushes once this is answered.
+            RequestKind::Subscribe => Ok(Response::default()),
             RequestKind::Search { query } => self.handle_search(query).await,
-            RequestKind::Paste { id } => self.handle_paste(id).await,
+            RequestKind::Paste { id, force, plain, hide_from_history } => {
+                self.handle_paste(id, force, plain, hide_from_history).await
+            }
+            RequestKind::Get { id, payload } => self.handle_get(id, payload).await,
             RequestKind::AddTag { id, tag } => self.handle_add_tag(id, tag).await,
             RequestKind::RemoveTag { id, tag } => self.handle_remove_tag(id, tag).await,
-            RequestKind::Export { path } => self.handle_export(path).await,
-            RequestKind::Import { path } => self.handle_import(path).await,
+            RequestKind::TagMatching { query, tag } => self.handle_tag_matching(query, tag).await,
+            RequestKind::DeleteMatching { query } => self.handle_delete_matching(query).await,
+            RequestKind::Delete { id } => self.handle_delete(id).await,
+            RequestKind::Clear { older_than_days } => self.handle_clear(older_than_days).await,
+            RequestKind::SetPinned { id, pinned } => self.handle_set_pinned(id, pinned).await,
+            RequestKind::MovePin { id, up } => self.handle_move_pin(id, up).await,
+            RequestKind::Export {
+                path,
+                redact,
+                passphrase,
+                user_key,
+            } => self.handle_export(path, redact, passphrase, user_key).await,
+            RequestKind::Import { path, passphrase } => self.handle_import(path, passphrase).await,
+            RequestKind::SaveEntry { id, path } => self.handle_save_entry(id, path).await,
+            RequestKind::ReloadConfig => self.handle_reload_config().await,
+            RequestKind::Diagnose => self.handle_diagnose().await,
+            RequestKind::DailyCounts => self.handle_daily_counts().await,
+            RequestKind::Sessions => self.handle_sessions().await,
+            RequestKind::TagSession { session, tag } => self.handle_tag_session(session, tag).await,
+            RequestKind::ExportSession { session, path } => {
+                self.handle_export_session(session, path).await
+            }
+            RequestKind::Profiles => self.handle_profiles().await,
+            RequestKind::SwitchProfile { name } => self.handle_switch_profile(name).await,
+            RequestKind::Keys => self.handle_keys().await,
+            RequestKind::RotateKey => self.handle_rotate_key().await,
+            RequestKind::Companion { reset } => self.handle_companion(reset).await,
+            RequestKind::ApproveSource { process } => self.handle_approve_source(process).await,
+            RequestKind::Checksums { id } => self.handle_checksums(id).await,
+            RequestKind::Source { id } => self.handle_source(id).await,
+            RequestKind::Snapshot => self.handle_snapshot().await,
+            RequestKind::Restore { id } => self.handle_restore(id).await,
+            RequestKind::PauseCapture { minutes } => self.handle_pause_capture(minutes),
+            RequestKind::Backups => self.handle_backups().await,
+            RequestKind::CreateBackup => self.handle_create_backup().await,
+            RequestKind::RestoreBackup { name } => self.handle_restore_backup(name).await,
+            RequestKind::Unarchive { id } => self.handle_unarchive(id).await,
+            RequestKind::Suggest => self.handle_suggest().await,
+            RequestKind::Stats { days } => self.handle_stats(days).await,
         }
     }
 
+    /// Database of the profile being captured into.
+    fn db(&self) -> Database {
+        self.profiles.active()
+    }
+
+    fn summarize(&self, entries: Vec<Entry>) -> Vec<EntrySummary> {
+        let time = self.time.read();
+        entries
+            .into_iter()
+            .map(|entry| {
+                let on_clipboard = self.on_clipboard(&entry);
+                EntrySummary {
+                    on_clipboard,
+                    ..entry_summary(entry, &time)
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the clipboard holds `entry`'s content.
+    fn on_clipboard(&self, entry: &Entry) -> bool {
+        match self.clipboard.on_clipboard() {
+            OnClipboard::Unknown => false,
+            OnClipboard::Entry(id) => entry.id == Some(id),
+            OnClipboard::Content(hash) => entry.hash == hash,
+        }
+    }
+
+    async fn handle_hello(&self, client_version: String, protocol: u32) -> Result<Response> {
+        if protocol > PROTOCOL_VERSION {
+            bail!(ErrorResponse::new(
+                ErrorCode::UnsupportedProtocol,
+                format!(
+                    "clipctl {client_version} needs protocol v{protocol} but clipd {} speaks v{PROTOCOL_VERSION}",
+                    env!("CARGO_PKG_VERSION")
+                ),
+            ));
+        }
+        if protocol != PROTOCOL_VERSION {
+            tracing::warn!(
+                %client_version,
+                client_protocol = protocol,
+                server_protocol = PROTOCOL_VERSION,
+                "client speaks a different protocol version"
+            );
+        } else {
+            tracing::debug!(%client_version, "client handshake");
+        }
+
+        Ok(Response {
+            server: Some(ServerInfo {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                protocol: PROTOCOL_VERSION,
+            }),
+            ..Response::default()
+        })
+    }
+
+    /// The first page of the history, which requests that change it answer with.
     async fn handle_list(&self) -> Result<Response> {
-        let entries = self.db.list_recent(256)?;
+        self.handle_list_page(None, None).await
+    }
+
+    async fn handle_list_page(&self, cursor: Option<String>, limit: Option<u32>) -> Result<Response> {
+        let limit = limit.unwrap_or(LIST_PAGE).clamp(1, LIST_PAGE_MAX);
+        let (entries, next_cursor) = self.db().list_page(cursor.as_deref(), limit as usize)?;
         Ok(Response {
-            entries: entries.into_iter().map(EntrySummary::from).collect(),
+            entries: self.summarize(entries),
+            next_cursor,
+            ..Response::default()
         })
     }
 
     async fn handle_search(&self, query: String) -> Result<Response> {
         tracing::debug!(%query, "searching clipboard history");
         
-        // If query is empty, return all recent entries
-        let entries = if query.is_empty() {
-            self.db.list_recent(256)?
-        } else {
-            self.db.search(&query, 256)?
-        };
+        // An empty query is the history itself, a page at a time
+        if query.is_empty() {
+            return self.handle_list().await;
+        }
+        let parsed = Query::parse(&query);
+        let day = self.time.read().day_sql();
+        let entries = self.db().search(&parsed, day, 256)?;
         
+        let mut entries = self.summarize(entries);
+        for entry in &mut entries {
+            entry.archived = parsed.archived();
+            entry.highlights = parsed.highlights(&entry.preview);
+        }
         Ok(Response {
-            entries: entries.into_iter().map(EntrySummary::from).collect(),
+            entries,
+            ..Response::default()
         })
     }
 
-    async fn handle_paste(&self, id: u64) -> Result<Response> {
-        tracing::info!(id, "received paste request");
-        self.handle_list().await
+    async fn handle_paste(&self, id: u64, force: bool, plain: bool, hide_from_history: bool) -> Result<Response> {
+        let target = self.clipboard.paste_target();
+        let entry = self.db().get_entry(id)?;
+        let blocked = match (&entry, target.as_deref()) {
+            (Some(entry), Some(target)) => self.clipboard.blocked_paste(entry, target),
+            _ => None,
+        };
+        match &blocked {
+            Some(reason) if force => tracing::warn!(id, %reason, "paste allowed by override"),
+            Some(reason) => tracing::info!(id, %reason, "paste blocked"),
+            None => tracing::info!(id, ?target, "received paste request"),
+        }
+        let blocked = blocked.filter(|_| !force);
+        if entry.is_some() && blocked.is_none() {
+            self.db().record_paste(id, target.as_deref())?;
+        }
+        let mut pasted = false;
+        if let Some(entry) = entry.filter(|_| blocked.is_none() && !plain) {
+            // Files go back as files whatever the format rules say; their
+            // paths come with them for apps that only take text.
+            if entry.kind == EntryKind::Files {
+                pasted = self.clipboard.paste_files(&entry, hide_from_history)?;
+            } else if self.clipboard.paste_format(target.as_deref()) == PasteFormat::Rich {
+                pasted = self.clipboard.paste_document(&entry, hide_from_history)?;
+            }
+        }
+        if pasted {
+            self.db().touch_entry(id)?;
+        }
+        Ok(Response {
+            paste: Some(PasteVerdict {
+                id,
+                target,
+                blocked,
+                pasted,
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_suggest(&self) -> Result<Response> {
+        let target = self.clipboard.paste_target();
+        let now = chrono::Utc::now();
+        let pastes = self.db().pastes_since(now - chrono::Duration::days(suggest::LOOKBACK_DAYS))?;
+        let suggestion = suggest::suggest(&pastes, target.as_deref(), now).map(|id| Suggestion { id, target });
+        Ok(Response {
+            suggestion,
+            ..Response::default()
+        })
+    }
+
+    /// Return the single entry `id`, or no entries if it does not exist;
+    /// with `payload`, its data too.
+    async fn handle_get(&self, id: u64, payload: bool) -> Result<Response> {
+        let entry = self.db().get_entry(id)?;
+        let payload = entry.as_ref().filter(|_| payload).map(|entry| EntryPayload {
+            id,
+            data: entry.data.clone().unwrap_or_default(),
+        });
+        Ok(Response {
+            entries: self.summarize(entry.into_iter().collect()),
+            payload,
+            ..Response::default()
+        })
     }
 
     async fn handle_add_tag(&self, id: u64, tag: String) -> Result<Response> {
         tracing::info!(id, %tag, "adding tag to entry");
-        self.db.add_tag(id, &tag)?;
+        self.db().add_tag(id, &tag)?;
         self.handle_list().await
     }
 
     async fn handle_remove_tag(&self, id: u64, tag: String) -> Result<Response> {
         tracing::info!(id, %tag, "removing tag from entry");
-        self.db.remove_tag(id, &tag)?;
+        self.db().remove_tag(id, &tag)?;
+        self.handle_list().await
+    }
+
+    /// Answers with the search results, like `Search`, and how many entries changed.
+    async fn handle_tag_matching(&self, query: String, tag: String) -> Result<Response> {
+        let day = self.time.read().day_sql();
+        let tagged = self.db().tag_matching(&Query::parse(&query), day, &tag)?;
+        tracing::info!(%query, %tag, tagged, "tagged search results");
+        Ok(Response {
+            changed: Some(tagged as u64),
+            ..self.handle_search(query).await?
+        })
+    }
+
+    async fn handle_delete_matching(&self, query: String) -> Result<Response> {
+        let deleted = if query.trim().is_empty() {
+            tracing::warn!("refusing to delete the whole history");
+            0
+        } else {
+            let day = self.time.read().day_sql();
+            self.db().delete_matching(&Query::parse(&query), day)?
+        };
+        tracing::info!(%query, deleted, "deleted search results");
+        Ok(Response {
+            changed: Some(deleted as u64),
+            ..self.handle_search(query).await?
+        })
+    }
+
+    async fn handle_delete(&self, id: u64) -> Result<Response> {
+        if !self.db().delete_entry(id)? {
+            bail!(not_found(id));
+        }
+        tracing::info!(id, "deleted entry");
+        self.handle_list().await
+    }
+
+    async fn handle_clear(&self, older_than_days: Option<u32>) -> Result<Response> {
+        let older_than = older_than_days.map(|days| chrono::Duration::days(days.into()));
+        let cleared = self.db().clear(older_than)?;
+        tracing::info!(?older_than_days, cleared, "cleared history");
+        Ok(Response {
+            changed: Some(cleared as u64),
+            ..self.handle_list().await?
+        })
+    }
+
+    async fn handle_set_pinned(&self, id: u64, pinned: bool) -> Result<Response> {
+        self.db().set_pinned(id, pinned)?;
         self.handle_list().await
     }
 
-    async fn handle_export(&self, path: String) -> Result<Response> {
-        tracing::info!(%path, "exporting clipboard history");
-        self.db.export_to_json(&path)?;
+    async fn handle_move_pin(&self, id: u64, up: bool) -> Result<Response> {
+        self.db().move_pin(id, up)?;
         self.handle_list().await
     }
 
-    async fn handle_import(&self, path: String) -> Result<Response> {
+    async fn handle_export(
+        &self,
+        path: String,
+        redact: bool,
+        passphrase: Option<String>,
+        user_key: bool,
+    ) -> Result<Response> {
+        let redact = redact || self.redact_exports.load(Ordering::Relaxed);
+        tracing::info!(%path, redact, encrypted = passphrase.is_some() || user_key, "exporting clipboard history");
+        let redactor = redact.then(|| self.redactor.read().clone());
+        {
+            let keys = self.keys.lock();
+            let protection = match passphrase.as_deref() {
+                Some(passphrase) => Some(Protection::Passphrase(passphrase)),
+                None => user_key.then_some(Protection::UserKey(&keys)),
+            };
+            self.db()
+                .export_to_json(&path, &self.time.read(), None, redactor.as_ref(), protection)?;
+        }
+        self.handle_list().await
+    }
+
+    async fn handle_import(&self, path: String, passphrase: Option<String>) -> Result<Response> {
         tracing::info!(%path, "importing clipboard history");
-        self.db.import_from_json(&path)?;
+        self.db()
+            .import_from_json(&path, passphrase.as_deref(), &self.keys.lock())?;
         self.handle_list().await
     }
-}
 
-impl From<Entry> for EntrySummary {
-    fn from(entry: Entry) -> Self {
-        let kind = match entry.kind {
-            crate::model::EntryKind::Text => "text",
-            crate::model::EntryKind::Url => "url",
-            crate::model::EntryKind::Image => "image",
-            crate::model::EntryKind::Rtf => "rtf",
+    async fn handle_save_entry(&self, id: u64, path: String) -> Result<Response> {
+        tracing::info!(id, %path, "saving entry to file");
+        let Some(entry) = self.db().get_entry(id)? else {
+            bail!(not_found(id));
         };
-        
-        Self {
-            id: entry.id.unwrap_or_default(),
-            preview: entry
-                .text
-                .unwrap_or_else(|| "<non-text entry>".to_string()),
-            created_at: entry.created_at.to_rfc3339(),
-            kind: kind.to_string(),
-            source_process: entry.source_process,
-            tags: entry.tags,
+        let contents = entry.file_contents()?;
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write entry {id} to {path}"))?;
+        self.handle_list().await
+    }
+
+    async fn handle_reload_config(&self) -> Result<Response> {
+        let config = Config::load()?;
+        tracing::info!(max_entries = config.max_entries, "reloading configuration");
+
+        if config.pipe_name != self.pipe_name {
+            tracing::warn!(
+                new_pipe = %config.pipe_name,
+                "pipe name changed; restart clipd to listen on the new pipe"
+            );
+        }
+        self.profiles.apply(&config)?;
+        self.clipboard.set_allowed_kinds(config.allowed_kinds);
+        self.clipboard.set_project_tags(config.project_tags);
+        self.clipboard.set_group_window(config.group_window_secs);
+        self.clipboard.set_quarantine(config.trusted_processes);
+        self.clipboard.set_paste_policy(config.paste);
+        self.clipboard.set_polling(config.polling);
+        self.clipboard.set_battery_policy(config.battery);
+        self.clipboard.set_max_entry_bytes(config.max_entry_bytes);
+        self.clipboard.set_ignore_rules(config.ignore);
+        *self.issues.write() = config.issues;
+        *self.time.write() = config.time;
+        self.redact_exports.store(config.redact_exports, Ordering::Relaxed);
+        *self.redactor.write() = config.redactor;
+        self.handle_list().await
+    }
+
+    async fn handle_diagnose(&self) -> Result<Response> {
+        let health = self.db().check_health()?;
+        if !health.problems.is_empty() {
+            tracing::warn!(problems = ?health.problems, "database integrity check failed");
+        }
+
+        Ok(Response {
+            diagnostics: Some(Diagnostics {
+                db_path: self.db().path().display().to_string(),
+                entries: health.entries,
+                integrity: health.problems,
+                queue: self.clipboard.queue_stats(),
+                blobs: self.db().blob_stats()?,
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_daily_counts(&self) -> Result<Response> {
+        let day = self.time.read().day_sql();
+        let days = self
+            .db()
+            .daily_counts(day)?
+            .into_iter()
+            .map(|(date, count)| DayCount { date, count })
+            .collect();
+        Ok(Response {
+            days: Some(days),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_stats(&self, days: u32) -> Result<Response> {
+        let time = self.time.read().clone();
+        let usage = self
+            .db()
+            .usage(chrono::Duration::days(days.into()), time.hour_sql(), STATS_TOP)?;
+        let counts = |counts: Vec<(String, u64)>| {
+            counts
+                .into_iter()
+                .map(|(name, count)| UsageCount { name, count })
+                .collect()
+        };
+        let biggest = usage
+            .biggest
+            .into_iter()
+            .map(|entry| LargeEntry {
+                id: entry.id.unwrap_or_default(),
+                label: entry
+                    .title
+                    .as_deref()
+                    .or_else(|| entry.plain_text().and_then(|text| text.lines().find(|line| !line.trim().is_empty())))
+                    .map(|label| label.trim().chars().take(STATS_LABEL_CHARS).collect())
+                    .unwrap_or_default(),
+                kind: entry.kind.name().to_string(),
+                bytes: entry.bytes_len as u64,
+                created: time.format(entry.created_at),
+            })
+            .collect();
+        Ok(Response {
+            stats: Some(UsageStats {
+                days,
+                entries: usage.entries,
+                apps: counts(usage.apps),
+                kinds: counts(usage.kinds),
+                tags: counts(usage.tags),
+                hours: usage.hours.to_vec(),
+                biggest,
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_profiles(&self) -> Result<Response> {
+        let active = self.profiles.active_name();
+        let mut profiles = Vec::new();
+        for name in self.profiles.names() {
+            let db = self.profiles.get(&name)?;
+            let latest = db.list_recent(1)?.into_iter().next();
+            profiles.push(ProfileSummary {
+                active: name == active,
+                entries: db.count()?,
+                latest: latest.and_then(|entry| entry.title.or(entry.text_extracted).or(entry.text)),
+                name,
+            });
+        }
+        Ok(Response {
+            profiles: Some(profiles),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_switch_profile(&self, name: String) -> Result<Response> {
+        self.profiles.switch(&name)?;
+        self.handle_list().await
+    }
+
+    async fn handle_approve_source(&self, process: String) -> Result<Response> {
+        tracing::info!(%process, "approving capture source");
+        self.clipboard.trust(&process);
+        self.db().release_quarantine(&process)?;
+        self.handle_list().await
+    }
+
+    async fn handle_keys(&self) -> Result<Response> {
+        let time = self.time.read();
+        let keys = self
+            .keys
+            .lock()
+            .keys()
+            .into_iter()
+            .map(|key| KeySummary {
+                id: key.id,
+                created: time.format(key.created_at),
+                current: key.current,
+            })
+            .collect();
+        Ok(Response {
+            keys: Some(keys),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_checksums(&self, id: u64) -> Result<Response> {
+        let Some(entry) = self.db().get_entry(id)? else {
+            bail!(not_found(id));
+        };
+        let contents = entry.file_contents()?;
+        Ok(Response {
+            checksums: Some(Checksums {
+                id,
+                sha256: format!("{:x}", Sha256::digest(&contents)),
+                md5: format!("{:x}", Md5::digest(&contents)),
+                crc32: format!("{:08x}", crc32fast::hash(&contents)),
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_source(&self, id: u64) -> Result<Response> {
+        let Some(entry) = self.db().get_entry(id)? else {
+            bail!(not_found(id));
+        };
+        let contents = entry.file_contents()?;
+        Ok(Response {
+            source: Some(EntrySource {
+                id,
+                text: String::from_utf8_lossy(&contents).into_owned(),
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_snapshot(&self) -> Result<Response> {
+        let entry = self.clipboard.snapshot()?;
+        // A snapshot is taken on request, not copied, so it joins no copy group.
+        let stored = self.db().insert_entry(&entry, chrono::Duration::zero(), false)?;
+        if let Stored::Added(id) | Stored::Bumped(id) = stored {
+            let _ = self.added.send(id);
         }
+        self.handle_list().await
+    }
+
+    async fn handle_restore(&self, id: u64) -> Result<Response> {
+        let Some(entry) = self.db().get_entry(id)? else {
+            bail!(not_found(id));
+        };
+        if entry.kind != EntryKind::Snapshot {
+            bail!("entry {id} is not a clipboard snapshot");
+        }
+        let data = entry.data.as_deref().context("snapshot entry has no data")?;
+        let formats = snapshot::decode(data)?;
+        self.clipboard.restore(&formats)?;
+        self.clipboard.set_on_clipboard(id);
+        self.db().touch_entry(id)?;
+        tracing::info!(id, formats = formats.len(), "restored clipboard snapshot");
+        self.handle_list().await
+    }
+
+    fn handle_pause_capture(&self, minutes: Option<u32>) -> Result<Response> {
+        let until = self.clipboard.pause(minutes);
+        Ok(Response {
+            capture: Some(CaptureStatus {
+                paused_until: until.map(|until| self.time.read().format(until)),
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_backups(&self) -> Result<Response> {
+        let time = self.time.read();
+        let backups = self
+            .db()
+            .backups()?
+            .into_iter()
+            .map(|backup| BackupSummary {
+                created: time.format(backup.created),
+                name: backup.name,
+                reason: backup.reason,
+                bytes: backup.bytes,
+            })
+            .collect();
+        Ok(Response {
+            backups: Some(backups),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_create_backup(&self) -> Result<Response> {
+        if self.db().backup("manual")?.is_none() {
+            bail!("backups are turned off; set general.backups above 0");
+        }
+        self.handle_backups().await
+    }
+
+    async fn handle_restore_backup(&self, name: String) -> Result<Response> {
+        self.db().restore_backup(&name)?;
+        self.handle_backups().await
+    }
+
+    async fn handle_unarchive(&self, id: u64) -> Result<Response> {
+        if self.db().unarchive(id)?.is_none() {
+            bail!(ErrorResponse::new(ErrorCode::NotFound, format!("entry {id} is not in the archive")));
+        }
+        self.handle_list().await
+    }
+
+    async fn handle_rotate_key(&self) -> Result<Response> {
+        self.keys.lock().rotate()?;
+        self.handle_keys().await
+    }
+
+    async fn handle_companion(&self, reset: bool) -> Result<Response> {
+        let key = if reset {
+            self.keys.lock().reset_companion_key()?
+        } else {
+            self.keys.lock().companion_key()?
+        };
+        let address = self.companion.lock().map(|address| companion::advertised(address).to_string());
+        Ok(Response {
+            companion: Some(CompanionInfo {
+                address,
+                key: keys::to_hex(&key),
+            }),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_sessions(&self) -> Result<Response> {
+        let current = self.db().current_session();
+        let time = self.time.read();
+        let sessions = self
+            .db()
+            .sessions()?
+            .into_iter()
+            .map(|session| SessionSummary {
+                id: session.id,
+                started: time.format(session.started),
+                ended: time.format(session.ended),
+                entries: session.entries,
+                current: session.id == current,
+            })
+            .collect();
+        Ok(Response {
+            sessions: Some(sessions),
+            ..Response::default()
+        })
+    }
+
+    async fn handle_tag_session(&self, session: u64, tag: String) -> Result<Response> {
+        let tagged = self.db().tag_session(session, &tag)?;
+        tracing::info!(session, %tag, tagged, "tagged session");
+        self.handle_sessions().await
+    }
+
+    async fn handle_export_session(&self, session: u64, path: String) -> Result<Response> {
+        tracing::info!(session, %path, "exporting session");
+        let redactor = self
+            .redact_exports
+            .load(Ordering::Relaxed)
+            .then(|| self.redactor.read().clone());
+        self.db()
+            .export_to_json(&path, &self.time.read(), Some(session), redactor.as_ref(), None)?;
+        self.handle_sessions().await
     }
 }
 
+/// Failure of a request for entry `id`, which does not exist.
+fn not_found(id: u64) -> ErrorResponse {
+    ErrorResponse::new(ErrorCode::NotFound, format!("no entry with id {id}"))
+}
+
+/// The id of the next entry stored, for a client that subscribed; never
+/// for one that did not.
+async fn next_added(added: &mut Option<broadcast::Receiver<u64>>) -> u64 {
+    let Some(added) = added else {
+        return std::future::pending().await;
+    };
+    loop {
+        match added.recv().await {
+            Ok(id) => return id,
+            Err(RecvError::Lagged(missed)) => tracing::debug!(missed, "subscribed client fell behind"),
+            Err(RecvError::Closed) => return std::future::pending().await,
+        }
+    }
+}
+
+/// `entry` as lists and searches send it, timestamps formatted per `time`.
+pub(crate) fn entry_summary(entry: Entry, time: &TimeDisplay) -> EntrySummary {
+    let warnings = entry.plain_text().map(hazard::scan).unwrap_or_default();
+    let styles = entry
+        .data
+        .as_deref()
+        .and_then(|data| entry.kind.parse_document(data))
+        // Offsets only fit text extracted the same way.
+        .filter(|document| entry.text_extracted.as_deref() == Some(document.text.as_str()))
+        .map(|document| document.styles.into_iter().map(Into::into).collect())
+        .unwrap_or_default();
+    let formats = match (&entry.kind, entry.data.as_deref()) {
+        (EntryKind::Snapshot, Some(data)) => snapshot::decode(data)
+            .map(|formats| formats.iter().map(|format| format.label()).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    EntrySummary {
+        id: entry.id.unwrap_or_default(),
+        preview: entry
+            .text_extracted
+            .or(entry.text)
+            .unwrap_or_else(|| "<non-text entry>".to_string()),
+        created_at: entry.created_at.to_rfc3339(),
+        created_display: Some(time.format(entry.created_at)),
+        kind: entry.kind.name().to_string(),
+        source_process: entry.source_process,
+        tags: entry.tags,
+        image: entry.image,
+        title: entry.title,
+        quarantined: entry.quarantined,
+        warnings,
+        lang: entry.lang,
+        pinned: entry.pinned,
+        styles,
+        copy_group: entry.copy_group,
+        formats,
+        archived: false,
+        highlights: Vec::new(),
+        entities: entry.entities,
+        on_clipboard: false,
+        use_count: entry.use_count,
+    }
+}
@@ -0,0 +1,181 @@
+//! Titles of the issues entries mention, looked up on GitHub and Jira with
+//! the tokens in `[issues]`, so the preview shows what `OPS-123` is about
+//! and searching for its title finds the entry. Without a token nothing
+//! leaves the PC; the references are still found for `has:issue`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clipcore::EntityKind;
+use serde::Deserialize;
+
+use crate::db::Database;
+
+pub const GITHUB_API: &str = "https://api.github.com";
+
+/// Longest a lookup may take; a slow tracker only delays the title.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The trackers with a token, which clipd asks for titles.
+#[derive(Debug, Clone, Default)]
+pub struct IssueTrackers {
+    pub github: Option<GitHub>,
+    pub jira: Option<Jira>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GitHub {
+    /// `https://api.github.com`, or a GitHub Enterprise server's API.
+    pub api: String,
+    pub token: String,
+    /// `owner/repo` that a bare `#456` refers to; without it those are not
+    /// looked up.
+    pub repo: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Jira {
+    /// The site, such as `https://example.atlassian.net`.
+    pub url: String,
+    /// The account an API token belongs to, for Jira Cloud; without it the
+    /// token is sent as a personal access token, as Jira Data Center takes it.
+    pub user: Option<String>,
+    pub token: String,
+    /// Project keys looked up; empty looks up every key.
+    pub projects: Vec<String>,
+}
+
+/// An issue on the tracker that has it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    GitHub { repo: String, number: u32 },
+    Jira { key: String },
+}
+
+impl Issue {
+    /// The issue named the same way wherever it was written, such as
+    /// `owner/repo#12` for a bare `#12`.
+    pub fn reference(&self) -> String {
+        match self {
+            Self::GitHub { repo, number } => format!("{repo}#{number}"),
+            Self::Jira { key } => key.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct JiraIssue {
+    fields: JiraFields,
+}
+
+#[derive(Deserialize)]
+struct JiraFields {
+    summary: String,
+}
+
+impl IssueTrackers {
+    pub fn is_empty(&self) -> bool {
+        self.github.is_none() && self.jira.is_none()
+    }
+
+    /// The issue an entry's `written` reference names, if a tracker with a
+    /// token has it.
+    pub fn resolve(&self, written: &str) -> Option<Issue> {
+        match written.split_once('#') {
+            Some((repo, number)) => {
+                let github = self.github.as_ref()?;
+                let repo = if repo.is_empty() { github.repo.clone()? } else { repo.to_string() };
+                Some(Issue::GitHub {
+                    repo,
+                    number: number.parse().ok()?,
+                })
+            }
+            None => {
+                let jira = self.jira.as_ref()?;
+                let (project, _) = written.split_once('-')?;
+                (jira.projects.is_empty() || jira.projects.iter().any(|known| known == project)).then(|| Issue::Jira {
+                    key: written.to_string(),
+                })
+            }
+        }
+    }
+
+    /// The title of `issue`, from its tracker; blocks until it answers.
+    pub fn fetch_title(&self, issue: &Issue) -> Result<String> {
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        match issue {
+            Issue::GitHub { repo, number } => {
+                let github = self.github.as_ref().context("no GitHub token in [issues]")?;
+                let url = format!("{}/repos/{repo}/issues/{number}", github.api.trim_end_matches('/'));
+                let found: GitHubIssue = agent
+                    .get(&url)
+                    .set("Accept", "application/vnd.github+json")
+                    .set("Authorization", &format!("Bearer {}", github.token))
+                    .call()
+                    .with_context(|| format!("failed to look up {repo}#{number} on GitHub"))?
+                    .into_json()
+                    .context("GitHub sent an issue clipd cannot read")?;
+                Ok(found.title)
+            }
+            Issue::Jira { key } => {
+                let jira = self.jira.as_ref().context("no Jira token in [issues]")?;
+                let url = format!("{}/rest/api/2/issue/{key}?fields=summary", jira.url.trim_end_matches('/'));
+                let authorization = match &jira.user {
+                    Some(user) => format!("Basic {}", STANDARD.encode(format!("{user}:{}", jira.token))),
+                    None => format!("Bearer {}", jira.token),
+                };
+                let found: JiraIssue = agent
+                    .get(&url)
+                    .set("Accept", "application/json")
+                    .set("Authorization", &authorization)
+                    .call()
+                    .with_context(|| format!("failed to look up {key} on Jira"))?
+                    .into_json()
+                    .context("Jira sent an issue clipd cannot read")?;
+                Ok(found.fields.summary)
+            }
+        }
+    }
+}
+
+/// Give the issues entry `id` mentions their titles: remembered ones right
+/// away, others once their tracker answers. An issue that cannot be looked
+/// up is left without a title, and asked about again with the next entry
+/// that mentions it.
+pub async fn add_titles(db: Database, trackers: IssueTrackers, id: u64) -> Result<()> {
+    let Some(entry) = db.get_entry(id)? else {
+        return Ok(());
+    };
+    let untitled = entry
+        .entities
+        .iter()
+        .filter(|entity| entity.kind == EntityKind::Issue && entity.title.is_none());
+    for entity in untitled {
+        let Some(issue) = trackers.resolve(&entity.value) else {
+            continue;
+        };
+        let reference = issue.reference();
+        let title = match db.issue_title(&reference)? {
+            Some(title) => title,
+            None => {
+                let trackers = trackers.clone();
+                match tokio::task::spawn_blocking(move || trackers.fetch_title(&issue)).await? {
+                    Ok(title) => title,
+                    Err(err) => {
+                        tracing::warn!(%reference, "{err:#}");
+                        continue;
+                    }
+                }
+            }
+        };
+        db.set_issue_title(&entity.value, &reference, &title)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! Write-ahead journal of captures on their way to the database.
+//!
+//! Each capture is appended to `captures.journal` and flushed to disk before it
+//! is queued, then marked once its SQLite transaction has committed. When clipd
+//! starts, captures that were never marked are queued again, so a crash
+//! between the copy and the insert cannot lose it. One that reached the
+//! database after all is skipped as a duplicate, even where copying stored
+//! content again would move it to the top. The file is emptied whenever
+//! nothing is outstanding, so it only ever holds the last few captures.
+//!
+//! Each line is either `[seq, capture]` or a bare `seq` marking that capture
+//! done. A line cut short by the crash is ignored.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use crate::clipboard::Capture;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Capture(u64, Capture),
+    Done(u64),
+}
+
+#[derive(Debug)]
+struct Inner {
+    file: File,
+    next_seq: u64,
+    /// Captures appended but not yet marked done.
+    outstanding: HashSet<u64>,
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl Journal {
+    /// Open the journal at `path`, returning the captures in it that never
+    /// reached the database, oldest first.
+    pub fn open(path: PathBuf) -> Result<(Self, Vec<(u64, Capture)>)> {
+        let mut pending = BTreeMap::new();
+        let mut next_seq = 1;
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read capture journal: {}", path.display()))?;
+            for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str(line) {
+                    Ok(Record::Capture(seq, capture)) => {
+                        next_seq = next_seq.max(seq + 1);
+                        pending.insert(seq, capture);
+                    }
+                    Ok(Record::Done(seq)) => {
+                        pending.remove(&seq);
+                    }
+                    Err(err) => tracing::warn!(%err, "ignoring unreadable capture journal record"),
+                }
+            }
+        }
+        if !pending.is_empty() {
+            tracing::warn!(count = pending.len(), "replaying captures the last run did not store");
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open capture journal: {}", path.display()))?;
+        if pending.is_empty() {
+            file.set_len(0)
+                .with_context(|| format!("failed to empty capture journal: {}", path.display()))?;
+        }
+        let journal = Self {
+            inner: Mutex::new(Inner {
+                file,
+                next_seq,
+                outstanding: pending.keys().copied().collect(),
+            }),
+            path,
+        };
+        Ok((journal, pending.into_iter().collect()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record `capture` durably; returns the number to mark it done with.
+    pub fn append(&self, capture: &Capture) -> Result<u64> {
+        let mut inner = self.inner.lock();
+        let seq = inner.next_seq;
+        let mut line = serde_json::to_vec(&(seq, capture)).context("failed to serialize capture")?;
+        line.push(b'\n');
+        inner.file.write_all(&line).context("failed to append to capture journal")?;
+        inner.file.sync_data().context("failed to flush capture journal")?;
+        inner.next_seq += 1;
+        inner.outstanding.insert(seq);
+        Ok(seq)
+    }
+
+    /// Mark capture `seq` as stored, or dropped on purpose.
+    pub fn done(&self, seq: u64) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if !inner.outstanding.remove(&seq) {
+            return Ok(());
+        }
+        if inner.outstanding.is_empty() {
+            inner.file.set_len(0).context("failed to empty capture journal")?;
+        } else {
+            // Not flushed: a lost mark only means the capture is replayed and skipped as a duplicate.
+            writeln!(inner.file, "{seq}").context("failed to append to capture journal")?;
+        }
+        Ok(())
+    }
+}
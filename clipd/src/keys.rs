@@ -0,0 +1,296 @@
+//! Encryption keys for clipd's data, protected at rest with Windows DPAPI.
+//!
+//! Keys are random 256-bit values. `keys.json` only holds them as returned by
+//! `CryptProtectData`, so only the same Windows user can unwrap them. Linux
+//! and macOS have no DPAPI; there the keys are stored as they are, in a file
+//! only its owner can read. Rotating adds a new current key and keeps the old
+//! ones, so anything sealed with them can still be opened. The key companion
+//! apps pair with ([`crate::companion`]) is kept there too, apart from the
+//! others.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use windows::core::PCWSTR;
+#[cfg(target_os = "windows")]
+use windows::Win32::Foundation::{LocalFree, HLOCAL};
+#[cfg(target_os = "windows")]
+use windows::Win32::Security::Cryptography::{
+    BCryptGenRandom, CryptProtectData, CryptUnprotectData, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+    CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+};
+
+/// Start of data sealed by [`KeyRing::seal`], followed by the key id (u32 LE)
+/// and the nonce.
+const SEALED_MAGIC: &[u8] = b"clipmgr-sealed-v1\n";
+const NONCE_LEN: usize = 12;
+
+/// Public part of a key, as listed by `clipctl keys`.
+#[derive(Debug, Clone)]
+pub struct KeyInfo {
+    pub id: u32,
+    pub created_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+/// The keys in `keys.json`; the first one is created on open.
+pub struct KeyRing {
+    path: PathBuf,
+    file: KeyFile,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct KeyFile {
+    current: u32,
+    keys: Vec<StoredKey>,
+    /// The companion pairing key wrapped by DPAPI, hex-encoded; made the
+    /// first time it is asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    companion: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredKey {
+    id: u32,
+    created_at: DateTime<Utc>,
+    /// The key wrapped by DPAPI, hex-encoded.
+    protected: String,
+}
+
+impl KeyRing {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read key file: {}", path.display()))?;
+            serde_json::from_str(&raw)
+                .with_context(|| format!("failed to parse key file: {}", path.display()))?
+        } else {
+            KeyFile::default()
+        };
+        let mut ring = Self { path, file };
+        if ring.file.keys.is_empty() {
+            ring.rotate()?;
+        }
+        Ok(ring)
+    }
+
+    pub fn keys(&self) -> Vec<KeyInfo> {
+        self.file
+            .keys
+            .iter()
+            .map(|key| KeyInfo {
+                id: key.id,
+                created_at: key.created_at,
+                current: key.id == self.file.current,
+            })
+            .collect()
+    }
+
+    /// Generate a new current key, keeping the previous ones for decryption.
+    pub fn rotate(&mut self) -> Result<u32> {
+        let id = self.file.keys.iter().map(|key| key.id).max().map_or(1, |id| id + 1);
+        let key = random_bytes::<32>()?;
+        self.file.keys.push(StoredKey {
+            id,
+            created_at: Utc::now(),
+            protected: to_hex(&protect(&key)?),
+        });
+        self.file.current = id;
+        self.save()?;
+        tracing::info!(id, "generated encryption key");
+        Ok(id)
+    }
+
+    /// The key companion apps pair with, made on first use.
+    pub fn companion_key(&mut self) -> Result<[u8; 32]> {
+        let Some(protected) = &self.file.companion else {
+            return self.reset_companion_key();
+        };
+        let key = from_hex(protected)
+            .and_then(|protected| unprotect(&protected).ok())
+            .and_then(|key| key.try_into().ok())
+            .with_context(|| format!("the companion key in {} cannot be unwrapped", self.path.display()))?;
+        Ok(key)
+    }
+
+    /// Replace the companion key, so every paired app has to pair again.
+    pub fn reset_companion_key(&mut self) -> Result<[u8; 32]> {
+        let key = random_bytes::<32>()?;
+        self.file.companion = Some(to_hex(&protect(&key)?));
+        self.save()?;
+        tracing::info!("generated companion pairing key");
+        Ok(key)
+    }
+
+    /// Encrypt `plaintext` with the current key.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let id = self.file.current;
+        let cipher = self.cipher(id)?;
+        let nonce = random_bytes::<NONCE_LEN>()?;
+        let ciphertext = cipher
+            .encrypt(&Nonce::from(nonce), plaintext)
+            .map_err(|_| anyhow!("failed to encrypt with key {id}"))?;
+
+        let mut sealed = SEALED_MAGIC.to_vec();
+        sealed.extend_from_slice(&id.to_le_bytes());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt data from [`KeyRing::seal`], with whichever key sealed it.
+    pub fn unseal(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let rest = sealed.strip_prefix(SEALED_MAGIC).context("data was not sealed by clipd")?;
+        if rest.len() < 4 + NONCE_LEN {
+            bail!("sealed data is truncated");
+        }
+        let (id, rest) = rest.split_at(4);
+        let id = u32::from_le_bytes(id.try_into().expect("split at 4 bytes"));
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at NONCE_LEN bytes");
+        self.cipher(id)?
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| anyhow!("failed to decrypt with key {id}; the data is damaged"))
+    }
+
+    fn cipher(&self, id: u32) -> Result<Aes256Gcm> {
+        let stored = self
+            .file
+            .keys
+            .iter()
+            .find(|key| key.id == id)
+            .with_context(|| format!("key {id} is not in {}", self.path.display()))?;
+        let protected = from_hex(&stored.protected)
+            .with_context(|| format!("key {id} in {} is corrupt", self.path.display()))?;
+        let key = unprotect(&protected).with_context(|| {
+            format!("failed to unwrap key {id}; it belongs to another Windows user or machine")
+        })?;
+        Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow!("key {id} has the wrong length"))
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.file)?;
+        write_private(&self.path, json.as_bytes())
+            .with_context(|| format!("failed to write key file: {}", self.path.display()))
+    }
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(SEALED_MAGIC)
+}
+
+/// Write `path` as `std::fs::write` would.
+#[cfg(target_os = "windows")]
+fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    std::fs::write(path, data)
+}
+
+/// Write `path`, readable and writable by its owner alone.
+#[cfg(not(target_os = "windows"))]
+fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    use std::fs::{OpenOptions, Permissions};
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    // The mode only applies to a new file.
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(data)
+}
+
+#[cfg(target_os = "windows")]
+fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    let input = blob(data);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptProtectData(
+            &input,
+            PCWSTR::null(),
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .context("CryptProtectData failed")?;
+        Ok(take_blob(output))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    let input = blob(data);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptUnprotectData(&input, None, None, None, None, CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+            .context("CryptUnprotectData failed")?;
+        Ok(take_blob(output))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    Ok(data.to_vec())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    Ok(data.to_vec())
+}
+
+/// DPAPI takes its input through a `*mut` it does not write to.
+#[cfg(target_os = "windows")]
+fn blob(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+    CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    }
+}
+
+/// Copy out and free a blob that DPAPI allocated.
+#[cfg(target_os = "windows")]
+unsafe fn take_blob(blob: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    let data = std::slice::from_raw_parts(blob.pbData, blob.cbData as usize).to_vec();
+    LocalFree(HLOCAL(blob.pbData.cast()));
+    data
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    unsafe { BCryptGenRandom(None, &mut bytes, BCRYPT_USE_SYSTEM_PREFERRED_RNG) }
+        .ok()
+        .context("BCryptGenRandom failed")?;
+    Ok(bytes)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes).context("the system random number generator failed")?;
+    Ok(bytes)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => Some((hex_digit(*high)? << 4) | hex_digit(*low)?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
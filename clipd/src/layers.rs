@@ -0,0 +1,331 @@
+//! The configuration as layers, each overriding the ones before it: the
+//! built-in defaults, the machine-wide file, the user's `config.toml`, the
+//! `CLIPMGR_*` environment variables, and `--set KEY=VALUE` flags.
+//!
+//! Layers are merged key by key, so a machine file can set what every user
+//! of the machine gets and each user's file only what differs; lists, like
+//! `[[views]]`, are replaced whole. Every value remembers the layer it came
+//! from, which `clipctl config show --origin` prints.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use toml_edit::{DocumentMut, ImDocument, InlineTable, Item, Table, TableLike, Value};
+
+use crate::schema::{self, Problem};
+
+/// Names the machine-wide file in place of the usual one, such as for a
+/// share that several machines read.
+const MACHINE_CONFIG_VAR: &str = "CLIPMGR_MACHINE_CONFIG";
+
+/// Variables older versions read, and the keys they set.
+const ENV_ALIASES: &[(&str, &str)] = &[
+    ("CLIPMGR_MAX_ENTRIES", "general.max_entries"),
+    ("CLIPMGR_PIPE", "general.pipe_name"),
+    ("CLIPMGR_SPAWN_DAEMON", "general.spawn_daemon"),
+    ("CLIPMGR_PROFILE", "profiles.active"),
+    ("CLIPMGR_GITHUB_TOKEN", "issues.github_token"),
+    ("CLIPMGR_JIRA_TOKEN", "issues.jira_token"),
+];
+
+/// Sections a `CLIPMGR_<SECTION>_<KEY>` variable can name.
+const ENV_SECTIONS: &[&str] = &[
+    "general", "capture", "time", "profiles", "export", "paste", "ignore", "issues", "battery", "companion",
+    "ui", "privacy", "transformers", "logging",
+];
+
+/// `--set` flags the program was started with.
+static FLAGS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Record the `--set KEY=VALUE` flags the program was started with, for
+/// [`Sources::system`]. Only the first call counts.
+pub fn set_flags(flags: Vec<String>) {
+    let _ = FLAGS.set(flags);
+}
+
+/// Which layer a value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Set nowhere, so the built-in default applies.
+    Default,
+    Machine { path: PathBuf, line: Option<usize> },
+    User { path: PathBuf, line: Option<usize> },
+    /// The environment variable that set it.
+    Env(String),
+    Flag,
+}
+
+impl Origin {
+    /// Set by the environment or a flag rather than a file.
+    pub fn is_override(&self) -> bool {
+        matches!(self, Origin::Env(_) | Origin::Flag)
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (layer, path, line) = match self {
+            Origin::Default => return f.write_str("default"),
+            Origin::Env(var) => return write!(f, "env {var}"),
+            Origin::Flag => return f.write_str("flag --set"),
+            Origin::Machine { path, line } => ("machine", path, line),
+            Origin::User { path, line } => ("user", path, line),
+        };
+        write!(f, "{layer} {}", path.display())?;
+        match line {
+            Some(line) => write!(f, ":{line}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where the layers are read from.
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    pub machine: Option<PathBuf>,
+    pub user: PathBuf,
+    /// `CLIPMGR_*` variables, by name.
+    pub env: Vec<(String, String)>,
+    /// `KEY=VALUE`, as given to `--set`.
+    pub flags: Vec<String>,
+}
+
+impl Sources {
+    /// The machine-wide file, `user`, this process's environment, and the
+    /// flags it was started with.
+    pub fn system(user: PathBuf) -> Self {
+        Self {
+            machine: Some(machine_path()),
+            user,
+            env: env::vars().filter(|(name, _)| name.starts_with("CLIPMGR_")).collect(),
+            flags: FLAGS.get().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Only the file at `user`.
+    pub fn file(user: &Path) -> Self {
+        Self {
+            user: user.to_path_buf(),
+            ..Self::default()
+        }
+    }
+}
+
+/// The machine-wide file: in ProgramData on Windows, `/etc` elsewhere.
+pub fn machine_path() -> PathBuf {
+    if let Some(path) = env::var_os(MACHINE_CONFIG_VAR) {
+        return PathBuf::from(path);
+    }
+    #[cfg(target_os = "windows")]
+    let dir = env::var_os("ProgramData").map_or_else(|| PathBuf::from(r"C:\ProgramData"), PathBuf::from);
+    #[cfg(not(target_os = "windows"))]
+    let dir = PathBuf::from("/etc");
+    dir.join("clipmgr").join("config.toml")
+}
+
+/// The layers merged, with where each value came from.
+#[derive(Debug, Default)]
+pub struct Layered {
+    /// Every value set, by the path of keys to it.
+    values: BTreeMap<Vec<String>, (Value, Origin)>,
+    /// Each problem with the source it is in: a file, a variable, or a flag.
+    /// Layers with problems are left out whole.
+    pub problems: Vec<(String, Problem)>,
+}
+
+/// Read and merge every layer in `sources`. Fails only when a file that
+/// exists cannot be read; problems are kept in [`Layered::problems`].
+pub fn gather(sources: &Sources) -> Result<Layered> {
+    let mut layered = Layered::default();
+    let files = [
+        (sources.machine.as_deref(), true),
+        (Some(sources.user.as_path()), false),
+    ];
+    for (path, machine) in files {
+        let Some(path) = path.filter(|path| path.exists()) else {
+            continue;
+        };
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file: {}", path.display()))?;
+        layered.add_file(path, &raw, machine);
+    }
+
+    for (var, raw) in &sources.env {
+        let Some(key) = env_key(var) else {
+            continue;
+        };
+        // An empty variable is as good as unset, as on Windows there is no other way to clear one.
+        if !raw.is_empty() {
+            layered.add_value(var, &key, raw, Origin::Env(var.clone()));
+        }
+    }
+    for flag in &sources.flags {
+        let source = format!("--set {flag}");
+        match flag.split_once('=') {
+            Some((key, raw)) => layered.add_value(&source, key.trim(), raw, Origin::Flag),
+            None => layered.problem(source, "expected KEY=VALUE, such as ui.theme=nord".to_string()),
+        }
+    }
+    Ok(layered)
+}
+
+impl Layered {
+    /// Fail listing every problem, each under its source, if there are any.
+    pub fn check(self) -> Result<Self> {
+        if self.problems.is_empty() {
+            return Ok(self);
+        }
+        let mut sources: Vec<(&str, Vec<&Problem>)> = Vec::new();
+        for (source, problem) in &self.problems {
+            match sources.iter_mut().find(|(name, _)| name == source) {
+                Some((_, problems)) => problems.push(problem),
+                None => sources.push((source, vec![problem])),
+            }
+        }
+        let report: Vec<String> = sources
+            .iter()
+            .map(|(source, problems)| {
+                let list: Vec<String> = problems.iter().map(|problem| format!("\n  {problem}")).collect();
+                format!("{source} has {} problem(s):{}", problems.len(), list.concat())
+            })
+            .collect();
+        bail!("{}", report.join("\n"))
+    }
+
+    /// The merged configuration as one document.
+    pub fn document(&self) -> DocumentMut {
+        let mut doc = DocumentMut::new();
+        for (path, (value, _)) in &self.values {
+            let (name, parents) = path.split_last().expect("paths are not empty");
+            let mut table = doc.as_table_mut();
+            for parent in parents {
+                let item = table.entry(parent).or_insert_with(|| Item::Table(implicit_table()));
+                // An empty inline table stands for a table nothing is set in yet.
+                if !item.is_table() {
+                    *item = Item::Table(implicit_table());
+                }
+                table = item.as_table_mut().expect("just made a table");
+            }
+            table.insert(name, Item::Value(value.clone()));
+        }
+        doc
+    }
+
+    /// Where the value of dotted key `key` came from.
+    pub fn origin(&self, key: &str) -> Origin {
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        self.values.get(&path).map_or(Origin::Default, |(_, origin)| origin.clone())
+    }
+
+    /// Every value set, by dotted key, with its origin, in key order.
+    pub fn values(&self) -> impl Iterator<Item = (String, &Value, &Origin)> {
+        self.values.iter().map(|(path, (value, origin))| (path.join("."), value, origin))
+    }
+
+    fn add_file(&mut self, path: &Path, raw: &str, machine: bool) {
+        let problems = schema::check_layer(raw, &self.profiles());
+        if !problems.is_empty() {
+            let source = path.display().to_string();
+            self.problems.extend(problems.into_iter().map(|problem| (source.clone(), problem)));
+            return;
+        }
+        let (Ok(doc), Ok(spans)) = (raw.parse::<DocumentMut>(), ImDocument::parse(raw)) else {
+            return;
+        };
+        let mut values = Vec::new();
+        flatten(doc.as_table(), &mut Vec::new(), &mut values);
+        for (keys, value) in values {
+            let line = line_of(raw, spans.as_table(), &keys);
+            let path = path.to_path_buf();
+            let origin = if machine { Origin::Machine { path, line } } else { Origin::User { path, line } };
+            self.values.insert(keys, (value, origin));
+        }
+    }
+
+    fn add_value(&mut self, source: &str, key: &str, raw: &str, origin: Origin) {
+        match schema::parse_value(key, raw) {
+            Ok(value) => {
+                let path = key.split('.').map(str::to_string).collect();
+                self.values.insert(path, (value, origin));
+            }
+            Err(message) => self.problem(source.to_string(), message),
+        }
+    }
+
+    fn problem(&mut self, source: String, message: String) {
+        self.problems.push((source, Problem { line: None, message }));
+    }
+
+    /// Profiles defined so far.
+    fn profiles(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .values
+            .keys()
+            .filter(|path| path.len() >= 2 && path[0] == "profiles" && path[1] != "active")
+            .map(|path| path[1].clone())
+            .collect();
+        names.dedup();
+        names
+    }
+}
+
+/// The key a `CLIPMGR_*` variable sets: an old name, or
+/// `CLIPMGR_<SECTION>_<KEY>` such as `CLIPMGR_UI_THEME`.
+fn env_key(var: &str) -> Option<String> {
+    if let Some((_, key)) = ENV_ALIASES.iter().find(|(name, _)| *name == var) {
+        return Some(key.to_string());
+    }
+    let name = var.strip_prefix("CLIPMGR_")?.to_lowercase();
+    let (section, key) = name.split_once('_')?;
+    ENV_SECTIONS.contains(&section).then(|| format!("{section}.{key}"))
+}
+
+/// Every value in `table` by its path of keys, tables taken apart so each
+/// key can be overridden on its own. Lists of tables stay whole, as inline
+/// arrays, and empty tables stay as empty inline tables.
+fn flatten(table: &dyn TableLike, path: &mut Vec<String>, values: &mut Vec<(Vec<String>, Value)>) {
+    if table.is_empty() && !path.is_empty() {
+        values.push((path.clone(), Value::InlineTable(InlineTable::new())));
+    }
+    for (name, item) in table.iter() {
+        path.push(name.to_string());
+        match item {
+            Item::Table(table) => flatten(table, path, values),
+            Item::Value(Value::InlineTable(table)) => flatten(table, path, values),
+            Item::Value(value) => values.push((path.clone(), plain(value.clone()))),
+            Item::ArrayOfTables(tables) => values.push((path.clone(), plain(tables.clone().into_array().into()))),
+            Item::None => {}
+        }
+        path.pop();
+    }
+}
+
+/// `value` without the comments and spacing it had in its file.
+fn plain(mut value: Value) -> Value {
+    value.decor_mut().clear();
+    if let Value::Array(array) = &mut value {
+        array.fmt();
+    }
+    value
+}
+
+/// Line of `raw` that sets the value at `path`.
+fn line_of(raw: &str, root: &Table, path: &[String]) -> Option<usize> {
+    let (name, parents) = path.split_last()?;
+    let mut table: &dyn TableLike = root;
+    for parent in parents {
+        table = table.get(parent)?.as_table_like()?;
+    }
+    let span = table.key(name).and_then(|key| key.span()).or_else(|| table.get(name)?.span())?;
+    Some(raw[..span.start.min(raw.len())].matches('\n').count() + 1)
+}
+
+fn implicit_table() -> Table {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    table
+}
@@ -0,0 +1,46 @@
+//! clipd's capture, storage, and IPC, shared by the daemon binary, the
+//! integration tests in `tests/`, and the fuzz targets in `fuzz/`.
+
+mod archive;
+pub mod backend;
+mod backup;
+pub mod clipboard;
+mod cold;
+mod collate;
+pub mod companion;
+pub mod config;
+pub mod db;
+mod document;
+mod entity;
+mod files;
+mod fold;
+pub mod fuzz;
+mod hazard;
+pub mod html;
+mod ignore;
+pub mod image;
+pub mod ipc;
+mod issues;
+mod journal;
+mod keys;
+mod lang;
+pub mod layers;
+mod listener;
+pub mod mock;
+pub mod model;
+mod paste;
+mod profiles;
+mod project;
+pub mod query;
+mod queue;
+mod redact;
+mod rtf;
+pub mod runtime;
+pub mod schema;
+pub mod service;
+#[cfg(target_os = "windows")]
+mod session;
+pub mod snapshot;
+mod suggest;
+pub mod timefmt;
+mod title;
@@ -0,0 +1,114 @@
+//! Where clipctl connects to clipd: the named pipe on Windows, a Unix domain
+//! socket on Linux and macOS. `pipe_name` names either.
+
+#[cfg(target_os = "windows")]
+pub use self::pipe::{Connection, Listener};
+#[cfg(not(target_os = "windows"))]
+pub use self::socket::{Connection, Listener};
+
+#[cfg(target_os = "windows")]
+mod pipe {
+    use anyhow::{Context, Result};
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+    pub type Connection = NamedPipeServer;
+
+    /// Pipe instances, one waiting for the next client at a time.
+    pub struct Listener {
+        name: String,
+        next: Option<NamedPipeServer>,
+    }
+
+    impl Listener {
+        /// Create the first instance of pipe `name`.
+        pub fn bind(name: &str) -> Result<Self> {
+            Ok(Self {
+                name: name.to_string(),
+                next: Some(create(name)?),
+            })
+        }
+
+        /// The next client, or `None` when one failed to connect. Fails when
+        /// no new instance can be created.
+        pub async fn accept(&mut self) -> Result<Option<Connection>> {
+            let pipe = match self.next.take() {
+                Some(pipe) => pipe,
+                None => create(&self.name)?,
+            };
+            if let Err(err) = pipe.connect().await {
+                tracing::warn!(%err, "failed to connect named pipe client");
+                return Ok(None);
+            }
+            Ok(Some(pipe))
+        }
+    }
+
+    fn create(name: &str) -> Result<NamedPipeServer> {
+        // Create named pipe with default security attributes
+        // This grants access to the same user that created the pipe
+        ServerOptions::new().create(name).with_context(|| {
+            format!(
+                "failed to create named pipe {name}. \
+                If you're getting 'Access is denied', ensure clipd is running \
+                and was started by the same user."
+            )
+        })
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod socket {
+    use std::fs::{self, Permissions};
+    use std::io::ErrorKind;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    use anyhow::{bail, Context, Result};
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub type Connection = UnixStream;
+
+    /// A socket only its owner can connect to, removed when dropped.
+    pub struct Listener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        /// Listen on the socket at `path`. One left by a clipd that exited
+        /// is replaced; one another clipd listens on is left alone.
+        pub fn bind(path: &str) -> Result<Self> {
+            let path = PathBuf::from(path);
+            match std::os::unix::net::UnixStream::connect(&path) {
+                Ok(_) => bail!("another clipd is already listening on {}", path.display()),
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(_) => fs::remove_file(&path)
+                    .with_context(|| format!("failed to remove the stale socket {}", path.display()))?,
+            }
+            let listener =
+                UnixListener::bind(&path).with_context(|| format!("failed to listen on {}", path.display()))?;
+            fs::set_permissions(&path, Permissions::from_mode(0o600))
+                .with_context(|| format!("failed to restrict access to {}", path.display()))?;
+            Ok(Self { listener, path })
+        }
+
+        /// The next client, or `None` when one failed to connect.
+        pub async fn accept(&mut self) -> Result<Option<Connection>> {
+            match self.listener.accept().await {
+                Ok((stream, _)) => Ok(Some(stream)),
+                Err(err) => {
+                    tracing::warn!(%err, "failed to accept socket client");
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            if let Err(err) = fs::remove_file(&self.path) {
+                tracing::warn!(%err, path = %self.path.display(), "failed to remove socket");
+            }
+        }
+    }
+}
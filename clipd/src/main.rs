@@ -1,18 +1,32 @@
 //! clipd - background clipboard capture daemon.
 
-mod clipboard;
-mod config;
-mod db;
-mod ipc;
-mod model;
-mod service;
-
-use anyhow::Result;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clipd::{config, layers, service};
 use tokio::signal;
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut flags = Vec::new();
+    let mut check = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check-config" => check = true,
+            "--set" => flags.push(args.next().context("--set needs KEY=VALUE")?),
+            other => match other.strip_prefix("--set=") {
+                Some(flag) => flags.push(flag.to_string()),
+                None => bail!("unknown argument {other}; clipd takes --check-config and --set KEY=VALUE"),
+            },
+        }
+    }
+    layers::set_flags(flags);
+    if check {
+        return check_config();
+    }
+
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .with_target(false)
@@ -23,21 +37,55 @@ async fn main() -> Result<()> {
 
     let config = config::Config::load()?;
     let service = service::ClipdService::bootstrap(config).await?;
+    let shutdown = service.shutdown_handle();
 
-    let service_task = service.run();
+    let mut service_task = tokio::spawn(service.run());
 
-    tokio::select! {
-        res = service_task => {
-            if let Err(err) = res {
-                tracing::error!(%err, "clipd service exited with error");
-            }
-        }
+    let result = tokio::select! {
+        res = &mut service_task => Some(res),
         _ = signal::ctrl_c() => {
-            tracing::info!("shutdown signal received");
+            tracing::info!("shutdown signal received, draining clients");
+            shutdown.trigger();
+            tokio::time::timeout(Duration::from_secs(5), &mut service_task)
+                .await
+                .ok()
         }
+    };
+
+    match result {
+        Some(Ok(Err(err))) => tracing::error!(%err, "clipd service exited with error"),
+        Some(Err(join_err)) => tracing::error!(%join_err, "clipd service task panicked"),
+        Some(Ok(Ok(()))) => {}
+        None => tracing::warn!("service did not stop in time"),
     }
 
     tracing::info!("clipd shutting down");
     Ok(())
 }
 
+/// `clipd --check-config`: list every problem with the machine and user
+/// files, the environment, and the flags, failing when there is one.
+fn check_config() -> Result<()> {
+    let sources = config::Config::sources()?;
+    for path in sources.machine.iter().chain([&sources.user]) {
+        if path.exists() {
+            println!("reading {}", path.display());
+        } else {
+            println!("{}: not found", path.display());
+        }
+    }
+
+    let problems = layers::gather(&sources)?.problems;
+    for (source, problem) in &problems {
+        match problem.line {
+            Some(line) => eprintln!("{source}:{line}: {}", problem.message),
+            None => eprintln!("{source}: {}", problem.message),
+        }
+    }
+    if !problems.is_empty() {
+        bail!("the configuration has {} problem(s)", problems.len());
+    }
+    println!("ok");
+    Ok(())
+}
+
@@ -0,0 +1,220 @@
+//! In-memory clipboard for driving clipd without a Windows desktop session.
+//!
+//! Tests copy into a [`MockClipboard`] the way an app would, bring mock
+//! windows to the front, and read back what clipd pastes or restores.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+
+use crate::backend::{ClipboardBackend, FormatId, CF_DIB, CF_HDROP, CF_UNICODETEXT};
+use crate::files;
+use crate::snapshot::{self, Format};
+
+/// Monitor every mock window is shown on.
+const MONITOR: &str = r"\\.\DISPLAY1";
+
+#[derive(Debug, Default)]
+struct State {
+    sequence: u32,
+    formats: Vec<Format>,
+    /// Process that wrote the clipboard; `None` after clipd writes it.
+    owner: Option<String>,
+    /// Process and title of every window brought to the front; a window's
+    /// handle is its index plus one.
+    windows: Vec<(String, String)>,
+    foreground: usize,
+    /// Reads fail as if another process held the clipboard open.
+    locked: bool,
+    /// Times `sequence` was polled since the clipboard or the window in
+    /// front last changed.
+    polls: u32,
+    workstation_locked: bool,
+    battery_percent: Option<u8>,
+}
+
+/// A scriptable clipboard; clones share the same contents.
+#[derive(Debug, Clone, Default)]
+pub struct MockClipboard {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bring a window of `process` titled `title` to the front.
+    pub fn focus(&self, process: &str, title: &str) {
+        let mut state = self.state.lock();
+        state.windows.push((process.to_string(), title.to_string()));
+        state.foreground = state.windows.len();
+        state.polls = 0;
+    }
+
+    /// Copy `text` from the window in front.
+    pub fn copy_text(&self, text: &str) {
+        self.copy(vec![unicode_text(text)]);
+    }
+
+    /// Copy a device-independent bitmap from the window in front.
+    pub fn copy_image(&self, dib: Vec<u8>) {
+        self.copy(vec![Format {
+            id: CF_DIB,
+            name: None,
+            data: dib,
+        }]);
+    }
+
+    /// Copy files at `paths` from the window in front, as Explorer does.
+    pub fn copy_files(&self, paths: &[&str]) {
+        self.copy(vec![Format {
+            id: CF_HDROP,
+            name: None,
+            data: files::encode_drop(paths),
+        }]);
+    }
+
+    /// Copy `formats` at once from the window in front, as Office and browsers do.
+    pub fn copy(&self, formats: Vec<Format>) {
+        let mut state = self.state.lock();
+        state.owner = state
+            .foreground
+            .checked_sub(1)
+            .map(|index| state.windows[index].0.clone());
+        state.formats = formats;
+        state.sequence += 1;
+        state.polls = 0;
+    }
+
+    /// Make reads fail, as while another process holds the clipboard open.
+    pub fn set_locked(&self, locked: bool) {
+        self.state.lock().locked = locked;
+    }
+
+    /// Lock or unlock the workstation.
+    pub fn set_workstation_locked(&self, locked: bool) {
+        self.state.lock().workstation_locked = locked;
+    }
+
+    /// Run on battery at `percent` charge, or on AC power when `None`.
+    pub fn set_battery(&self, percent: Option<u8>) {
+        self.state.lock().battery_percent = percent;
+    }
+
+    /// Everything on the clipboard now, such as what clipd pasted.
+    pub fn formats(&self) -> Vec<Format> {
+        self.state.lock().formats.clone()
+    }
+
+    /// The clipboard's Unicode text.
+    pub fn text(&self) -> Option<String> {
+        snapshot::text(&self.state.lock().formats)
+    }
+
+    /// Paths of the files on the clipboard.
+    pub fn files(&self) -> Option<Vec<String>> {
+        self.find(FormatId::Standard(CF_HDROP), |format| files::parse_drop(&format.data))?
+    }
+
+    /// Apply `f` to `format` on the clipboard, unless reads fail.
+    fn find<T>(&self, format: FormatId<'_>, f: impl FnOnce(&Format) -> T) -> Option<T> {
+        let state = self.state.lock();
+        if state.locked {
+            return None;
+        }
+        state
+            .formats
+            .iter()
+            .find(|candidate| match format {
+                FormatId::Standard(id) => candidate.name.is_none() && candidate.id == id,
+                FormatId::Registered(name) => candidate.name.as_deref() == Some(name),
+            })
+            .map(f)
+    }
+
+    /// Whether the watcher is done with the latest change: it has polled
+    /// again since it first saw it.
+    pub fn settled(&self) -> bool {
+        self.state.lock().polls >= 2
+    }
+}
+
+/// `text` as `CF_UNICODETEXT` data.
+pub fn unicode_text(text: &str) -> Format {
+    Format {
+        id: CF_UNICODETEXT,
+        name: None,
+        data: text.encode_utf16().chain(Some(0)).flat_map(u16::to_le_bytes).collect(),
+    }
+}
+
+impl ClipboardBackend for MockClipboard {
+    fn sequence(&self) -> u32 {
+        let mut state = self.state.lock();
+        state.polls = state.polls.saturating_add(1);
+        state.sequence
+    }
+
+    fn read(&self, format: FormatId<'_>) -> Option<Vec<u8>> {
+        self.find(format, |format| format.data.clone())
+    }
+
+    fn size(&self, format: FormatId<'_>) -> Option<usize> {
+        self.find(format, |format| format.data.len())
+    }
+
+    fn read_all(&self) -> Result<Vec<Format>> {
+        let state = self.state.lock();
+        if state.locked {
+            bail!("failed to open the clipboard");
+        }
+        Ok(state.formats.clone())
+    }
+
+    fn write(&self, formats: &[Format], written: &dyn Fn(u32)) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.locked {
+            bail!("failed to open the clipboard");
+        }
+        state.owner = None;
+        state.formats = formats.to_vec();
+        state.sequence += 1;
+        state.polls = 0;
+        written(state.sequence);
+        Ok(())
+    }
+
+    fn owner_process(&self) -> Option<String> {
+        self.state.lock().owner.clone()
+    }
+
+    fn foreground_window(&self) -> usize {
+        self.state.lock().foreground
+    }
+
+    fn window_process(&self, window: usize) -> Option<String> {
+        let state = self.state.lock();
+        let (process, _) = state.windows.get(window.checked_sub(1)?)?;
+        Some(process.clone())
+    }
+
+    fn window_title(&self, window: usize) -> Option<String> {
+        let state = self.state.lock();
+        let (_, title) = state.windows.get(window.checked_sub(1)?)?;
+        Some(title.clone())
+    }
+
+    fn window_monitor(&self, window: usize) -> Option<(String, Option<u32>)> {
+        (window != 0).then(|| (MONITOR.to_string(), Some(96)))
+    }
+
+    fn workstation_locked(&self) -> bool {
+        self.state.lock().workstation_locked
+    }
+
+    fn battery_percent(&self) -> Option<u8> {
+        self.state.lock().battery_percent
+    }
+}
@@ -1,16 +1,80 @@
 //! Shared data models for clipboard entries.
 
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use clipcore::Entity;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::document::Document;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EntryKind {
     Text,
     Url,
     Image,
     Rtf,
+    Html,
+    /// Every format that was on the clipboard, stored by `snapshot::encode`.
+    Snapshot,
+    /// Files copied in Explorer, stored as their `CF_HDROP` data with the
+    /// paths, one per line, as text.
+    Files,
+}
+
+impl EntryKind {
+    /// Kinds captured when `capture.allowed_kinds` is not set. HTML is opt-in,
+    /// since browsers put it on the clipboard alongside the text of every copy.
+    pub const DEFAULT: [EntryKind; 5] =
+        [EntryKind::Text, EntryKind::Url, EntryKind::Image, EntryKind::Rtf, EntryKind::Files];
+
+    /// Parse the lowercase name used in `capture.allowed_kinds`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "url" => Some(Self::Url),
+            "image" => Some(Self::Image),
+            "rtf" => Some(Self::Rtf),
+            "html" => Some(Self::Html),
+            "snapshot" => Some(Self::Snapshot),
+            "files" => Some(Self::Files),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name used in the database, config, and IPC.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Url => "url",
+            Self::Image => "image",
+            Self::Rtf => "rtf",
+            Self::Html => "html",
+            Self::Snapshot => "snapshot",
+            Self::Files => "files",
+        }
+    }
+
+    /// Text and formatting of an RTF or HTML document stored as `data`;
+    /// `None` for other kinds.
+    pub fn parse_document(&self, data: &[u8]) -> Option<Document> {
+        match self {
+            Self::Rtf => Some(crate::rtf::parse(data)),
+            Self::Html => Some(crate::html::parse(&crate::html::fragment(data))),
+            Self::Text | Self::Url | Self::Image | Self::Snapshot | Self::Files => None,
+        }
+    }
+
+    /// Plain text of an RTF or HTML document stored as `data`; `None` for
+    /// other kinds and documents without text.
+    pub fn extract_text(&self, data: &[u8]) -> Option<String> {
+        let text = self.parse_document(data)?.text;
+        (!text.is_empty()).then_some(text)
+    }
 }
 
+/// Size and origin of a captured image, stored as clients are sent it.
+pub use clipcore::ImageInfo;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     pub id: Option<u64>,
@@ -22,5 +86,79 @@ pub struct Entry {
     pub hash: String,
     pub source_process: Option<String>,
     pub tags: Vec<String>,
+    /// Present for image entries; older exports lack it.
+    #[serde(default)]
+    pub image: Option<ImageInfo>,
+    /// Display title derived from the text when it is stored.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// clipd run that captured the entry; `None` for imported and older entries.
+    #[serde(default)]
+    pub session: Option<u64>,
+    /// Captured from a process not in `capture.trusted_processes` while
+    /// quarantine is on; hidden and soon purged unless the source is approved.
+    #[serde(default)]
+    pub quarantined: bool,
+    /// ISO 639-1 code of the text's language, detected when it is stored.
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// Kept at the top of the list, in an order the user arranges, and never pruned.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Plain text of RTF and HTML documents and the Unicode text of
+    /// snapshots, which `text` does not hold.
+    #[serde(default)]
+    pub text_extracted: Option<String>,
+    /// Id of the first of several entries copied from one app in quick
+    /// succession; `None` for entries copied on their own.
+    #[serde(default)]
+    pub copy_group: Option<u64>,
+    /// Email addresses and phone numbers in the text, found when it is stored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<Entity>,
+    /// Times the content was copied, the first included; copying it again
+    /// bumps the entry instead of storing another.
+    #[serde(default = "first_copy")]
+    pub use_count: u32,
+}
+
+fn first_copy() -> u32 {
+    1
 }
 
+
+impl Entry {
+    /// Text shown, searched, and pasted for the entry: the plain text of a
+    /// document, otherwise its own text.
+    pub fn plain_text(&self) -> Option<&str> {
+        self.text_extracted.as_deref().or(self.text.as_deref())
+    }
+
+    /// The entry as it should be written to a file: PNG for images, the raw
+    /// RTF document, the copied HTML, the stored formats of a snapshot, or
+    /// UTF-8 text, which for files is their paths.
+    pub fn file_contents(&self) -> Result<Vec<u8>> {
+        match self.kind {
+            EntryKind::Image => {
+                let dib = self.data.as_deref().context("image entry has no data")?;
+                crate::image::dib_to_png(dib)
+            }
+            EntryKind::Rtf => {
+                let mut rtf = self.data.clone().context("rtf entry has no data")?;
+                // Clipboard memory is NUL-terminated and may be padded further.
+                while rtf.last() == Some(&0) {
+                    rtf.pop();
+                }
+                Ok(rtf)
+            }
+            EntryKind::Html => {
+                let data = self.data.as_deref().context("html entry has no data")?;
+                Ok(crate::html::fragment(data).into_bytes())
+            }
+            EntryKind::Snapshot => self.data.clone().context("snapshot entry has no data"),
+            EntryKind::Text | EntryKind::Url | EntryKind::Files => {
+                Ok(self.text.clone().unwrap_or_default().into_bytes())
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Rules for pasting into the app in front (`[paste]` in config.toml).
+//!
+//! clipctl asks before it puts an entry on the clipboard, and clipd answers
+//! from these rules and the app the paste is headed for: whether the paste may
+//! go ahead, and whether an RTF or HTML entry keeps its formatting.
+
+use serde::Deserialize;
+
+use crate::model::Entry;
+
+/// `[[paste.block]]`: entries with any of `tags` may not be pasted while one
+/// of `processes` is in front, unless the user confirms.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BlockRule {
+    pub tags: Vec<String>,
+    /// Executable names, such as `chrome.exe`, compared case-insensitively.
+    pub processes: Vec<String>,
+}
+
+/// How RTF and HTML entries are pasted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteFormat {
+    /// The document as copied, with its text alongside.
+    #[default]
+    Rich,
+    /// Only the document's text.
+    Plain,
+}
+
+/// `[[paste.formats]]`: the format used while one of `processes` is in front.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FormatRule {
+    pub processes: Vec<String>,
+    pub format: PasteFormat,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PastePolicy {
+    block: Vec<BlockRule>,
+    /// Format for apps no `[[paste.formats]]` rule names.
+    default_format: PasteFormat,
+    formats: Vec<FormatRule>,
+}
+
+impl PastePolicy {
+
+    /// Why `entry` may not be pasted into `process`, if a rule forbids it.
+    pub fn blocked(&self, entry: &Entry, process: &str) -> Option<String> {
+        self.block
+            .iter()
+            .filter(|rule| rule.processes.iter().any(|name| name.eq_ignore_ascii_case(process)))
+            .find_map(|rule| entry.tags.iter().find(|tag| rule.tags.contains(tag)))
+            .map(|tag| format!("entry is tagged {tag} and {process} is in front"))
+    }
+
+    /// Format for pasting into `process`; the first matching rule wins.
+    pub fn format(&self, process: Option<&str>) -> PasteFormat {
+        process
+            .and_then(|process| {
+                self.formats
+                    .iter()
+                    .find(|rule| rule.processes.iter().any(|name| name.eq_ignore_ascii_case(process)))
+            })
+            .map_or(self.default_format, |rule| rule.format)
+    }
+}
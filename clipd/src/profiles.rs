@@ -0,0 +1,111 @@
+//! Named profiles (`work`, `personal`, ...), each with its own database and retention.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+
+use crate::config::{Config, Profile};
+use crate::db::Database;
+
+/// The profiles clipd knows about and the one it currently captures into.
+///
+/// Databases are opened on first use and stay open until clipd exits.
+#[derive(Clone)]
+pub struct Profiles {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    definitions: Vec<Profile>,
+    open: HashMap<String, Database>,
+    active: String,
+}
+
+impl Profiles {
+    /// Open the active profile's database.
+    pub fn open(config: &Config) -> Result<Self> {
+        let profiles = Self {
+            state: Arc::new(Mutex::new(State {
+                definitions: config.profiles.clone(),
+                open: HashMap::new(),
+                active: config.active_profile.clone(),
+            })),
+        };
+        profiles.get(&config.active_profile)?;
+        tracing::info!(profile = %config.active_profile, "capturing into profile");
+        Ok(profiles)
+    }
+
+    pub fn active_name(&self) -> String {
+        self.state.lock().active.clone()
+    }
+
+    /// Database of the active profile.
+    pub fn active(&self) -> Database {
+        let state = self.state.lock();
+        state.open[&state.active].clone()
+    }
+
+    /// Database of profile `name`, opening it if needed.
+    pub fn get(&self, name: &str) -> Result<Database> {
+        let mut state = self.state.lock();
+        if let Some(db) = state.open.get(name) {
+            return Ok(db.clone());
+        }
+        let Some(profile) = state.definitions.iter().find(|profile| profile.name == name) else {
+            bail!("unknown profile '{name}'");
+        };
+        let db = Database::open(profile.db_path.clone(), profile.max_entries, profile.keep_backups)?;
+        db.set_archive(profile.archive)?;
+        db.set_duplicates(profile.duplicates);
+        db.set_max_bytes(profile.max_bytes, profile.eviction)?;
+        db.set_sort_locale(&profile.sort_locale)?;
+        state.open.insert(name.to_string(), db.clone());
+        Ok(db)
+    }
+
+    /// Capture into profile `name` from now on.
+    pub fn switch(&self, name: &str) -> Result<()> {
+        self.get(name)?;
+        let previous = std::mem::replace(&mut self.state.lock().active, name.to_string());
+        tracing::info!(%previous, profile = name, "switched profile");
+        Ok(())
+    }
+
+    /// All configured profiles, `default` first and the rest by name.
+    pub fn names(&self) -> Vec<String> {
+        let state = self.state.lock();
+        state.definitions.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    /// Pick up added profiles and retention changes after `ReloadConfig`.
+    ///
+    /// The active profile only changes through `switch`, so a reload does not
+    /// move capture somewhere else behind the user's back.
+    pub fn apply(&self, config: &Config) -> Result<()> {
+        let mut state = self.state.lock();
+        for profile in &config.profiles {
+            if let Some(db) = state.open.get(&profile.name) {
+                // Archive first, so entries a lower limit drops are archived.
+                db.set_archive(profile.archive)?;
+                db.set_max_entries(profile.max_entries)?;
+                db.set_keep_backups(profile.keep_backups);
+                db.set_duplicates(profile.duplicates);
+                db.set_max_bytes(profile.max_bytes, profile.eviction)?;
+                db.set_sort_locale(&profile.sort_locale)?;
+            }
+        }
+        let active = state.active.clone();
+        if !config.profiles.iter().any(|profile| profile.name == active) {
+            tracing::warn!(profile = %active, "active profile was removed from config; still capturing into it");
+            let kept = state.definitions.iter().find(|profile| profile.name == active).cloned();
+            state.definitions = config.profiles.clone();
+            state.definitions.extend(kept);
+        } else {
+            state.definitions = config.profiles.clone();
+        }
+        Ok(())
+    }
+}
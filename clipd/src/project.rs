@@ -0,0 +1,122 @@
+//! Project detection from the foreground window, for automatic `project:` tags.
+
+/// Editors that title windows `file - folder - App`.
+const CODE_EDITORS: &[&str] = &[
+    "code.exe",
+    "code - insiders.exe",
+    "cursor.exe",
+    "windsurf.exe",
+    "vscodium.exe",
+];
+
+/// JetBrains IDEs title windows `project – file`, with an en dash.
+const JETBRAINS_IDES: &[&str] = &[
+    "idea64.exe",
+    "pycharm64.exe",
+    "rustrover64.exe",
+    "clion64.exe",
+    "goland64.exe",
+    "webstorm64.exe",
+    "rider64.exe",
+    "phpstorm64.exe",
+];
+
+/// Shells and terminals, whose titles usually show the working directory.
+const TERMINALS: &[&str] = &[
+    "windowsterminal.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "cmd.exe",
+    "conhost.exe",
+    "wezterm-gui.exe",
+    "alacritty.exe",
+];
+
+/// Project names longer than this are not titles we understand.
+const MAX_NAME: usize = 60;
+
+/// Project name for the window `title` of `process`, if it follows a known pattern.
+pub fn detect(process: Option<&str>, title: &str) -> Option<String> {
+    let process = process?.to_lowercase();
+    // VS Code used an em dash separator before 1.63.
+    let title = title.trim().replace(" — ", " - ");
+    let title = title.as_str();
+
+    let name = if CODE_EDITORS.contains(&process.as_str()) {
+        code_editor(title)
+    } else if JETBRAINS_IDES.contains(&process.as_str()) {
+        title.split(" – ").next()
+    } else if process == "devenv.exe" {
+        // `Solution (Running) - Microsoft Visual Studio`
+        title.rsplit_once(" - ").map(|(solution, _)| solution)
+    } else if TERMINALS.contains(&process.as_str()) {
+        working_directory(title)
+    } else {
+        None
+    }?;
+
+    tag_name(name)
+}
+
+/// `● main.rs - rusty-clipboard [WSL: Ubuntu] - Visual Studio Code`
+fn code_editor(title: &str) -> Option<&str> {
+    let segments: Vec<_> = title.split(" - ").collect();
+    match segments.as_slice() {
+        // Either a folder with no editor open or a file with no folder.
+        [only, _app] => (!looks_like_file(only)).then_some(*only),
+        [.., folder, _app] => Some(*folder),
+        _ => None,
+    }
+}
+
+/// Last component of a path in a terminal title such as `PS C:\src\app>` or
+/// `user@host: ~/src/app`.
+fn working_directory(title: &str) -> Option<&str> {
+    let start = title
+        .char_indices()
+        .find(|&(index, c)| {
+            let rest = &title[index..];
+            (c.is_ascii_alphabetic() && rest[1..].starts_with(":\\"))
+                || rest.starts_with("~/")
+                || (c == '/' && index > 0 && title[..index].ends_with(' '))
+        })
+        .map(|(index, _)| index)?;
+    let path = title[start..].trim_end_matches(['>', '$', '#', ' ']);
+
+    let mut components = path.rsplit(['\\', '/']).filter(|part| !part.is_empty());
+    let name = components.next()?;
+    let parent = components.next().unwrap_or("");
+    // Home directories and the default `C:\WINDOWS\system32\cmd.exe` title are not projects.
+    if name.ends_with(':')
+        || name == "~"
+        || looks_like_file(name)
+        || parent.eq_ignore_ascii_case("users")
+        || parent == "home"
+        || name.eq_ignore_ascii_case("system32")
+    {
+        return None;
+    }
+    Some(name)
+}
+
+fn looks_like_file(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(stem, extension)| !stem.is_empty() && (1..=5).contains(&extension.len()))
+}
+
+/// Strip editor decorations and make the name safe to use as a single tag word.
+fn tag_name(raw: &str) -> Option<String> {
+    let mut name = raw.trim().trim_start_matches('●').trim();
+    for suffix in [" (Workspace)", " (Running)", " (Debugging)", " (Administrator)"] {
+        name = name.strip_suffix(suffix).unwrap_or(name);
+    }
+    // `folder [WSL: Ubuntu]`, `project [C:\src\project]`
+    if let Some((before, _)) = name.split_once(" [") {
+        name = before;
+    }
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME {
+        return None;
+    }
+    Some(name.split_whitespace().collect::<Vec<_>>().join("-"))
+}
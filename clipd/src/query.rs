@@ -0,0 +1,370 @@
+//! Search query parsing: free text plus field filters such as `width>1920`.
+//!
+//! Free text is matched through the `entries_fts` full-text index: a word
+//! matches the words it starts, and a quoted phrase matches those words in
+//! that order. Both go by the words the index splits text into, after the
+//! same folding as `search_text`.
+
+use clipcore::{EntityKind, Highlight};
+use rusqlite::types::Value;
+
+use crate::db::{NOT_QUARANTINED, RECENT_FIRST};
+use crate::fold;
+use crate::model::EntryKind;
+
+/// Numeric fields usable as `field<op>number`, with the column or expression each maps to.
+const NUMERIC_FIELDS: &[(&str, &str)] = &[
+    ("width", "image_width"),
+    ("height", "image_height"),
+    ("dpi", "image_dpi"),
+    ("chars", "length(text)"),
+    ("lines", "(length(text) - length(replace(text, char(10), '')) + 1)"),
+    ("bytes", "bytes_len"),
+];
+
+/// `sort:` keys and the ORDER BY each stands for; most recent first otherwise.
+/// `title` and `tag` are alphabetical in the sort locale; `tag` goes by an
+/// entry's first tag and lists untagged entries last.
+const SORT_ORDERS: &[(&str, &str)] = &[
+    ("recent", RECENT_FIRST),
+    ("largest", "bytes_len DESC, created_at DESC"),
+    ("smallest", "bytes_len ASC, created_at DESC"),
+    ("title", "COALESCE(title, text) COLLATE locale ASC, created_at DESC"),
+    (
+        "tag",
+        "json_extract(tags, '$[0]') IS NULL, json_extract(tags, '$[0]') COLLATE locale ASC, created_at DESC",
+    ),
+];
+/// `is:pinned` lists the snippet board in its arranged order.
+const PIN_ORDER: &str = "pin_order ASC";
+/// `group:N` lists entries copied together in the order they were copied.
+const GROUP_ORDER: &str = "created_at ASC";
+/// Free text lists the best matches first, by FTS5's bm25 rank.
+const RELEVANCE_FIRST: &str = "match_rank ASC, COALESCE(last_used_at, created_at) DESC";
+
+/// Longest operators first so `>=` is not read as `>`.
+const OPERATORS: &[&str] = &[">=", "<=", "!=", ">", "<", "="];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Compare {
+        column: &'static str,
+        op: &'static str,
+        value: i64,
+    },
+    /// `monitor:DISPLAY2`, matched as a substring of the device name.
+    Monitor(String),
+    /// `date:2025-11-21`, the calendar day an entry was captured.
+    Date(String),
+    /// `session:12`, the clipd run that captured an entry.
+    Session(i64),
+    /// `kind:image`, one of text, url, image, rtf, html, or files.
+    Kind(&'static str),
+    /// `group:12`, entries copied together with entry 12.
+    Group(i64),
+    /// `lang:de`, the detected language of an entry's text.
+    Lang(String),
+    /// `has:uuid` (or `type:uuid`), entries with such an entity in their text.
+    Has(EntityKind),
+    /// `is:pinned`, entries pinned to the top of the list.
+    Pinned,
+    /// `is:quarantined`, entries held back from untrusted processes; they
+    /// match no other search.
+    Quarantined,
+    /// `in:archive`, search the archive instead of the history.
+    Archived,
+}
+
+/// A word or quoted phrase of a query's free text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    /// Folded words, as the full-text index splits them.
+    words: Vec<String>,
+    /// Quoted: the last word has to match whole, not just start a word.
+    exact: bool,
+    /// The folded term as typed, matched as a substring when it has no
+    /// words (`->`) and in the archive, which has no index.
+    folded: String,
+}
+
+impl Term {
+    fn new(raw: &str, exact: bool) -> Self {
+        let folded = fold::fold(raw);
+        Self {
+            words: words(&folded).map(|(_, word)| word.to_string()).collect(),
+            exact,
+            folded,
+        }
+    }
+
+    /// FTS5 phrase for the term, with a prefix `*` unless it is quoted.
+    fn to_fts(&self) -> String {
+        let phrase = format!("\"{}\"", self.words.join(" "));
+        if self.exact {
+            phrase
+        } else {
+            phrase + "*"
+        }
+    }
+
+    /// Whether `found`, folded words of a text, start with the term's words.
+    fn matches(&self, found: &[String]) -> bool {
+        let Some((last, rest)) = self.words.split_last() else {
+            return false;
+        };
+        found.len() >= self.words.len()
+            && rest.iter().zip(found).all(|(word, found)| word == found)
+            && if self.exact {
+                found[rest.len()] == *last
+            } else {
+                found[rest.len()].starts_with(last.as_str())
+            }
+    }
+}
+
+/// Words of `text` with their byte offsets: runs of letters and digits,
+/// as the index's `unicode61` tokenizer splits them.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// `raw` split at whitespace outside double quotes, each piece flagged when
+/// it was quoted. An unclosed quote runs to the end.
+fn split_quoted(raw: &str) -> Vec<(&str, bool)> {
+    let mut pieces = Vec::new();
+    let mut rest = raw.trim_start();
+    while !rest.is_empty() {
+        let (piece, quoted, next) = match rest.strip_prefix('"') {
+            Some(inner) => {
+                let end = inner.find('"').unwrap_or(inner.len());
+                (&inner[..end], true, inner.get(end + 1..).unwrap_or(""))
+            }
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], false, &rest[end..])
+            }
+        };
+        pieces.push((piece, quoted));
+        rest = next.trim_start();
+    }
+    pieces
+}
+
+/// A parsed search: words that are not filters are matched as text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+    filters: Vec<Filter>,
+    /// From `sort:largest` or `sort:smallest`.
+    order: Option<&'static str>,
+}
+
+impl Query {
+    pub fn parse(raw: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut filters = Vec::new();
+        let mut order = None;
+        for (word, quoted) in split_quoted(raw) {
+            if quoted {
+                if !word.trim().is_empty() {
+                    terms.push(Term::new(word, true));
+                }
+                continue;
+            }
+            if let Some(&(_, sql)) = word
+                .strip_prefix("sort:")
+                .and_then(|key| SORT_ORDERS.iter().find(|(name, _)| *name == key))
+            {
+                order = Some(sql);
+                continue;
+            }
+            match parse_filter(word) {
+                Some(filter) => filters.push(filter),
+                None => terms.push(Term::new(word, false)),
+            }
+        }
+        Self { terms, filters, order }
+    }
+
+    /// Whether the query searches the archive (`in:archive`).
+    pub fn archived(&self) -> bool {
+        self.filters.contains(&Filter::Archived)
+    }
+
+    /// ORDER BY clause for the matches. The best matches of the free text
+    /// come first unless the query orders them otherwise; `match_rank` is
+    /// then a column of [`Query::relevance`].
+    pub fn order_by(&self) -> &'static str {
+        match self.order {
+            Some(order) => order,
+            None if self.filters.contains(&Filter::Pinned) => PIN_ORDER,
+            None if self.filters.iter().any(|filter| matches!(filter, Filter::Group(_))) => GROUP_ORDER,
+            None if self.relevance().is_some() => RELEVANCE_FIRST,
+            None => RECENT_FIRST,
+        }
+    }
+
+    /// FTS5 query for the free text, when some of it can go through the index.
+    fn fts(&self) -> Option<String> {
+        let phrases: Vec<_> = self
+            .terms
+            .iter()
+            .filter(|term| !term.words.is_empty())
+            .map(Term::to_fts)
+            .collect();
+        (!phrases.is_empty()).then(|| phrases.join(" "))
+    }
+
+    /// Subquery to join on `match_id = entries.id` for the `match_rank` of
+    /// each match, and the value for its `?`, when results go by relevance.
+    pub fn relevance(&self) -> Option<(&'static str, Value)> {
+        if self.order.is_some() || self.archived() {
+            return None;
+        }
+        let subquery = "(SELECT rowid AS match_id, rank AS match_rank FROM entries_fts WHERE entries_fts MATCH ?)";
+        self.fts().map(|fts| (subquery, fts.into()))
+    }
+
+    /// Where the free text matches `text`, by whole words of it, in order.
+    pub fn highlights(&self, text: &str) -> Vec<Highlight> {
+        let (spans, folded): (Vec<_>, Vec<_>) = words(text)
+            .map(|(start, word)| ((start, start + word.len()), fold::fold(word)))
+            .unzip();
+        let mut highlights = Vec::new();
+        let mut i = 0;
+        while i < folded.len() {
+            match self.terms.iter().find(|term| term.matches(&folded[i..])) {
+                Some(term) => {
+                    let last = i + term.words.len() - 1;
+                    highlights.push(Highlight {
+                        start: spans[i].0,
+                        end: spans[last].1,
+                    });
+                    i = last + 1;
+                }
+                None => i += 1,
+            }
+        }
+        highlights
+    }
+
+    /// SQL condition over the `entries` table and the values for its `?` placeholders.
+    ///
+    /// `day` is the SQL expression for an entry's calendar day, which depends on
+    /// the configured timezone.
+    pub fn to_sql(&self, day: &str) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        // The archive has no index, so its text is searched term by term.
+        let indexed = !self.archived();
+        if let Some(fts) = self.fts().filter(|_| indexed) {
+            conditions.push("id IN (SELECT rowid FROM entries_fts WHERE entries_fts MATCH ?)".to_string());
+            values.push(fts.into());
+        }
+        for term in &self.terms {
+            if indexed && !term.words.is_empty() {
+                continue;
+            }
+            // search_text is folded, so the terms are too.
+            conditions.push("(search_text LIKE ? OR tags LIKE ?)".to_string());
+            values.push(format!("%{}%", term.folded).into());
+            values.push(format!("%{}%", term.folded).into());
+        }
+        for filter in &self.filters {
+            match filter {
+                Filter::Compare { column, op, value } => {
+                    conditions.push(format!("{column} {op} ?"));
+                    values.push((*value).into());
+                }
+                Filter::Monitor(name) => {
+                    conditions.push("image_monitor LIKE ?".to_string());
+                    values.push(format!("%{name}%").into());
+                }
+                Filter::Date(date) => {
+                    conditions.push(format!("{day} = ?"));
+                    values.push(date.clone().into());
+                }
+                Filter::Session(session) => {
+                    conditions.push("session = ?".to_string());
+                    values.push((*session).into());
+                }
+                Filter::Group(group) => {
+                    conditions.push("copy_group = ?".to_string());
+                    values.push((*group).into());
+                }
+                Filter::Kind(kind) => {
+                    conditions.push("kind = ?".to_string());
+                    values.push(kind.to_string().into());
+                }
+                Filter::Lang(lang) => {
+                    conditions.push("lang = ?".to_string());
+                    values.push(lang.clone().into());
+                }
+                Filter::Has(kind) => {
+                    // The archive has no entities table; its entries carry
+                    // their entities as JSON like the history's.
+                    conditions.push(if indexed {
+                        "id IN (SELECT entry_id FROM entities WHERE kind = ?)".to_string()
+                    } else {
+                        "EXISTS (SELECT 1 FROM json_each(entities) WHERE json_extract(value, '$.kind') = ?)".to_string()
+                    });
+                    values.push(kind.name().to_string().into());
+                }
+                Filter::Pinned => conditions.push("pinned = 1".to_string()),
+                Filter::Quarantined => conditions.push("quarantined = 1".to_string()),
+                // Picks the table, not the rows.
+                Filter::Archived => {}
+            }
+        }
+        if !self.filters.contains(&Filter::Quarantined) {
+            conditions.push(NOT_QUARANTINED.to_string());
+        }
+
+        (conditions.join(" AND "), values)
+    }
+}
+
+fn parse_filter(word: &str) -> Option<Filter> {
+    if let Some(name) = word.strip_prefix("monitor:") {
+        return (!name.is_empty()).then(|| Filter::Monitor(name.to_string()));
+    }
+    if let Some(date) = word.strip_prefix("date:") {
+        let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        return Some(Filter::Date(date.format("%Y-%m-%d").to_string()));
+    }
+    if let Some(session) = word.strip_prefix("session:") {
+        return session.parse().ok().map(Filter::Session);
+    }
+    if let Some(group) = word.strip_prefix("group:") {
+        return group.parse().ok().map(Filter::Group);
+    }
+    if let Some(kind) = word.strip_prefix("kind:") {
+        return EntryKind::from_name(&kind.to_lowercase()).map(|kind| Filter::Kind(kind.name()));
+    }
+    if let Some(lang) = word.strip_prefix("lang:") {
+        return (!lang.is_empty()).then(|| Filter::Lang(lang.to_lowercase()));
+    }
+    if let Some(kind) = word.strip_prefix("has:").or_else(|| word.strip_prefix("type:")) {
+        return EntityKind::from_name(&kind.to_lowercase()).map(Filter::Has);
+    }
+    if word == "is:pinned" {
+        return Some(Filter::Pinned);
+    }
+    if word == "is:quarantined" {
+        return Some(Filter::Quarantined);
+    }
+    if word == "in:archive" {
+        return Some(Filter::Archived);
+    }
+
+    let &(field, column) = NUMERIC_FIELDS
+        .iter()
+        .find(|(field, _)| word.starts_with(field))?;
+    let rest = &word[field.len()..];
+    let op = *OPERATORS.iter().find(|op| rest.starts_with(**op))?;
+    let value = rest[op.len()..].parse().ok()?;
+    Some(Filter::Compare { column, op, value })
+}
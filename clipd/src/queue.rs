@@ -0,0 +1,375 @@
+//! The queue between the clipboard watcher and the database writer.
+//!
+//! Captures wait here while SQLite is busy, such as during a large import or
+//! while another process holds a lock on the file. The queue holds
+//! [`CAPACITY`] captures, and no more text and data than
+//! `capture.memory_budget_mb` unless a single capture is bigger;
+//! `capture.backpressure` decides what happens to a copy made while it is
+//! full: the watcher waits for room (`block`, which can
+//! miss copies made meanwhile), the oldest waiting capture is dropped
+//! (`drop_oldest`), or the new one is written to a file in `spill/` and stored
+//! once the queue has drained (`spill`). Spilled captures left by a crash are
+//! stored when clipd next starts.
+//!
+//! Every capture is also written to the [`Journal`] before it is queued, and
+//! marked there once stored or dropped.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::clipboard::Capture;
+use crate::journal::Journal;
+
+/// Captures held in memory before `capture.backpressure` applies.
+pub const CAPACITY: usize = 256;
+
+/// Captures that waited longer than this before being stored are logged as warnings.
+const SLOW_CAPTURE: Duration = Duration::from_secs(1);
+
+/// What a capture does when the queue is full (`capture.backpressure`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backpressure {
+    #[default]
+    Block,
+    DropOldest,
+    Spill,
+}
+
+impl Backpressure {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "block" => Some(Self::Block),
+            "drop_oldest" => Some(Self::DropOldest),
+            "spill" => Some(Self::Spill),
+            _ => None,
+        }
+    }
+}
+
+/// A capture waiting to be stored.
+#[derive(Debug)]
+pub struct Queued {
+    pub capture: Capture,
+    /// [`Capture::memory_size`], counted against the memory budget.
+    bytes: usize,
+    queued_at: SystemTime,
+    /// Number in the journal; `None` if journaling it failed.
+    seq: Option<u64>,
+    /// Left over from a run that crashed, which may have stored it already.
+    pub replayed: bool,
+}
+
+/// Counts since clipd started, for `clipctl doctor`.
+pub use clipcore::QueueStats;
+
+#[derive(Debug, Default)]
+struct Counters {
+    captured: AtomicU64,
+    stored: AtomicU64,
+    dropped: AtomicU64,
+    spilled: AtomicU64,
+    blocked: AtomicU64,
+    peak: AtomicUsize,
+    peak_bytes: AtomicU64,
+    rejected: AtomicU64,
+    slowest_ms: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    items: Mutex<VecDeque<Queued>>,
+    /// Memory held by `items`; changed only with `items` locked.
+    bytes: AtomicUsize,
+    /// `capture.memory_budget_mb`, in bytes.
+    budget: usize,
+    policy: Backpressure,
+    spill_dir: PathBuf,
+    /// Spilled captures may be waiting on disk.
+    spill_pending: AtomicBool,
+    /// Numbers spill files written in the same millisecond.
+    spill_seq: AtomicU64,
+    /// When this run's queue was made; spill files from before it are replays.
+    started: SystemTime,
+    journal: Journal,
+    ready: Notify,
+    space: Notify,
+    closed: AtomicBool,
+    counters: Counters,
+}
+
+/// Handle shared by the watcher, which pushes, and the writer, which pops.
+#[derive(Debug, Clone)]
+pub struct CaptureQueue {
+    inner: Arc<Inner>,
+}
+
+impl CaptureQueue {
+    /// `pending` are the captures the journal replays, stored before any new one.
+    pub fn new(
+        policy: Backpressure,
+        budget: usize,
+        spill_dir: PathBuf,
+        journal: Journal,
+        pending: Vec<(u64, Capture)>,
+    ) -> Self {
+        // Spill files left by an earlier run are stored as soon as the writer starts.
+        let spill_pending = std::fs::read_dir(&spill_dir).is_ok_and(|mut dir| dir.next().is_some());
+        let now = SystemTime::now();
+        let items: VecDeque<_> = pending
+            .into_iter()
+            .map(|(seq, capture)| Queued {
+                bytes: capture.memory_size(),
+                capture,
+                queued_at: now,
+                seq: Some(seq),
+                replayed: true,
+            })
+            .collect();
+        let bytes = items.iter().map(|queued| queued.bytes).sum();
+        Self {
+            inner: Arc::new(Inner {
+                items: Mutex::new(items),
+                bytes: AtomicUsize::new(bytes),
+                budget,
+                policy,
+                spill_dir,
+                spill_pending: AtomicBool::new(spill_pending),
+                spill_seq: AtomicU64::new(0),
+                started: now,
+                journal,
+                ready: Notify::new(),
+                space: Notify::new(),
+                closed: AtomicBool::new(false),
+                counters: Counters::default(),
+            }),
+        }
+    }
+
+    /// Queue `capture` for the writer, applying the backpressure policy when full.
+    pub async fn push(&self, capture: Capture) {
+        let inner = &self.inner;
+        inner.counters.captured.fetch_add(1, Ordering::Relaxed);
+        let seq = match inner.journal.append(&capture) {
+            Ok(seq) => Some(seq),
+            Err(err) => {
+                tracing::error!(%err, "capture is not journaled and would be lost in a crash");
+                None
+            }
+        };
+        let queued = Queued {
+            bytes: capture.memory_size(),
+            capture,
+            queued_at: SystemTime::now(),
+            seq,
+            replayed: false,
+        };
+        let mut waited = false;
+        loop {
+            {
+                let mut items = inner.items.lock();
+                if self.has_room(&items, queued.bytes) {
+                    self.push_locked(&mut items, queued);
+                    drop(items);
+                    inner.ready.notify_one();
+                    return;
+                }
+                match inner.policy {
+                    Backpressure::Block => {}
+                    Backpressure::DropOldest => {
+                        let mut dropped = Vec::new();
+                        while !self.has_room(&items, queued.bytes) {
+                            let Some(oldest) = items.pop_front() else { break };
+                            inner.bytes.fetch_sub(oldest.bytes, Ordering::Relaxed);
+                            dropped.push(oldest.seq);
+                        }
+                        self.push_locked(&mut items, queued);
+                        drop(items);
+                        inner.counters.dropped.fetch_add(dropped.len() as u64, Ordering::Relaxed);
+                        tracing::warn!(dropped = dropped.len(), "capture queue is full; dropped the oldest captures");
+                        for seq in dropped {
+                            self.journal_done(seq);
+                        }
+                        return;
+                    }
+                    Backpressure::Spill => {
+                        drop(items);
+                        match self.spill(&queued) {
+                            Ok(path) => {
+                                inner.counters.spilled.fetch_add(1, Ordering::Relaxed);
+                                tracing::warn!(path = %path.display(), "capture queue is full; spilled capture to disk");
+                            }
+                            // Still in the journal, so the next start stores it.
+                            Err(err) => {
+                                inner.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                                tracing::error!(%err, "capture queue is full and spilling failed; capture dropped");
+                            }
+                        }
+                        return;
+                    }
+                }
+            }
+            if !waited {
+                waited = true;
+                inner.counters.blocked.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    capacity = CAPACITY,
+                    budget = inner.budget,
+                    "capture queue is full; watcher waits for the database"
+                );
+            }
+            inner.space.notified().await;
+        }
+    }
+
+    /// The next capture to store, or `None` once the watcher has stopped and
+    /// everything queued is stored.
+    pub async fn pop(&self) -> Option<Queued> {
+        let inner = &self.inner;
+        loop {
+            let next = inner.items.lock().pop_front().inspect(|queued| {
+                inner.bytes.fetch_sub(queued.bytes, Ordering::Relaxed);
+            });
+            if let Some(queued) = next {
+                inner.space.notify_one();
+                return Some(queued);
+            }
+            if inner.spill_pending.load(Ordering::Relaxed) {
+                match self.unspill() {
+                    Ok(Some(queued)) => return Some(queued),
+                    Ok(None) => inner.spill_pending.store(false, Ordering::Relaxed),
+                    Err(err) => {
+                        tracing::error!(%err, "failed to read spilled captures");
+                        inner.spill_pending.store(false, Ordering::Relaxed);
+                    }
+                }
+                continue;
+            }
+            if inner.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            inner.ready.notified().await;
+        }
+    }
+
+    /// Record that `queued` was stored, logging how long it waited.
+    pub fn stored(&self, queued: &Queued) {
+        let counters = &self.inner.counters;
+        counters.stored.fetch_add(1, Ordering::Relaxed);
+        self.journal_done(queued.seq);
+        let latency = queued.queued_at.elapsed().unwrap_or_default();
+        let latency_ms = latency.as_millis() as u64;
+        counters.slowest_ms.fetch_max(latency_ms, Ordering::Relaxed);
+        let waiting = self.inner.items.lock().len();
+        if latency > SLOW_CAPTURE {
+            tracing::warn!(latency_ms, waiting, "capture was slow to reach the database");
+        } else {
+            tracing::debug!(latency_ms, waiting, "capture stored");
+        }
+    }
+
+    /// Count a clipboard format left unread for exceeding `capture.max_entry_mb`.
+    pub fn reject(&self) {
+        self.inner.counters.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stop waiting for captures once the queue is empty.
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        self.inner.ready.notify_one();
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let counters = &self.inner.counters;
+        QueueStats {
+            captured: counters.captured.load(Ordering::Relaxed),
+            stored: counters.stored.load(Ordering::Relaxed),
+            dropped: counters.dropped.load(Ordering::Relaxed),
+            spilled: counters.spilled.load(Ordering::Relaxed),
+            blocked: counters.blocked.load(Ordering::Relaxed),
+            peak: counters.peak.load(Ordering::Relaxed),
+            peak_bytes: counters.peak_bytes.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+            slowest_ms: counters.slowest_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Whether `items` can take a capture of `bytes` more: below
+    /// [`CAPACITY`] and the memory budget, or empty.
+    fn has_room(&self, items: &VecDeque<Queued>, bytes: usize) -> bool {
+        let held = self.inner.bytes.load(Ordering::Relaxed);
+        items.is_empty() || (items.len() < CAPACITY && held.saturating_add(bytes) <= self.inner.budget)
+    }
+
+    fn push_locked(&self, items: &mut VecDeque<Queued>, queued: Queued) {
+        let inner = &self.inner;
+        let held = inner.bytes.fetch_add(queued.bytes, Ordering::Relaxed) + queued.bytes;
+        items.push_back(queued);
+        inner.counters.peak.fetch_max(items.len(), Ordering::Relaxed);
+        inner.counters.peak_bytes.fetch_max(held as u64, Ordering::Relaxed);
+    }
+
+    fn journal_done(&self, seq: Option<u64>) {
+        if let Some(seq) = seq {
+            if let Err(err) = self.inner.journal.done(seq) {
+                tracing::warn!(%err, path = %self.inner.journal.path().display(), "failed to update capture journal");
+            }
+        }
+    }
+
+    /// Write `queued` and its journal number to its own file in `spill/`,
+    /// named by capture time so files sort in capture order.
+    fn spill(&self, queued: &Queued) -> Result<PathBuf> {
+        let inner = &self.inner;
+        std::fs::create_dir_all(&inner.spill_dir)
+            .with_context(|| format!("failed to create {}", inner.spill_dir.display()))?;
+        let millis = queued.queued_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let seq = inner.spill_seq.fetch_add(1, Ordering::Relaxed);
+        let path = inner.spill_dir.join(format!("{millis:016}-{seq:06}.json"));
+        let json = serde_json::to_vec(&(queued.seq, &queued.capture)).context("failed to serialize capture")?;
+        std::fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))?;
+        inner.spill_pending.store(true, Ordering::Relaxed);
+        Ok(path)
+    }
+
+    /// Take the oldest spilled capture off disk.
+    fn unspill(&self) -> Result<Option<Queued>> {
+        let dir = &self.inner.spill_dir;
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(None);
+        };
+        let Some(path) = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .min()
+        else {
+            return Ok(None);
+        };
+        let json = std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        std::fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        let (seq, capture) = serde_json::from_slice(&json)
+            .with_context(|| format!("failed to parse spilled capture {}", path.display()))?;
+        let millis = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split('-').next())
+            .and_then(|millis| millis.parse().ok())
+            .unwrap_or_default();
+        let queued_at = UNIX_EPOCH + Duration::from_millis(millis);
+        Ok(Some(Queued {
+            // Goes straight to the writer without counting against the budget.
+            bytes: 0,
+            capture,
+            queued_at,
+            seq,
+            // The journal replayed it too, ahead of the spill files.
+            replayed: queued_at < self.inner.started,
+        }))
+    }
+}
@@ -0,0 +1,104 @@
+//! Redaction of secrets in exports, so backups can live on cloud drives.
+
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+use crate::model::{Entry, EntryKind};
+
+/// Replaces the content of redacted entries.
+pub const PLACEHOLDER: &str = "[REDACTED]";
+
+/// Entries tagged with any of these are always redacted.
+const SENSITIVE_TAGS: &[&str] = &["sensitive", "secret"];
+
+/// Credentials common enough to recognize without configuration.
+const BUILTIN_PATTERNS: &[&str] = &[
+    // PEM private keys: RSA, EC, OpenSSH, PKCS#8...
+    r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+    // AWS access key ids
+    r"\b(AKIA|ASIA)[0-9A-Z]{16}\b",
+    // GitHub tokens
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    r"\bgithub_pat_[A-Za-z0-9_]{22,}\b",
+    // Slack tokens
+    r"\bxox[abposr]-[A-Za-z0-9-]{10,}",
+    // Stripe live keys
+    r"\b[sr]k_live_[A-Za-z0-9]{16,}",
+    // JSON Web Tokens
+    r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+    // `password = hunter22`, `API_KEY: ...`, `"client_secret": "..."`
+    r#"(?i)\b(password|passwd|pwd|secret|api[_-]?key|access[_-]?token|auth[_-]?token|client[_-]?secret)["']?\s*[:=]\s*["']?[^\s"']{6,}"#,
+];
+
+/// Decides which entries an export masks.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// The built-in detectors plus `extra` patterns from `export.redact_patterns`;
+    /// invalid extra patterns are skipped with a warning.
+    pub fn new(extra: &[String]) -> Self {
+        let builtin = BUILTIN_PATTERNS
+            .iter()
+            .map(|pattern| Regex::new(pattern).expect("built-in redaction pattern is valid"));
+        let extra = extra.iter().filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(err) => {
+                tracing::warn!(%pattern, %err, "ignoring invalid pattern in export.redact_patterns");
+                None
+            }
+        });
+        Self {
+            patterns: builtin.chain(extra).collect(),
+        }
+    }
+
+    /// Whether `entry` is tagged sensitive or its text looks like a secret.
+    fn is_secret(&self, entry: &Entry) -> bool {
+        if entry.tags.iter().any(|tag| SENSITIVE_TAGS.contains(&tag.as_str())) {
+            return true;
+        }
+        let text = match entry.kind {
+            // Documents and snapshots carry their plain text alongside.
+            EntryKind::Text
+            | EntryKind::Url
+            | EntryKind::Rtf
+            | EntryKind::Html
+            | EntryKind::Snapshot
+            | EntryKind::Files => entry.plain_text(),
+            EntryKind::Image => None,
+        };
+        text.is_some_and(|text| self.patterns.iter().any(|regex| regex.is_match(text)))
+    }
+
+    /// Replace the content of `entry` with [`PLACEHOLDER`] if it is a secret,
+    /// returning whether it was.
+    ///
+    /// Kind, tags, times, source, and image size are kept. The content hash is
+    /// replaced too, since a hash of a short password is easy to reverse.
+    pub fn redact(&self, entry: &mut Entry) -> bool {
+        if !self.is_secret(entry) {
+            return false;
+        }
+        entry.hash = redacted_hash(entry);
+        entry.text = Some(PLACEHOLDER.to_string());
+        entry.data = None;
+        entry.text_extracted = None;
+        entry.bytes_len = PLACEHOLDER.len();
+        entry.title = None;
+        entry.entities.clear();
+        true
+    }
+}
+
+/// A stand-in hash that keeps redacted entries distinct on re-import without
+/// depending on their content.
+fn redacted_hash(entry: &Entry) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(PLACEHOLDER.as_bytes());
+    hasher.update(entry.id.unwrap_or_default().to_le_bytes());
+    hasher.update(entry.created_at.timestamp_micros().to_le_bytes());
+    format!("{:x}", hasher.finalize())
+}
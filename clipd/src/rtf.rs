@@ -0,0 +1,226 @@
+//! Plain text of RTF documents, for search and preview.
+//!
+//! Walks groups and control words instead of decoding the bytes as UTF-8:
+//! font tables, styles, pictures, and other destinations are skipped, `\par`
+//! and `\tab` become whitespace, and `\'hh` and `\uN` escapes become the
+//! characters they stand for. `\'hh` is read as Windows-1252, the code page of
+//! nearly every RTF the clipboard sees. Bold, italic, and underline are kept for
+//! the preview.
+
+use crate::document::{Document, Style};
+
+/// Destinations whose content is not document text.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "object",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "footnote",
+    "fldinst",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "themedata",
+    "colorschememapping",
+    "latentstyles",
+    "datastore",
+    "xmlnstbl",
+    "filetbl",
+    "revtbl",
+];
+
+/// Windows-1252 characters for bytes 0x80..=0x9F, where it departs from Latin-1.
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+#[derive(Clone, Copy)]
+struct Group {
+    /// Inside a destination that holds no document text.
+    skip: bool,
+    /// Fallback characters following each `\uN` (`\ucN`).
+    unicode_skip: usize,
+    style: Style,
+}
+
+/// The text of `rtf`, one line per paragraph, with its bold, italic, and
+/// underlined stretches.
+pub fn parse(rtf: &[u8]) -> Document {
+    let mut document = Document::default();
+    let mut stack = Vec::new();
+    let mut group = Group {
+        skip: false,
+        unicode_skip: 1,
+        style: Style::default(),
+    };
+    // Fallback characters still to drop after a `\uN`.
+    let mut fallback = 0;
+    // Set by `\*`: the next control word starts a destination we may not know.
+    let mut ignorable = false;
+    // High half of a surrogate pair written as two `\uN`.
+    let mut high_surrogate = None;
+
+    let mut i = 0;
+    while i < rtf.len() {
+        let byte = rtf[i];
+        i += 1;
+        match byte {
+            b'{' => {
+                stack.push(group);
+                fallback = 0;
+            }
+            b'}' => {
+                group = stack.pop().unwrap_or(group);
+                fallback = 0;
+            }
+            b'\\' => {
+                let Some(&next) = rtf.get(i) else { break };
+                if next.is_ascii_alphabetic() {
+                    let start = i;
+                    while i < rtf.len() && rtf[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word = std::str::from_utf8(&rtf[start..i]).unwrap_or_default();
+                    let param = read_param(rtf, &mut i);
+                    // A single space ends the control word and is not text.
+                    if rtf.get(i) == Some(&b' ') {
+                        i += 1;
+                    }
+                    if std::mem::take(&mut ignorable) || SKIPPED_DESTINATIONS.contains(&word) {
+                        group.skip = true;
+                    }
+                    if group.skip {
+                        continue;
+                    }
+                    // `\b`, `\i`, and `\ul` switch on; a parameter of 0 switches off.
+                    let on = param != Some(0);
+                    match word {
+                        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "u" => {
+                            if let Some(param) = param {
+                                // Code units above 32767 are written as negative numbers.
+                                let unit = if param < 0 { param + 65536 } else { param };
+                                if let Some(c) = pair_unit(&mut high_surrogate, unit as u32) {
+                                    document.push(c, group.style);
+                                }
+                                fallback = group.unicode_skip;
+                            }
+                        }
+                        "b" => group.style.bold = on,
+                        "i" => group.style.italic = on,
+                        "ul" => group.style.underline = on,
+                        "ulnone" => group.style.underline = false,
+                        "plain" => group.style = Style::default(),
+                        _ => {
+                            if let Some(c) = control_char(word) {
+                                document.push(c, group.style);
+                            }
+                        }
+                    }
+                    continue;
+                }
+
+                i += 1;
+                match next {
+                    b'\'' => {
+                        let hex = rtf.get(i..i + 2).and_then(|hex| std::str::from_utf8(hex).ok());
+                        let value = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                        i += 2;
+                        if let Some(value) = value {
+                            if fallback > 0 {
+                                fallback -= 1;
+                            } else if !group.skip {
+                                document.push(cp1252(value), group.style);
+                            }
+                        }
+                    }
+                    b'*' => ignorable = true,
+                    _ if group.skip => {}
+                    b'\\' | b'{' | b'}' => document.push(next as char, group.style),
+                    b'~' => document.push('\u{A0}', group.style),
+                    b'_' => document.push('\u{2011}', group.style),
+                    b'\r' | b'\n' => document.push('\n', group.style),
+                    // `\-` is an optional hyphen; `\:` an index subentry.
+                    _ => {}
+                }
+            }
+            // Line breaks in the source are formatting only.
+            b'\r' | b'\n' | 0 => {}
+            _ if group.skip => {}
+            _ if fallback > 0 => fallback -= 1,
+            _ => document.push(cp1252(byte), group.style),
+        }
+    }
+
+    // Documents end with a paragraph mark and clipboard padding.
+    document.trim_end();
+    document
+}
+
+/// The optional signed number after a control word.
+fn read_param(rtf: &[u8], i: &mut usize) -> Option<i32> {
+    let start = *i;
+    if rtf.get(*i) == Some(&b'-') {
+        *i += 1;
+    }
+    while *i < rtf.len() && rtf[*i].is_ascii_digit() {
+        *i += 1;
+    }
+    let param = std::str::from_utf8(&rtf[start..*i]).ok()?.parse().ok();
+    if param.is_none() {
+        *i = start;
+    }
+    param
+}
+
+/// Text that a control word stands for, if any.
+fn control_char(word: &str) -> Option<char> {
+    Some(match word {
+        "par" | "line" | "row" | "sect" | "page" => '\n',
+        "tab" | "cell" => '\t',
+        "emdash" => '—',
+        "endash" => '–',
+        "emspace" | "enspace" | "qmspace" => ' ',
+        "bullet" => '•',
+        "lquote" => '‘',
+        "rquote" => '’',
+        "ldblquote" => '“',
+        "rdblquote" => '”',
+        _ => return None,
+    })
+}
+
+/// The character of a UTF-16 code unit from `\uN`, or `None` for the high
+/// half of a surrogate pair, which is held until the low half follows.
+fn pair_unit(high_surrogate: &mut Option<u32>, unit: u32) -> Option<char> {
+    match (high_surrogate.take(), unit) {
+        (_, 0xD800..=0xDBFF) => {
+            *high_surrogate = Some(unit);
+            None
+        }
+        (Some(high), 0xDC00..=0xDFFF) => {
+            let c = 0x10000 + ((high - 0xD800) << 10) + (unit - 0xDC00);
+            Some(char::from_u32(c).unwrap_or('\u{FFFD}'))
+        }
+        _ => Some(char::from_u32(unit).unwrap_or('\u{FFFD}')),
+    }
+}
+
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
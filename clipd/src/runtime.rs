@@ -0,0 +1,157 @@
+//! The runtime file a listening clipd keeps in its data directory.
+//!
+//! Once its first pipe instance exists, clipd writes `clipd.json` (or
+//! `clipd-<profile>.json`, started in a profile with a pipe of its own) with
+//! the pipe it listens on, its PID, protocol, and start time, and removes it
+//! when it stops. Clients read it to find the pipe; a file whose process is
+//! gone was left by a daemon that crashed, and means no daemon is running.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use windows::Win32::Foundation::{CloseHandle, FILETIME, STILL_ACTIVE};
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Threading::{
+    GetExitCodeProcess, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+use crate::ipc::PROTOCOL_VERSION;
+
+/// Seconds from 1601-01-01, where `FILETIME`s count from, to the Unix epoch.
+#[cfg(target_os = "windows")]
+const FILETIME_UNIX_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// How the refusal to start beside another clipd tells to stop it.
+#[cfg(target_os = "windows")]
+const STOP_COMMAND: &str = "Stop-Process -Id";
+#[cfg(not(target_os = "windows"))]
+const STOP_COMMAND: &str = "kill";
+
+/// What a running clipd announces about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeInfo {
+    pub pipe_name: String,
+    pub pid: u32,
+    pub protocol: u32,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl RuntimeInfo {
+    /// This process, listening on `pipe_name`.
+    pub fn current(pipe_name: &str) -> Self {
+        Self {
+            pipe_name: pipe_name.to_string(),
+            pid: std::process::id(),
+            protocol: PROTOCOL_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// The runtime file at `path`, or `None` when there is none.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let raw = match fs::read(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+        };
+        let info = serde_json::from_slice(&raw).with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(Some(info))
+    }
+
+    /// Write the file through a temporary one, so readers never see half of it.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write {}", partial.display()))?;
+        fs::rename(&partial, path).with_context(|| format!("failed to replace {}", path.display()))
+    }
+
+    /// Whether the process that wrote the file is still running. A process
+    /// that reused the PID started after `started_at`, and does not count.
+    #[cfg(target_os = "windows")]
+    pub fn is_running(&self) -> bool {
+        unsafe {
+            let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, self.pid) else {
+                return false;
+            };
+            let mut exit_code = 0;
+            let (mut created, mut exited, mut kernel, mut user) = Default::default();
+            let running = GetExitCodeProcess(process, &mut exit_code).is_ok()
+                && exit_code == STILL_ACTIVE.0 as u32
+                && GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user).is_ok()
+                && filetime(created).is_some_and(|created| created <= self.started_at);
+            let _ = CloseHandle(process);
+            running
+        }
+    }
+
+    /// Whether a process with the PID that wrote the file is running; Linux
+    /// and macOS do not tell when a process started, so one that reused the
+    /// PID counts too.
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_running(&self) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(self.pid) else {
+            return false;
+        };
+        // Signal 0 only checks; another user's process refuses it, but exists.
+        let signalled = unsafe { libc::kill(pid, 0) } == 0;
+        signalled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+}
+
+/// Removes the runtime file when dropped, if it is still this process's.
+pub struct Announcement {
+    path: PathBuf,
+}
+
+/// Announce this process in the runtime file at `path`, listening on
+/// `pipe_name`. Fails when the file names another clipd that is still
+/// running; one left behind by a daemon that exited is replaced.
+pub fn announce(path: &Path, pipe_name: &str) -> Result<Announcement> {
+    match RuntimeInfo::read(path) {
+        Ok(Some(other)) if other.pid != std::process::id() && other.is_running() => bail!(
+            "clipd {} (PID {}) is already running on {} since {}; stop it first: {STOP_COMMAND} {}",
+            other.version,
+            other.pid,
+            other.pipe_name,
+            other.started_at.to_rfc3339(),
+            other.pid
+        ),
+        Ok(Some(stale)) => tracing::warn!(
+            pid = stale.pid,
+            started_at = %stale.started_at,
+            "replacing runtime file of a clipd that exited"
+        ),
+        Ok(None) => {}
+        Err(err) => tracing::warn!(%err, "replacing unreadable runtime file"),
+    }
+    RuntimeInfo::current(pipe_name).write(path)?;
+    tracing::info!(path = %path.display(), "announced pipe in runtime file");
+    Ok(Announcement { path: path.to_path_buf() })
+}
+
+impl Drop for Announcement {
+    fn drop(&mut self) {
+        let ours = matches!(RuntimeInfo::read(&self.path), Ok(Some(info)) if info.pid == std::process::id());
+        if ours {
+            if let Err(err) = fs::remove_file(&self.path) {
+                tracing::warn!(%err, path = %self.path.display(), "failed to remove runtime file");
+            }
+        }
+    }
+}
+
+/// `time` as a UTC timestamp.
+#[cfg(target_os = "windows")]
+fn filetime(time: FILETIME) -> Option<DateTime<Utc>> {
+    let ticks = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+    let secs = (ticks / 10_000_000) as i64 - FILETIME_UNIX_OFFSET_SECS;
+    DateTime::from_timestamp(secs, (ticks % 10_000_000) as u32 * 100)
+}
@@ -0,0 +1,549 @@
+//! What `config.toml` may hold, and every way a file breaks it.
+//!
+//! clipd and clipctl share the file, so the schema covers both programs'
+//! keys, along with the ones the bundled template lists that nothing reads
+//! yet. Each problem carries its line, and a misspelled key is reported as
+//! one instead of quietly leaving the default in place.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::ops::Range;
+
+use chrono::format::{Item as FormatItem, StrftimeItems};
+use regex::Regex;
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::config::{is_valid_profile_name, DEFAULT_PROFILE};
+
+/// Something wrong with the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Line it is on, from 1; `None` when the file has no line to point at.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// What a key holds.
+#[derive(Clone, Copy)]
+enum Kind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    /// A number; integers are accepted too.
+    Float { min: f64, max: f64 },
+    /// Any string.
+    Text,
+    /// One of these strings.
+    Choice(&'static [&'static str]),
+    /// A list of strings, each one of these.
+    Subset(&'static [&'static str]),
+    /// A list of any strings, such as executable names.
+    Names,
+    /// A list of regular expressions.
+    Patterns,
+    /// Where clipd listens: a named pipe on Windows, a socket in an existing
+    /// directory elsewhere.
+    Pipe,
+    Address,
+    /// An http or https URL; empty for none.
+    Url,
+    /// A strftime-style format.
+    TimeFormat,
+    /// A locale for month and day names, such as `de_DE`; empty for the system's.
+    Locale,
+    /// A locale to sort by, such as `sv`; empty for the system's.
+    SortLocale,
+    /// A table with these keys.
+    Table(&'static [Key]),
+    /// A list of tables with these keys, such as `[[views]]`.
+    Tables(&'static [Key]),
+    /// A table of strings under any names, such as `[keys]`.
+    Strings,
+    /// `[profiles]`: `active`, then a table with these keys per profile.
+    Profiles(&'static [Key]),
+}
+
+struct Key {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const fn key(name: &'static str, kind: Kind) -> Key {
+    Key { name, kind, required: false }
+}
+
+const fn required(name: &'static str, kind: Kind) -> Key {
+    Key { name, kind, required: true }
+}
+
+const fn range(min: i64, max: i64) -> Kind {
+    Kind::Integer { min, max }
+}
+
+const COUNT: Kind = range(0, i64::MAX);
+const MAX_ENTRIES: Kind = range(1, 1_000_000);
+const FRACTION: Kind = Kind::Float { min: 0.0, max: 1.0 };
+
+const GENERAL: &[Key] = &[
+    key("pipe_name", Kind::Pipe),
+    key("max_entries", MAX_ENTRIES),
+    key("backups", range(0, 100)),
+    key("max_db_bytes", COUNT),
+    key("eviction", Kind::Choice(&["oldest", "largest"])),
+    key("archive", Kind::Bool),
+    key("duplicates", Kind::Choice(&["bump", "skip"])),
+    key("sort_locale", Kind::SortLocale),
+    key("spawn_daemon", Kind::Choice(&["auto", "ask", "never"])),
+    key("paste_behavior", Kind::Choice(&["send_input", "stdout", "type", "pane"])),
+    key("send_command", Kind::Text),
+    key("hide_from_windows_history", Kind::Bool),
+    key("type_delay_ms", range(0, 1000)),
+    // In the template, not read yet.
+    key("panel_width", FRACTION),
+    key("hotkey", Kind::Text),
+    key("max_days", COUNT),
+];
+
+const CAPTURE: &[Key] = &[
+    key("allowed_kinds", Kind::Subset(&["text", "url", "rtf", "html", "image", "files", "snapshot"])),
+    key("project_tags", Kind::Bool),
+    key("group_window_secs", range(0, 3600)),
+    key("quarantine", Kind::Bool),
+    key("trusted_processes", Kind::Names),
+    key("backpressure", Kind::Choice(&["block", "drop_oldest", "spill"])),
+    key("max_entry_mb", range(1, 4096)),
+    key("memory_budget_mb", range(1, 16384)),
+    key("poll_interval_ms", range(50, 5000)),
+    key("idle_poll_interval_ms", range(50, 60000)),
+    key("ignore_window_titles", Kind::Patterns),
+];
+
+const TIME: &[Key] = &[
+    key("format", Kind::TimeFormat),
+    key("timezone", Kind::Choice(&["local", "utc"])),
+    key("locale", Kind::Locale),
+];
+
+const PROFILE: &[Key] = &[
+    key("max_entries", MAX_ENTRIES),
+    key("max_db_bytes", COUNT),
+    key("pipe_name", Kind::Pipe),
+];
+
+const EXPORT: &[Key] = &[key("redact", Kind::Bool), key("redact_patterns", Kind::Patterns)];
+
+const PASTE_FORMATS: &[&str] = &["rich", "plain"];
+
+const PASTE: &[Key] = &[
+    key("block", Kind::Tables(&[key("tags", Kind::Names), key("processes", Kind::Names)])),
+    key("default_format", Kind::Choice(PASTE_FORMATS)),
+    key(
+        "formats",
+        Kind::Tables(&[key("processes", Kind::Names), key("format", Kind::Choice(PASTE_FORMATS))]),
+    ),
+];
+
+const IGNORE: &[Key] = &[
+    key("processes", Kind::Names),
+    key("patterns", Kind::Patterns),
+    key("cards", Kind::Bool),
+    key("max_entropy", Kind::Float { min: 0.0, max: 8.0 }),
+    key("pause_minutes", range(1, 1440)),
+];
+
+const ISSUES: &[Key] = &[
+    key("github_token", Kind::Text),
+    key("github_repo", Kind::Text),
+    key("github_api", Kind::Url),
+    key("jira_url", Kind::Url),
+    key("jira_user", Kind::Text),
+    key("jira_token", Kind::Text),
+    key("jira_projects", Kind::Names),
+];
+
+const BATTERY: &[Key] = &[key("low_percent", range(0, 100)), key("skip_images", Kind::Bool)];
+
+const COMPANION: &[Key] = &[
+    key("enabled", Kind::Bool),
+    key("port", range(1, 65535)),
+    key("bind", Kind::Address),
+];
+
+const UI: &[Key] = &[
+    key("theme", Kind::Choice(&["nord", "dracula", "tokyo-night", "gruvbox", "high-contrast"])),
+    key("preview_length", range(20, 500)),
+    key("confirm_prompts", Kind::Bool),
+    key("show_timestamps", Kind::Bool),
+    key("guest_mode", Kind::Bool),
+    key("unit_conversions", Kind::Bool),
+    key("suggestions", Kind::Bool),
+    key("accessible", Kind::Bool),
+    key("language", Kind::Text),
+    key("highlight_matches", Kind::Bool),
+    key("preview_expanded_width", FRACTION),
+];
+
+const VIEW: &[Key] = &[required("name", Kind::Text), required("query", Kind::Text)];
+
+const PRIVACY: &[Key] = &[key("start_in_privacy_mode", Kind::Bool), key("secure_wipe", Kind::Bool)];
+
+const TRANSFORMERS: &[Key] = &[key("pre_paste", Kind::Names), key("post_capture", Kind::Names)];
+
+const LOGGING: &[Key] = &[key("level", Kind::Text), key("file", Kind::Text)];
+
+const ROOT: &[Key] = &[
+    key("general", Kind::Table(GENERAL)),
+    key("capture", Kind::Table(CAPTURE)),
+    key("time", Kind::Table(TIME)),
+    key("profiles", Kind::Profiles(PROFILE)),
+    key("export", Kind::Table(EXPORT)),
+    key("paste", Kind::Table(PASTE)),
+    key("ignore", Kind::Table(IGNORE)),
+    key("issues", Kind::Table(ISSUES)),
+    key("battery", Kind::Table(BATTERY)),
+    key("companion", Kind::Table(COMPANION)),
+    key("ui", Kind::Table(UI)),
+    key("views", Kind::Tables(VIEW)),
+    key("keys", Kind::Strings),
+    key("privacy", Kind::Table(PRIVACY)),
+    key("transformers", Kind::Table(TRANSFORMERS)),
+    key("logging", Kind::Table(LOGGING)),
+];
+
+/// Every problem with the contents of a config file; empty when clipd and
+/// clipctl can use all of it.
+pub fn check(raw: &str) -> Vec<Problem> {
+    check_layer(raw, &[])
+}
+
+/// Like [`check`], for a file layered over others that define `profiles`,
+/// which its `profiles.active` may name too.
+pub fn check_layer(raw: &str, profiles: &[String]) -> Vec<Problem> {
+    let mut checker = Checker { raw, profiles, problems: Vec::new() };
+    match ImDocument::parse(raw) {
+        Ok(doc) => checker.table("", doc.as_table(), ROOT),
+        Err(err) => checker.report(err.span(), err.message().trim_end().replace('\n', "; ")),
+    }
+    checker.problems
+}
+
+/// `raw`, as given in an environment variable or a `--set` flag, as the
+/// value of the dotted key `path`; lists are separated by commas. The error
+/// says what is wrong with it, or that there is no such key.
+pub fn parse_value(path: &str, raw: &str) -> Result<toml_edit::Value, String> {
+    let kind = lookup(path)?;
+    let invalid = || format!("{path} {}, got '{raw}'", expects(kind));
+    let value: toml_edit::Value = match kind {
+        Kind::Bool => match raw.trim() {
+            "true" | "on" | "yes" | "1" => true.into(),
+            "false" | "off" | "no" | "0" => false.into(),
+            _ => return Err(invalid()),
+        },
+        Kind::Integer { .. } => raw.trim().parse::<i64>().map_err(|_| invalid())?.into(),
+        Kind::Float { .. } => raw.trim().parse::<f64>().map_err(|_| invalid())?.into(),
+        Kind::Subset(_) | Kind::Names | Kind::Patterns => raw
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .collect::<toml_edit::Array>()
+            .into(),
+        _ => raw.into(),
+    };
+
+    let mut checker = Checker { raw: "", profiles: &[], problems: Vec::new() };
+    checker.item(path, &Item::Value(value.clone()), kind, None);
+    match checker.problems.into_iter().next() {
+        Some(problem) => Err(problem.message),
+        None => Ok(value),
+    }
+}
+
+/// What the dotted key `path` holds, if it is a single value.
+fn lookup(path: &str) -> Result<Kind, String> {
+    let mut kind = Kind::Table(ROOT);
+    let mut walked = String::new();
+    for name in path.split('.') {
+        kind = match kind {
+            Kind::Table(keys) => match keys.iter().find(|key| key.name == name) {
+                Some(key) => key.kind,
+                None => return Err(unknown(&join(&walked, name), name, keys)),
+            },
+            Kind::Profiles(_) if name == "active" => Kind::Text,
+            Kind::Profiles(keys) if is_valid_profile_name(name) => Kind::Table(keys),
+            Kind::Profiles(_) => {
+                return Err(format!("{} is not a profile name; use letters, digits, '-' and '_'", join(&walked, name)));
+            }
+            Kind::Strings => Kind::Text,
+            _ => return Err(format!("{walked} has no keys")),
+        };
+        walked = join(&walked, name);
+    }
+    match kind {
+        Kind::Table(_) | Kind::Strings | Kind::Profiles(_) => {
+            Err(format!("{path} is a table; set its keys one by one"))
+        }
+        Kind::Tables(_) => Err(format!("{path} is a list of tables, which only a config file can hold")),
+        kind => Ok(kind),
+    }
+}
+
+struct Checker<'a> {
+    raw: &'a str,
+    /// Profiles the layers beneath define.
+    profiles: &'a [String],
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, span: Option<Range<usize>>, message: String) {
+        let line = span.map(|span| self.raw[..span.start.min(self.raw.len())].matches('\n').count() + 1);
+        self.problems.push(Problem { line, message });
+    }
+
+    /// Check the keys of `table`, at `path`, against `keys`.
+    fn table(&mut self, path: &str, table: &dyn TableLike, keys: &[Key]) {
+        for (name, item) in table.iter() {
+            let full = join(path, name);
+            let span = span_of(table, name, item);
+            match keys.iter().find(|key| key.name == name) {
+                Some(key) => self.item(&full, item, key.kind, span),
+                None => self.report(span, unknown(&full, name, keys)),
+            }
+        }
+        for key in keys.iter().filter(|key| key.required && !table.contains_key(key.name)) {
+            let span = table.iter().next().and_then(|(name, item)| span_of(table, name, item));
+            self.report(span, format!("{path} is missing {}", key.name));
+        }
+    }
+
+    fn item(&mut self, path: &str, item: &Item, kind: Kind, span: Option<Range<usize>>) {
+        match kind {
+            Kind::Table(keys) => match item.as_table_like() {
+                Some(table) => self.table(path, table, keys),
+                None => self.report(span, format!("{path} must be a table, got {}", item.type_name())),
+            },
+            Kind::Tables(keys) => self.tables(path, item, keys, span),
+            Kind::Strings => match item.as_table_like() {
+                Some(table) => {
+                    for (name, value) in table.iter().filter(|(_, value)| !value.is_str()) {
+                        let span = span_of(table, name, value);
+                        let message = format!("{} must be a string, got {}", join(path, name), value.type_name());
+                        self.report(span, message);
+                    }
+                }
+                None => self.report(span, format!("{path} must be a table, got {}", item.type_name())),
+            },
+            Kind::Profiles(keys) => self.profiles(path, item, keys, span),
+            _ => match item.as_value() {
+                Some(value) => {
+                    let span = value.span().or(span);
+                    if let Err(message) = check_value(path, value, kind) {
+                        self.report(span, message);
+                    }
+                    if matches!(kind, Kind::Subset(_) | Kind::Names | Kind::Patterns) {
+                        self.list(path, value, kind);
+                    }
+                }
+                None => self.report(span, format!("{path} {}, got {}", expects(kind), item.type_name())),
+            },
+        }
+    }
+
+    /// Check the items of a list key one by one, so each problem has its line.
+    fn list(&mut self, path: &str, value: &toml_edit::Value, kind: Kind) {
+        let Some(items) = value.as_array() else {
+            return;
+        };
+        for item in items {
+            let Some(text) = item.as_str() else {
+                self.report(item.span(), format!("{path} must list strings, got {}", item.type_name()));
+                continue;
+            };
+            let problem = match kind {
+                Kind::Subset(names) if !names.contains(&text) => {
+                    Some(format!("{path} entries must be among: {}, got '{text}'", names.join(", ")))
+                }
+                Kind::Patterns => Regex::new(text).err().map(|err| {
+                    // The error's last line says what is wrong; the rest draws the pattern.
+                    let reason = err.to_string().lines().last().unwrap_or_default().to_string();
+                    let reason = reason.strip_prefix("error: ").unwrap_or(&reason).to_string();
+                    format!("{path} has an invalid regular expression '{text}': {reason}")
+                }),
+                _ => None,
+            };
+            if let Some(message) = problem {
+                self.report(item.span(), message);
+            }
+        }
+    }
+
+    fn tables(&mut self, path: &str, item: &Item, keys: &[Key], span: Option<Range<usize>>) {
+        if let Some(tables) = item.as_array_of_tables() {
+            for table in tables.iter() {
+                self.table(path, table, keys);
+            }
+            return;
+        }
+        let inline: Option<Vec<_>> =
+            item.as_array().and_then(|array| array.iter().map(|value| value.as_inline_table()).collect());
+        match inline {
+            Some(tables) => {
+                for table in tables {
+                    self.table(path, table, keys);
+                }
+            }
+            None => self.report(span, format!("{path} must be a list of tables, got {}", item.type_name())),
+        }
+    }
+
+    fn profiles(&mut self, path: &str, item: &Item, keys: &'static [Key], span: Option<Range<usize>>) {
+        let Some(table) = item.as_table_like() else {
+            self.report(span, format!("{path} must be a table, got {}", item.type_name()));
+            return;
+        };
+        let mut active = None;
+        for (name, item) in table.iter() {
+            let full = join(path, name);
+            let span = span_of(table, name, item);
+            if name == "active" {
+                self.item(&full, item, Kind::Text, span.clone());
+                active = item.as_str().map(|active| (active, span));
+            } else if !is_valid_profile_name(name) {
+                self.report(span, format!("{full} is not a profile name; use letters, digits, '-' and '_'"));
+            } else {
+                self.item(&full, item, Kind::Table(keys), span);
+            }
+        }
+
+        if let Some((active, span)) = active {
+            let defined = table.contains_key(active) || self.profiles.iter().any(|name| name == active);
+            if active != DEFAULT_PROFILE && !defined {
+                let message = format!("{path}.active is '{active}', but there is no [{path}.{active}] table");
+                self.report(span, message);
+            }
+        }
+    }
+}
+
+/// What is wrong with `value` for a key of `kind`, other than in the items of a list.
+fn check_value(path: &str, value: &toml_edit::Value, kind: Kind) -> Result<(), String> {
+    let got = || format!("{path} {}, got {}", expects(kind), value.type_name());
+    match kind {
+        Kind::Bool => value.as_bool().map(|_| ()).ok_or_else(got),
+        Kind::Integer { min, max } => {
+            let number = value.as_integer().ok_or_else(got)?;
+            if !(min..=max).contains(&number) {
+                return Err(format!("{path} must be between {min} and {max}, got {number}"));
+            }
+            Ok(())
+        }
+        Kind::Float { min, max } => {
+            let number = value.as_float().or(value.as_integer().map(|n| n as f64)).ok_or_else(got)?;
+            if !(min..=max).contains(&number) {
+                return Err(format!("{path} must be between {min} and {max}, got {number}"));
+            }
+            Ok(())
+        }
+        Kind::Subset(_) | Kind::Names | Kind::Patterns => value.as_array().map(|_| ()).ok_or_else(got),
+        _ => {
+            let text = value.as_str().ok_or_else(got)?;
+            check_text(path, text, kind)
+        }
+    }
+}
+
+fn check_text(path: &str, text: &str, kind: Kind) -> Result<(), String> {
+    let valid = match kind {
+        Kind::Choice(names) if !names.contains(&text) => {
+            return Err(format!("{path} must be one of: {}, got '{text}'", names.join(", ")));
+        }
+        Kind::Pipe => return check_pipe(path, text),
+        Kind::Address => text.parse::<IpAddr>().is_ok(),
+        Kind::Url => text.is_empty() || text.starts_with("https://") || text.starts_with("http://"),
+        Kind::TimeFormat => !StrftimeItems::new(text).any(|item| matches!(item, FormatItem::Error)),
+        Kind::Locale => text.is_empty() || crate::timefmt::parse_locale(text).is_some(),
+        Kind::SortLocale => text.is_empty() || crate::collate::is_known(text),
+        _ => true,
+    };
+    if !valid {
+        return Err(format!("{path} {}, got '{text}'", expects(kind)));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn check_pipe(path: &str, name: &str) -> Result<(), String> {
+    if !name.starts_with(r"\\.\pipe\") {
+        return Err(format!(r"{path} must start with \\.\pipe\, got '{name}'"));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn check_pipe(path: &str, name: &str) -> Result<(), String> {
+    use std::path::Path;
+
+    let socket = Path::new(name);
+    if !socket.is_absolute() {
+        return Err(format!("{path} must be the absolute path of a socket, got '{name}'"));
+    }
+    if !socket.parent().is_some_and(Path::is_dir) {
+        return Err(format!("{path} is '{name}', but its directory does not exist"));
+    }
+    Ok(())
+}
+
+/// What a key of `kind` must hold, as in "ui.theme must be one of ...".
+fn expects(kind: Kind) -> String {
+    match kind {
+        Kind::Bool => "expects true or false".to_string(),
+        Kind::Integer { .. } => "expects an integer".to_string(),
+        Kind::Float { .. } => "expects a number".to_string(),
+        Kind::Text => "expects a string".to_string(),
+        Kind::Choice(names) => format!("must be one of: {}", names.join(", ")),
+        Kind::Subset(_) | Kind::Names | Kind::Patterns => "expects a list of strings".to_string(),
+        Kind::Pipe => "expects a pipe name".to_string(),
+        Kind::Address => "must be an IP address such as 127.0.0.1".to_string(),
+        Kind::Url => "must be an http:// or https:// URL".to_string(),
+        Kind::TimeFormat => "must be a strftime format such as %Y-%m-%d %H:%M".to_string(),
+        Kind::Locale | Kind::SortLocale => "must be a locale such as de_DE".to_string(),
+        Kind::Table(_) | Kind::Strings | Kind::Profiles(_) => "must be a table".to_string(),
+        Kind::Tables(_) => "must be a list of tables".to_string(),
+    }
+}
+
+/// Where `name` is written in `table`: its key, or its value when the key
+/// has no place in the file of its own.
+fn span_of(table: &dyn TableLike, name: &str, item: &Item) -> Option<Range<usize>> {
+    table.key(name).and_then(|key| key.span()).or_else(|| item.span())
+}
+
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path => format!("{path}.{name}"),
+    }
+}
+
+/// The problem with unknown key `path`, suggesting the known key it is
+/// probably a typo of.
+fn unknown(path: &str, name: &str, keys: &[Key]) -> String {
+    let closest = keys
+        .iter()
+        .map(|key| (strsim::levenshtein(name, key.name), key.name))
+        .filter(|(distance, known)| *distance <= (known.len() / 3).max(1))
+        .min();
+    match closest {
+        Some((_, known)) => format!("unknown key {path}; did you mean {known}?"),
+        None => format!("unknown key {path}"),
+    }
+}
@@ -1,51 +1,182 @@
 //! Orchestrates clipboard capture, persistence, and IPC server.
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
 use anyhow::{Error, Result};
-use tokio::sync::mpsc;
+use tokio::sync::watch;
 
-use crate::clipboard::ClipboardWatcher;
+use crate::backend::{ClipboardBackend, SystemClipboard};
+use crate::clipboard::{Capture, ClipboardWatcher};
+use crate::companion::Companion;
 use crate::config::Config;
-use crate::db::Database;
+use crate::db::Stored;
 use crate::ipc::Server;
-use crate::model::Entry;
+use crate::journal::Journal;
+use crate::keys::KeyRing;
+use crate::profiles::Profiles;
+use crate::queue::CaptureQueue;
 
 pub struct ClipdService {
     clipboard: ClipboardWatcher,
-    db: Database,
+    profiles: Profiles,
     server: Server,
+    shutdown: watch::Sender<bool>,
+    runtime_path: PathBuf,
+    /// Where the companion endpoint listens, if it is on.
+    companion: Option<SocketAddr>,
+}
+
+/// Handle used to ask a running service to stop.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    tx: watch::Sender<bool>,
+}
+
+impl ShutdownHandle {
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Receiver for [`Server::connect_in_memory`], whose clients are told
+    /// when the service stops.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.tx.subscribe()
+    }
 }
 
 impl ClipdService {
     pub async fn bootstrap(config: Config) -> Result<Self> {
-        let db = Database::open(config.db_path.clone(), config.max_entries)?;
-        let server = Server::new(config.pipe_name.clone(), db.clone());
+        Self::with_backend(config, Arc::new(SystemClipboard::default()))
+    }
+
+    /// Build the service around `backend`, such as a
+    /// [`MockClipboard`](crate::mock::MockClipboard) in tests.
+    pub fn with_backend(config: Config, backend: Arc<dyn ClipboardBackend>) -> Result<Self> {
+        let profiles = Profiles::open(&config)?;
+        let (journal, pending) = Journal::open(config.journal_path.clone())?;
+        let clipboard = ClipboardWatcher::new(
+            backend,
+            config.allowed_kinds.clone(),
+            config.project_tags,
+            config.trusted_processes.clone(),
+            config.group_window_secs,
+            config.paste.clone(),
+            CaptureQueue::new(
+                config.backpressure,
+                config.memory_budget_bytes,
+                config.spill_dir.clone(),
+                journal,
+                pending,
+            ),
+        );
+        clipboard.set_polling(config.polling);
+        clipboard.set_max_entry_bytes(config.max_entry_bytes);
+        clipboard.set_battery_policy(config.battery);
+        clipboard.set_ignore_rules(config.ignore.clone());
+        let server = Server::new(
+            config.pipe_name.clone(),
+            profiles.clone(),
+            clipboard.clone(),
+            config.time.clone(),
+            config.redact_exports,
+            config.redactor.clone(),
+            KeyRing::open(config.keys_path.clone())?,
+        );
+        server.set_issue_trackers(config.issues.clone());
+
+        let (shutdown, _) = watch::channel(false);
 
         Ok(Self {
-            clipboard: ClipboardWatcher::new(),
-            db,
+            clipboard,
+            profiles,
             server,
+            shutdown,
+            runtime_path: config.runtime_path,
+            companion: config.companion,
         })
     }
 
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            tx: self.shutdown.clone(),
+        }
+    }
+
+    /// Handle for connecting clients without the pipe, with
+    /// [`Server::connect_in_memory`].
+    pub fn server(&self) -> Server {
+        self.server.clone()
+    }
+
+    /// Capture, store, and answer clients on the named pipe, or the socket
+    /// on Linux and macOS, until shut down.
     pub async fn run(self) -> Result<()> {
-        let (entry_tx, entry_rx) = mpsc::channel::<Entry>(256);
+        self.run_with(true).await
+    }
+
+    /// Like [`run`](Self::run) without the pipe, for tests and
+    /// `clipctl --embedded`, whose clients connect in memory instead.
+    pub async fn run_headless(self) -> Result<()> {
+        self.run_with(false).await
+    }
+
+    async fn run_with(self, listen: bool) -> Result<()> {
         let Self {
             clipboard,
-            db,
+            profiles,
             server,
+            shutdown,
+            runtime_path,
+            companion,
         } = self;
 
+        let watcher = clipboard.clone();
+        let store_server = server.clone();
+        let companion_server = server.clone();
+        let companion_shutdown = shutdown.subscribe();
         tokio::try_join!(
-            clipboard.run(entry_tx.clone()),
+            clipboard.run(shutdown.subscribe()),
+            async move {
+                let queue = watcher.queue();
+                while let Some(queued) = queue.pop().await {
+                    match &queued.capture {
+                        Capture::Entry(entry) => {
+                            let group_window = watcher.group_window();
+                            match profiles.active().insert_entry(entry, group_window, queued.replayed)? {
+                                Stored::Added(id) => {
+                                    store_server.entry_added(id);
+                                    store_server.add_issue_titles(id);
+                                }
+                                // Sent again so clients move it to the top.
+                                Stored::Bumped(id) => store_server.entry_added(id),
+                                Stored::Skipped => {}
+                            }
+                        }
+                        Capture::Pasted(id) => profiles.active().touch_entry(*id)?,
+                    }
+                    queue.stored(&queued);
+                }
+                Ok::<(), Error>(())
+            },
+            async move {
+                if listen {
+                    server.run(shutdown.subscribe(), &runtime_path).await?;
+                }
+                Ok(())
+            },
             async move {
-                let mut entry_rx = entry_rx;
-                drop(entry_tx);
-                while let Some(entry) = entry_rx.recv().await {
-                    db.insert_entry(&entry)?;
+                let Some(address) = companion.filter(|_| listen) else {
+                    return Ok(());
+                };
+                // A port taken by something else should not stop capture.
+                match Companion::bind(companion_server, address).await {
+                    Ok(companion) => companion.run(companion_shutdown).await?,
+                    Err(err) => tracing::warn!("companion endpoint is off: {err:#}"),
                 }
                 Ok::<(), Error>(())
             },
-            async move { server.run().await },
         )?;
 
         Ok(())
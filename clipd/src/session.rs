@@ -0,0 +1,95 @@
+//! Whether the workstation is locked, from the session notifications Windows
+//! sends to a message-only window.
+//!
+//! The window lives on a thread of its own, started the first time the lock
+//! state is asked for, and stays until clipd exits. The session is taken to
+//! be unlocked until the first notification arrives.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use anyhow::{bail, Context, Result};
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::RemoteDesktop::{WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, HWND_MESSAGE, MSG,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_WTSSESSION_CHANGE, WNDCLASSW, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+};
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+static WATCH: Once = Once::new();
+
+/// Whether the workstation clipd runs in is locked.
+pub fn locked() -> bool {
+    WATCH.call_once(|| {
+        let spawned = std::thread::Builder::new()
+            .name("session-watcher".to_string())
+            .spawn(|| {
+                if let Err(err) = unsafe { watch() } {
+                    tracing::warn!(%err, "workstation lock state unavailable; polling as if unlocked");
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::warn!(%err, "failed to start the session watcher");
+        }
+    });
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Create the window, register it for session notifications, and pump its
+/// messages until the thread's message queue is closed.
+unsafe fn watch() -> Result<()> {
+    let instance = GetModuleHandleW(None).context("failed to get the module handle")?;
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance.into(),
+        lpszClassName: w!("RustyClipboardSession"),
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        bail!("failed to register the session window class");
+    }
+    let window = CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        class.lpszClassName,
+        None,
+        WINDOW_STYLE::default(),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        None,
+        instance,
+        None,
+    )
+    .context("failed to create the session window")?;
+    WTSRegisterSessionNotification(window, NOTIFY_FOR_THIS_SESSION)
+        .context("failed to register for session notifications")?;
+
+    let mut msg = MSG::default();
+    while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+        DispatchMessageW(&msg);
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if message == WM_WTSSESSION_CHANGE {
+        match wparam.0 as u32 {
+            WTS_SESSION_LOCK => {
+                tracing::info!("workstation locked");
+                LOCKED.store(true, Ordering::Relaxed);
+            }
+            WTS_SESSION_UNLOCK => {
+                tracing::info!("workstation unlocked");
+                LOCKED.store(false, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+        return LRESULT(0);
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
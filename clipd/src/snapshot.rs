@@ -0,0 +1,134 @@
+//! Every format on the clipboard at one moment, stored as a single entry.
+//!
+//! Office and browsers put the same copy on the clipboard in many formats at
+//! once (an Excel range is text, HTML, RTF, and several Biff spreadsheets), and
+//! a later copy replaces them all. A snapshot keeps the raw bytes of each
+//! format so the exact state can be put back. Registered formats are stored by
+//! name, since their ids differ between Windows sessions.
+
+use anyhow::{bail, Context, Result};
+
+/// Start of stored snapshot data, followed by a format count.
+const MAGIC: &[u8; 8] = b"CLIPSNP1";
+
+/// Ids of the formats predefined by Windows (`CF_*`), which have no name to
+/// register.
+const STANDARD_FORMATS: &[(u32, &str)] = &[
+    (1, "Text"),
+    (2, "Bitmap"),
+    (3, "Metafile picture"),
+    (4, "SYLK"),
+    (5, "DIF"),
+    (6, "TIFF"),
+    (7, "OEM text"),
+    (8, "DIB"),
+    (9, "Palette"),
+    (10, "Pen data"),
+    (11, "RIFF"),
+    (12, "Wave"),
+    (13, "Unicode text"),
+    (14, "Enhanced metafile"),
+    (15, "Files"),
+    (16, "Locale"),
+    (17, "DIB v5"),
+];
+
+/// `CF_UNICODETEXT`.
+const UNICODE_TEXT: u32 = 13;
+
+/// One clipboard format's data.
+#[derive(Debug, Clone)]
+pub struct Format {
+    /// Id the format had when captured; only meaningful for standard formats.
+    pub id: u32,
+    /// Registered name, such as `HTML Format` or `Biff12`; `None` for standard formats.
+    pub name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Format {
+    /// Name shown in the preview.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => STANDARD_FORMATS
+                .iter()
+                .find(|(id, _)| *id == self.id)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("format {}", self.id)),
+        }
+    }
+}
+
+/// Store `formats` as entry data.
+pub fn encode(formats: &[Format]) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&(formats.len() as u32).to_le_bytes());
+    for format in formats {
+        let name = format.name.as_deref().unwrap_or_default().as_bytes();
+        data.extend_from_slice(&format.id.to_le_bytes());
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        data.extend_from_slice(name);
+        data.extend_from_slice(&(format.data.len() as u32).to_le_bytes());
+        data.extend_from_slice(&format.data);
+    }
+    data
+}
+
+/// Read back the formats stored by [`encode`].
+pub fn decode(data: &[u8]) -> Result<Vec<Format>> {
+    let mut reader = Reader { data };
+    if reader.take(MAGIC.len())? != MAGIC {
+        bail!("not clipboard snapshot data");
+    }
+    let count = reader.u32()?;
+    let mut formats = Vec::new();
+    for _ in 0..count {
+        let id = reader.u32()?;
+        let name_len = reader.u32()? as usize;
+        let name = std::str::from_utf8(reader.take(name_len)?)
+            .context("snapshot format name is not UTF-8")?;
+        let data_len = reader.u32()? as usize;
+        formats.push(Format {
+            id,
+            name: (!name.is_empty()).then(|| name.to_string()),
+            data: reader.take(data_len)?.to_vec(),
+        });
+    }
+    Ok(formats)
+}
+
+/// The snapshot's Unicode text, for search and preview.
+pub fn text(formats: &[Format]) -> Option<String> {
+    let format = formats
+        .iter()
+        .find(|format| format.name.is_none() && format.id == UNICODE_TEXT)?;
+    let units: Vec<u16> = format
+        .data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    let text = String::from_utf16_lossy(&units);
+    (!text.is_empty()).then_some(text)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            bail!("clipboard snapshot data is truncated");
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
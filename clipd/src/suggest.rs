@@ -0,0 +1,56 @@
+//! Which entry clipctl selects when it opens: the one pasted most into the
+//! app in front and around this time of day, recent pastes counting more
+//! than old ones. Nothing is suggested until the pastes show a habit.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, Timelike, Utc};
+
+/// How far back pastes count; older ones are forgotten.
+pub const LOOKBACK_DAYS: i64 = 60;
+
+/// Age at which a paste counts half as much as one made now.
+const HALF_LIFE_DAYS: f64 = 7.0;
+
+/// How much more a paste into the app in front counts than one elsewhere.
+const SAME_APP: f64 = 4.0;
+
+/// How much more a paste within an hour of the time of day counts.
+const SAME_TIME: f64 = 2.0;
+
+/// Lowest score suggested: one recent paste into the app in front, or a
+/// few elsewhere.
+const MIN_SCORE: f64 = 3.0;
+
+/// Entry `entry_id` was pasted into `target` at `at`.
+#[derive(Debug, Clone)]
+pub struct Paste {
+    pub entry_id: u64,
+    pub target: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// The entry to suggest at `now` with `target` in front, if one scores
+/// high enough; ties go to the newer entry.
+pub fn suggest(pastes: &[Paste], target: Option<&str>, now: DateTime<Utc>) -> Option<u64> {
+    let hour = now.with_timezone(&Local).hour() as i32;
+    let mut scores: HashMap<u64, f64> = HashMap::new();
+    for paste in pastes {
+        let age_days = (now - paste.at).num_seconds().max(0) as f64 / 86_400.0;
+        let mut score = 0.5f64.powf(age_days / HALF_LIFE_DAYS);
+        let same_app = target.zip(paste.target.as_deref()).is_some_and(|(a, b)| a.eq_ignore_ascii_case(b));
+        if same_app {
+            score *= SAME_APP;
+        }
+        let hours_apart = (paste.at.with_timezone(&Local).hour() as i32 - hour).rem_euclid(24);
+        if hours_apart.min(24 - hours_apart) <= 1 {
+            score *= SAME_TIME;
+        }
+        *scores.entry(paste.entry_id).or_default() += score;
+    }
+    scores
+        .into_iter()
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .max_by(|(a_id, a), (b_id, b)| a.total_cmp(b).then(a_id.cmp(b_id)))
+        .map(|(id, _)| id)
+}
@@ -0,0 +1,97 @@
+//! Display formatting for capture timestamps, configured under `[time]`.
+//!
+//! Timestamps stay UTC in the database and on the wire; this only produces the
+//! human-readable form shown by clipctl and written alongside exports.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Locale, Utc};
+
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, Clone)]
+pub struct TimeDisplay {
+    format: String,
+    /// Show UTC instead of converting to the local timezone.
+    utc: bool,
+    locale: Locale,
+}
+
+impl Default for TimeDisplay {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_FORMAT.to_string(),
+            utc: false,
+            locale: system_locale(),
+        }
+    }
+}
+
+impl TimeDisplay {
+    /// Build from `[time]` values; invalid ones are logged and replaced by defaults.
+    pub fn new(format: Option<String>, timezone: Option<String>, locale: Option<String>) -> Self {
+        let mut display = Self::default();
+
+        if let Some(format) = format {
+            if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+                tracing::warn!(%format, "ignoring invalid time.format");
+            } else {
+                display.format = format;
+            }
+        }
+
+        match timezone.as_deref() {
+            None | Some("local") => {}
+            Some("utc") => display.utc = true,
+            Some(other) => tracing::warn!(timezone = other, "ignoring unknown time.timezone"),
+        }
+
+        if let Some(name) = locale.filter(|name| !name.is_empty()) {
+            match parse_locale(&name) {
+                Some(locale) => display.locale = locale,
+                None => tracing::warn!(locale = %name, "ignoring unknown time.locale"),
+            }
+        }
+
+        display
+    }
+
+    /// SQL expression for an entry's calendar day in the display timezone.
+    pub fn day_sql(&self) -> &'static str {
+        if self.utc {
+            "date(created_at)"
+        } else {
+            "date(created_at, 'localtime')"
+        }
+    }
+
+    /// SQL expression for the hour, 0 to 23, an entry was captured in, in the
+    /// display timezone.
+    pub fn hour_sql(&self) -> &'static str {
+        if self.utc {
+            "CAST(strftime('%H', created_at) AS INTEGER)"
+        } else {
+            "CAST(strftime('%H', created_at, 'localtime') AS INTEGER)"
+        }
+    }
+
+    pub fn format(&self, at: DateTime<Utc>) -> String {
+        if self.utc {
+            at.format_localized(&self.format, self.locale).to_string()
+        } else {
+            at.with_timezone(&Local)
+                .format_localized(&self.format, self.locale)
+                .to_string()
+        }
+    }
+}
+
+/// Accepts both `de_DE` and the BCP 47 form `de-DE` that Windows reports.
+pub(crate) fn parse_locale(name: &str) -> Option<Locale> {
+    Locale::try_from(name.replace('-', "_").as_str()).ok()
+}
+
+fn system_locale() -> Locale {
+    sys_locale::get_locale()
+        .and_then(|name| parse_locale(&name))
+        .unwrap_or(Locale::POSIX)
+}
@@ -0,0 +1,106 @@
+//! Display titles for text entries, shown in the list instead of raw content.
+
+use crate::files;
+use crate::model::EntryKind;
+
+/// Titles longer than this are cut at a character boundary.
+const MAX_TITLE: usize = 80;
+
+/// Copied files named in a title before the rest are only counted.
+const MAX_FILE_NAMES: usize = 3;
+
+/// Keywords that introduce a named definition in common languages.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn", "def", "function", "func", "class", "struct", "enum", "trait", "impl", "interface",
+    "module", "mod",
+];
+
+/// Modifiers skipped before a definition keyword, e.g. `pub async fn`.
+const MODIFIERS: &[&str] = &[
+    "pub", "pub(crate)", "async", "export", "default", "public", "private", "protected",
+    "static", "abstract", "final", "unsafe", "const",
+];
+
+/// Title for an entry's text, or `None` when the text already reads fine as-is.
+///
+/// Links use their host, code its first definition (`fn parse`), other text
+/// its first line with meaningful content, and copied files their names.
+pub fn derive(kind: &EntryKind, text: &str) -> Option<String> {
+    if *kind == EntryKind::Files {
+        return file_names(text);
+    }
+    if !matches!(kind, EntryKind::Text | EntryKind::Url) {
+        return None;
+    }
+    let trimmed = text.trim();
+
+    let title = url_host(trimmed)
+        .or_else(|| first_definition(trimmed))
+        .or_else(|| first_line(trimmed))?;
+    (title != trimmed).then_some(title)
+}
+
+fn url_host(text: &str) -> Option<String> {
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let rest = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+fn first_definition(text: &str) -> Option<String> {
+    // A single line is more useful shown whole than reduced to one name.
+    if !text.contains('\n') {
+        return None;
+    }
+    text.lines().find_map(|line| {
+        let mut words = line
+            .split_whitespace()
+            .skip_while(|word| MODIFIERS.contains(word));
+        let keyword = words.next()?;
+        if !DEFINITION_KEYWORDS.contains(&keyword) {
+            return None;
+        }
+        let name: String = words
+            .next()?
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        // Prose such as "class starts at nine" has no signature punctuation.
+        let is_signature = line.contains(['(', '{', ':', '<', '=']);
+        (!name.is_empty() && is_signature).then(|| format!("{keyword} {name}"))
+    })
+}
+
+/// `a.txt, b.txt, c.txt and 2 more` for files listed one path per line.
+fn file_names(text: &str) -> Option<String> {
+    let names: Vec<&str> = text.lines().map(files::file_name).filter(|name| !name.is_empty()).collect();
+    let (first, rest) = names.split_at(names.len().min(MAX_FILE_NAMES));
+    let mut title = first.join(", ");
+    if !rest.is_empty() {
+        title.push_str(&format!(" and {} more", rest.len()));
+    }
+    (!title.is_empty()).then(|| shorten(&title))
+}
+
+fn first_line(text: &str) -> Option<String> {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| line.chars().any(char::is_alphanumeric))?;
+    Some(shorten(line))
+}
+
+/// `line`, cut to [`MAX_TITLE`] characters.
+fn shorten(line: &str) -> String {
+    if line.chars().count() <= MAX_TITLE {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_TITLE - 1).collect();
+    format!("{}…", cut.trim_end())
+}
@@ -0,0 +1,261 @@
+//! Invariants of the history database over generated captures and searches:
+//! retention never drops pinned entries, searches only find what the list
+//! could show and find every listed entry with a word, re-importing an export changes nothing, and identical images
+//! are stored once for as long as an entry holds them.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+use clipd::db::Database;
+use clipd::model::{Entry, EntryKind};
+use clipd::query::Query;
+use clipd::timefmt::TimeDisplay;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+/// Words captures and searches are made of, few enough that searches match.
+const WORDS: &[&str] = &["alpha", "beta", "gamma", "Straße", "naïve", "TODO"];
+
+/// Search words besides `WORDS`: filters and sort orders. `is:quarantined` is
+/// left out, as it finds entries the list hides on purpose.
+const FILTERS: &[&str] = &[
+    "kind:text",
+    "kind:url",
+    "is:pinned",
+    "chars>10",
+    "lines=1",
+    "bytes<=12",
+    "sort:largest",
+    "sort:smallest",
+    "sort:recent",
+];
+
+const TAGS: &[&str] = &["work", "todo"];
+
+/// Image data generated images are made of, few enough to repeat.
+const PICTURES: &[&[u8]] = &[b"first picture", b"second picture", b"third"];
+
+/// One thing copied, and what the user did with it.
+#[derive(Debug, Clone)]
+struct Capture {
+    text: String,
+    url: bool,
+    tags: Vec<String>,
+    pinned: bool,
+    quarantined: bool,
+}
+
+fn capture() -> impl Strategy<Value = Capture> {
+    (
+        vec(select(WORDS), 1..4),
+        any::<bool>(),
+        vec(select(TAGS), 0..2),
+        any::<bool>(),
+        prop::bool::weighted(0.2),
+    )
+        .prop_map(|(words, url, tags, pinned, quarantined)| {
+            let text = words.join(" ");
+            Capture {
+                text: if url { format!("https://example.com/{}", text.replace(' ', "/")) } else { text },
+                url,
+                tags: tags.into_iter().map(str::to_string).collect(),
+                pinned,
+                quarantined,
+            }
+        })
+}
+
+fn query() -> impl Strategy<Value = String> {
+    vec(select([WORDS, FILTERS].concat()), 0..4).prop_map(|words| words.join(" "))
+}
+
+/// `capture` as the watcher stores it, copied at `at`.
+fn entry(capture: &Capture, at: DateTime<Utc>) -> Entry {
+    Entry {
+        id: None,
+        created_at: at,
+        kind: if capture.url { EntryKind::Url } else { EntryKind::Text },
+        text: Some(capture.text.clone()),
+        data: None,
+        bytes_len: capture.text.len(),
+        hash: format!("{:x}", Sha256::digest(capture.text.as_bytes())),
+        source_process: Some("notepad.exe".to_string()),
+        tags: capture.tags.clone(),
+        image: None,
+        title: None,
+        session: None,
+        quarantined: capture.quarantined,
+        lang: None,
+        pinned: false,
+        text_extracted: None,
+        copy_group: None,
+        entities: Vec::new(),
+        use_count: 1,
+    }
+}
+
+/// Image entry `n`, holding `data` under a hash of its own.
+fn image(n: usize, data: &[u8], at: DateTime<Utc>) -> Entry {
+    Entry {
+        id: None,
+        created_at: at,
+        kind: EntryKind::Image,
+        text: Some(format!("<image {} bytes>", data.len())),
+        data: Some(data.to_vec()),
+        bytes_len: data.len(),
+        hash: format!("image-{n}"),
+        source_process: None,
+        tags: Vec::new(),
+        image: None,
+        title: None,
+        session: None,
+        quarantined: false,
+        lang: None,
+        pinned: false,
+        text_extracted: None,
+        copy_group: None,
+        entities: Vec::new(),
+        use_count: 1,
+    }
+}
+
+fn open(dir: &TempDir, name: &str, max_entries: usize) -> Database {
+    Database::open(dir.path().join(name), max_entries, 0).unwrap()
+}
+
+/// Store `captures` in order, pinning the ones the user pinned, and return
+/// the hashes of the entries that ended up pinned.
+fn copy_all(db: &Database, captures: &[Capture]) -> HashSet<String> {
+    let start = Utc::now();
+    let mut pinned = HashSet::new();
+    for (i, capture) in captures.iter().enumerate() {
+        let entry = entry(capture, start + Duration::seconds(i as i64));
+        db.insert_entry(&entry, Duration::zero(), false).unwrap();
+        if !capture.pinned {
+            continue;
+        }
+        // Gone already when retention keeps nothing unpinned; hidden if quarantined.
+        let stored = db.list_recent(1000).unwrap().into_iter().find(|stored| stored.hash == entry.hash);
+        if let Some(stored) = stored {
+            db.set_pinned(stored.id.unwrap(), true).unwrap();
+            pinned.insert(stored.hash);
+        }
+    }
+    pinned
+}
+
+/// What an entry holds apart from its id: hash, kind, text, tags, pinned,
+/// quarantined, and when it was copied.
+type Stored = (String, String, Option<String>, Vec<String>, bool, bool, DateTime<Utc>);
+
+/// Everything stored, listed or quarantined, in an order that does not
+/// depend on ids or pin order.
+fn history(db: &Database) -> Vec<Stored> {
+    let mut entries = db.list_recent(1000).unwrap();
+    entries.extend(db.search(&Query::parse("is:quarantined"), TimeDisplay::default().day_sql(), 1000).unwrap());
+    let mut history: Vec<_> = entries
+        .into_iter()
+        .map(|entry| {
+            let kind = entry.kind.name().to_string();
+            (entry.hash, kind, entry.text, entry.tags, entry.pinned, entry.quarantined, entry.created_at)
+        })
+        .collect();
+    history.sort();
+    history
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn retention_never_deletes_pinned_entries(captures in vec(capture(), 1..30), max_entries in 0usize..8) {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir, "history.db", max_entries);
+        let pinned = copy_all(&db, &captures);
+
+        let listed = db.list_recent(1000).unwrap();
+        let hashes: HashSet<_> = listed.iter().map(|entry| entry.hash.clone()).collect();
+        prop_assert!(pinned.is_subset(&hashes), "pinned {pinned:?}, listed {hashes:?}");
+        let unpinned = listed.iter().filter(|entry| !entry.pinned).count();
+        prop_assert!(unpinned <= max_entries, "{unpinned} unpinned entries kept, limit {max_entries}");
+    }
+
+    #[test]
+    fn search_results_are_listed(captures in vec(capture(), 0..20), query in query()) {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir, "history.db", 1000);
+        copy_all(&db, &captures);
+
+        let listed: HashSet<_> = db.list_recent(1000).unwrap().into_iter().map(|entry| entry.id).collect();
+        let found = db.search(&Query::parse(&query), TimeDisplay::default().day_sql(), 1000).unwrap();
+        for entry in found {
+            prop_assert!(listed.contains(&entry.id), "{query:?} found {:?}, which is not listed", entry.text);
+        }
+    }
+
+    #[test]
+    fn word_searches_find_every_listed_entry_with_the_word(
+        captures in vec(capture(), 0..20),
+        max_entries in 0usize..8,
+        word in select(WORDS),
+    ) {
+        let dir = TempDir::new().unwrap();
+        // Retention deletes entries, which the search index has to forget.
+        let db = open(&dir, "history.db", max_entries);
+        copy_all(&db, &captures);
+
+        let has_word = |entry: &Entry| {
+            let text = entry.text.as_deref().unwrap_or_default();
+            text.split(|c: char| !c.is_alphanumeric()).any(|found| found == word)
+                || entry.tags.iter().any(|tag| tag.eq_ignore_ascii_case(word))
+        };
+        let ids = |entries: Vec<Entry>| entries.into_iter().map(|entry| entry.id).collect::<HashSet<_>>();
+        let listed = ids(db.list_recent(1000).unwrap().into_iter().filter(has_word).collect());
+        let found = ids(db.search(&Query::parse(word), TimeDisplay::default().day_sql(), 1000).unwrap());
+        prop_assert_eq!(found, listed, "searching {:?}", word);
+    }
+
+    #[test]
+    fn importing_an_export_again_changes_nothing(captures in vec(capture(), 0..20)) {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir, "history.db", 1000);
+        copy_all(&db, &captures);
+        let before = history(&db);
+
+        let export: PathBuf = dir.path().join("export.json");
+        db.export_to_json(export.to_str().unwrap(), &TimeDisplay::default(), None, None, None).unwrap();
+        let json = std::fs::read(&export).unwrap();
+        prop_assert_eq!(db.import_json(&json).unwrap(), (0, before.len()));
+        prop_assert_eq!(history(&db), before.clone());
+
+        // Into an empty history, the export brings back the same entries.
+        let copy = open(&dir, "copy.db", 1000);
+        prop_assert_eq!(copy.import_json(&json).unwrap(), (before.len(), 0));
+        prop_assert_eq!(history(&copy), before);
+    }
+
+    #[test]
+    fn identical_images_share_one_blob(pictures in vec(0..PICTURES.len(), 1..20), max_entries in 0usize..8) {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir, "history.db", max_entries);
+        let start = Utc::now();
+        for (n, &picture) in pictures.iter().enumerate() {
+            let entry = image(n, PICTURES[picture], start + Duration::seconds(n as i64));
+            db.insert_entry(&entry, Duration::zero(), false).unwrap();
+        }
+
+        // Retention deleted some; the blobs follow the entries that are left.
+        let kept: Vec<Vec<u8>> =
+            db.list_recent(1000).unwrap().into_iter().map(|entry| entry.data.unwrap()).collect();
+        let distinct: HashSet<&Vec<u8>> = kept.iter().collect();
+        let total: usize = kept.iter().map(Vec::len).sum();
+        let stored: usize = distinct.iter().map(|data| data.len()).sum();
+        let stats = db.blob_stats().unwrap();
+        prop_assert_eq!((stats.images, stats.blobs), (kept.len() as u64, distinct.len() as u64));
+        prop_assert_eq!((stats.stored_bytes, stats.saved_bytes), (stored as u64, (total - stored) as u64));
+    }
+}